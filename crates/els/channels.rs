@@ -8,15 +8,16 @@ use lsp_types::request::{
     CodeActionRequest, CodeActionResolveRequest, CodeLensRequest, Completion,
     DocumentSymbolRequest, ExecuteCommand, FoldingRangeRequest, GotoDefinition, GotoImplementation,
     GotoImplementationParams, HoverRequest, InlayHintRequest, InlayHintResolveRequest, References,
-    ResolveCompletionItem, SemanticTokensFullRequest, SignatureHelpRequest, WillRenameFiles,
-    WorkspaceSymbol,
+    ResolveCompletionItem, SemanticTokensFullDeltaRequest, SemanticTokensFullRequest,
+    SemanticTokensRangeRequest, SignatureHelpRequest, WillRenameFiles, WorkspaceSymbol,
 };
 use lsp_types::{
     CallHierarchyIncomingCallsParams, CallHierarchyOutgoingCallsParams, CallHierarchyPrepareParams,
     CodeAction, CodeActionParams, CodeLensParams, CompletionItem, CompletionParams,
     DocumentSymbolParams, ExecuteCommandParams, FoldingRangeParams, GotoDefinitionParams,
     HoverParams, InlayHint, InlayHintParams, ReferenceParams, RenameFilesParams,
-    SemanticTokensParams, SignatureHelpParams, WorkspaceSymbolParams,
+    SemanticTokensDeltaParams, SemanticTokensParams, SemanticTokensRangeParams,
+    SignatureHelpParams, WorkspaceSymbolParams,
 };
 
 use crate::server::Server;
@@ -40,6 +41,8 @@ pub struct SendChannels {
     goto_definition: mpsc::Sender<WorkerMessage<GotoDefinitionParams>>,
     goto_implementation: mpsc::Sender<WorkerMessage<GotoImplementationParams>>,
     semantic_tokens_full: mpsc::Sender<WorkerMessage<SemanticTokensParams>>,
+    semantic_tokens_range: mpsc::Sender<WorkerMessage<SemanticTokensRangeParams>>,
+    semantic_tokens_full_delta: mpsc::Sender<WorkerMessage<SemanticTokensDeltaParams>>,
     inlay_hint: mpsc::Sender<WorkerMessage<InlayHintParams>>,
     inlay_hint_resolve: mpsc::Sender<WorkerMessage<InlayHint>>,
     hover: mpsc::Sender<WorkerMessage<HoverParams>>,
@@ -66,6 +69,8 @@ impl SendChannels {
         let (tx_goto_definition, rx_goto_definition) = mpsc::channel();
         let (tx_goto_implementation, rx_goto_implementation) = mpsc::channel();
         let (tx_semantic_tokens_full, rx_semantic_tokens_full) = mpsc::channel();
+        let (tx_semantic_tokens_range, rx_semantic_tokens_range) = mpsc::channel();
+        let (tx_semantic_tokens_full_delta, rx_semantic_tokens_full_delta) = mpsc::channel();
         let (tx_inlay_hint, rx_inlay_hint) = mpsc::channel();
         let (tx_inlay_hint_resolve, rx_inlay_hint_resolve) = mpsc::channel();
         let (tx_hover, rx_hover) = mpsc::channel();
@@ -90,6 +95,8 @@ impl SendChannels {
                 goto_definition: tx_goto_definition,
                 goto_implementation: tx_goto_implementation,
                 semantic_tokens_full: tx_semantic_tokens_full,
+                semantic_tokens_range: tx_semantic_tokens_range,
+                semantic_tokens_full_delta: tx_semantic_tokens_full_delta,
                 inlay_hint: tx_inlay_hint,
                 inlay_hint_resolve: tx_inlay_hint_resolve,
                 hover: tx_hover,
@@ -114,6 +121,8 @@ impl SendChannels {
                 goto_definition: rx_goto_definition,
                 goto_implementation: rx_goto_implementation,
                 semantic_tokens_full: rx_semantic_tokens_full,
+                semantic_tokens_range: rx_semantic_tokens_range,
+                semantic_tokens_full_delta: rx_semantic_tokens_full_delta,
                 inlay_hint: rx_inlay_hint,
                 inlay_hint_resolve: rx_inlay_hint_resolve,
                 hover: rx_hover,
@@ -140,6 +149,12 @@ impl SendChannels {
         self.resolve_completion.send(WorkerMessage::Kill).unwrap();
         self.goto_definition.send(WorkerMessage::Kill).unwrap();
         self.semantic_tokens_full.send(WorkerMessage::Kill).unwrap();
+        self.semantic_tokens_range
+            .send(WorkerMessage::Kill)
+            .unwrap();
+        self.semantic_tokens_full_delta
+            .send(WorkerMessage::Kill)
+            .unwrap();
         self.inlay_hint.send(WorkerMessage::Kill).unwrap();
         self.inlay_hint_resolve.send(WorkerMessage::Kill).unwrap();
         self.hover.send(WorkerMessage::Kill).unwrap();
@@ -173,6 +188,8 @@ pub struct ReceiveChannels {
     pub(crate) goto_definition: mpsc::Receiver<WorkerMessage<GotoDefinitionParams>>,
     pub(crate) goto_implementation: mpsc::Receiver<WorkerMessage<GotoImplementationParams>>,
     pub(crate) semantic_tokens_full: mpsc::Receiver<WorkerMessage<SemanticTokensParams>>,
+    pub(crate) semantic_tokens_range: mpsc::Receiver<WorkerMessage<SemanticTokensRangeParams>>,
+    pub(crate) semantic_tokens_full_delta: mpsc::Receiver<WorkerMessage<SemanticTokensDeltaParams>>,
     pub(crate) inlay_hint: mpsc::Receiver<WorkerMessage<InlayHintParams>>,
     pub(crate) inlay_hint_resolve: mpsc::Receiver<WorkerMessage<InlayHint>>,
     pub(crate) hover: mpsc::Receiver<WorkerMessage<HoverParams>>,
@@ -228,6 +245,16 @@ impl_sendable!(
     SemanticTokensParams,
     semantic_tokens_full
 );
+impl_sendable!(
+    SemanticTokensRangeRequest,
+    SemanticTokensRangeParams,
+    semantic_tokens_range
+);
+impl_sendable!(
+    SemanticTokensFullDeltaRequest,
+    SemanticTokensDeltaParams,
+    semantic_tokens_full_delta
+);
 impl_sendable!(InlayHintRequest, InlayHintParams, inlay_hint);
 impl_sendable!(InlayHintResolveRequest, InlayHint, inlay_hint_resolve);
 impl_sendable!(HoverRequest, HoverParams, hover);
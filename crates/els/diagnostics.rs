@@ -114,6 +114,8 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
             self.send_log(format!("{uri}: {}", module.context.name))?;
             self.modules.insert(uri.clone(), module);
         }
+        // the token modifiers depend on the analysis result
+        self.sema_cache.invalidate(&uri);
         let dependents = self.dependents_of(&uri);
         for dep in dependents {
             // _log!(self, "dep: {dep}");
@@ -154,10 +154,24 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                             MarkedString::from_language_code(PROG_LANG.into(), code_block);
                         contents.push(definition);
                     }
-                    let typ = MarkedString::from_language_code(
-                        ERG_LANG.into(),
-                        format!("{}: {}", token.content, vi.t),
-                    );
+                    // the type of a call site is the resolved overload, so the overload set is taken from the definition
+                    let def_t = self
+                        .get_index()
+                        .and_then(|index| index.get_refs(&vi.def_loc))
+                        .map(|value| value.vi.t.clone())
+                        .filter(|t| t.is_overloaded());
+                    let typ = if let Some(def_t) = def_t {
+                        // overloads are listed one per line
+                        def_t
+                            .intersection_types()
+                            .iter()
+                            .map(|t| format!("{}: {t}", token.content))
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    } else {
+                        format!("{}: {}", token.content, vi.t)
+                    };
+                    let typ = MarkedString::from_language_code(ERG_LANG.into(), typ);
                     contents.push(typ);
                     self.show_type_defs(&vi, &mut contents)?;
                     self.show_doc_comment(Some(token), &mut contents, &vi.def_loc)?;
@@ -1,5 +1,10 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
 use erg_common::dict::Dict;
 use erg_common::error::Location;
+use erg_common::shared::Shared;
 use erg_common::traits::{Locational, Runnable};
 
 use erg_compiler::artifact::BuildRunnable;
@@ -9,29 +14,92 @@ use erg_compiler::erg_parser::ast::{
 };
 use erg_compiler::erg_parser::parse::Parsable;
 use erg_compiler::erg_parser::token::TokenKind;
+use erg_compiler::varinfo::VarInfo;
 use erg_compiler::ASTBuilder;
 
 use lsp_types::{
-    SemanticToken, SemanticTokenType, SemanticTokens, SemanticTokensParams, SemanticTokensResult,
+    Range, SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokens,
+    SemanticTokensDelta, SemanticTokensDeltaParams, SemanticTokensEdit,
+    SemanticTokensFullDeltaResult, SemanticTokensParams, SemanticTokensRangeParams,
+    SemanticTokensRangeResult, SemanticTokensResult,
 };
 
 use crate::server::{ELSResult, RedirectableStdout, Server};
 use crate::util::{self, NormalizedUrl};
 
+/// The legend of token types (the index is the value sent to the client).
+pub(crate) const TOKEN_TYPES: [SemanticTokenType; 13] = [
+    SemanticTokenType::NAMESPACE,
+    SemanticTokenType::TYPE,
+    SemanticTokenType::CLASS,
+    SemanticTokenType::INTERFACE,
+    SemanticTokenType::TYPE_PARAMETER,
+    SemanticTokenType::PARAMETER,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::PROPERTY,
+    SemanticTokenType::FUNCTION,
+    SemanticTokenType::METHOD,
+    SemanticTokenType::STRING,
+    SemanticTokenType::NUMBER,
+    SemanticTokenType::OPERATOR,
+];
+
+/// The legend of token modifiers (the `n`th modifier is the `1 << n` bit).
+pub(crate) const TOKEN_MODIFIERS: [SemanticTokenModifier; 5] = [
+    SemanticTokenModifier::new("mutable"),
+    SemanticTokenModifier::new("procedural"),
+    SemanticTokenModifier::new("constant"),
+    SemanticTokenModifier::READONLY,
+    SemanticTokenModifier::DEPRECATED,
+];
+
+const MUTABLE: u32 = 1 << 0;
+const PROCEDURAL: u32 = 1 << 1;
+const CONSTANT: u32 = 1 << 2;
+const READONLY: u32 = 1 << 3;
+const DEPRECATED: u32 = 1 << 4;
+
+fn modifiers_of(vi: &VarInfo) -> u32 {
+    let mut modifiers = 0;
+    if vi.t.is_mut_type() {
+        modifiers |= MUTABLE;
+    }
+    if vi.t.is_procedure() {
+        modifiers |= PROCEDURAL;
+    }
+    if vi.muty.is_const() {
+        modifiers |= CONSTANT;
+    }
+    if !vi.t.is_mut_type() && !vi.t.is_subr() {
+        modifiers |= READONLY;
+    }
+    if vi
+        .comptime_decos
+        .as_ref()
+        .is_some_and(|decos| decos.contains("Deprecated"))
+    {
+        modifiers |= DEPRECATED;
+    }
+    modifiers
+}
+
 #[derive(Debug)]
 struct ASTSemanticState {
     prev_line: u32,
     prev_col: u32,
     namespaces: Vec<Dict<String, SemanticTokenType>>,
+    /// token modifiers of the names (definitions and references) in the file
+    modifiers: Dict<Location, u32>,
     tokens: Vec<SemanticToken>,
 }
 
 impl ASTSemanticState {
-    fn new() -> Self {
+    fn new(modifiers: Dict<Location, u32>) -> Self {
         Self {
             prev_line: 1,
             prev_col: 0,
             namespaces: vec![Dict::new()],
+            modifiers,
             tokens: Vec::new(),
         }
     }
@@ -66,31 +134,15 @@ impl ASTSemanticState {
     }
 
     fn token_type_as_u32(token_type: SemanticTokenType) -> u32 {
-        match token_type.as_str() {
-            "namespace" => 0,
-            "type" => 1,
-            "class" => 2,
-            "enum" => 3,
-            "interface" => 4,
-            "struct" => 5,
-            "typeParameter" => 6,
-            "parameter" => 7,
-            "variable" => 8,
-            "property" => 9,
-            "enumMember" => 10,
-            "event" => 11,
-            "function" => 12,
-            "method" => 13,
-            "macro" => 14,
-            "keyword" => 15,
-            "modifier" => 16,
-            "comment" => 17,
-            "string" => 18,
-            "number" => 19,
-            "regexp" => 20,
-            "operator" => 21,
-            _ => 8,
-        }
+        TOKEN_TYPES
+            .iter()
+            .position(|typ| typ == &token_type)
+            .or_else(|| {
+                TOKEN_TYPES
+                    .iter()
+                    .position(|typ| typ == &SemanticTokenType::VARIABLE)
+            })
+            .unwrap_or(0) as u32
     }
 
     fn gen_token(&mut self, loc: Location, token_type: SemanticTokenType) -> SemanticToken {
@@ -105,7 +157,7 @@ impl ASTSemanticState {
             delta_start,
             length: loc.length().unwrap_or(1),
             token_type: Self::token_type_as_u32(token_type),
-            token_modifiers_bitset: 0,
+            token_modifiers_bitset: self.modifiers.get(&loc).copied().unwrap_or(0),
         };
         self.prev_line = loc.ln_begin().unwrap_or(self.prev_line);
         self.prev_col = loc.col_begin().unwrap_or(self.prev_col);
@@ -169,7 +221,7 @@ impl ASTSemanticState {
             .unwrap_or_else(|| (None, "_".to_string()));
         let typ = match def.def_kind() {
             DefKind::Class => SemanticTokenType::CLASS,
-            DefKind::Trait => SemanticTokenType::INTERFACE,
+            DefKind::Trait | DefKind::StructuralTrait => SemanticTokenType::INTERFACE,
             _ if def.is_subr() => SemanticTokenType::FUNCTION,
             _ => SemanticTokenType::VARIABLE,
         };
@@ -283,24 +335,243 @@ impl ASTSemanticState {
     }
 }
 
+/// `SemanticToken`s are encoded relative to the previous token.
+/// This decodes them into `(line, start, token)`.
+fn decode(tokens: &[SemanticToken]) -> Vec<(u32, u32, SemanticToken)> {
+    let mut line = 0;
+    let mut start = 0;
+    let mut decoded = Vec::with_capacity(tokens.len());
+    for token in tokens.iter() {
+        if token.delta_line == 0 {
+            start += token.delta_start;
+        } else {
+            line += token.delta_line;
+            start = token.delta_start;
+        }
+        decoded.push((line, start, *token));
+    }
+    decoded
+}
+
+fn encode(tokens: impl IntoIterator<Item = (u32, u32, SemanticToken)>) -> Vec<SemanticToken> {
+    let (mut prev_line, mut prev_start) = (0, 0);
+    let mut encoded = vec![];
+    for (line, start, token) in tokens {
+        let delta_line = line - prev_line;
+        let delta_start = if delta_line == 0 {
+            start - prev_start
+        } else {
+            start
+        };
+        encoded.push(SemanticToken {
+            delta_line,
+            delta_start,
+            ..token
+        });
+        (prev_line, prev_start) = (line, start);
+    }
+    encoded
+}
+
+fn in_range(range: &Range, line: u32, start: u32, length: u32) -> bool {
+    let after_start = line > range.start.line
+        || (line == range.start.line && start + length > range.start.character);
+    let before_end =
+        line < range.end.line || (line == range.end.line && start < range.end.character);
+    after_start && before_end
+}
+
+/// Computes the edit which converts `old` into `new` (the common prefix and suffix are kept).
+fn diff(old: &[SemanticToken], new: &[SemanticToken]) -> Vec<SemanticTokensEdit> {
+    let prefix = old.iter().zip(new).take_while(|(l, r)| l == r).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(l, r)| l == r)
+        .count();
+    let deleted = old.len() - prefix - suffix;
+    let inserted = &new[prefix..new.len() - suffix];
+    if deleted == 0 && inserted.is_empty() {
+        return vec![];
+    }
+    // each token consists of 5 integers
+    vec![SemanticTokensEdit {
+        start: (prefix * 5) as u32,
+        delete_count: (deleted * 5) as u32,
+        data: Some(inserted.to_vec()),
+    }]
+}
+
+#[derive(Debug, Clone)]
+pub struct SemanticTokensEntry {
+    /// the version of the document (`None` if the entry is outdated)
+    ver: Option<i32>,
+    result_id: String,
+    tokens: Vec<SemanticToken>,
+}
+
+/// The last result of semantic tokens for each document.
+/// Requests for the same version of a document reuse the result,
+/// and `semanticTokens/full/delta` is computed from it.
+#[derive(Debug, Clone, Default)]
+pub struct SemanticTokensCache {
+    entries: Shared<Dict<NormalizedUrl, SemanticTokensEntry>>,
+    next_id: Arc<AtomicUsize>,
+}
+
+impl SemanticTokensCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, uri: &NormalizedUrl) -> Option<SemanticTokensEntry> {
+        self.entries.borrow().get(uri).cloned()
+    }
+
+    fn insert(
+        &self,
+        uri: NormalizedUrl,
+        ver: Option<i32>,
+        tokens: Vec<SemanticToken>,
+    ) -> SemanticTokensEntry {
+        let result_id = self.next_id.fetch_add(1, Ordering::Relaxed).to_string();
+        let entry = SemanticTokensEntry {
+            ver,
+            result_id,
+            tokens,
+        };
+        self.entries.borrow_mut().insert(uri, entry.clone());
+        entry
+    }
+
+    /// The tokens will be recomputed on the next request (the previous result is kept for delta requests).
+    pub fn invalidate(&self, uri: &NormalizedUrl) {
+        if let Some(entry) = self.entries.borrow_mut().get_mut(uri) {
+            entry.ver = None;
+        }
+    }
+
+    pub fn remove(&self, uri: &NormalizedUrl) {
+        self.entries.borrow_mut().remove(uri);
+    }
+
+    pub fn clear(&self) {
+        self.entries.borrow_mut().clear();
+    }
+}
+
 impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
+    /// Collects the token modifiers of the names in `path` from the module index.
+    fn token_modifiers(&self, path: &Path) -> Dict<Location, u32> {
+        let mut modifiers = Dict::new();
+        let Some(index) = self.get_index() else {
+            return modifiers;
+        };
+        let Some(referees) = index.get_referees_in(path) else {
+            return modifiers;
+        };
+        for referee in referees.iter() {
+            let Some(value) = index.get_refs(referee) else {
+                continue;
+            };
+            let bits = modifiers_of(&value.vi);
+            if bits == 0 {
+                continue;
+            }
+            if referee.module.as_deref() == Some(path) {
+                modifiers.insert(referee.loc, bits);
+            }
+            for referrer in value.referrers.iter() {
+                if referrer.module.as_deref() == Some(path) {
+                    modifiers.insert(referrer.loc, bits);
+                }
+            }
+        }
+        modifiers
+    }
+
+    /// Returns the cached tokens if the document has not changed, otherwise walks the AST again.
+    fn get_semantic_tokens(
+        &mut self,
+        uri: &NormalizedUrl,
+    ) -> ELSResult<Option<SemanticTokensEntry>> {
+        let ver = self.file_cache.get_ver(uri);
+        if let Some(entry) = self.sema_cache.get(uri) {
+            if entry.ver.is_some() && entry.ver == ver {
+                return Ok(Some(entry));
+            }
+        }
+        let path = util::uri_to_path(uri);
+        let src = self.file_cache.get_entire_code(uri)?;
+        let mut builder = ASTBuilder::new(self.cfg.inherit(path.clone()));
+        let Ok(artifact) = builder.build_without_desugaring(src) else {
+            return Ok(None);
+        };
+        let mut state = ASTSemanticState::new(self.token_modifiers(&path));
+        let tokens = state.enumerate_tokens(artifact.ast);
+        Ok(Some(self.sema_cache.insert(uri.clone(), ver, tokens.data)))
+    }
+
     pub(crate) fn handle_semantic_tokens_full(
         &mut self,
         params: SemanticTokensParams,
     ) -> ELSResult<Option<SemanticTokensResult>> {
         self.send_log(format!("full semantic tokens request: {params:?}"))?;
         let uri = NormalizedUrl::new(params.text_document.uri);
-        let path = util::uri_to_path(&uri);
-        let src = self.file_cache.get_entire_code(&uri)?;
-        let mut builder = ASTBuilder::new(self.cfg.inherit(path));
-        let result = match builder.build_without_desugaring(src) {
-            Ok(artifact) => {
-                let mut state = ASTSemanticState::new();
-                let tokens = state.enumerate_tokens(artifact.ast);
-                Some(SemanticTokensResult::Tokens(tokens))
-            }
-            Err(_) => None,
-        };
+        let result = self.get_semantic_tokens(&uri)?.map(|entry| {
+            SemanticTokensResult::Tokens(SemanticTokens {
+                result_id: Some(entry.result_id),
+                data: entry.tokens,
+            })
+        });
         Ok(result)
     }
+
+    pub(crate) fn handle_semantic_tokens_range(
+        &mut self,
+        params: SemanticTokensRangeParams,
+    ) -> ELSResult<Option<SemanticTokensRangeResult>> {
+        self.send_log(format!("range semantic tokens request: {params:?}"))?;
+        let uri = NormalizedUrl::new(params.text_document.uri);
+        let result = self.get_semantic_tokens(&uri)?.map(|entry| {
+            let tokens = decode(&entry.tokens)
+                .into_iter()
+                .filter(|(line, start, token)| {
+                    in_range(&params.range, *line, *start, token.length)
+                });
+            SemanticTokensRangeResult::Tokens(SemanticTokens {
+                result_id: None,
+                data: encode(tokens),
+            })
+        });
+        Ok(result)
+    }
+
+    pub(crate) fn handle_semantic_tokens_full_delta(
+        &mut self,
+        params: SemanticTokensDeltaParams,
+    ) -> ELSResult<Option<SemanticTokensFullDeltaResult>> {
+        self.send_log(format!("delta semantic tokens request: {params:?}"))?;
+        let uri = NormalizedUrl::new(params.text_document.uri);
+        let prev = self
+            .sema_cache
+            .get(&uri)
+            .filter(|prev| prev.result_id == params.previous_result_id);
+        let Some(entry) = self.get_semantic_tokens(&uri)? else {
+            return Ok(None);
+        };
+        let result = if let Some(prev) = prev {
+            SemanticTokensFullDeltaResult::TokensDelta(SemanticTokensDelta {
+                edits: diff(&prev.tokens, &entry.tokens),
+                result_id: Some(entry.result_id),
+            })
+        } else {
+            SemanticTokensFullDeltaResult::Tokens(SemanticTokens {
+                result_id: Some(entry.result_id),
+                data: entry.tokens,
+            })
+        };
+        Ok(Some(result))
+    }
 }
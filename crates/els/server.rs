@@ -35,8 +35,8 @@ use lsp_types::request::{
     CodeActionRequest, CodeActionResolveRequest, CodeLensRequest, Completion,
     DocumentSymbolRequest, ExecuteCommand, FoldingRangeRequest, GotoDefinition, GotoImplementation,
     HoverRequest, InlayHintRequest, InlayHintResolveRequest, References, Rename, Request,
-    ResolveCompletionItem, SemanticTokensFullRequest, SignatureHelpRequest, WillRenameFiles,
-    WorkspaceSymbol,
+    ResolveCompletionItem, SemanticTokensFullDeltaRequest, SemanticTokensFullRequest,
+    SemanticTokensRangeRequest, SignatureHelpRequest, WillRenameFiles, WorkspaceSymbol,
 };
 use lsp_types::{
     CallHierarchyServerCapability, CodeActionKind, CodeActionOptions, CodeActionProviderCapability,
@@ -44,9 +44,9 @@ use lsp_types::{
     DidChangeTextDocumentParams, DidOpenTextDocumentParams, ExecuteCommandOptions,
     FoldingRangeProviderCapability, HoverProviderCapability, ImplementationProviderCapability,
     InitializeParams, InitializeResult, InlayHintOptions, InlayHintServerCapabilities, OneOf,
-    Position, SemanticTokenType, SemanticTokensFullOptions, SemanticTokensLegend,
-    SemanticTokensOptions, SemanticTokensServerCapabilities, ServerCapabilities,
-    SignatureHelpOptions, WorkDoneProgressOptions,
+    Position, SemanticTokensFullOptions, SemanticTokensLegend, SemanticTokensOptions,
+    SemanticTokensServerCapabilities, ServerCapabilities, SignatureHelpOptions,
+    WorkDoneProgressOptions,
 };
 
 use serde::{Deserialize, Serialize};
@@ -58,6 +58,7 @@ use crate::completion::CompletionCache;
 use crate::file_cache::FileCache;
 use crate::hir_visitor::{ExprKind, HIRVisitor};
 use crate::message::{ErrorMessage, LSPResult};
+use crate::semantic::{SemanticTokensCache, TOKEN_MODIFIERS, TOKEN_TYPES};
 use crate::util::{self, loc_to_pos, project_root_of, NormalizedUrl};
 
 pub const HEALTH_CHECKER_ID: i64 = 10000;
@@ -303,6 +304,7 @@ pub struct Server<Checker: BuildRunnable = HIRBuilder, Parser: Parsable = Simple
     pub(crate) modules: ModuleCache,
     pub(crate) flags: Flags,
     pub(crate) analysis_result: AnalysisResultCache,
+    pub(crate) sema_cache: SemanticTokensCache,
     pub(crate) channels: Option<SendChannels>,
     pub(crate) stdout_redirect: Option<mpsc::Sender<Value>>,
     pub(crate) _parser: std::marker::PhantomData<fn() -> Parser>,
@@ -343,6 +345,7 @@ impl<C: BuildRunnable, P: Parsable> Clone for Server<C, P> {
             comp_cache: self.comp_cache.clone(),
             modules: self.modules.clone(),
             analysis_result: self.analysis_result.clone(),
+            sema_cache: self.sema_cache.clone(),
             channels: self.channels.clone(),
             flags: self.flags.clone(),
             stdout_redirect: self.stdout_redirect.clone(),
@@ -366,6 +369,7 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
             file_cache: FileCache::new(stdout_redirect.clone()),
             modules: ModuleCache::new(),
             analysis_result: AnalysisResultCache::new(),
+            sema_cache: SemanticTokensCache::new(),
             channels: None,
             flags: Flags::default(),
             stdout_redirect,
@@ -461,25 +465,11 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                 },
             )));
         let mut sema_options = SemanticTokensOptions::default();
-        sema_options.range = Some(false);
-        sema_options.full = Some(SemanticTokensFullOptions::Bool(true));
+        sema_options.range = Some(true);
+        sema_options.full = Some(SemanticTokensFullOptions::Delta { delta: Some(true) });
         sema_options.legend = SemanticTokensLegend {
-            token_types: vec![
-                SemanticTokenType::NAMESPACE,
-                SemanticTokenType::TYPE,
-                SemanticTokenType::CLASS,
-                SemanticTokenType::INTERFACE,
-                SemanticTokenType::TYPE_PARAMETER,
-                SemanticTokenType::PARAMETER,
-                SemanticTokenType::VARIABLE,
-                SemanticTokenType::PROPERTY,
-                SemanticTokenType::FUNCTION,
-                SemanticTokenType::METHOD,
-                SemanticTokenType::STRING,
-                SemanticTokenType::NUMBER,
-                SemanticTokenType::OPERATOR,
-            ],
-            token_modifiers: vec![],
+            token_types: TOKEN_TYPES.to_vec(),
+            token_modifiers: TOKEN_MODIFIERS.to_vec(),
         };
         capabilities.semantic_tokens_provider = self
             .disabled_features
@@ -561,6 +551,14 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
             receivers.semantic_tokens_full,
             Self::handle_semantic_tokens_full,
         );
+        self.start_service::<SemanticTokensRangeRequest>(
+            receivers.semantic_tokens_range,
+            Self::handle_semantic_tokens_range,
+        );
+        self.start_service::<SemanticTokensFullDeltaRequest>(
+            receivers.semantic_tokens_full_delta,
+            Self::handle_semantic_tokens_full_delta,
+        );
         self.start_service::<InlayHintRequest>(receivers.inlay_hint, Self::handle_inlay_hint);
         self.start_service::<InlayHintResolveRequest>(
             receivers.inlay_hint_resolve,
@@ -639,6 +637,7 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
         self.comp_cache.clear();
         self.modules = ModuleCache::new();
         self.analysis_result = AnalysisResultCache::new();
+        self.sema_cache.clear();
         self.channels.as_ref().unwrap().close();
         self.start_language_services();
         self.start_workspace_diagnostics();
@@ -782,6 +781,12 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
             SemanticTokensFullRequest::METHOD => {
                 self.parse_send::<SemanticTokensFullRequest>(id, msg)
             }
+            SemanticTokensRangeRequest::METHOD => {
+                self.parse_send::<SemanticTokensRangeRequest>(id, msg)
+            }
+            SemanticTokensFullDeltaRequest::METHOD => {
+                self.parse_send::<SemanticTokensFullDeltaRequest>(id, msg)
+            }
             InlayHintRequest::METHOD => self.parse_send::<InlayHintRequest>(id, msg),
             InlayHintResolveRequest::METHOD => self.parse_send::<InlayHintResolveRequest>(id, msg),
             CodeActionRequest::METHOD => self.parse_send::<CodeActionRequest>(id, msg),
@@ -1051,6 +1056,7 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
 
     pub(crate) fn clear_cache(&mut self, uri: &NormalizedUrl) {
         self.analysis_result.remove(uri);
+        self.sema_cache.remove(uri);
         if let Some(module) = self.modules.remove(uri) {
             let shared = module.context.shared();
             let path = util::uri_to_path(uri);
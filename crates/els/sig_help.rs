@@ -2,8 +2,9 @@ use erg_common::traits::{DequeStream, LimitedDisplay, Locational, NoTypeDisplay}
 use erg_compiler::artifact::BuildRunnable;
 use erg_compiler::erg_parser::parse::Parsable;
 use erg_compiler::erg_parser::token::{Token, TokenCategory, TokenKind};
-use erg_compiler::hir::{Call, Expr};
-use erg_compiler::ty::{HasType, ParamTy};
+use erg_compiler::hir::{Accessor, Call, Expr};
+use erg_compiler::ty::{HasType, ParamTy, Type};
+use erg_compiler::varinfo::AbsLocation;
 
 use lsp_types::{
     ParameterInformation, ParameterLabel, Position, SignatureHelp, SignatureHelpContext,
@@ -146,7 +147,7 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                     return None;
                 }
                 let nth = self.nth(uri, &call, pos) as u32;
-                return self.make_sig_help(call.obj.as_ref(), nth);
+                return self.make_call_sig_help(&call, nth);
             } else {
                 crate::_log!(self, "failed to get the call");
             }
@@ -158,7 +159,7 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
 
     fn get_first_help(&mut self, uri: &NormalizedUrl, pos: Position) -> Option<SignatureHelp> {
         if let Some((_token, Expr::Accessor(acc))) = self.get_min_expr(uri, pos, -2) {
-            return self.make_sig_help(&acc, 0);
+            return self.make_sig_help(&acc, Some(&acc.var_info().def_loc), 0);
         } else {
             crate::_log!(self, "lex error occurred");
         }
@@ -168,7 +169,7 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
     fn get_continuous_help(&mut self, uri: &NormalizedUrl, pos: Position) -> Option<SignatureHelp> {
         if let Some(call) = self.get_min::<Call>(uri, pos) {
             let nth = self.nth(uri, &call, pos) as u32 + 1;
-            let help = self.make_sig_help(call.obj.as_ref(), nth);
+            let help = self.make_call_sig_help(&call, nth);
             return help;
         } else {
             crate::_log!(self, "failed to get continuous help");
@@ -179,11 +180,42 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
     fn make_sig_help<S: HasType + NoTypeDisplay>(
         &self,
         sig: &S,
+        def_loc: Option<&AbsLocation>,
         nth: u32,
     ) -> Option<SignatureHelp> {
         let sig_t = sig.ref_t();
-        let mut parameters = vec![];
         let sig = sig.to_string_notype();
+        // the callee type of a call is the resolved overload, so the overload set is taken from the definition
+        let def_t = def_loc.and_then(|loc| {
+            self.get_index()
+                .and_then(|index| index.get_refs(loc).map(|value| value.vi.t.clone()))
+        });
+        let overloads = match def_t {
+            Some(def_t) if def_t.is_overloaded() => def_t.intersection_types(),
+            _ => sig_t.intersection_types(),
+        };
+        let signatures = overloads
+            .iter()
+            .map(|t| Self::make_sig_info(&sig, t, nth))
+            .collect::<Option<Vec<_>>>()?;
+        let active_signature = if signatures.len() > 1 {
+            overloads
+                .iter()
+                .position(|t| t == sig_t)
+                .map(|i| i as u32)
+                .or(Some(0))
+        } else {
+            None
+        };
+        Some(SignatureHelp {
+            signatures,
+            active_parameter: None,
+            active_signature,
+        })
+    }
+
+    fn make_sig_info(sig: &str, sig_t: &Type, nth: u32) -> Option<SignatureInformation> {
+        let mut parameters = vec![];
         let label = format!("{sig}: {}", sig_t.to_string_unabbreviated());
         let mut end = sig.len() + 1; // +1: (
         for nd_param in sig_t.non_default_params()? {
@@ -221,16 +253,22 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
         let nth = (parameters.len().saturating_sub(1) as u32)
             .min(nth)
             .min(var_args_nth);
-        let info = SignatureInformation {
+        Some(SignatureInformation {
             label,
             documentation: None,
             parameters: Some(parameters),
             active_parameter: Some(nth),
-        };
-        Some(SignatureHelp {
-            signatures: vec![info],
-            active_parameter: None,
-            active_signature: None,
         })
     }
+
+    /// `f(...)` => `f`, `obj.method(...)` => `obj.method`
+    fn make_call_sig_help(&self, call: &Call, nth: u32) -> Option<SignatureHelp> {
+        if let Some(attr_name) = &call.attr_name {
+            let callee = Accessor::attr(call.obj.as_ref().clone(), attr_name.clone());
+            self.make_sig_help(&callee, Some(&attr_name.vi.def_loc), nth)
+        } else {
+            let def_loc = call.obj.var_info().map(|vi| &vi.def_loc);
+            self.make_sig_help(call.obj.as_ref(), def_loc, nth)
+        }
+    }
 }
@@ -2,13 +2,17 @@ use std::path::Path;
 
 use lsp_types::{
     CompletionResponse, DocumentSymbolResponse, FoldingRange, FoldingRangeKind,
-    GotoDefinitionResponse, HoverContents, MarkedString,
+    GotoDefinitionResponse, HoverContents, MarkedString, SemanticTokens,
+    SemanticTokensFullDeltaResult,
 };
+use serde_json::{json, Value};
 const FILE_A: &str = "tests/a.er";
 const FILE_B: &str = "tests/b.er";
 const FILE_IMPORTS: &str = "tests/imports.er";
+const FILE_OVERLOAD: &str = "tests/overload.er";
 
 use els::{NormalizedUrl, Server};
+use erg_common::config::ErgConfig;
 use molc::{add_char, oneline_range};
 
 #[test]
@@ -93,6 +97,41 @@ fn test_signature_help() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn test_overload_signature_help() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = Server::bind_fake_client();
+    client.request_initialize()?;
+    let uri = NormalizedUrl::from_file_path(Path::new(FILE_OVERLOAD).canonicalize()?)?;
+    client.notify_open(FILE_OVERLOAD)?;
+    client.notify_change(uri.clone().raw(), add_char(2, 0, "b.abs"))?;
+    client.notify_change(uri.clone().raw(), add_char(2, 5, "("))?;
+    let help = client
+        .request_signature_help(uri.raw(), 2, 6, "(")?
+        .unwrap();
+    assert_eq!(help.signatures.len(), 2);
+    assert_eq!(help.signatures[1].label, "(::b).abs: (x: Complex) -> Float");
+    assert_eq!(help.active_signature, Some(0));
+    Ok(())
+}
+
+#[test]
+fn test_overload_hover() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = Server::bind_fake_client();
+    client.request_initialize()?;
+    let uri = NormalizedUrl::from_file_path(Path::new(FILE_OVERLOAD).canonicalize()?)?;
+    client.notify_open(FILE_OVERLOAD)?;
+    let hover = client.request_hover(uri.raw(), 1, 7)?.unwrap();
+    let HoverContents::Array(contents) = hover.contents else {
+        todo!()
+    };
+    assert!(contents.iter().any(|content| matches!(
+        content,
+        MarkedString::LanguageString(content)
+            if content.value == "abs: (x: Int) -> Nat\nabs: (x: Complex) -> Float"
+    )));
+    Ok(())
+}
+
 #[test]
 fn test_hover() -> Result<(), Box<dyn std::error::Error>> {
     let mut client = Server::bind_fake_client();
@@ -181,3 +220,74 @@ fn test_document_symbol() -> Result<(), Box<dyn std::error::Error>> {
     assert_eq!(&symbols[0].name, "x");
     Ok(())
 }
+
+/// `FakeClient` has no semantic tokens requests, so the messages are sent directly.
+fn wait_result(receiver: &std::sync::mpsc::Receiver<Value>, id: i64) -> Value {
+    loop {
+        let msg = receiver.recv().unwrap();
+        if msg.get("method").is_none() && msg.get("id").is_some_and(|val| val == id) {
+            return msg["result"].clone();
+        }
+    }
+}
+
+#[test]
+fn test_semantic_tokens() -> Result<(), Box<dyn std::error::Error>> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut server: Server = Server::new(ErgConfig::default(), Some(sender));
+    server.dispatch(json!({ "jsonrpc": "2.0", "id": 0, "method": "initialize" }))?;
+    let capas = wait_result(&receiver, 0);
+    let legend = &capas["capabilities"]["semanticTokensProvider"]["legend"];
+    let readonly = legend["tokenModifiers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .position(|modifier| modifier == "readonly")
+        .unwrap();
+    let uri = NormalizedUrl::from_file_path(Path::new(FILE_A).canonicalize()?)?;
+    let text = std::fs::read_to_string(FILE_A)?;
+    server.dispatch(json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {
+            "textDocument": { "uri": uri.clone().raw(), "languageId": "erg", "version": 0, "text": text },
+        },
+    }))?;
+    while !receiver.recv()?.to_string().contains("tests/a.er passed") {}
+    let doc = json!({ "uri": uri.raw() });
+    server.dispatch(json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "textDocument/semanticTokens/full",
+        "params": { "textDocument": doc },
+    }))?;
+    let full: SemanticTokens = serde_json::from_value(wait_result(&receiver, 1))?;
+    // `1`, `x`, `+`, `1` (`x` on line 1 refers to an immutable variable)
+    assert_eq!(full.data.len(), 4);
+    assert_eq!(full.data[1].delta_line, 1);
+    assert_ne!(full.data[1].token_modifiers_bitset & (1 << readonly), 0);
+    server.dispatch(json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "textDocument/semanticTokens/range",
+        "params": {
+            "textDocument": doc,
+            "range": { "start": { "line": 1, "character": 0 }, "end": { "line": 2, "character": 0 } },
+        },
+    }))?;
+    let range: SemanticTokens = serde_json::from_value(wait_result(&receiver, 2))?;
+    assert!(!range.data.is_empty() && range.data.len() < full.data.len());
+    assert_eq!(range.data[0].delta_line, 1);
+    server.dispatch(json!({
+        "jsonrpc": "2.0",
+        "id": 3,
+        "method": "textDocument/semanticTokens/full/delta",
+        "params": { "textDocument": doc, "previousResultId": full.result_id.unwrap() },
+    }))?;
+    let delta: SemanticTokensFullDeltaResult = serde_json::from_value(wait_result(&receiver, 3))?;
+    let SemanticTokensFullDeltaResult::TokensDelta(delta) = delta else {
+        return Err(format!("not a delta: {delta:?}").into());
+    };
+    assert!(delta.edits.is_empty());
+    Ok(())
+}
@@ -8,12 +8,13 @@ use std::path::PathBuf;
 use std::process;
 use std::str::FromStr;
 
+use crate::error::ErrorFormat;
 use crate::help_messages::{command_message, mode_message, OPTIONS};
 use crate::io::{Input, Output};
 use crate::levenshtein::get_similar_name;
 use crate::normalize_path;
 use crate::python_util::{detect_magic_number, get_python_version, PythonVersion};
-use crate::serialize::{get_magic_num_from_bytes, get_ver_from_magic_num};
+use crate::serialize::{get_magic_num_from_bytes, get_ver_from_magic_num, PycInvalidationMode};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ErgMode {
@@ -23,10 +24,14 @@ pub enum ErgMode {
     TypeCheck,
     FullCheck,
     Compile,
+    Build,
+    Bundle,
+    Explain,
     Transpile,
     Execute,
     LanguageServer,
     Read,
+    Kernel,
 }
 
 impl TryFrom<&str> for ErgMode {
@@ -39,10 +44,14 @@ impl TryFrom<&str> for ErgMode {
             "typecheck" | "lower" | "tc" => Ok(Self::TypeCheck),
             "fullcheck" | "check" | "checker" => Ok(Self::FullCheck),
             "comp" | "compile" | "compiler" => Ok(Self::Compile),
+            "build" => Ok(Self::Build),
+            "bundle" => Ok(Self::Bundle),
+            "explain" => Ok(Self::Explain),
             "trans" | "transpile" | "transpiler" => Ok(Self::Transpile),
             "run" | "execute" => Ok(Self::Execute),
             "server" | "language-server" => Ok(Self::LanguageServer),
             "byteread" | "read" | "reader" | "dis" => Ok(Self::Read),
+            "kernel" | "jupyter-kernel" => Ok(Self::Kernel),
             _ => Err(()),
         }
     }
@@ -57,10 +66,14 @@ impl From<ErgMode> for &str {
             ErgMode::TypeCheck => "typecheck",
             ErgMode::FullCheck => "fullcheck",
             ErgMode::Compile => "compile",
+            ErgMode::Build => "build",
+            ErgMode::Bundle => "bundle",
+            ErgMode::Explain => "explain",
             ErgMode::Transpile => "transpile",
             ErgMode::Execute => "execute",
             ErgMode::LanguageServer => "language-server",
             ErgMode::Read => "read",
+            ErgMode::Kernel => "kernel",
         }
     }
 }
@@ -104,6 +117,8 @@ pub struct ErgConfig {
     pub target_version: Option<PythonVersion>,
     pub transpile_target: Option<TranspileTarget>,
     pub py_server_timeout: u64,
+    /// how the generated `.pyc` files are validated by Python (PEP 552)
+    pub invalidation_mode: PycInvalidationMode,
     pub quiet_repl: bool,
     pub show_type: bool,
     pub input: Input,
@@ -115,6 +130,10 @@ pub struct ErgConfig {
     /// * 0: display errors, warns
     /// * 1 (default): display errors, warnings and hints
     pub verbose: u8,
+    /// output format of errors and warnings (`human`, `json` or `sarif`)
+    pub error_format: ErrorFormat,
+    /// print the disassembly of `read` mode as JSON
+    pub json_output: bool,
     /// needed for `jupyter-erg`
     pub ps1: &'static str,
     pub ps2: &'static str,
@@ -133,6 +152,7 @@ impl Default for ErgConfig {
             target_version: None,
             transpile_target: None,
             py_server_timeout: 10,
+            invalidation_mode: PycInvalidationMode::default(),
             quiet_repl: false,
             show_type: false,
             input: Input::repl(),
@@ -140,6 +160,8 @@ impl Default for ErgConfig {
             dist_dir: None,
             module: "<module>",
             verbose: 1,
+            error_format: ErrorFormat::Human,
+            json_output: false,
             ps1: ">>> ",
             ps2: "... ",
             runtime_args: vec![],
@@ -233,6 +255,28 @@ impl ErgConfig {
                 "--compile" | "--dump-as-pyc" => {
                     cfg.mode = ErgMode::Compile;
                 }
+                "--error-format" => {
+                    let format = args
+                        .next()
+                        .expect("the value of `--error-format` is not passed");
+                    cfg.error_format = ErrorFormat::try_from(&format[..]).unwrap_or_else(|_| {
+                        eprintln!("invalid error format: {format} (expected human, json or sarif)");
+                        process::exit(1);
+                    });
+                }
+                "--invalidation-mode" => {
+                    let mode = args
+                        .next()
+                        .expect("the value of `--invalidation-mode` is not passed");
+                    cfg.invalidation_mode = PycInvalidationMode::try_from(&mode[..])
+                        .unwrap_or_else(|_| {
+                            eprintln!("invalid invalidation mode: {mode} (expected timestamp, checked-hash or unchecked-hash)");
+                            process::exit(1);
+                        });
+                }
+                "--json" => {
+                    cfg.json_output = true;
+                }
                 "--language-server" => {
                     cfg.mode = ErgMode::LanguageServer;
                 }
@@ -427,7 +471,16 @@ USAGE:
                 }
             }
         }
-        if cfg.input.is_repl() && cfg.mode != ErgMode::LanguageServer {
+        if cfg.input.is_repl()
+            && !matches!(
+                cfg.mode,
+                ErgMode::LanguageServer
+                    | ErgMode::Build
+                    | ErgMode::Bundle
+                    | ErgMode::Explain
+                    | ErgMode::Kernel
+            )
+        {
             let is_stdin_piped = !stdin().is_terminal();
             let input = if is_stdin_piped {
                 let mut buffer = String::new();
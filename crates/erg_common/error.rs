@@ -4,8 +4,12 @@
 use std::cmp::{self, Ordering};
 use std::fmt;
 use std::io::{stderr, BufWriter, Write as _};
+use std::sync::{Arc, Mutex};
+
+use crate::consts::SEMVER;
 
 use crate::io::{Input, InputKind};
+use crate::style::remove_style;
 use crate::style::Attribute;
 use crate::style::Characters;
 use crate::style::Color;
@@ -127,6 +131,11 @@ impl ErrorKind {
         (0..=59).contains(&(*self as u8)) || (100..=179).contains(&(*self as u8))
     }
 
+    /// e.g. `TypeError` -> `E0013` (see `erg explain`)
+    pub fn code(&self) -> String {
+        format!("E{:04}", *self as u8)
+    }
+
     pub fn is_exception(&self) -> bool {
         (200..=255).contains(&(*self as u8))
     }
@@ -896,6 +905,180 @@ impl ErrorCore {
     }
 }
 
+/// The SARIF results reported so far.
+/// This is shared between the clones of the same `ErgConfig`.
+#[derive(Debug, Clone, Default)]
+pub struct SarifLog(Arc<Mutex<Vec<String>>>);
+
+impl SarifLog {
+    pub fn push(&self, result: String) {
+        self.0.lock().unwrap().push(result);
+    }
+
+    pub fn take(&self) -> Vec<String> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+/// The output format of errors and warnings.
+/// * `human` (default): colored messages with code snippets
+/// * `json`: one JSON object per line
+/// * `sarif`: a SARIF 2.1.0 log, written by `ErrorFormat::flush_sarif_log` at the end of the process
+#[derive(Debug, Clone, Default)]
+pub enum ErrorFormat {
+    #[default]
+    Human,
+    Json,
+    Sarif(SarifLog),
+}
+
+impl TryFrom<&str> for ErrorFormat {
+    type Error = ();
+    fn try_from(s: &str) -> Result<Self, ()> {
+        match s {
+            "human" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            "sarif" => Ok(Self::Sarif(SarifLog::default())),
+            _ => Err(()),
+        }
+    }
+}
+
+impl ErrorFormat {
+    pub const fn is_human(&self) -> bool {
+        matches!(self, Self::Human)
+    }
+
+    /// Writes the SARIF log of the errors and warnings reported so far to `w`
+    /// (if the format is `sarif`).
+    pub fn flush_sarif_log(&self, w: &mut impl std::io::Write) {
+        let Self::Sarif(log) = self else {
+            return;
+        };
+        let results = log.take();
+        writeln!(
+            w,
+            "{{\"version\":\"2.1.0\",\"$schema\":\"https://json.schemastore.org/sarif-2.1.0.json\",\"runs\":[{{\"tool\":{{\"driver\":{{\"name\":\"erg\",\"version\":\"{SEMVER}\",\"informationUri\":\"https://erg-lang.org\"}}}},\"results\":[{}]}}]}}",
+            results.join(",")
+        )
+        .unwrap();
+    }
+}
+
+/// Returns `s` as a JSON string literal (with the quotes).
+pub fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn json_str_or_null(s: Option<&str>) -> String {
+    s.map_or("null".to_string(), |s| escape_json(&remove_style(s)))
+}
+
+impl Location {
+    /// lines are 1-origin, columns are 0-origin (same as `Location`)
+    /// ```
+    /// # use erg_common::error::Location;
+    /// let loc = Location::range(1, 0, 1, 3);
+    /// assert_eq!(loc.to_json(), r#"{"ln_begin":1,"col_begin":0,"ln_end":1,"col_end":3}"#);
+    /// assert_eq!(Location::Unknown.to_json(), "null");
+    /// ```
+    pub fn to_json(&self) -> String {
+        match *self {
+            Self::Range {
+                ln_begin,
+                col_begin,
+                ln_end,
+                col_end,
+            } => format!(
+                "{{\"ln_begin\":{ln_begin},\"col_begin\":{col_begin},\"ln_end\":{ln_end},\"col_end\":{col_end}}}"
+            ),
+            Self::LineRange(ln_begin, ln_end) => {
+                format!("{{\"ln_begin\":{ln_begin},\"ln_end\":{ln_end}}}")
+            }
+            Self::Line(ln) => format!("{{\"ln_begin\":{ln},\"ln_end\":{ln}}}"),
+            Self::Unknown => "null".to_string(),
+        }
+    }
+
+    /// SARIF `region` (columns are 1-origin and the end column is exclusive)
+    fn to_sarif_region(self) -> Option<String> {
+        match self {
+            Self::Range {
+                ln_begin,
+                col_begin,
+                ln_end,
+                col_end,
+            } => Some(format!(
+                "{{\"startLine\":{ln_begin},\"startColumn\":{},\"endLine\":{ln_end},\"endColumn\":{}}}",
+                col_begin + 1,
+                col_end + 1
+            )),
+            Self::LineRange(ln_begin, ln_end) => Some(format!(
+                "{{\"startLine\":{ln_begin},\"endLine\":{ln_end}}}"
+            )),
+            Self::Line(ln) => Some(format!("{{\"startLine\":{ln},\"endLine\":{ln}}}")),
+            Self::Unknown => None,
+        }
+    }
+}
+
+impl SubMessage {
+    /// e.g. `{"location":{...},"messages":["expected: Int"],"hint":null}`
+    pub fn to_json(&self) -> String {
+        let msgs = self
+            .msg
+            .iter()
+            .map(|msg| escape_json(&remove_style(msg)))
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            "{{\"location\":{},\"messages\":[{msgs}],\"hint\":{}}}",
+            self.loc.to_json(),
+            json_str_or_null(self.get_hint())
+        )
+    }
+}
+
+impl ErrorCore {
+    /// `"error"`, `"warning"` or `"exception"`
+    pub fn severity(&self) -> &'static str {
+        if self.kind.is_error() {
+            "error"
+        } else if self.kind.is_warning() {
+            "warning"
+        } else {
+            "exception"
+        }
+    }
+}
+
+fn sarif_location(path: &str, loc: Location, msg: Option<String>) -> String {
+    let region = loc
+        .to_sarif_region()
+        .map_or(String::new(), |region| format!(",\"region\":{region}"));
+    let msg = msg.map_or(String::new(), |msg| {
+        format!(",\"message\":{{\"text\":{}}}", escape_json(&msg))
+    });
+    format!(
+        "{{\"physicalLocation\":{{\"artifactLocation\":{{\"uri\":{}}}{region}}}{msg}}}",
+        escape_json(path)
+    )
+}
+
 /// format:
 /// ```txt
 /// Error[#{.errno}]: File {file}, line {.loc (as line)}, in {.caused_by}
@@ -937,11 +1120,25 @@ pub trait ErrorDisplay {
     }
 
     fn write_to<W: std::io::Write>(&self, w: &mut W) {
+        self.write_to_with(w, &ErrorFormat::Human)
+    }
+
+    fn write_to_stderr_with(&self, format: &ErrorFormat) {
+        let mut stderr = stderr();
+        self.write_to_with(&mut stderr, format)
+    }
+
+    /// `Sarif` results are not written to `w` but stored in the log of `format`.
+    fn write_to_with<W: std::io::Write>(&self, w: &mut W, format: &ErrorFormat) {
         let mut writer = BufWriter::new(w);
-        writer.write_all(self.show().as_bytes()).unwrap();
+        match format {
+            ErrorFormat::Human => writer.write_all(self.show().as_bytes()).unwrap(),
+            ErrorFormat::Json => writeln!(writer, "{}", self.to_json()).unwrap(),
+            ErrorFormat::Sarif(log) => log.push(self.to_sarif_result()),
+        }
         writer.flush().unwrap();
         if let Some(inner) = self.ref_inner() {
-            inner.write_to_stderr()
+            inner.write_to_stderr_with(format)
         }
     }
 
@@ -960,12 +1157,80 @@ pub trait ErrorDisplay {
             msg += &sub_msg.format_code_and_pointer(self, color, gutter_color, mark, chars);
         }
         msg += &core.kind.to_string();
+        if crate::explain::EXPLAINED_KINDS.contains(&core.kind) {
+            msg += &format!("[{}]", core.kind.code());
+        }
         msg += ": ";
         msg += &core.main_message;
         msg += "\n\n";
         msg
     }
 
+    /// e.g.
+    /// ```json
+    /// {"kind":"TypeError","code":"E0013","errno":1970,"severity":"error","path":"foo.er","caused_by":"<module>",
+    ///  "message":"...","hint":null,"location":{...},"sub_messages":[...]}
+    /// ```
+    fn to_json(&self) -> String {
+        let core = self.core();
+        let sub_msgs = core
+            .sub_messages
+            .iter()
+            .map(|sub| sub.to_json())
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            "{{\"kind\":\"{}\",\"code\":\"{}\",\"errno\":{},\"severity\":\"{}\",\"path\":{},\"caused_by\":{},\"message\":{},\"hint\":{},\"location\":{},\"sub_messages\":[{sub_msgs}]}}",
+            core.kind,
+            core.kind.code(),
+            core.errno,
+            core.severity(),
+            escape_json(&self.input().path().display().to_string()),
+            escape_json(self.caused_by()),
+            escape_json(&remove_style(&core.main_message)),
+            json_str_or_null(core.get_hint()),
+            core.get_loc_with_fallback().to_json(),
+        )
+    }
+
+    /// a SARIF `result` object
+    fn to_sarif_result(&self) -> String {
+        let core = self.core();
+        let path = self.input().path().display().to_string();
+        let level = if core.kind.is_warning() {
+            "warning"
+        } else {
+            "error"
+        };
+        let related = core
+            .sub_messages
+            .iter()
+            .filter(|sub| !sub.loc.is_unknown())
+            .map(|sub| {
+                let mut msgs = sub
+                    .msg
+                    .iter()
+                    .map(|msg| remove_style(msg))
+                    .collect::<Vec<_>>();
+                if let Some(hint) = sub.get_hint() {
+                    msgs.push(format!("hint: {}", remove_style(hint)));
+                }
+                let msg = (!msgs.is_empty()).then(|| msgs.join("\n"));
+                sarif_location(&path, sub.loc, msg)
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            "{{\"ruleId\":\"{}\",\"level\":\"{level}\",\"message\":{{\"text\":{}}},\"locations\":[{}],\"relatedLocations\":[{related}],\"properties\":{{\"kind\":\"{}\",\"errno\":{},\"causedBy\":{}}}}}",
+            core.kind.code(),
+            escape_json(&remove_style(&core.main_message)),
+            sarif_location(&path, core.get_loc_with_fallback(), None),
+            core.kind,
+            core.errno,
+            escape_json(self.caused_by()),
+        )
+    }
+
     /// for fmt::Display
     fn format(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let core = self.core();
@@ -1018,6 +1283,18 @@ pub trait MultiErrorDisplay<Item: ErrorDisplay>: Stream<Item> {
         }
     }
 
+    fn write_all_stderr_with(&self, format: &ErrorFormat) {
+        for err in self.iter() {
+            err.write_to_stderr_with(format);
+        }
+    }
+
+    fn write_all_to_with(&self, w: &mut impl std::io::Write, format: &ErrorFormat) {
+        for err in self.iter() {
+            err.write_to_with(w, format);
+        }
+    }
+
     fn fmt_all(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for err in self.iter() {
             err.format(f)?;
@@ -1025,3 +1302,86 @@ pub trait MultiErrorDisplay<Item: ErrorDisplay>: Stream<Item> {
         write!(f, "")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct TestError {
+        core: ErrorCore,
+        input: Input,
+    }
+
+    impl ErrorDisplay for TestError {
+        fn core(&self) -> &ErrorCore {
+            &self.core
+        }
+        fn input(&self) -> &Input {
+            &self.input
+        }
+        fn caused_by(&self) -> &str {
+            "<module>"
+        }
+        fn ref_inner(&self) -> Option<&Self> {
+            None
+        }
+    }
+
+    fn test_error() -> TestError {
+        let loc = Location::range(1, 4, 1, 7);
+        let sub_msg = SubMessage::ambiguous_new(
+            loc,
+            vec!["expected: Int".to_string()],
+            Some("use \"int\"".to_string()),
+        );
+        let core = ErrorCore::new(vec![sub_msg], "type mismatch", 1, TypeError, loc);
+        TestError {
+            core,
+            input: Input::file("foo.er".into()),
+        }
+    }
+
+    #[test]
+    fn error_to_json() {
+        let err = test_error();
+        assert_eq!(
+            err.to_json(),
+            r#"{"kind":"TypeError","code":"E0013","errno":1,"severity":"error","path":"foo.er","caused_by":"<module>","message":"type mismatch","hint":"use \"int\"","location":{"ln_begin":1,"col_begin":4,"ln_end":1,"col_end":7},"sub_messages":[{"location":{"ln_begin":1,"col_begin":4,"ln_end":1,"col_end":7},"messages":["expected: Int"],"hint":"use \"int\""}]}"#
+        );
+    }
+
+    #[test]
+    fn error_to_sarif_result() {
+        let err = test_error();
+        assert_eq!(
+            err.to_sarif_result(),
+            r#"{"ruleId":"E0013","level":"error","message":{"text":"type mismatch"},"locations":[{"physicalLocation":{"artifactLocation":{"uri":"foo.er"},"region":{"startLine":1,"startColumn":5,"endLine":1,"endColumn":8}}}],"relatedLocations":[{"physicalLocation":{"artifactLocation":{"uri":"foo.er"},"region":{"startLine":1,"startColumn":5,"endLine":1,"endColumn":8}},"message":{"text":"expected: Int\nhint: use \"int\""}}],"properties":{"kind":"TypeError","errno":1,"causedBy":"<module>"}}"#
+        );
+    }
+
+    #[test]
+    fn error_format_flush_sarif_log() {
+        let err = test_error();
+        let format = ErrorFormat::try_from("sarif").unwrap();
+        let mut out = vec![];
+        // SARIF results are stored in the log shared by the clones, not written
+        err.write_to_with(&mut out, &format.clone());
+        err.write_to_with(&mut out, &format);
+        assert!(out.is_empty());
+        format.flush_sarif_log(&mut out);
+        let result = err.to_sarif_result();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!("{{\"version\":\"2.1.0\",\"$schema\":\"https://json.schemastore.org/sarif-2.1.0.json\",\"runs\":[{{\"tool\":{{\"driver\":{{\"name\":\"erg\",\"version\":\"{SEMVER}\",\"informationUri\":\"https://erg-lang.org\"}}}},\"results\":[{result},{result}]}}]}}\n")
+        );
+        // the log is emptied by flushing
+        let mut out = vec![];
+        format.flush_sarif_log(&mut out);
+        assert!(String::from_utf8(out).unwrap().contains("\"results\":[]"));
+        // nothing is written in the other formats
+        let mut out = vec![];
+        ErrorFormat::Json.flush_sarif_log(&mut out);
+        assert!(out.is_empty());
+    }
+}
@@ -0,0 +1,380 @@
+//! provides the catalog of error codes shown by `erg explain`.
+//!
+//! `erg explain`で表示されるエラーコードの解説を提供する
+use crate::config::ErgConfig;
+use crate::error::ErrorKind::{self, *};
+use crate::switch_lang;
+use crate::traits::ExitStatus;
+
+/// The error kinds that have an entry in the catalog (compile errors and warnings).
+pub const EXPLAINED_KINDS: [ErrorKind; 33] = [
+    AssignError,
+    AttributeError,
+    BytecodeError,
+    CompilerSystemError,
+    EnvironmentError,
+    FeatureError,
+    ImportError,
+    IndentationError,
+    NameError,
+    NotImplementedError,
+    PatternError,
+    SyntaxError,
+    TabError,
+    TypeError,
+    UnboundLocalError,
+    PurityError,
+    HasEffect,
+    MoveError,
+    NotConstExpr,
+    InheritanceError,
+    VisibilityError,
+    MethodError,
+    AttributeWarning,
+    CastWarning,
+    DeprecationWarning,
+    FutureWarning,
+    ImportWarning,
+    PendingDeprecationWarning,
+    SyntaxWarning,
+    TypeWarning,
+    NameWarning,
+    UnusedWarning,
+    Warning,
+];
+
+/// Accepts `E0013`, `e13`, `13` and `TypeError`.
+/// ```
+/// # use erg_common::error::ErrorKind;
+/// # use erg_common::explain::parse_code;
+/// assert_eq!(parse_code("E0013"), Some(ErrorKind::TypeError));
+/// assert_eq!(parse_code("MoveError"), Some(ErrorKind::MoveError));
+/// assert_eq!(parse_code("E0100"), None);
+/// ```
+pub fn parse_code(code: &str) -> Option<ErrorKind> {
+    let code = code.trim();
+    let num = code
+        .strip_prefix(['E', 'e'])
+        .unwrap_or(code)
+        .parse::<u8>()
+        .ok();
+    EXPLAINED_KINDS
+        .iter()
+        .copied()
+        .find(|kind| Some(*kind as u8) == num || kind.to_string() == code)
+}
+
+fn description(kind: ErrorKind) -> Option<&'static str> {
+    let desc = match kind {
+        AssignError => switch_lang!(
+            "japanese" => "変数に2回以上代入しようとしました。Ergの変数はデフォルトで不変であり、一度しか代入できません。値を変更したい場合は可変オブジェクト(`!`)を使ってください。",
+            "simplified_chinese" => "试图对变量进行多次赋值。Erg 的变量默认是不可变的, 只能赋值一次。如果需要修改值, 请使用可变对象(`!`)。",
+            "traditional_chinese" => "試圖對變量進行多次賦值。Erg 的變量默認是不可變的, 只能賦值一次。如果需要修改值, 請使用可變對象(`!`)。",
+            "english" => "A variable was assigned more than once. Variables in Erg are immutable by default and can be assigned only once. Use a mutable object (`!`) if you want to change the value.",
+        ),
+        AttributeError => switch_lang!(
+            "japanese" => "オブジェクトに存在しない属性にアクセスしようとしました。属性名のスペルと、オブジェクトの型を確認してください。",
+            "simplified_chinese" => "试图访问对象上不存在的属性。请检查属性名的拼写和对象的类型。",
+            "traditional_chinese" => "試圖訪問對象上不存在的屬性。請檢查屬性名的拼寫和對象的類型。",
+            "english" => "An attribute that does not exist on the object was accessed. Check the spelling of the attribute and the type of the object.",
+        ),
+        BytecodeError => switch_lang!(
+            "japanese" => "Pythonバイトコードの生成または読み込みに失敗しました。対象のPythonバージョンがサポートされているか(`--python-version`)、.pycファイルが壊れていないか確認してください。",
+            "simplified_chinese" => "生成或读取 Python 字节码失败。请检查目标 Python 版本是否受支持(`--python-version`), 以及 .pyc 文件是否已损坏。",
+            "traditional_chinese" => "生成或讀取 Python 字節碼失敗。請檢查目標 Python 版本是否受支持(`--python-version`), 以及 .pyc 文件是否已損壞。",
+            "english" => "Failed to generate or read Python bytecode. Check that the target Python version is supported (`--python-version`) and that the .pyc file is not corrupted.",
+        ),
+        CompilerSystemError => switch_lang!(
+            "japanese" => "コンパイラの内部エラーです。これはErgのバグなので、再現するコードと共に https://github.com/erg-lang/erg に報告してください。",
+            "simplified_chinese" => "编译器内部错误。这是 Erg 的 bug, 请将可以复现的代码报告给 https://github.com/erg-lang/erg 。",
+            "traditional_chinese" => "編譯器內部錯誤。這是 Erg 的 bug, 請將可以復現的代碼報告給 https://github.com/erg-lang/erg 。",
+            "english" => "An internal error of the compiler. This is a bug of Erg, please report it with the code to reproduce it to https://github.com/erg-lang/erg.",
+        ),
+        EnvironmentError => switch_lang!(
+            "japanese" => "実行環境に問題があります。Pythonがインストールされているか、Ergの標準ライブラリ(`~/.erg`)が正しくインストールされているか確認してください。",
+            "simplified_chinese" => "运行环境有问题。请检查是否安装了 Python, 以及 Erg 的标准库(`~/.erg`)是否安装正确。",
+            "traditional_chinese" => "運行環境有問題。請檢查是否安裝了 Python, 以及 Erg 的標準庫(`~/.erg`)是否安裝正確。",
+            "english" => "There is a problem with the environment. Check that Python is installed and that the standard library of Erg (`~/.erg`) is installed correctly.",
+        ),
+        FeatureError => switch_lang!(
+            "japanese" => "この機能はまだ実装されていないか、現在のビルドでは有効になっていません。",
+            "simplified_chinese" => "此功能尚未实现, 或者在当前构建中未启用。",
+            "traditional_chinese" => "此功能尚未實現, 或者在當前構建中未啟用。",
+            "english" => "This feature is not implemented yet, or not enabled in the current build.",
+        ),
+        ImportError => switch_lang!(
+            "japanese" => "モジュールが見つからないか、読み込めませんでした。モジュール名と、ファイルがインポート元から見える場所にあるか確認してください。",
+            "simplified_chinese" => "找不到模块或无法加载模块。请检查模块名, 以及文件是否位于可以导入的位置。",
+            "traditional_chinese" => "找不到模塊或無法加載模塊。請檢查模塊名, 以及文件是否位於可以導入的位置。",
+            "english" => "The module was not found or could not be loaded. Check the module name and that the file is located where it can be imported.",
+        ),
+        IndentationError => switch_lang!(
+            "japanese" => "インデントが不正です。同じブロックの行は同じ深さでインデントしてください。",
+            "simplified_chinese" => "缩进不正确。同一块中的行必须以相同的深度缩进。",
+            "traditional_chinese" => "縮進不正確。同一塊中的行必須以相同的深度縮進。",
+            "english" => "The indentation is invalid. Lines in the same block must be indented to the same depth.",
+        ),
+        NameError => switch_lang!(
+            "japanese" => "定義されていない名前を使用したか、すでに宣言された名前を再定義しようとしました。名前のスペルと、定義の位置を確認してください。",
+            "simplified_chinese" => "使用了未定义的名称, 或者试图重新定义已经声明的名称。请检查名称的拼写和定义的位置。",
+            "traditional_chinese" => "使用了未定義的名稱, 或者試圖重新定義已經聲明的名稱。請檢查名稱的拼寫和定義的位置。",
+            "english" => "An undefined name was used, or an already declared name was redefined. Check the spelling of the name and where it is defined.",
+        ),
+        NotImplementedError => switch_lang!(
+            "japanese" => "この処理はコンパイラにまだ実装されていません。",
+            "simplified_chinese" => "编译器尚未实现此处理。",
+            "traditional_chinese" => "編譯器尚未實現此處理。",
+            "english" => "This operation is not implemented in the compiler yet.",
+        ),
+        PatternError => switch_lang!(
+            "japanese" => "パターンが不正です。代入や引数の左辺には、変数・リテラル・配列などのパターンのみ書けます。",
+            "simplified_chinese" => "模式不正确。赋值或参数的左侧只能写变量、字面量、数组等模式。",
+            "traditional_chinese" => "模式不正確。賦值或參數的左側只能寫變量、字面量、數組等模式。",
+            "english" => "The pattern is invalid. Only patterns such as variables, literals and arrays can be written on the left side of assignments and parameters.",
+        ),
+        SyntaxError => switch_lang!(
+            "japanese" => "構文が不正です。エラー箇所の前後で、括弧の対応・演算子の被演算子・改行の位置を確認してください。",
+            "simplified_chinese" => "语法不正确。请检查错误位置前后的括号匹配、运算符的操作数以及换行的位置。",
+            "traditional_chinese" => "語法不正確。請檢查錯誤位置前後的括號匹配、運算符的操作數以及換行的位置。",
+            "english" => "The syntax is invalid. Check the matching of the brackets, the operands of the operators and the position of the line breaks around the error.",
+        ),
+        TabError => switch_lang!(
+            "japanese" => "インデントにタブ文字が使われています。Ergではインデントにスペースを使ってください。",
+            "simplified_chinese" => "缩进中使用了制表符。Erg 的缩进请使用空格。",
+            "traditional_chinese" => "縮進中使用了制表符。Erg 的縮進請使用空格。",
+            "english" => "A tab character is used for indentation. Use spaces for indentation in Erg.",
+        ),
+        TypeError => switch_lang!(
+            "japanese" => "値の型が期待される型と一致しません。Ergは暗黙の型変換を行わないので、型を揃えるか、明示的に変換してください。",
+            "simplified_chinese" => "值的类型与期望的类型不匹配。Erg 不会进行隐式类型转换, 请统一类型或进行显式转换。",
+            "traditional_chinese" => "值的類型與期望的類型不匹配。Erg 不會進行隱式類型轉換, 請統一類型或進行顯式轉換。",
+            "english" => "The type of the value does not match the expected type. Erg does not convert types implicitly, so make the types consistent or convert the value explicitly.",
+        ),
+        UnboundLocalError => switch_lang!(
+            "japanese" => "ローカル変数が、値を代入される前に参照されました。",
+            "simplified_chinese" => "局部变量在赋值之前被引用。",
+            "traditional_chinese" => "局部變量在賦值之前被引用。",
+            "english" => "A local variable was referenced before it was assigned.",
+        ),
+        PurityError => switch_lang!(
+            "japanese" => "関数の純粋性に違反しています。関数(`!`のない名前)は副作用を持つことができません。",
+            "simplified_chinese" => "违反了函数的纯粹性。函数(没有`!`的名称)不能有副作用。",
+            "traditional_chinese" => "違反了函數的純粹性。函數(沒有`!`的名稱)不能有副作用。",
+            "english" => "The purity of a function is violated. Functions (names without `!`) cannot have side-effects.",
+        ),
+        HasEffect => switch_lang!(
+            "japanese" => "副作用のある式(プロシージャの呼び出しなど)が、関数や通常の変数など副作用を許可しない場所で使われました。副作用が必要な場合は、名前の末尾に`!`をつけてプロシージャとして定義してください。",
+            "simplified_chinese" => "有副作用的表达式(例如过程调用)被用在不允许副作用的地方, 例如函数或普通变量。如果需要副作用, 请在名称末尾加上`!`将其定义为过程。",
+            "traditional_chinese" => "有副作用的表達式(例如過程調用)被用在不允許副作用的地方, 例如函數或普通變量。如果需要副作用, 請在名稱末尾加上`!`將其定義為過程。",
+            "english" => "An expression with side-effects (e.g. a procedure call) is used where side-effects are not allowed, such as in a function or a normal variable. If you need side-effects, add `!` to the end of the name to define it as a procedure.",
+        ),
+        MoveError => switch_lang!(
+            "japanese" => "所有権がすでに移動したオブジェクトを使用しました。可変オブジェクトは代入や引数渡しで所有権が移動します。移動後も使いたい場合は`.copy()`するか、参照(`Ref`)として渡してください。",
+            "simplified_chinese" => "使用了所有权已经移动的对象。可变对象在赋值或作为参数传递时会移动所有权。如果移动后还要使用, 请`.copy()`或作为引用(`Ref`)传递。",
+            "traditional_chinese" => "使用了所有權已經移動的對象。可變對象在賦值或作為參數傳遞時會移動所有權。如果移動後還要使用, 請`.copy()`或作為引用(`Ref`)傳遞。",
+            "english" => "An object whose ownership has already been moved was used. The ownership of a mutable object is moved by assignment or by passing it as an argument. Use `.copy()` or pass it as a reference (`Ref`) if you want to use it after the move.",
+        ),
+        NotConstExpr => switch_lang!(
+            "japanese" => "コンパイル時に評価できない式が、定数式が必要な場所(型引数、定数の定義など)で使われました。",
+            "simplified_chinese" => "在需要常量表达式的地方(类型参数、常量定义等)使用了无法在编译时求值的表达式。",
+            "traditional_chinese" => "在需要常量表達式的地方(類型參數、常量定義等)使用了無法在編譯時求值的表達式。",
+            "english" => "An expression that cannot be evaluated at compile time is used where a constant expression is required (type arguments, constant definitions, etc.).",
+        ),
+        InheritanceError => switch_lang!(
+            "japanese" => "継承できないクラスを継承しようとしました。Ergのクラスはデフォルトで継承不可能です。継承を許可するには`@Inheritable`デコレータをつけてください。",
+            "simplified_chinese" => "试图继承不可继承的类。Erg 的类默认不可继承。如果要允许继承, 请添加`@Inheritable`装饰器。",
+            "traditional_chinese" => "試圖繼承不可繼承的類。Erg 的類默認不可繼承。如果要允許繼承, 請添加`@Inheritable`裝飾器。",
+            "english" => "A class that cannot be inherited was inherited. Classes in Erg are not inheritable by default. Add the `@Inheritable` decorator to allow inheritance.",
+        ),
+        VisibilityError => switch_lang!(
+            "japanese" => "非公開の変数・属性に外部からアクセスしようとしました。モジュールやクラスの外から参照したい場合は、名前の前に`.`をつけて公開してください。",
+            "simplified_chinese" => "试图从外部访问私有变量或属性。如果要从模块或类的外部引用, 请在名称前加上`.`将其公开。",
+            "traditional_chinese" => "試圖從外部訪問私有變量或屬性。如果要從模塊或類的外部引用, 請在名稱前加上`.`將其公開。",
+            "english" => "A private variable or attribute was accessed from the outside. Add `.` before the name to make it public if you want to refer to it from outside the module or class.",
+        ),
+        MethodError => switch_lang!(
+            "japanese" => "メソッドを定義できない対象にメソッドを定義しようとしました。メソッドは同じモジュールで定義されたクラスにのみ定義できます。",
+            "simplified_chinese" => "试图在无法定义方法的对象上定义方法。方法只能定义在同一模块中定义的类上。",
+            "traditional_chinese" => "試圖在無法定義方法的對象上定義方法。方法只能定義在同一模塊中定義的類上。",
+            "english" => "A method was defined on an object that cannot have methods. Methods can only be defined on classes defined in the same module.",
+        ),
+        AttributeWarning => switch_lang!(
+            "japanese" => "属性の使い方に問題がある可能性があります。",
+            "simplified_chinese" => "属性的使用方式可能有问题。",
+            "traditional_chinese" => "屬性的使用方式可能有問題。",
+            "english" => "The attribute may be used incorrectly.",
+        ),
+        CastWarning => switch_lang!(
+            "japanese" => "型のキャストが失敗する、あるいは不要である可能性があります。",
+            "simplified_chinese" => "类型转换可能会失败, 或者是不必要的。",
+            "traditional_chinese" => "類型轉換可能會失敗, 或者是不必要的。",
+            "english" => "The type cast may fail or be unnecessary.",
+        ),
+        DeprecationWarning => switch_lang!(
+            "japanese" => "非推奨となったAPIを使用しています。将来のバージョンで削除される可能性があります。",
+            "simplified_chinese" => "使用了已弃用的 API。它可能会在将来的版本中被删除。",
+            "traditional_chinese" => "使用了已棄用的 API。它可能會在將來的版本中被刪除。",
+            "english" => "A deprecated API is used. It may be removed in a future version.",
+        ),
+        FutureWarning => switch_lang!(
+            "japanese" => "将来のバージョンで動作が変わる機能を使用しています。",
+            "simplified_chinese" => "使用了在将来的版本中行为会改变的功能。",
+            "traditional_chinese" => "使用了在將來的版本中行為會改變的功能。",
+            "english" => "A feature whose behavior will change in a future version is used.",
+        ),
+        ImportWarning => switch_lang!(
+            "japanese" => "モジュールのインポートに問題がある可能性があります。",
+            "simplified_chinese" => "模块的导入可能有问题。",
+            "traditional_chinese" => "模塊的導入可能有問題。",
+            "english" => "There may be a problem with the import of the module.",
+        ),
+        PendingDeprecationWarning => switch_lang!(
+            "japanese" => "将来非推奨になる予定のAPIを使用しています。",
+            "simplified_chinese" => "使用了将来计划弃用的 API。",
+            "traditional_chinese" => "使用了將來計劃棄用的 API。",
+            "english" => "An API that will be deprecated in the future is used.",
+        ),
+        SyntaxWarning => switch_lang!(
+            "japanese" => "構文は正しいですが、意図しない動作をする可能性があります。",
+            "simplified_chinese" => "语法正确, 但可能会产生意外的行为。",
+            "traditional_chinese" => "語法正確, 但可能會產生意外的行為。",
+            "english" => "The syntax is valid, but it may not behave as intended.",
+        ),
+        TypeWarning => switch_lang!(
+            "japanese" => "型に関する問題がある可能性があります(不要な型変数、推論された戻り値型が単一でないなど)。型を明示すると解消できることがあります。",
+            "simplified_chinese" => "可能存在类型相关的问题(不必要的类型变量、推断的返回类型不唯一等)。显式指定类型可能会解决此问题。",
+            "traditional_chinese" => "可能存在類型相關的問題(不必要的類型變量、推斷的返回類型不唯一等)。顯式指定類型可能會解決此問題。",
+            "english" => "There may be a problem with the types (unnecessary type variables, the inferred return type is not unique, etc.). Specifying the types explicitly may solve it.",
+        ),
+        NameWarning => switch_lang!(
+            "japanese" => "組み込みの名前や他の属性と同じ名前が使われているため、混乱を招く可能性があります。",
+            "simplified_chinese" => "使用了与内置名称或其他属性相同的名称, 可能会引起混淆。",
+            "traditional_chinese" => "使用了與內置名稱或其他屬性相同的名稱, 可能會引起混淆。",
+            "english" => "The name is the same as a built-in name or another attribute, which may be confusing.",
+        ),
+        UnusedWarning => switch_lang!(
+            "japanese" => "定義された変数・引数・式の値が使われていません。意図的に使わない場合は、名前を`_`にしてください。前のアームで網羅済みのため到達しない`match`のアームもこの警告になります。",
+            "simplified_chinese" => "定义的变量、参数或表达式的值没有被使用。如果是有意不使用, 请将名称改为`_`。已被前面的分支涵盖而永远不会执行的`match`分支也会产生此警告。",
+            "traditional_chinese" => "定義的變量、參數或表達式的值沒有被使用。如果是有意不使用, 請將名稱改為`_`。已被前面的分支涵蓋而永遠不會執行的`match`分支也會產生此警告。",
+            "english" => "The defined variable, parameter or value of the expression is not used. Name it `_` if it is intentionally unused. A `match` arm already covered by the preceding arms is also reported as unused.",
+        ),
+        Warning => switch_lang!(
+            "japanese" => "一般的な警告です。",
+            "simplified_chinese" => "一般警告。",
+            "traditional_chinese" => "一般警告。",
+            "english" => "A general warning.",
+        ),
+        _ => return None,
+    };
+    Some(desc)
+}
+
+fn example(kind: ErrorKind) -> Option<&'static str> {
+    let example = match kind {
+        AssignError => {
+            "\
+x = 1
+x = 2 # AssignError
+
+y = !1
+y.update! _ -> 2 # OK"
+        }
+        AttributeError => {
+            "\
+print! \"a\".foo # AttributeError
+
+print! \"a\".upper() # OK"
+        }
+        ImportError => "foo = import \"no_such_module\" # ImportError",
+        NameError => {
+            "\
+print! y # NameError
+
+y = 1
+print! y # OK"
+        }
+        SyntaxError => "x = 1 + # SyntaxError",
+        TypeError => {
+            "\
+x: Int = \"a\" # TypeError
+
+x: Int = int(\"1\") # OK"
+        }
+        HasEffect => {
+            "\
+f x = print! x # HasEffect
+
+f! x = print! x # OK"
+        }
+        MoveError => {
+            "\
+x = ![1]
+y = x
+print! x # MoveError
+
+print! y # OK"
+        }
+        InheritanceError => {
+            "\
+C = Class()
+D = Inherit C # InheritanceError
+
+@Inheritable
+E = Class()
+F = Inherit E # OK"
+        }
+        VisibilityError => {
+            "\
+# foo.er
+_private = 1
+.public = 1
+
+# main.er
+foo = import \"foo\"
+print! foo._private # VisibilityError
+print! foo.public # OK"
+        }
+        UnusedWarning => {
+            "\
+f x = 1 # UnusedWarning
+g _ = 1 # OK"
+        }
+        _ => return None,
+    };
+    Some(example)
+}
+
+/// Returns the long-form explanation of the error kind, or `None` if it is not in the catalog.
+pub fn explain(kind: ErrorKind) -> Option<String> {
+    let desc = description(kind)?;
+    let mut explanation = format!("{}: {kind}\n\n{desc}\n", kind.code());
+    if let Some(example) = example(kind) {
+        let header = switch_lang!(
+            "japanese" => "例:",
+            "simplified_chinese" => "示例:",
+            "traditional_chinese" => "示例:",
+            "english" => "Example:",
+        );
+        explanation += &format!("\n{header}\n\n```erg\n{example}\n```\n");
+    }
+    Some(explanation)
+}
+
+/// `erg explain <code>`
+pub fn run(cfg: ErgConfig) -> ExitStatus {
+    let code = cfg.input.filename();
+    if cfg.input.is_repl() {
+        eprintln!("usage: erg explain <code> (e.g. `erg explain E0013`)");
+        return ExitStatus::ERR1;
+    }
+    match parse_code(&code).and_then(explain) {
+        Some(explanation) => {
+            println!("{explanation}");
+            ExitStatus::OK
+        }
+        None => {
+            eprintln!("no explanation for the error code: {code}");
+            ExitStatus::ERR1
+        }
+    }
+}
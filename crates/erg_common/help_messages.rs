@@ -20,6 +20,9 @@ OPTIONS
     --python-version/-p (uint 32 number) Pythonバージョンを指定
     --py-server-timeout (uint 64 number) PythonのREPLサーバーのタイムアウト時間を指定
     --dump-as-pyc                        .pycファイルにダンプ
+    --error-format human|json|sarif      エラーの出力形式を指定
+    --invalidation-mode (mode)           .pycファイルの検証方法を指定(timestamp|checked-hash|unchecked-hash)
+    --json                               readモードの結果をJSONで出力
     --mode (mode)                        指定モードで実行(詳細は--mode --helpを参照)
     --code/-c (string)                   文字列として渡したプログラムを実行
     --module/-m (string)                 モジュールを実行
@@ -30,9 +33,14 @@ COMMAND
     typecheck|tc                         型検査
     check                                全ての検査(所有権検査, 副作用検査などを含む)
     compile                              コンパイル
+    build                                パッケージ全体をビルド
+    bundle <file>                        実行可能な.pyzファイルにまとめる
+    read <file>.pyc                      .pycファイルを逆アセンブル
+    explain <code>                       エラーコードの解説を表示
     transpile                            トランスパイル
     run|exec                             実行(デフォルト)
-    server                               言語サーバーを起動",
+    server                               言語サーバーを起動
+    kernel [<connection file>]           Jupyterカーネルを起動(ファイル省略時はkernel.jsonを出力)",
 
     "simplified_chinese" =>
     "\
@@ -51,6 +59,9 @@ OPTIONS
     --python-version/-p (uint 32 number) Python 版本
     --py-server-timeout (uint 64 number) 指定等待 REPL 输出的秒数
     --dump-as-pyc                        转储为 .pyc 文件
+    --error-format human|json|sarif      指定错误的输出格式
+    --invalidation-mode (mode)           指定 .pyc 文件的验证方式(timestamp|checked-hash|unchecked-hash)
+    --json                               以 JSON 格式输出 read 模式的结果
     --mode (mode)                        执行模式 (更多信息见`--mode --help`)
     --code/-c (string)                   作为字符串传入程序
     --module/-m (string)                 要执行的模块
@@ -61,9 +72,14 @@ COMMAND
     typecheck|tc                         类型检查
     check                                全部检查(包括所有权检查, 副作用检查等)
     compile                              编译
+    build                                构建整个包
+    bundle <file>                        打包为可执行的 .pyz 文件
+    read <file>.pyc                      反汇编 .pyc 文件
+    explain <code>                       显示错误代码的说明
     transpile                            转译
     run|exec                             执行(默认模式)
-    server                               执行语言服务器",
+    server                               执行语言服务器
+    kernel [<connection file>]           执行 Jupyter 内核(省略文件时输出 kernel.json)",
 
     "traditional_chinese" =>
         "\
@@ -82,6 +98,9 @@ OPTIONS
     --python-version/-p (uint 32 number) Python 版本
     --py-server-timeout (uint 64 number) 指定等待 REPL 輸出的秒數
     --dump-as-pyc                        轉儲為 .pyc 文件
+    --error-format human|json|sarif      指定錯誤的輸出格式
+    --invalidation-mode (mode)           指定 .pyc 文件的驗證方式(timestamp|checked-hash|unchecked-hash)
+    --json                               以 JSON 格式輸出 read 模式的結果
     --mode (mode)                        執行模式 (更多信息見`--mode --help`)
     --code/-c (string)                   作為字串傳入程式
     --module/-m (string)                 要執行的模塊
@@ -92,9 +111,14 @@ COMMAND
     typecheck|tc                         型檢查
     check                                全部檢查(包括所有權檢查, 副作用檢查等)
     compile                              編譯
+    build                                構建整個包
+    bundle <file>                        打包為可執行的 .pyz 檔案
+    read <file>.pyc                      反組譯 .pyc 檔案
+    explain <code>                       顯示錯誤代碼的說明
     transpile                            轉譯
     run|exec                             執行(預設模式)
-    server                               執行語言伺服器",
+    server                               執行語言伺服器
+    kernel [<connection file>]           執行 Jupyter 內核(省略檔案時輸出 kernel.json)",
 
     "english" =>
         "\
@@ -113,6 +137,9 @@ OPTIONS
     --python-version/-p (uint 32 number) Python version
     --py-server-timeout (uint 64 number) timeout for the Python REPL server
     --dump-as-pyc                        dump as .pyc file
+    --error-format human|json|sarif      specify the output format of errors
+    --invalidation-mode (mode)           specify how .pyc files are validated (timestamp|checked-hash|unchecked-hash)
+    --json                               print the result of read mode as JSON
     --mode (mode)                        execution mode (See `--mode --help` for details)
     --code/-c (string)                   program passed in as string
     --module/-m (string)                 module to be executed
@@ -123,9 +150,14 @@ COMMAND
     typecheck|tc                         type check
     check                                full check (including ownership check, effect check, etc.)
     compile                              compile
+    build                                build the whole package
+    bundle <file>                        package the program into an executable .pyz file
+    read <file>.pyc                      disassemble the .pyc file
+    explain <code>                       show the explanation of the error code
     transpile                            transpile
     run|exec                             execute (default mode)
-    server                               execute language server",
+    server                               execute language server
+    kernel [<connection file>]           execute Jupyter kernel (print kernel.json if the file is omitted)",
     )
 }
 
@@ -164,7 +196,8 @@ run/exec
     compileを実行し、更に<filename>.pycを実行
 
 read
-    <filename>.pycをデシリアライズしコードオブジェクトの情報をダンプ",
+    <filename>.pycをデシリアライズしコードオブジェクトの情報をダンプ
+    --jsonを指定するとJSONで出力",
 
     "simplified_chinese" =>
     "\
@@ -200,7 +233,8 @@ run/exec
     在执行 <文件名>.pyc 后删除 <文件名>.pyc
 
 read
-    反序列化 <文件名>.pyc 和 dump",
+    反序列化 <文件名>.pyc 和 dump
+    指定 --json 时以 JSON 格式输出",
 
     "traditional_chinese" =>
     "\
@@ -236,7 +270,8 @@ exec
     在執行 <檔名>.pyc 後删除 <檔名>.pyc
 
 read
-    反序列化 <檔名>.pyc 和 dump",
+    反序列化 <檔名>.pyc 和 dump
+    指定 --json 時以 JSON 格式輸出",
 
     "english" =>
     "\
@@ -271,7 +306,8 @@ run/exec
     Execute compile and then <filename>.pyc
 
 read
-    Deserialize <filename>.pyc and dump code object information",
+    Deserialize <filename>.pyc and dump code object information
+    With --json, the result is printed as JSON",
     )
 }
 
@@ -283,6 +319,7 @@ pub const OPTIONS: &[&str] = &[
     "--compile",
     "--dest",
     "--dump-as-pyc",
+    "--error-format",
     "--language-server",
     "--no-std",
     "--help",
@@ -290,6 +327,8 @@ pub const OPTIONS: &[&str] = &[
     "-h",
     "--hex-py-magic-num",
     "--hex-python-magic-number",
+    "--invalidation-mode",
+    "--json",
     "--mode",
     "--module",
     "-m",
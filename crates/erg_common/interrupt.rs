@@ -0,0 +1,123 @@
+//! Catches Ctrl+C (SIGINT) instead of terminating the process.
+//! Used by the REPL to interrupt the code running on the Python server.
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+/// the number of living `InterruptGuard`s
+static GUARDS: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(unix)]
+mod sys {
+    use super::*;
+
+    const SIGINT: i32 = 2;
+    const SIG_DFL: usize = 0;
+
+    extern "C" {
+        fn signal(signum: i32, handler: usize) -> usize;
+    }
+
+    extern "C" fn on_interrupt(_signum: i32) {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+    }
+
+    pub(super) fn catch() {
+        unsafe {
+            signal(SIGINT, on_interrupt as extern "C" fn(i32) as usize);
+        }
+    }
+
+    pub(super) fn restore() {
+        unsafe {
+            signal(SIGINT, SIG_DFL);
+        }
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use super::*;
+
+    const CTRL_C_EVENT: u32 = 0;
+
+    type Handler = unsafe extern "system" fn(u32) -> i32;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetConsoleCtrlHandler(handler: Option<Handler>, add: i32) -> i32;
+    }
+
+    unsafe extern "system" fn on_interrupt(ctrl_type: u32) -> i32 {
+        if ctrl_type == CTRL_C_EVENT {
+            INTERRUPTED.store(true, Ordering::SeqCst);
+            1
+        } else {
+            0
+        }
+    }
+
+    pub(super) fn catch() {
+        unsafe {
+            SetConsoleCtrlHandler(Some(on_interrupt), 1);
+        }
+    }
+
+    pub(super) fn restore() {
+        unsafe {
+            SetConsoleCtrlHandler(Some(on_interrupt), 0);
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod sys {
+    pub(super) fn catch() {}
+    pub(super) fn restore() {}
+}
+
+/// While this is alive, Ctrl+C does not terminate the process but is recorded.
+/// Guards can be nested; the handler is restored when the last one is dropped.
+///
+/// ```no_run
+/// # use erg_common::interrupt::InterruptGuard;
+/// let guard = InterruptGuard::new();
+/// while !guard.interrupted() {
+///     // wait for something
+/// }
+/// ```
+#[derive(Debug)]
+pub struct InterruptGuard(());
+
+impl Default for InterruptGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for InterruptGuard {
+    fn drop(&mut self) {
+        if GUARDS.fetch_sub(1, Ordering::SeqCst) == 1 {
+            sys::restore();
+        }
+    }
+}
+
+impl InterruptGuard {
+    pub fn new() -> Self {
+        INTERRUPTED.store(false, Ordering::SeqCst);
+        if GUARDS.fetch_add(1, Ordering::SeqCst) == 0 {
+            sys::catch();
+        }
+        Self(())
+    }
+
+    /// Returns whether Ctrl+C has been pressed since the last call.
+    pub fn interrupted(&self) -> bool {
+        INTERRUPTED.swap(false, Ordering::SeqCst)
+    }
+}
+
+/// Records an interruption as if Ctrl+C was pressed (e.g. when requested by a Jupyter client).
+pub fn interrupt() {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
@@ -12,7 +12,7 @@ use crate::env::{
 use crate::pathutil::{add_postfix_foreach, remove_postfix};
 use crate::python_util::get_sys_path;
 use crate::random::random;
-use crate::stdin::GLOBAL_STDIN;
+use crate::stdin::{LineEditorHelper, GLOBAL_STDIN};
 use crate::{normalize_path, power_assert, read_file};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -303,6 +303,14 @@ impl Input {
         }
     }
 
+    /// Same as `read`, but the line editor of the REPL uses `helper` (completion, highlighting, etc.).
+    pub fn read_with_helper(&mut self, helper: &mut dyn LineEditorHelper) -> String {
+        match &self.kind {
+            InputKind::REPL => GLOBAL_STDIN.read_with_helper(helper),
+            _ => self.read(),
+        }
+    }
+
     pub fn try_read(&mut self) -> std::io::Result<String> {
         match &mut self.kind {
             InputKind::File(filename) => {
@@ -10,9 +10,11 @@ pub mod dict;
 pub mod env;
 pub mod erg_util;
 pub mod error;
+pub mod explain;
 pub mod fresh;
 pub mod fxhash;
 pub mod help_messages;
+pub mod interrupt;
 pub mod io;
 pub mod lang;
 pub mod levenshtein;
@@ -95,6 +95,14 @@ macro_rules! impl_u8_enum {
             pub const fn take_arg(&self) -> bool {
                 90 <= (*self as u8) && (*self as u8) < 220
             }
+
+            /// Unlike `From<u8>`, this does not panic for unknown bytes.
+            pub const fn try_from_u8(byte: u8) -> Option<Self> {
+                match byte {
+                    $($val => Some($Enum::$Variant),)*
+                    _ => None,
+                }
+            }
         }
     };
     ($Enum: ident; $size: tt; $($Variant: ident = $val: expr $(,)?)*) => {
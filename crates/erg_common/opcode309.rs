@@ -58,6 +58,8 @@ impl_u8_enum! {Opcode309;
     GET_YIELD_FROM_ITER = 69,
     PRINT_EXPR = 70,
     LOAD_BUILD_CLASS = 71,
+    YIELD_FROM = 72,
+    GET_AWAITABLE = 73,
     // LOAD_ASSERTION_ERROR = 74,
     // WITH_CLEANUP_START = 81,
     WITH_CLEANUP_FINISH = 82,
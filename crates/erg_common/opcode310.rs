@@ -57,6 +57,8 @@ impl_u8_enum! {Opcode310;
     GET_YIELD_FROM_ITER = 69,
     PRINT_EXPR = 70,
     LOAD_BUILD_CLASS = 71,
+    YIELD_FROM = 72,
+    GET_AWAITABLE = 73,
     LOAD_ASSERTION_ERROR = 74,
     LIST_TO_TUPLE = 82,
     RETURN_VALUE = 83,
@@ -96,6 +98,7 @@ impl_u8_enum! {Opcode310;
     LOAD_FAST = 124,
     STORE_FAST = 125,
     DELETE_FAST = 126,
+    GEN_START = 129,
     RAISE_VARARGS = 130,
     CALL_FUNCTION = 131,
     MAKE_FUNCTION = 132,
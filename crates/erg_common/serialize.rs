@@ -7,9 +7,10 @@ use crate::Str;
 
 /* Python bytecode specification */
 // 0~3 byte: magic number
-// 4~7 byte: padding (0;4)
-// 8~B byte: UNIX timestamp
-// C~F byte: padding (0;4)
+// 4~7 byte: flags (0: timestamp-based, 0b01: unchecked hash-based, 0b11: checked hash-based)
+// 8~B byte: UNIX timestamp (timestamp-based)
+// C~F byte: padding (0;4) (timestamp-based)
+// 8~F byte: SipHash of the source (hash-based)
 // 10~ byte: marshalled code objects
 // the unary magic number of Python bytecode
 // magic number = version number (2byte) + 168624128 (0x0A0D0000)
@@ -45,6 +46,145 @@ pub fn get_timestamp_bytes() -> [u8; 4] {
     secs.to_le_bytes()
 }
 
+/// How Python checks whether a `.pyc` file is up to date (PEP 552).
+/// * `timestamp`: the header contains the time of the compilation
+/// * `checked-hash`: the header contains the hash of the source, and Python checks it
+/// * `unchecked-hash`: the header contains the hash of the source, but Python does not check it
+///
+/// The hash-based modes generate the same `.pyc` file from the same source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PycInvalidationMode {
+    Timestamp,
+    CheckedHash,
+    UncheckedHash,
+}
+
+impl_display_from_debug!(PycInvalidationMode);
+
+impl TryFrom<&str> for PycInvalidationMode {
+    type Error = ();
+    fn try_from(s: &str) -> Result<Self, ()> {
+        match s {
+            "timestamp" => Ok(Self::Timestamp),
+            "checked-hash" => Ok(Self::CheckedHash),
+            "unchecked-hash" => Ok(Self::UncheckedHash),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Same as `py_compile`: `checked-hash` if `SOURCE_DATE_EPOCH` is set, `timestamp` otherwise
+impl Default for PycInvalidationMode {
+    fn default() -> Self {
+        if std::env::var_os("SOURCE_DATE_EPOCH").is_some() {
+            Self::CheckedHash
+        } else {
+            Self::Timestamp
+        }
+    }
+}
+
+impl PycInvalidationMode {
+    pub const fn is_hash_based(&self) -> bool {
+        matches!(self, Self::CheckedHash | Self::UncheckedHash)
+    }
+
+    /// 4~7 byte of the header
+    pub const fn flags_bytes(&self) -> [u8; 4] {
+        match self {
+            Self::Timestamp => 0u32.to_le_bytes(),
+            Self::CheckedHash => 0b11u32.to_le_bytes(),
+            Self::UncheckedHash => 0b01u32.to_le_bytes(),
+        }
+    }
+}
+
+struct SipHasher {
+    v: [u64; 4],
+    c_rounds: usize,
+    d_rounds: usize,
+}
+
+impl SipHasher {
+    const fn new(k0: u64, k1: u64, c_rounds: usize, d_rounds: usize) -> Self {
+        Self {
+            v: [
+                k0 ^ 0x736f6d6570736575,
+                k1 ^ 0x646f72616e646f6d,
+                k0 ^ 0x6c7967656e657261,
+                k1 ^ 0x7465646279746573,
+            ],
+            c_rounds,
+            d_rounds,
+        }
+    }
+
+    fn round(&mut self) {
+        let v = &mut self.v;
+        v[0] = v[0].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(13) ^ v[0];
+        v[0] = v[0].rotate_left(32);
+        v[2] = v[2].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(16) ^ v[2];
+        v[0] = v[0].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(21) ^ v[0];
+        v[2] = v[2].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(17) ^ v[2];
+        v[2] = v[2].rotate_left(32);
+    }
+
+    fn compress(&mut self, m: u64) {
+        self.v[3] ^= m;
+        for _ in 0..self.c_rounds {
+            self.round();
+        }
+        self.v[0] ^= m;
+    }
+
+    fn hash(mut self, src: &[u8]) -> u64 {
+        let mut chunks = src.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.compress(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+        let mut last = [0u8; 8];
+        let rest = chunks.remainder();
+        last[..rest.len()].copy_from_slice(rest);
+        last[7] = src.len() as u8;
+        self.compress(u64::from_le_bytes(last));
+        self.v[2] ^= 0xff;
+        for _ in 0..self.d_rounds {
+            self.round();
+        }
+        self.v[0] ^ self.v[1] ^ self.v[2] ^ self.v[3]
+    }
+}
+
+/// Same as `importlib.util.source_hash` (SipHash keyed with the magic number).
+/// SipHash-1-3 is used since Python 3.11, SipHash-2-4 before that.
+pub fn get_source_hash_bytes(py_magic_num: u32, src: &[u8]) -> [u8; 8] {
+    let key = u32::from_le_bytes(get_magic_num_bytes(py_magic_num)) as u64;
+    let hasher = if get_ver_from_magic_num(py_magic_num).minor >= Some(11) {
+        SipHasher::new(key, 0, 1, 3)
+    } else {
+        SipHasher::new(key, 0, 2, 4)
+    };
+    hasher.hash(src).to_le_bytes()
+}
+
+/// 0~F byte of the `.pyc` file
+pub fn get_header_bytes(py_magic_num: u32, mode: PycInvalidationMode, src: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(16);
+    bytes.extend(get_magic_num_bytes(py_magic_num));
+    bytes.extend(mode.flags_bytes());
+    if mode.is_hash_based() {
+        bytes.extend(get_source_hash_bytes(py_magic_num, src));
+    } else {
+        bytes.extend(get_timestamp_bytes());
+        bytes.extend([0; 4]); // padding
+    }
+    bytes
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum DataTypePrefix {
@@ -70,6 +210,8 @@ pub enum DataTypePrefix {
     Interned = b't', // 0x74 + len + payload
     SmallTuple = b')', // 0x29 + len:  u8 + payload
     Tuple = b'(', // 0x28 + len:  u32 + payload
+    FrozenSet = b'>', // 0x3E + len:  u32 + payload
+    Dict = b'{', // 0x7B + (key + value)* + NULL
     Code = b'c' + 0x80, // 0x63 (0xE3)
     /* Erg specific prefix */
     Builtin = b'b', // 0x62 + str
@@ -99,6 +241,8 @@ impl From<u8> for DataTypePrefix {
             't' => Self::Interned,
             '(' | '\u{00A8}' => Self::Tuple,
             ')' | '\u{00A9}' => Self::SmallTuple,
+            '>' | '\u{00BE}' => Self::FrozenSet,
+            '{' | '\u{00FB}' => Self::Dict,
             'c' | '\u{00E3}' => Self::Code,
             'b' => Self::Builtin,
             'n' => Self::Nat,
@@ -126,6 +270,8 @@ impl DataTypePrefix {
                 | Self::Interned
                 | Self::SmallTuple
                 | Self::Tuple
+                | Self::FrozenSet
+                | Self::Dict
                 | Self::Code
                 | Self::Builtin
         )
@@ -170,3 +316,23 @@ pub fn raw_string_into_bytes(mut cont: Vec<u8>) -> Vec<u8> {
     tuple.append(&mut cont);
     tuple
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_hash() {
+        // the reference vector of SipHash-2-4 (key: 00..0f, message: 00..0e)
+        let k0 = u64::from_le_bytes([0, 1, 2, 3, 4, 5, 6, 7]);
+        let k1 = u64::from_le_bytes([8, 9, 10, 11, 12, 13, 14, 15]);
+        let msg = (0..15).collect::<Vec<u8>>();
+        let hash = SipHasher::new(k0, k1, 2, 4).hash(&msg);
+        assert_eq!(hash, 0xa129ca6149be45e5);
+        // importlib.util.source_hash(b"print! 1") (Python 3.11)
+        assert_eq!(
+            get_source_hash_bytes(3495, b"print! 1"),
+            [116, 65, 219, 110, 211, 14, 118, 30]
+        );
+    }
+}
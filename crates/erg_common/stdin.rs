@@ -1,3 +1,4 @@
+use std::ops::Range;
 use std::sync::OnceLock;
 
 #[cfg(not(feature = "full-repl"))]
@@ -5,19 +6,369 @@ use std::io::{stdin, BufRead, BufReader};
 
 #[cfg(feature = "full-repl")]
 use crossterm::{
-    cursor::MoveToColumn,
+    cursor::{MoveToColumn, MoveUp},
     event::{read, Event, KeyCode, KeyEvent, KeyModifiers},
-    execute,
+    execute, queue,
     style::Print,
     terminal::{disable_raw_mode, enable_raw_mode},
     terminal::{Clear, ClearType},
 };
 #[cfg(feature = "full-repl")]
+use std::collections::VecDeque;
+#[cfg(feature = "full-repl")]
+use std::io::Write;
+#[cfg(feature = "full-repl")]
+use std::path::PathBuf;
+#[cfg(feature = "full-repl")]
 use std::process::Command;
 #[cfg(feature = "full-repl")]
 use std::process::Output;
 
+#[cfg(feature = "full-repl")]
+use crate::env::erg_path;
 use crate::shared::Shared;
+use crate::style::Highlight;
+#[cfg(feature = "full-repl")]
+use crate::style::{remove_style, Attribute, COLORS, RESET};
+
+/// Provides the language-dependent features of the line editor of the REPL (`full-repl` feature).
+pub trait LineEditorHelper {
+    /// Returns the candidates that complete `word` (a name, or an attribute access like `obj.at`).
+    fn complete(&mut self, _word: &str) -> Vec<String> {
+        vec![]
+    }
+    /// Returns the styles of `line`. `cursor` is the char index of the cursor.
+    fn highlight(&mut self, _line: &str, _cursor: usize) -> Vec<Highlight> {
+        vec![]
+    }
+    /// Returns the errors found in `line` before evaluating it (char ranges and messages).
+    fn diagnose(&mut self, _line: &str) -> Vec<(Range<usize>, String)> {
+        vec![]
+    }
+}
+
+impl LineEditorHelper for () {}
+
+/// The maximum number of entries saved in the history file
+#[cfg(feature = "full-repl")]
+const HISTORY_LIMIT: usize = 1000;
+/// The prompt of the continuation lines in the line editor
+#[cfg(feature = "full-repl")]
+const CONTINUATION: &str = "... ";
+
+#[cfg(feature = "full-repl")]
+fn history_path() -> PathBuf {
+    erg_path().join("history")
+}
+
+/// An entry of the history file is a line (`\n` is escaped).
+#[cfg(feature = "full-repl")]
+fn escape_history(entry: &str) -> String {
+    entry.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+#[cfg(feature = "full-repl")]
+fn unescape_history(line: &str) -> String {
+    let mut entry = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => entry.push('\n'),
+                Some(c) => entry.push(c),
+                None => entry.push('\\'),
+            }
+        } else {
+            entry.push(c);
+        }
+    }
+    entry
+}
+
+/// The name (or attribute access) just before the cursor, e.g. `foo.ba` in `print! foo.ba`
+pub fn word_before(line: &str) -> &str {
+    let start = line
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !(c.is_alphanumeric() || matches!(c, '_' | '!' | '.')))
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    &line[start..]
+}
+
+#[cfg(feature = "full-repl")]
+fn common_prefix(candidates: &[String]) -> &str {
+    let first = &candidates[0];
+    let mut len = first.len();
+    for cand in candidates[1..].iter() {
+        len = len.min(
+            first
+                .bytes()
+                .zip(cand.bytes())
+                .take_while(|(l, r)| l == r)
+                .count(),
+        );
+    }
+    while !first.is_char_boundary(len) {
+        len -= 1;
+    }
+    &first[..len]
+}
+
+/// Applies `highlights` and underlines `errors` (ranges are char indices).
+#[cfg(feature = "full-repl")]
+fn stylize_line(line: &str, highlights: &[Highlight], errors: &[Range<usize>]) -> String {
+    let mut styled = String::new();
+    let mut prev_style = String::new();
+    for (i, c) in line.chars().enumerate() {
+        let mut style = String::new();
+        for hl in highlights.iter().filter(|hl| hl.range.contains(&i)) {
+            if let Some(color) = hl.color {
+                style.push_str(color.as_str());
+            }
+            if let Some(attr) = hl.attribute {
+                style.push_str(attr.as_str());
+            }
+        }
+        if errors.iter().any(|range| range.contains(&i)) {
+            style.push_str(COLORS.error.as_str());
+            style.push_str(Attribute::Underline.as_str());
+        }
+        if style != prev_style {
+            styled.push_str(RESET);
+            styled.push_str(&style);
+            prev_style = style;
+        }
+        styled.push(c);
+    }
+    if !prev_style.is_empty() {
+        styled.push_str(RESET);
+    }
+    styled
+}
+
+/// A multi-line editing buffer of the REPL.
+/// The first line is displayed after the prompt, and the others after `CONTINUATION`.
+#[cfg(feature = "full-repl")]
+struct LineEditor<'h> {
+    helper: &'h mut dyn LineEditorHelper,
+    lines: Vec<String>,
+    row: usize,
+    /// byte index in `lines[row]`
+    col: usize,
+    /// the column where the first line begins
+    first_col: u16,
+    /// the row of the cursor on the screen (relative to the first line)
+    screen_row: usize,
+    /// the errors shown before submission
+    errors: Vec<(Range<usize>, String)>,
+    stdout: std::io::Stdout,
+}
+
+#[cfg(feature = "full-repl")]
+impl<'h> LineEditor<'h> {
+    fn new(helper: &'h mut dyn LineEditorHelper, first_col: u16) -> Self {
+        Self {
+            helper,
+            lines: vec![String::new()],
+            row: 0,
+            col: 0,
+            first_col,
+            screen_row: 0,
+            errors: vec![],
+            stdout: std::io::stdout(),
+        }
+    }
+
+    fn set_lines(&mut self, lines: Vec<String>) {
+        self.lines = lines;
+        self.row = self.lines.len() - 1;
+        self.col = self.lines[self.row].len();
+    }
+
+    fn set_entry(&mut self, entry: &str) {
+        self.set_lines(entry.split('\n').map(|l| l.to_string()).collect());
+    }
+
+    fn insert(&mut self, s: &str) {
+        self.lines[self.row].insert_str(self.col, s);
+        self.col += s.len();
+    }
+
+    fn split_line(&mut self) {
+        let rest = self.lines[self.row].split_off(self.col);
+        self.row += 1;
+        self.lines.insert(self.row, rest);
+        self.col = 0;
+    }
+
+    fn move_row(&mut self, row: usize) {
+        self.row = row;
+        self.col = self.col.min(self.lines[row].len());
+        while !self.lines[row].is_char_boundary(self.col) {
+            self.col -= 1;
+        }
+    }
+
+    fn left(&mut self) {
+        if let Some(c) = self.lines[self.row][..self.col].chars().next_back() {
+            self.col -= c.len_utf8();
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.col = self.lines[self.row].len();
+        }
+    }
+
+    fn right(&mut self) {
+        if let Some(c) = self.lines[self.row][self.col..].chars().next() {
+            self.col += c.len_utf8();
+        } else if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = 0;
+        }
+    }
+
+    fn backspace(&mut self) {
+        if self.col > 0 {
+            self.left();
+            self.lines[self.row].remove(self.col);
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.lines[self.row].len();
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.col < self.lines[self.row].len() {
+            self.lines[self.row].remove(self.col);
+        } else if self.row + 1 < self.lines.len() {
+            let line = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    /// Completes the word before the cursor with the common prefix of the candidates.
+    /// If it cannot be extended, the candidates are listed below the input.
+    fn complete(&mut self) -> std::io::Result<()> {
+        let word = word_before(&self.lines[self.row][..self.col]).to_string();
+        if word.is_empty() {
+            self.insert("    ");
+            return self.render(None);
+        }
+        let mut candidates = self
+            .helper
+            .complete(&word)
+            .into_iter()
+            .filter(|cand| cand.starts_with(&word))
+            .collect::<Vec<_>>();
+        candidates.sort();
+        candidates.dedup();
+        if candidates.is_empty() {
+            return Ok(());
+        }
+        let prefix = common_prefix(&candidates).to_string();
+        if prefix.len() > word.len() {
+            self.insert(&prefix[word.len()..]);
+            return self.render(None);
+        }
+        if candidates.len() == 1 {
+            return Ok(());
+        }
+        let names = candidates
+            .iter()
+            .map(|cand| cand.rsplit('.').next().unwrap_or(cand))
+            .collect::<Vec<_>>()
+            .join("  ");
+        self.render(Some(&names))
+    }
+
+    /// Checks the input before submission.
+    /// Returns `false` if errors are found (they are shown, and the input is submitted by pressing Enter again).
+    fn diagnose(&mut self) -> std::io::Result<bool> {
+        if !self.errors.is_empty() || self.lines.len() > 1 {
+            return Ok(true);
+        }
+        self.errors = self.helper.diagnose(&self.lines[0]);
+        if self.errors.is_empty() {
+            return Ok(true);
+        }
+        self.render(None)?;
+        Ok(false)
+    }
+
+    /// Redraws the lines (and `footer` below them) and moves the cursor.
+    /// If there are errors and no `footer`, the error messages are shown below.
+    fn render(&mut self, footer: Option<&str>) -> std::io::Result<()> {
+        if self.screen_row > 0 {
+            queue!(self.stdout, MoveUp(self.screen_row as u16))?;
+        }
+        queue!(
+            self.stdout,
+            MoveToColumn(self.first_col),
+            Clear(ClearType::FromCursorDown)
+        )?;
+        let cursor = self.lines[self.row][..self.col].chars().count();
+        for (i, line) in self.lines.iter().enumerate() {
+            if i > 0 {
+                queue!(self.stdout, Print("\r\n"), Print(CONTINUATION))?;
+            }
+            let highlights = self
+                .helper
+                .highlight(line, if i == self.row { cursor } else { usize::MAX });
+            let errors = if i == 0 {
+                self.errors.iter().map(|(range, _)| range.clone()).collect()
+            } else {
+                vec![]
+            };
+            queue!(self.stdout, Print(stylize_line(line, &highlights, &errors)))?;
+        }
+        let mut rows_below = self.lines.len() - 1 - self.row;
+        let messages;
+        let footer = match footer {
+            Some(footer) => Some(footer),
+            None if !self.errors.is_empty() => {
+                messages = self
+                    .errors
+                    .iter()
+                    .map(|(_, msg)| format!("{}{msg}{RESET}", COLORS.error.as_str()))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Some(&messages[..])
+            }
+            None => None,
+        };
+        if let Some(footer) = footer {
+            let width = crossterm::terminal::size().map_or(80, |(w, _)| w.max(1) as usize);
+            for line in footer.split('\n') {
+                queue!(self.stdout, Print("\r\n"), Print(line))?;
+                rows_below += 1 + remove_style(line).chars().count().saturating_sub(1) / width;
+            }
+        }
+        if rows_below > 0 {
+            queue!(self.stdout, MoveUp(rows_below as u16))?;
+        }
+        let line_start = if self.row == 0 {
+            self.first_col
+        } else {
+            CONTINUATION.len() as u16
+        };
+        let col = line_start + cursor as u16;
+        queue!(self.stdout, MoveToColumn(col))?;
+        self.screen_row = self.row;
+        self.stdout.flush()
+    }
+
+    /// Clears the footer and moves the cursor to the next line of the input.
+    fn finish(&mut self) -> std::io::Result<()> {
+        self.errors.clear();
+        self.row = self.lines.len() - 1;
+        self.col = self.lines[self.row].len();
+        self.render(None)?;
+        execute!(self.stdout, Print("\r\n"))?;
+        Ok(())
+    }
+}
 
 /// e.g.
 /// ```erg
@@ -35,8 +386,12 @@ pub struct StdinReader {
     block_begin: usize,
     lineno: usize,
     buf: Vec<String>,
+    /// entries of the history (a block is an entry), shared across sessions
+    #[cfg(feature = "full-repl")]
+    history: Vec<String>,
+    /// the rest of the lines entered at once
     #[cfg(feature = "full-repl")]
-    history_input_position: usize,
+    pending: VecDeque<String>,
     indent: u16,
 }
 
@@ -101,35 +456,74 @@ impl StdinReader {
     }
 
     #[cfg(feature = "full-repl")]
-    pub fn read(&mut self) -> String {
-        enable_raw_mode().unwrap();
-        let mut output = std::io::stdout();
-        let mut line = String::new();
-        self.input(&mut line).unwrap();
-        disable_raw_mode().unwrap();
-        execute!(output, MoveToColumn(0)).unwrap();
+    pub fn read(&mut self, helper: &mut dyn LineEditorHelper) -> String {
+        let line = if let Some(line) = self.pending.pop_front() {
+            // the rest of a multi-line input
+            println!("{line}");
+            line
+        } else {
+            enable_raw_mode().unwrap();
+            let mut output = std::io::stdout();
+            let mut lines = self.input(helper).unwrap();
+            disable_raw_mode().unwrap();
+            execute!(output, MoveToColumn(0)).unwrap();
+            let first = lines.remove(0);
+            self.pending
+                .extend(lines.into_iter().map(|l| l.trim_start().to_string()));
+            first
+        };
+        self.record_history(&line);
         self.lineno += 1;
         self.buf.push(line);
         self.buf.last().cloned().unwrap_or_default()
     }
 
+    /// Lines in a block are appended to the history entry of the block.
+    #[cfg(feature = "full-repl")]
+    fn record_history(&mut self, line: &str) {
+        if self.indent <= 1 {
+            if line.trim().is_empty() || line == ":exit" {
+                return;
+            }
+            self.history.push(line.to_string());
+        } else if let Some(last) = self.history.last_mut() {
+            last.push('\n');
+            last.push_str(line);
+        }
+        let start = self.history.len().saturating_sub(HISTORY_LIMIT);
+        let content = self.history[start..]
+            .iter()
+            .map(|entry| escape_history(entry) + "\n")
+            .collect::<String>();
+        let _ = std::fs::write(history_path(), content);
+    }
+
     #[cfg(feature = "full-repl")]
-    fn input(&mut self, line: &mut String) -> std::io::Result<()> {
-        let mut position = 0;
-        let mut consult_history = false;
-        let mut stdout = std::io::stdout();
+    fn load_history() -> Vec<String> {
+        std::fs::read_to_string(history_path())
+            .map(|content| content.lines().map(unescape_history).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the lines entered (multiple lines are entered with Alt+Enter).
+    #[cfg(feature = "full-repl")]
+    fn input(&mut self, helper: &mut dyn LineEditorHelper) -> std::io::Result<Vec<String>> {
+        let mut editor = LineEditor::new(helper, self.indent * 4);
+        let mut history_position = self.history.len();
+        // the lines being edited before going back in the history
+        let mut draft = None;
         while let Event::Key(KeyEvent {
             code, modifiers, ..
         }) = read()?
         {
-            consult_history = false;
+            if code != KeyCode::Enter {
+                editor.errors.clear();
+            }
             match (code, modifiers) {
                 (KeyCode::Char('z'), KeyModifiers::CONTROL)
                 | (KeyCode::Char('d'), KeyModifiers::CONTROL) => {
                     println!();
-                    line.clear();
-                    line.push_str(":exit");
-                    return Ok(());
+                    return Ok(vec![":exit".to_string()]);
                 }
                 (KeyCode::Char('v'), KeyModifiers::CONTROL) => {
                     let output = match Self::access_clipboard() {
@@ -145,108 +539,124 @@ impl StdinReader {
                             .replace(['\n', '\r'], "")
                             .replace(|c: char| c.len_utf8() >= 2, "")
                     };
-                    line.insert_str(position, &clipboard);
-                    position += clipboard.len();
+                    editor.insert(&clipboard);
+                }
+                (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+                    let submit = self.search_history(&mut editor)?;
+                    if submit {
+                        editor.finish()?;
+                        return Ok(editor.lines);
+                    }
                 }
                 (_, KeyModifiers::CONTROL) => continue,
                 (KeyCode::Tab, _) => {
-                    line.insert_str(position, "    ");
-                    position += 4;
+                    editor.complete()?;
+                    continue;
                 }
                 (KeyCode::Home, _) => {
-                    position = 0;
+                    editor.col = 0;
                 }
                 (KeyCode::End, _) => {
-                    position = line.len();
-                }
-                (KeyCode::Backspace, _) => {
-                    if position == 0 {
-                        continue;
-                    }
-                    line.remove(position - 1);
-                    position -= 1;
-                }
-                (KeyCode::Delete, _) => {
-                    if position == line.len() {
-                        continue;
-                    }
-                    line.remove(position);
+                    editor.col = editor.lines[editor.row].len();
                 }
+                (KeyCode::Backspace, _) => editor.backspace(),
+                (KeyCode::Delete, _) => editor.delete(),
+                (KeyCode::Up, _) if editor.row > 0 => editor.move_row(editor.row - 1),
                 (KeyCode::Up, _) => {
-                    consult_history = true;
-                    if self.history_input_position == 0 {
+                    if history_position == 0 {
                         continue;
                     }
-                    self.history_input_position -= 1;
-                    execute!(stdout, MoveToColumn(4), Clear(ClearType::UntilNewLine))?;
-                    if let Some(l) = self.buf.get(self.history_input_position) {
-                        position = l.len();
-                        line.clear();
-                        line.push_str(l);
+                    if draft.is_none() {
+                        draft = Some(editor.lines.clone());
                     }
+                    history_position -= 1;
+                    editor.set_entry(&self.history[history_position]);
+                }
+                (KeyCode::Down, _) if editor.row + 1 < editor.lines.len() => {
+                    editor.move_row(editor.row + 1)
                 }
                 (KeyCode::Down, _) => {
-                    if self.history_input_position == self.buf.len() {
+                    if history_position >= self.history.len() {
                         continue;
                     }
-                    if self.history_input_position == self.buf.len() - 1 {
-                        *line = "".to_string();
-                        position = 0;
-                        self.history_input_position += 1;
-                        execute!(
-                            stdout,
-                            MoveToColumn(4),
-                            Clear(ClearType::UntilNewLine),
-                            MoveToColumn(self.indent * 4),
-                            Print(line.to_owned()),
-                            MoveToColumn(self.indent * 4 + position as u16)
-                        )?;
-                        continue;
-                    }
-                    self.history_input_position += 1;
-                    execute!(stdout, MoveToColumn(4), Clear(ClearType::UntilNewLine))?;
-                    if let Some(l) = self.buf.get(self.history_input_position) {
-                        position = l.len();
-                        line.clear();
-                        line.push_str(l);
-                    }
-                }
-                (KeyCode::Left, _) => {
-                    if position == 0 {
-                        continue;
+                    history_position += 1;
+                    if let Some(entry) = self.history.get(history_position) {
+                        editor.set_entry(entry);
+                    } else if let Some(draft) = draft.take() {
+                        editor.set_lines(draft);
                     }
-                    position -= 1;
                 }
-                (KeyCode::Right, _) => {
-                    if position == line.len() {
+                (KeyCode::Left, _) => editor.left(),
+                (KeyCode::Right, _) => editor.right(),
+                (KeyCode::Enter, KeyModifiers::ALT) => editor.split_line(),
+                (KeyCode::Enter, _) => {
+                    // lines in a block cannot be checked alone
+                    if self.indent <= 1 && !editor.diagnose()? {
                         continue;
                     }
-                    position += 1;
-                }
-                (KeyCode::Enter, _) => {
-                    println!();
-                    break;
+                    editor.finish()?;
+                    return Ok(editor.lines);
                 }
                 // TODO: check a full-width char and possible to insert
                 (KeyCode::Char(c), _) if c.len_utf8() < 2 => {
-                    line.insert(position, c);
-                    position += 1;
+                    editor.insert(c.encode_utf8(&mut [0; 4]));
                 }
                 _ => {}
             }
-            execute!(
-                stdout,
-                MoveToColumn(4),
-                Clear(ClearType::UntilNewLine),
-                MoveToColumn(self.indent * 4),
-                Print(line.to_owned()),
-                MoveToColumn(self.indent * 4 + position as u16)
-            )?;
+            editor.render(None)?;
         }
-        if !consult_history {
-            self.history_input_position = self.buf.len() + 1;
+        Ok(editor.lines)
+    }
+
+    /// Reverse incremental search (Ctrl+R).
+    /// Returns `true` if the found entry should be submitted as is.
+    #[cfg(feature = "full-repl")]
+    fn search_history(&self, editor: &mut LineEditor) -> std::io::Result<bool> {
+        let original = editor.lines.clone();
+        let mut query = String::new();
+        let mut found = None;
+        let mut failing = false;
+        loop {
+            let status = if failing { "failing " } else { "" };
+            editor.render(Some(&format!("({status}reverse-i-search)`{query}'")))?;
+            let Event::Key(KeyEvent {
+                code, modifiers, ..
+            }) = read()?
+            else {
+                continue;
+            };
+            let before = match (code, modifiers) {
+                (KeyCode::Char('r'), KeyModifiers::CONTROL) => found.unwrap_or(self.history.len()),
+                (KeyCode::Char('g'), KeyModifiers::CONTROL)
+                | (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
+                    editor.set_lines(original);
+                    editor.render(None)?;
+                    return Ok(false);
+                }
+                (KeyCode::Enter, _) => return Ok(true),
+                (KeyCode::Backspace, _) => {
+                    query.pop();
+                    self.history.len()
+                }
+                (KeyCode::Char(c), m) if !m.contains(KeyModifiers::CONTROL) && c.len_utf8() < 2 => {
+                    query.push(c);
+                    found.map_or(self.history.len(), |i| i + 1)
+                }
+                // accept the found entry and continue editing
+                _ => {
+                    editor.render(None)?;
+                    return Ok(false);
+                }
+            };
+            match (0..before).rfind(|&i| self.history[i].contains(&query)) {
+                Some(i) => {
+                    failing = false;
+                    found = Some(i);
+                    editor.set_entry(&self.history[i]);
+                }
+                None => failing = true,
+            }
         }
-        Ok(())
     }
 
     pub fn reread(&self) -> String {
@@ -275,14 +685,29 @@ impl GlobalStdin {
                 lineno: 1,
                 buf: vec![],
                 #[cfg(feature = "full-repl")]
-                history_input_position: 1,
+                history: StdinReader::load_history(),
+                #[cfg(feature = "full-repl")]
+                pending: VecDeque::new(),
                 indent: 1,
             })
         })
     }
 
     pub fn read(&'static self) -> String {
-        self.get().borrow_mut().read()
+        self.read_with_helper(&mut ())
+    }
+
+    /// `helper` is used by the line editor of the `full-repl` feature.
+    pub fn read_with_helper(&'static self, helper: &mut dyn LineEditorHelper) -> String {
+        #[cfg(feature = "full-repl")]
+        {
+            self.get().borrow_mut().read(helper)
+        }
+        #[cfg(not(feature = "full-repl"))]
+        {
+            let _ = helper;
+            self.get().borrow_mut().read()
+        }
     }
 
     pub fn reread(&'static self) -> String {
@@ -183,6 +183,28 @@ impl Attribute {
     }
 }
 
+/// The style of a range (char indices) of a line, used for syntax highlighting in the REPL
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Highlight {
+    pub range: std::ops::Range<usize>,
+    pub color: Option<Color>,
+    pub attribute: Option<Attribute>,
+}
+
+impl Highlight {
+    pub const fn new(
+        range: std::ops::Range<usize>,
+        color: Option<Color>,
+        attribute: Option<Attribute>,
+    ) -> Self {
+        Self {
+            range,
+            color,
+            attribute,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ThemeColors {
     pub error: Color,
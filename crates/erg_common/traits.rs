@@ -6,6 +6,8 @@ use std::collections::VecDeque;
 use std::env::consts::{ARCH, OS};
 use std::io::{stdout, BufWriter, Write};
 use std::mem;
+use std::ops::Range;
+use std::path::PathBuf;
 use std::process;
 use std::slice::{Iter, IterMut};
 
@@ -13,6 +15,8 @@ use crate::config::ErgConfig;
 use crate::consts::{BUILD_DATE, GIT_HASH_SHORT, SEMVER};
 use crate::error::{ErrorDisplay, ErrorKind, Location, MultiErrorDisplay};
 use crate::io::{Input, InputKind};
+use crate::stdin::LineEditorHelper;
+use crate::style::Highlight;
 use crate::{addr_eq, chomp, log, switch_unreachable};
 
 pub trait DequeStream<T>: Sized {
@@ -729,6 +733,46 @@ pub trait Runnable: Sized + Default {
         self.cfg().ps2.to_string()
     }
 
+    /// Returns the type of `src` without evaluating it (used by the `:type` command).
+    /// `None` means that the command is not supported.
+    fn type_of(&mut self, _src: String) -> Option<Result<String, Self::Errs>> {
+        None
+    }
+    /// Returns the signature and the doc comment of `name` (used by the `:doc` command).
+    fn doc_of(&self, _name: &str) -> Option<String> {
+        None
+    }
+    /// Returns the bindings of the session, or the attributes of `receiver` (used by the `:dir` command).
+    fn dir_of(&self, _receiver: Option<&str>) -> Option<Vec<String>> {
+        None
+    }
+    /// Evaluates `src` read from `path` in the current session (used by the `:load` command).
+    fn eval_file(&mut self, path: PathBuf, src: String) -> Result<String, Self::Errs> {
+        let input = mem::replace(&mut self.cfg_mut().input, Input::file(path));
+        let res = self.eval(src);
+        self.cfg_mut().input = input;
+        res
+    }
+    /// Re-imports the modules modified since they were imported (used by the `:reload` command).
+    /// Returns the names of the rebound variables. `None` means that the command is not supported.
+    fn reload(&mut self) -> Option<Result<Vec<String>, Self::Errs>> {
+        None
+    }
+    /// Returns the candidates that complete `word` (a name, or an attribute access like `obj.at`).
+    /// Used by the line editor of the REPL (`full-repl` feature).
+    fn complete(&self, _word: &str) -> Vec<String> {
+        vec![]
+    }
+    /// Returns the syntax highlighting of `line` (used by the line editor of the REPL).
+    /// `cursor` is the char index of the cursor (to highlight the matching bracket).
+    fn highlight(&self, _line: &str, _cursor: usize) -> Vec<Highlight> {
+        vec![]
+    }
+    /// Checks `src` without evaluating it, to show the errors before submission (used by the line editor of the REPL).
+    fn pre_check(&mut self, _src: String) -> Result<(), Self::Errs> {
+        Ok(())
+    }
+
     #[inline]
     fn quit(&mut self, code: i32) -> ! {
         self.finish();
@@ -760,6 +804,8 @@ pub trait Runnable: Sized + Default {
                 }
                 output.flush().unwrap();
                 let mut vm = VirtualMachine::new();
+                // successfully evaluated inputs (saved by `:save`)
+                let mut history = vec![];
                 loop {
                     let indent = vm.indent();
                     if vm.now_block.len() > 1 {
@@ -771,7 +817,13 @@ pub trait Runnable: Sized + Default {
                         output.flush().unwrap();
                     }
                     instance.cfg().input.set_indent(vm.length);
-                    let line = chomp(&instance.cfg_mut().input.read());
+                    let mut input = mem::replace(&mut instance.cfg_mut().input, Input::dummy());
+                    let mut helper = ReplHelper {
+                        instance: &mut instance,
+                        top_level: vm.now_block.len() == 1 && vm.now == BlockKind::Main,
+                    };
+                    let line = chomp(&input.read_with_helper(&mut helper));
+                    instance.cfg_mut().input = input;
                     let line = line.trim_end();
                     match line {
                         ":quit" | ":exit" => {
@@ -793,9 +845,14 @@ pub trait Runnable: Sized + Default {
                                 vm.push_code("\n");
                                 continue;
                             }
-                            match instance.eval(mem::take(&mut vm.codes)) {
-                                Ok(out) if out.is_empty() => continue,
+                            let src = mem::take(&mut vm.codes);
+                            match instance.eval(src.clone()) {
+                                Ok(out) if out.is_empty() => {
+                                    history.push(src);
+                                    continue;
+                                }
                                 Ok(out) => {
+                                    history.push(src);
                                     output.write_all((out + "\n").as_bytes()).unwrap();
                                     output.flush().unwrap();
                                 }
@@ -808,7 +865,7 @@ pub trait Runnable: Sized + Default {
                                         instance.quit_successfully(output);
                                     }
                                     num_errors += errs.len();
-                                    errs.write_all_stderr();
+                                    errs.write_all_stderr_with(&instance.cfg().error_format);
                                 }
                             }
                             instance.input().set_block_begin();
@@ -818,6 +875,18 @@ pub trait Runnable: Sized + Default {
                         }
                         _ => {}
                     }
+                    if vm.now_block.len() == 1 {
+                        if let Some((cmd, arg)) = parse_repl_command(line) {
+                            num_errors += exec_repl_command(
+                                &mut instance,
+                                cmd,
+                                arg,
+                                &mut history,
+                                &mut output,
+                            );
+                            continue;
+                        }
+                    }
                     let line = if let Some(comment_start) = line.find('#') {
                         &line[..comment_start]
                     } else {
@@ -918,8 +987,10 @@ pub trait Runnable: Sized + Default {
                     }
 
                     if vm.now == BlockKind::Main {
-                        match instance.eval(mem::take(&mut vm.codes)) {
+                        let src = mem::take(&mut vm.codes);
+                        match instance.eval(src.clone()) {
                             Ok(out) => {
+                                history.push(src);
                                 output.write_all((out + "\n").as_bytes()).unwrap();
                                 output.flush().unwrap();
                             }
@@ -932,7 +1003,7 @@ pub trait Runnable: Sized + Default {
                                     return ExitStatus::new(0, 0, num_errors);
                                 }
                                 num_errors += errs.len();
-                                errs.write_all_stderr();
+                                errs.write_all_stderr_with(&instance.cfg().error_format);
                             }
                         }
                         instance.input().set_block_begin();
@@ -947,13 +1018,148 @@ pub trait Runnable: Sized + Default {
             Ok(status) => status,
             Err(errs) => {
                 num_errors += errs.len();
-                errs.write_all_stderr();
+                errs.write_all_stderr_with(&instance.cfg().error_format);
                 ExitStatus::new(1, 0, num_errors)
             }
         }
     }
 }
 
+/// Connects the line editor to the REPL hooks of `Runnable`
+struct ReplHelper<'a, R: Runnable> {
+    instance: &'a mut R,
+    /// whether the line is outside of any block (only such lines can be checked)
+    top_level: bool,
+}
+
+impl<R: Runnable> LineEditorHelper for ReplHelper<'_, R> {
+    fn complete(&mut self, word: &str) -> Vec<String> {
+        self.instance.complete(word)
+    }
+
+    fn highlight(&mut self, line: &str, cursor: usize) -> Vec<Highlight> {
+        self.instance.highlight(line, cursor)
+    }
+
+    fn diagnose(&mut self, line: &str) -> Vec<(Range<usize>, String)> {
+        if !self.top_level
+            || line.trim().is_empty()
+            || line.starts_with(':')
+            || self.instance.expect_block(line) != BlockKind::None
+        {
+            return vec![];
+        }
+        let Err(errs) = self.instance.pre_check(line.to_string()) else {
+            return vec![];
+        };
+        let len = line.chars().count();
+        errs.iter()
+            .map(|err| {
+                let core = err.core();
+                let range = match (core.loc.col_begin(), core.loc.col_end()) {
+                    (Some(begin), Some(end)) if core.loc.ln_begin() == core.loc.ln_end() => {
+                        begin as usize..(end as usize).max(begin as usize + 1)
+                    }
+                    _ => 0..len,
+                };
+                (range, core.main_message.clone())
+            })
+            .collect()
+    }
+}
+
+/// REPL commands other than `:quit`/`:exit` and `:clear`/`:cln`
+const REPL_COMMANDS: [&str; 6] = [":type", ":doc", ":load", ":reload", ":dir", ":save"];
+
+/// e.g. `:type f 1` -> `Some((":type", "f 1"))`
+fn parse_repl_command(line: &str) -> Option<(&str, &str)> {
+    let (cmd, arg) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    REPL_COMMANDS.contains(&cmd).then_some((cmd, arg.trim()))
+}
+
+/// Executes a REPL command and returns the number of errors.
+fn exec_repl_command<R: Runnable>(
+    instance: &mut R,
+    cmd: &str,
+    arg: &str,
+    history: &mut Vec<String>,
+    output: &mut impl Write,
+) -> usize {
+    let mut num_errors = 0;
+    let mut handle_errs = |instance: &mut R, errs: R::Errs| {
+        if errs
+            .first()
+            .map(|e| e.core().kind == ErrorKind::SystemExit)
+            .unwrap_or(false)
+        {
+            instance.quit(0);
+        }
+        num_errors += errs.len();
+        errs.write_all_stderr_with(&instance.cfg().error_format);
+        String::new()
+    };
+    let unsupported = format!("{cmd} is not supported by {}", R::NAME);
+    let out = match (cmd, arg) {
+        (":type" | ":doc" | ":load" | ":save", "") => {
+            let arg = match cmd {
+                ":type" => "<expr>",
+                ":doc" => "<name>",
+                _ => "<file>",
+            };
+            format!("usage: {cmd} {arg}")
+        }
+        (":type", expr) => match instance.type_of(format!("{expr}\n")) {
+            Some(Ok(t)) => format!("{expr}: {t}"),
+            Some(Err(errs)) => handle_errs(instance, errs),
+            None => unsupported,
+        },
+        (":doc", name) => instance
+            .doc_of(name)
+            .unwrap_or_else(|| format!("{name} is not defined")),
+        (":dir", receiver) => {
+            let receiver = (!receiver.is_empty()).then_some(receiver);
+            match (instance.dir_of(receiver), receiver) {
+                (Some(names), _) => names.join("\n"),
+                (None, Some(receiver)) => format!("{receiver} is not defined"),
+                (None, None) => unsupported,
+            }
+        }
+        (":load", path) => match std::fs::read_to_string(path) {
+            Ok(mut src) => {
+                if !src.ends_with('\n') {
+                    src.push('\n');
+                }
+                match instance.eval_file(PathBuf::from(path), src.clone()) {
+                    Ok(out) => {
+                        history.push(src);
+                        out
+                    }
+                    Err(errs) => handle_errs(instance, errs),
+                }
+            }
+            Err(err) => format!("cannot load {path}: {err}"),
+        },
+        (":reload", _) => match instance.reload() {
+            Some(Ok(names)) if names.is_empty() => "no modules have been modified".to_string(),
+            Some(Ok(names)) => format!("reloaded: {}", names.join(", ")),
+            Some(Err(errs)) => handle_errs(instance, errs),
+            None => unsupported,
+        },
+        (":save", path) => match std::fs::write(path, history.concat()) {
+            Ok(()) => format!("saved {} inputs to {path}", history.len()),
+            Err(err) => format!("cannot save to {path}: {err}"),
+        },
+        _ => switch_unreachable!(),
+    };
+    instance.input().set_block_begin();
+    instance.clear();
+    if !out.is_empty() {
+        output.write_all((out + "\n").as_bytes()).unwrap();
+        output.flush().unwrap();
+    }
+    num_errors
+}
+
 pub trait Locational {
     /// NOTE: `loc` cannot be treated as a light method when `self` is a large grammatical element.
     /// If possible, delay the computation by passing `&impl Locational` or other means.
@@ -1,11 +1,17 @@
+use std::path::PathBuf;
+
 use erg_common::config::ErgConfig;
 use erg_common::dict::Dict;
 use erg_common::error::MultiErrorDisplay;
-use erg_common::traits::{ExitStatus, Runnable, Stream};
-use erg_common::Str;
+use erg_common::io::Input;
+use erg_common::pathutil::NormalizedPathBuf;
+use erg_common::style::Highlight;
+use erg_common::traits::{ExitStatus, Locational, Runnable, Stream};
+use erg_common::{switch_lang, Str};
 
 use erg_parser::ast::{VarName, AST};
 use erg_parser::build_ast::ASTBuilder;
+use erg_parser::highlight::highlight;
 
 use crate::artifact::{BuildRunnable, Buildable, CompleteArtifact, IncompleteArtifact};
 use crate::context::{Context, ContextKind, ContextProvider, ModuleContext};
@@ -13,9 +19,10 @@ use crate::effectcheck::SideEffectChecker;
 use crate::error::{CompileError, CompileErrors, LowerWarnings};
 use crate::link_hir::HIRLinker;
 use crate::lower::ASTLowerer;
-use crate::module::SharedCompilerResource;
+use crate::module::{ModId, SharedCompilerResource};
 use crate::ownercheck::OwnershipChecker;
-use crate::ty::VisibilityModifier;
+use crate::ty::value::ValueObj;
+use crate::ty::{HasType, TyParam, Type, VisibilityModifier};
 use crate::varinfo::VarInfo;
 
 /// Summarize lowering, side-effect checking, and ownership checking
@@ -71,11 +78,15 @@ impl Runnable for HIRBuilder {
         let artifact = builder
             .build(self.cfg_mut().input.read())
             .map_err(|arti| arti.errors)?;
-        artifact.warns.write_all_stderr();
+        artifact
+            .warns
+            .write_all_stderr_with(&self.cfg().error_format);
         let artifact = self
             .check(artifact.ast, "exec")
             .map_err(|arti| arti.errors)?;
-        artifact.warns.write_all_stderr();
+        artifact
+            .warns
+            .write_all_stderr_with(&self.cfg().error_format);
         println!("{}", artifact.object);
         Ok(ExitStatus::compile_passed(artifact.warns.len()))
     }
@@ -83,13 +94,56 @@ impl Runnable for HIRBuilder {
     fn eval(&mut self, src: String) -> Result<String, Self::Errs> {
         let mut builder = ASTBuilder::new(self.cfg().copy());
         let artifact = builder.build(src).map_err(|arti| arti.errors)?;
-        artifact.warns.write_all_stderr();
+        artifact
+            .warns
+            .write_all_stderr_with(&self.cfg().error_format);
         let artifact = self
             .check(artifact.ast, "eval")
             .map_err(|arti| arti.errors)?;
-        artifact.warns.write_all_stderr();
+        artifact
+            .warns
+            .write_all_stderr_with(&self.cfg().error_format);
         Ok(artifact.object.to_string())
     }
+
+    fn type_of(&mut self, src: String) -> Option<Result<String, Self::Errs>> {
+        Some(self.infer_type(src).map(|t| t.to_string()))
+    }
+
+    fn doc_of(&self, name: &str) -> Option<String> {
+        self.signature_and_doc(name)
+    }
+
+    fn dir_of(&self, receiver: Option<&str>) -> Option<Vec<String>> {
+        self.dir_lines(receiver)
+    }
+
+    fn complete(&self, word: &str) -> Vec<String> {
+        self.completion_candidates(word)
+    }
+
+    fn highlight(&self, line: &str, cursor: usize) -> Vec<Highlight> {
+        highlight(line, cursor)
+    }
+
+    fn pre_check(&mut self, src: String) -> Result<(), Self::Errs> {
+        self.pre_check(src)
+    }
+
+    fn eval_file(&mut self, path: PathBuf, src: String) -> Result<String, Self::Errs> {
+        let input = self.replace_input(Input::file(path));
+        let res = self.eval(src);
+        self.replace_input(input);
+        res
+    }
+
+    fn reload(&mut self) -> Option<Result<Vec<String>, Self::Errs>> {
+        let (names, src) = self.unload_modified_modules();
+        if names.is_empty() {
+            return Some(Ok(names));
+        }
+        Some(self.eval(src).map(|_| names))
+    }
 }
 
 impl Buildable for HIRBuilder {
@@ -192,6 +246,125 @@ impl HIRBuilder {
         Ok(CompleteArtifact::new(hir, artifact.warns))
     }
 
+    /// Lowers `ast` and then restores the module context,
+    /// so that the check has no effect on the session (e.g. `x.push! 1` does not change the type of `x`).
+    fn lower_without_effects(&mut self, ast: AST) -> Result<CompleteArtifact, IncompleteArtifact> {
+        let snapshot = self.lowerer.module.clone();
+        let res = self.lowerer.lower(ast, "eval");
+        self.lowerer.module = snapshot;
+        res
+    }
+
+    /// Infers the type of the last expression of `src`.
+    /// Nothing is evaluated or registered, so definitions are not allowed.
+    pub fn infer_type(&mut self, src: String) -> Result<Type, CompileErrors> {
+        let mut ast_builder = ASTBuilder::new(self.cfg().copy());
+        let artifact = ast_builder
+            .build(src)
+            .map_err(|iart| CompileErrors::from(iart.errors))?;
+        if let Some(def) = artifact
+            .ast
+            .module
+            .iter()
+            .find(|chunk| chunk.is_definition())
+        {
+            return Err(CompileErrors::from(CompileError::syntax_error(
+                self.cfg().input.clone(),
+                line!() as usize,
+                def.loc(),
+                self.lowerer.module.context.caused_by(),
+                switch_lang!(
+                    "japanese" => "定義の型は評価せずに表示できません",
+                    "simplified_chinese" => "无法在不求值的情况下显示定义的类型",
+                    "traditional_chinese" => "無法在不求值的情況下顯示定義的類型",
+                    "english" => "the type of a definition cannot be shown without evaluating it",
+                )
+                .to_string(),
+                None,
+            )));
+        }
+        // the ownership checker is not used (`:type x` does not move `x`)
+        let artifact = self
+            .lower_without_effects(artifact.ast)
+            .map_err(|iart| iart.errors)?;
+        Ok(artifact
+            .object
+            .module
+            .last()
+            .map_or(Type::NoneType, |expr| expr.ref_t().clone()))
+    }
+
+    /// Checks `src` before evaluating it (see `Runnable::pre_check`).
+    /// The session is not affected by the check.
+    pub fn pre_check(&mut self, src: String) -> Result<(), CompileErrors> {
+        let mut ast_builder = ASTBuilder::new(self.cfg().copy());
+        let artifact = ast_builder
+            .build(src)
+            .map_err(|iart| CompileErrors::from(iart.errors))?;
+        self.lower_without_effects(artifact.ast)
+            .map(|_| ())
+            .map_err(|iart| iart.errors)
+    }
+
+    /// Unloads the modules whose source files have been modified since they were imported
+    /// (and the modules that depend on them).
+    /// Returns the names of the variables bound to them, and the code that re-imports them.
+    pub fn unload_modified_modules(&mut self) -> (Vec<String>, String) {
+        let shared = self.lowerer.module.context.shared().clone();
+        let modified = shared
+            .mod_cache
+            .ref_inner()
+            .iter()
+            .filter(|(_, entry)| entry.id != ModId::builtin() && entry.is_modified())
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        if modified.is_empty() {
+            return (vec![], String::new());
+        }
+        let ctx = &self.lowerer.module.context;
+        let mut rebound = vec![];
+        for (name, vi) in ctx.locals.iter() {
+            let Some(path) = ctx.get_path_with_mod_t(&vi.t) else {
+                continue;
+            };
+            if !vi.t.is_erg_module() {
+                continue;
+            }
+            let path = NormalizedPathBuf::new(path);
+            let deps = shared.graph.ancestors(&path);
+            if modified
+                .iter()
+                .any(|modified| path == *modified || deps.contains(modified))
+            {
+                let TyParam::Value(ValueObj::Str(mod_name)) = vi.t.typarams().remove(0) else {
+                    continue;
+                };
+                rebound.push((name.inspect().clone(), mod_name));
+            }
+        }
+        for path in modified.iter() {
+            shared.clear(path);
+        }
+        let mut names = vec![];
+        let mut code = String::new();
+        for (name, mod_name) in rebound {
+            self.lowerer.unregister(&name);
+            code += &format!("{name} = import \"{mod_name}\"\n");
+            names.push(name.to_string());
+        }
+        (names, code)
+    }
+
+    /// Replaces the input and returns the old one.
+    pub fn replace_input(&mut self, input: Input) -> Input {
+        self.lowerer.replace_input(input)
+    }
+
+    /// see `Context::register_external`
+    pub fn register_external(&mut self, name: &str, t: Type) {
+        self.lowerer.register_external(name, t)
+    }
+
     pub fn pop_mod_ctx(&mut self) -> Option<ModuleContext> {
         self.lowerer.pop_mod_ctx()
     }
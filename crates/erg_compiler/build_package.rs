@@ -0,0 +1,401 @@
+//! defines `PackageBuilder`, which compiles all modules in a package.
+//!
+//! パッケージ内の全モジュールをコンパイルする`PackageBuilder`を定義する
+use std::fs::{copy, create_dir_all, read_dir, read_to_string, write};
+use std::path::{Path, PathBuf};
+
+use erg_common::config::{ErgConfig, ErgMode};
+use erg_common::dict::Dict;
+use erg_common::env::erg_std_path;
+use erg_common::error::{Location, MultiErrorDisplay};
+use erg_common::set::Set;
+use erg_common::traits::{ExitStatus, Runnable, Stream};
+use erg_common::{get_hash, log};
+
+use erg_parser::ast::Expr;
+use erg_parser::build_ast::ASTBuilder;
+
+use crate::error::{CompileError, CompileErrors, CompileWarnings};
+use crate::module::{ModuleGraph, SharedCompilerResource};
+use crate::Compiler;
+
+/// The file that records the state of the last build (in the output directory).
+const BUILD_STATE: &str = ".erg_build_state";
+/// Runtime helpers imported by the code generator.
+const STD_RUNTIME_ROOTS: [&str; 3] = ["_erg_std_prelude", "_erg_control", "_erg_convertors"];
+
+/// The names of the runtime helpers (`_erg_*.py` in the std directory) required by the compiled code.
+pub(crate) fn std_runtime_modules() -> std::io::Result<Set<String>> {
+    let mut needed = Set::new();
+    let mut stack = STD_RUNTIME_ROOTS.map(String::from).to_vec();
+    while let Some(name) = stack.pop() {
+        if !needed.insert(name.clone()) {
+            continue;
+        }
+        let src = read_to_string(erg_std_path().join(format!("{name}.py")))?;
+        for line in src.lines() {
+            let Some(rest) = line.trim_start().strip_prefix("from _erg_") else {
+                continue;
+            };
+            if let Some(module) = rest.split_whitespace().next() {
+                stack.push(format!("_erg_{module}"));
+            }
+        }
+    }
+    Ok(needed)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BuildEntry {
+    hash: usize,
+    /// all Erg modules in the package that the module depends on (transitively)
+    deps: Vec<PathBuf>,
+}
+
+/// format (tab-separated, paths are relative to the package root):
+/// ```text
+/// <hash>  foo.er  bar.er  baz/__init__.er
+/// ```
+#[derive(Debug, Default)]
+struct BuildState(Dict<PathBuf, BuildEntry>);
+
+impl BuildState {
+    fn load(path: &Path) -> Self {
+        let Ok(src) = read_to_string(path) else {
+            return Self::default();
+        };
+        let mut state = Dict::new();
+        for line in src.lines() {
+            let mut fields = line.split('\t');
+            let (Some(hash), Some(module)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            let Ok(hash) = hash.parse() else {
+                continue;
+            };
+            let deps = fields.map(PathBuf::from).collect();
+            state.insert(PathBuf::from(module), BuildEntry { hash, deps });
+        }
+        Self(state)
+    }
+
+    fn save(&self, path: &Path) -> std::io::Result<()> {
+        let mut lines = vec![];
+        for (module, entry) in self.0.iter() {
+            let mut line = format!("{}\t{}", entry.hash, module.display());
+            for dep in entry.deps.iter() {
+                line += &format!("\t{}", dep.display());
+            }
+            lines.push(line);
+        }
+        lines.sort();
+        write(path, lines.join("\n") + "\n")
+    }
+}
+
+/// Compiles all Erg modules under the package root (the directory which has `package.er`)
+/// and writes the `.pyc` files to the output directory, mirroring the package tree.
+///
+/// Modules are compiled in dependency order, and modules whose source and dependencies have not changed
+/// since the last build are skipped.
+#[derive(Debug)]
+pub struct PackageBuilder {
+    cfg: ErgConfig,
+    root: PathBuf,
+    out_dir: PathBuf,
+    state: BuildState,
+    hashes: Dict<PathBuf, usize>,
+    /// modules compiled (or failed to compile) in this build
+    rebuilt: Set<PathBuf>,
+}
+
+impl PackageBuilder {
+    pub fn new(cfg: ErgConfig) -> Option<Self> {
+        let root = Self::find_root(&cfg)?;
+        let out_dir = if let Some(dist_dir) = cfg.dist_dir {
+            PathBuf::from(dist_dir)
+        } else {
+            root.join("build").join("debug")
+        };
+        Some(Self {
+            cfg,
+            root,
+            out_dir,
+            state: BuildState::default(),
+            hashes: Dict::new(),
+            rebuilt: Set::new(),
+        })
+    }
+
+    pub fn run(cfg: ErgConfig) -> ExitStatus {
+        let Some(mut builder) = Self::new(cfg) else {
+            eprintln!("package.er not found (the current directory is not in an Erg package)");
+            return ExitStatus::ERR1;
+        };
+        match builder.build() {
+            Ok(warns) => {
+                warns.write_all_stderr_with(&builder.cfg.error_format);
+                ExitStatus::compile_passed(warns.len())
+            }
+            Err((errs, warns)) => {
+                warns.write_all_stderr_with(&builder.cfg.error_format);
+                errs.write_all_stderr_with(&builder.cfg.error_format);
+                ExitStatus::new(1, warns.len(), errs.len())
+            }
+        }
+    }
+
+    /// If the input is a file or directory, search from there, otherwise from the current directory.
+    fn find_root(cfg: &ErgConfig) -> Option<PathBuf> {
+        let mut dir = if cfg.input.is_repl() {
+            std::env::current_dir().ok()?
+        } else {
+            cfg.input.path().to_path_buf()
+        };
+        loop {
+            if dir.is_dir() && dir.join("package.er").exists() {
+                return Some(dir);
+            }
+            if !dir.pop() {
+                return None;
+            }
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn out_dir(&self) -> &Path {
+        &self.out_dir
+    }
+
+    fn collect_modules(&self, dir: &Path, modules: &mut Vec<PathBuf>) {
+        let Ok(entries) = read_dir(dir) else {
+            return;
+        };
+        let mut entries = entries.flatten().map(|e| e.path()).collect::<Vec<_>>();
+        entries.sort();
+        for path in entries {
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            if file_name.starts_with('.') || file_name == "__pycache__" {
+                continue;
+            }
+            if path.is_dir() {
+                // declaration directories (`foo.d/`) and the build directory are not compiled
+                if !file_name.ends_with(".d") && !path.starts_with(&self.out_dir) {
+                    self.collect_modules(&path, modules);
+                }
+            } else if file_name.ends_with(".er")
+                && !file_name.ends_with(".d.er")
+                && path != self.root.join("package.er")
+            {
+                modules.push(path);
+            }
+        }
+    }
+
+    fn relative<'p>(&self, path: &'p Path) -> &'p Path {
+        path.strip_prefix(&self.root).unwrap_or(path)
+    }
+
+    fn hash_of(&mut self, path: &Path) -> Option<usize> {
+        if let Some(hash) = self.hashes.get(path) {
+            return Some(*hash);
+        }
+        let hash = get_hash(&read_to_string(path).ok()?);
+        self.hashes.insert(path.to_path_buf(), hash);
+        Some(hash)
+    }
+
+    fn pyc_path(&self, path: &Path) -> PathBuf {
+        let mut pyc = self.out_dir.join(self.relative(path));
+        pyc.set_extension("pyc");
+        pyc
+    }
+
+    /// A module is up to date if its `.pyc` exists and neither it nor its dependencies have changed.
+    /// Imported Erg modules are linked into the `.pyc`, so a change in a dependency requires recompilation.
+    fn is_up_to_date(&mut self, path: &Path) -> bool {
+        if !self.pyc_path(path).exists() {
+            return false;
+        }
+        let rel = self.relative(path).to_path_buf();
+        let Some(entry) = self.state.0.get(&rel).cloned() else {
+            return false;
+        };
+        if self.hash_of(path) != Some(entry.hash) {
+            return false;
+        }
+        entry.deps.iter().all(|dep| {
+            if self.rebuilt.contains(dep) {
+                return false;
+            }
+            let recorded = self.state.0.get(dep).map(|entry| entry.hash);
+            recorded.is_some() && self.hash_of(&self.root.join(dep)) == recorded
+        })
+    }
+
+    /// The Erg modules imported at the top level of the module (`x = import "x"`).
+    /// Imports in nested scopes are found when the module is compiled (and recorded in the build state).
+    fn imports_of(&self, path: &Path) -> Vec<PathBuf> {
+        let mut cfg = self.cfg.inherit(path.to_path_buf());
+        let Ok(src) = cfg.input.try_read() else {
+            return vec![];
+        };
+        let Ok(artifact) = ASTBuilder::new(cfg.copy()).build(src) else {
+            return vec![];
+        };
+        artifact
+            .ast
+            .module
+            .iter()
+            .filter_map(|expr| {
+                let Expr::Def(def) = expr else {
+                    return None;
+                };
+                if !def.def_kind().is_erg_import() {
+                    return None;
+                }
+                let Some(Expr::Call(call)) = def.body.block.first() else {
+                    return None;
+                };
+                let Some(Expr::Literal(lit)) = call.args.get_left_or_key("Path") else {
+                    return None;
+                };
+                let name = lit.token.content.trim_matches('"');
+                cfg.input.resolve_real_path(Path::new(name))
+            })
+            .collect()
+    }
+
+    /// Modules are sorted by the module graph of the current sources.
+    fn sort_modules(&self, modules: Vec<PathBuf>) -> Vec<PathBuf> {
+        let mut graph = ModuleGraph::new();
+        for module in modules.iter() {
+            graph.add_node_if_none(module);
+        }
+        for module in modules.iter() {
+            for dep in self.imports_of(module) {
+                if modules.contains(&dep) {
+                    let _ = graph.inc_ref(module, dep);
+                }
+            }
+        }
+        match graph.sorted() {
+            Ok(graph) => graph
+                .into_iter()
+                .map(|node| node.id.to_path_buf())
+                .collect(),
+            Err(_) => modules,
+        }
+    }
+
+    /// All modules share `shared`, so that an imported module is analyzed once in the build
+    /// (in `ErgMode::Build`, the linker does not consume the analyzed modules).
+    fn compile_module(
+        &mut self,
+        path: &Path,
+        shared: &SharedCompilerResource,
+    ) -> Result<CompileWarnings, CompileErrors> {
+        let mut cfg = self.cfg.inherit(path.to_path_buf());
+        cfg.mode = ErgMode::Build;
+        cfg.dist_dir = None;
+        let src = cfg.input.read();
+        let mut compiler = Compiler::new_with_cache(cfg, shared.inherit(path.to_path_buf()));
+        let pyc_path = self.pyc_path(path);
+        if let Some(dir) = pyc_path.parent() {
+            create_dir_all(dir).map_err(|err| {
+                CompileErrors::from(self.io_error(
+                    "failed to create the output directory",
+                    dir,
+                    err,
+                ))
+            })?;
+        }
+        let warns = compiler
+            .compile_and_dump_as_pyc(&pyc_path, src, "exec")
+            .map_err(|eart| {
+                eart.warns.write_all_stderr_with(&self.cfg.error_format);
+                eart.errors
+            })?;
+        let mut deps = vec![];
+        for dep in shared.graph.ancestors(path).iter() {
+            if dep.starts_with(&self.root) && dep.extension().is_some_and(|ext| ext == "er") {
+                deps.push(self.relative(dep).to_path_buf());
+            }
+        }
+        deps.sort();
+        if let Some(hash) = self.hash_of(path) {
+            let rel = self.relative(path).to_path_buf();
+            self.state.0.insert(rel, BuildEntry { hash, deps });
+        }
+        Ok(warns)
+    }
+
+    fn io_error(&self, desc: &str, path: &Path, err: std::io::Error) -> CompileError {
+        CompileError::file_error(
+            self.cfg.input.clone(),
+            line!() as usize,
+            format!("{desc} ({}): {err}", path.display()),
+            Location::Unknown,
+            "".to_owned(),
+            None,
+        )
+    }
+
+    /// Copies the runtime helpers (`_erg_*.py`) required by the compiled code to the output directory.
+    fn copy_std_runtime(&self) -> std::io::Result<()> {
+        for name in std_runtime_modules()?.iter() {
+            let file = format!("{name}.py");
+            let src = erg_std_path().join(&file);
+            let dst = self.out_dir.join(&file);
+            if read_to_string(&dst).ok() != read_to_string(&src).ok() {
+                copy(src, dst)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the warnings, or the errors and warnings if any module fails to compile.
+    pub fn build(&mut self) -> Result<CompileWarnings, (CompileErrors, CompileWarnings)> {
+        let state_path = self.out_dir.join(BUILD_STATE);
+        self.state = BuildState::load(&state_path);
+        self.rebuilt = Set::new();
+        let mut modules = vec![];
+        self.collect_modules(&self.root, &mut modules);
+        let mut errors = CompileErrors::empty();
+        let mut warns = CompileWarnings::empty();
+        let mut cfg = self.cfg.copy();
+        cfg.mode = ErgMode::Build;
+        let shared = SharedCompilerResource::new(cfg);
+        for module in self.sort_modules(modules) {
+            if self.is_up_to_date(&module) {
+                log!(info "{} is up to date", module.display());
+                continue;
+            }
+            log!(info "compiling {}", module.display());
+            self.rebuilt.insert(self.relative(&module).to_path_buf());
+            match self.compile_module(&module, &shared) {
+                Ok(ws) => warns.extend(ws),
+                Err(errs) => {
+                    self.state.0.remove(self.relative(&module));
+                    errors.extend(errs);
+                }
+            }
+        }
+        if let Err(err) = create_dir_all(&self.out_dir) {
+            errors.push(self.io_error("failed to create the output directory", &self.out_dir, err));
+        } else if !self.cfg.no_std {
+            if let Err(err) = self.copy_std_runtime() {
+                errors.push(self.io_error("failed to copy the Erg runtime", &self.out_dir, err));
+            }
+        }
+        let _ = self.state.save(&state_path);
+        if errors.is_empty() {
+            Ok(warns)
+        } else {
+            Err((errors, warns))
+        }
+    }
+}
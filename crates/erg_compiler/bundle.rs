@@ -0,0 +1,315 @@
+//! defines `Bundler`, which packages a program into a single `.pyz` zipapp.
+//!
+//! プログラムを単一の`.pyz`ファイル(zipapp)にまとめる`Bundler`を定義する
+use std::fs::{read, read_dir, File};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+use erg_common::config::ErgConfig;
+use erg_common::env::{erg_std_path, is_std_decl_path};
+use erg_common::error::{Location, MultiErrorDisplay};
+use erg_common::python_util::env_magic_number;
+use erg_common::serialize::{get_ver_from_magic_num, PycInvalidationMode};
+use erg_common::set::Set;
+use erg_common::traits::{ExitStatus, Stream};
+
+use crate::build_package::std_runtime_modules;
+use crate::error::{CompileError, CompileErrors, CompileWarnings};
+use crate::module::SharedCompilerResource;
+use crate::Compiler;
+
+/// DOS date of 1980-01-01 (the timestamps of entries are fixed so that the archive is reproducible)
+const DOS_EPOCH_DATE: u16 = (1 << 5) | 1;
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Writes an uncompressed zip archive.
+/// Directories have their own entries because `zipimport` needs them to find (namespace) packages.
+#[derive(Debug, Default)]
+struct ZipWriter {
+    bytes: Vec<u8>,
+    central_dir: Vec<u8>,
+    entries: u16,
+}
+
+impl ZipWriter {
+    fn new(prefix: &[u8]) -> Self {
+        Self {
+            bytes: prefix.to_vec(),
+            ..Self::default()
+        }
+    }
+
+    fn add(&mut self, name: &str, data: &[u8]) {
+        let is_dir = name.ends_with('/');
+        let offset = self.bytes.len() as u32;
+        let mut header = vec![];
+        header.extend(20u16.to_le_bytes()); // version needed to extract (2.0)
+        header.extend(0u16.to_le_bytes()); // flags
+        header.extend(0u16.to_le_bytes()); // method (stored)
+        header.extend(0u16.to_le_bytes()); // time
+        header.extend(DOS_EPOCH_DATE.to_le_bytes());
+        header.extend(crc32(data).to_le_bytes());
+        header.extend((data.len() as u32).to_le_bytes()); // compressed size
+        header.extend((data.len() as u32).to_le_bytes());
+        header.extend((name.len() as u16).to_le_bytes());
+        header.extend(0u16.to_le_bytes()); // extra field length
+        self.bytes.extend(0x04034b50u32.to_le_bytes());
+        self.bytes.extend(&header);
+        self.bytes.extend(name.as_bytes());
+        self.bytes.extend(data);
+        self.central_dir.extend(0x02014b50u32.to_le_bytes());
+        self.central_dir.extend(20u16.to_le_bytes()); // version made by
+        self.central_dir.extend(&header);
+        self.central_dir.extend(0u16.to_le_bytes()); // comment length
+        self.central_dir.extend(0u16.to_le_bytes()); // disk number
+        self.central_dir.extend(0u16.to_le_bytes()); // internal attributes
+        let external_attrs = if is_dir { 0x10u32 } else { 0 };
+        self.central_dir.extend(external_attrs.to_le_bytes());
+        self.central_dir.extend(offset.to_le_bytes());
+        self.central_dir.extend(name.as_bytes());
+        self.entries += 1;
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let offset = self.bytes.len() as u32;
+        let size = self.central_dir.len() as u32;
+        self.bytes.append(&mut self.central_dir);
+        self.bytes.extend(0x06054b50u32.to_le_bytes());
+        self.bytes.extend(0u16.to_le_bytes()); // disk number
+        self.bytes.extend(0u16.to_le_bytes()); // disk where the central directory starts
+        self.bytes.extend(self.entries.to_le_bytes());
+        self.bytes.extend(self.entries.to_le_bytes());
+        self.bytes.extend(size.to_le_bytes());
+        self.bytes.extend(offset.to_le_bytes());
+        self.bytes.extend(0u16.to_le_bytes()); // comment length
+        self.bytes
+    }
+}
+
+/// Compiles the entry module and packages it into a `.pyz` zipapp, which can be run with `python <name>.pyz`.
+///
+/// The archive contains:
+/// * `__main__.pyc`: the entry module (imported Erg modules are linked into it)
+/// * the runtime helpers (`_erg_*.py`)
+/// * the local Python modules imported with `pyimport`, placed at the same paths relative to the current directory
+///
+/// The `.pyc` file depends on the version of Python, so the archive can only be run by the same version.
+#[derive(Debug)]
+pub struct Bundler {
+    cfg: ErgConfig,
+    out_path: PathBuf,
+}
+
+impl Bundler {
+    pub fn new(cfg: ErgConfig) -> Option<Self> {
+        let entry = cfg.input.path();
+        if !entry.is_file() {
+            return None;
+        }
+        let mut file_name = PathBuf::from(entry.file_name()?);
+        file_name.set_extension("pyz");
+        let out_path = if let Some(dist_dir) = cfg.dist_dir {
+            PathBuf::from(dist_dir).join(file_name)
+        } else {
+            entry.with_file_name(file_name)
+        };
+        Some(Self { cfg, out_path })
+    }
+
+    pub fn run(cfg: ErgConfig) -> ExitStatus {
+        let Some(mut bundler) = Self::new(cfg) else {
+            eprintln!("the entry module is not specified (usage: erg bundle <file>.er)");
+            return ExitStatus::ERR1;
+        };
+        match bundler.bundle() {
+            Ok(warns) => {
+                warns.write_all_stderr_with(&bundler.cfg.error_format);
+                ExitStatus::compile_passed(warns.len())
+            }
+            Err((errs, warns)) => {
+                warns.write_all_stderr_with(&bundler.cfg.error_format);
+                errs.write_all_stderr_with(&bundler.cfg.error_format);
+                ExitStatus::new(1, warns.len(), errs.len())
+            }
+        }
+    }
+
+    pub fn out_path(&self) -> &Path {
+        &self.out_path
+    }
+
+    /// `__import__("a.b")` is generated for `pyimport "b"` in `a/main.er` (see `HIRLinker::replace_py_import`),
+    /// so the module is placed at `a/b.py` in the archive.
+    fn archive_name(path: &Path) -> Option<String> {
+        let cur_dir = std::env::current_dir().ok()?.canonicalize().ok()?;
+        let rel = path.strip_prefix(cur_dir).ok()?;
+        let comps = rel
+            .components()
+            .map(|comp| match comp {
+                Component::Normal(name) => name.to_str(),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(comps.join("/"))
+    }
+
+    /// `foo.d.er` -> `foo.py`, `__pycache__/foo.d.er` -> `foo.py`, `foo.d/__init__.d.er` -> `foo/`
+    fn py_module_of(decl_path: &Path) -> Option<PathBuf> {
+        let mut path = PathBuf::new();
+        for comp in decl_path.components() {
+            let name = comp.as_os_str().to_str()?;
+            if let Some(module) = name.strip_suffix(".d.er") {
+                if module == "__init__" {
+                    return Some(path);
+                }
+                path.push(format!("{module}.py"));
+            } else if let Some(dir) = name.strip_suffix(".d") {
+                path.push(dir);
+            } else if name != "__pycache__" {
+                path.push(comp);
+            }
+        }
+        Some(path)
+    }
+
+    fn collect_py_files(path: &Path, files: &mut Vec<PathBuf>) {
+        if path.is_dir() {
+            let Ok(entries) = read_dir(path) else {
+                return;
+            };
+            for entry in entries.flatten() {
+                if entry.file_name() != "__pycache__" {
+                    Self::collect_py_files(&entry.path(), files);
+                }
+            }
+        } else if path.extension().is_some_and(|ext| ext == "py") {
+            files.push(path.to_path_buf());
+        }
+    }
+
+    /// Returns the archive names and the contents of the local Python modules the entry depends on.
+    fn local_py_modules(&self, shared: &SharedCompilerResource) -> Vec<(String, Vec<u8>)> {
+        let mut files = vec![];
+        for dep in shared.graph.ancestors(self.cfg.input.path()).iter() {
+            if !dep.to_string_lossy().ends_with(".d.er") || is_std_decl_path(dep) {
+                continue;
+            }
+            if let Some(module) = Self::py_module_of(dep) {
+                Self::collect_py_files(&module, &mut files);
+            }
+        }
+        files
+            .into_iter()
+            .filter_map(|file| Some((Self::archive_name(&file)?, read(&file).ok()?)))
+            .collect()
+    }
+
+    fn io_error(&self, desc: &str, path: &Path, err: std::io::Error) -> CompileError {
+        CompileError::file_error(
+            self.cfg.input.clone(),
+            line!() as usize,
+            format!("{desc} ({}): {err}", path.display()),
+            Location::Unknown,
+            "".to_owned(),
+            None,
+        )
+    }
+
+    /// Returns the warnings, or the errors and warnings if the entry module fails to compile
+    /// (or the bundle cannot be written).
+    pub fn bundle(&mut self) -> Result<CompileWarnings, (CompileErrors, CompileWarnings)> {
+        let mut cfg = self.cfg.copy();
+        let py_magic_num = cfg.py_magic_num.unwrap_or_else(env_magic_number);
+        cfg.py_magic_num = Some(py_magic_num);
+        let src = cfg.input.read();
+        let shared = SharedCompilerResource::new(cfg.copy());
+        let mut compiler = Compiler::new_with_cache(cfg, shared.clone());
+        let arti = compiler
+            .compile(src.clone(), "exec")
+            .map_err(|eart| (eart.errors, eart.warns))?;
+        // there is no source in the archive to check the hash against
+        let main = arti.object.into_bytecode_with_mode(
+            Some(py_magic_num),
+            PycInvalidationMode::UncheckedHash,
+            &src,
+        );
+        let mut files = vec![("__main__.pyc".to_string(), main)];
+        if !self.cfg.no_std {
+            let runtime = std_runtime_modules().and_then(|names| {
+                names
+                    .into_iter()
+                    .map(|name| {
+                        let file = format!("{name}.py");
+                        read(erg_std_path().join(&file)).map(|src| (file, src))
+                    })
+                    .collect::<std::io::Result<Vec<_>>>()
+            });
+            match runtime {
+                Ok(runtime) => files.extend(runtime),
+                Err(err) => {
+                    let err = self.io_error("failed to read the Erg runtime", erg_std_path(), err);
+                    return Err((CompileErrors::from(err), arti.warns));
+                }
+            }
+        }
+        files.extend(self.local_py_modules(&shared));
+        files.sort();
+        files.dedup_by(|l, r| l.0 == r.0);
+        let ver = get_ver_from_magic_num(py_magic_num);
+        let shebang = format!(
+            "#!/usr/bin/env python{}.{}\n",
+            ver.major,
+            ver.minor.unwrap_or(0)
+        );
+        let mut zip = ZipWriter::new(shebang.as_bytes());
+        let mut dirs = Set::new();
+        for (name, data) in files.iter() {
+            let mut dir = String::new();
+            for comp in name.split('/').collect::<Vec<_>>().split_last().unwrap().1 {
+                dir += comp;
+                dir.push('/');
+                if dirs.insert(dir.clone()) {
+                    zip.add(&dir, &[]);
+                }
+            }
+            zip.add(name, data);
+        }
+        if let Err(err) =
+            File::create(&self.out_path).and_then(|mut file| file.write_all(&zip.finish()))
+        {
+            let err = self.io_error("failed to write the bundle", &self.out_path, err);
+            return Err((CompileErrors::from(err), arti.warns));
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let _ =
+                std::fs::set_permissions(&self.out_path, std::fs::Permissions::from_mode(0o755));
+        }
+        Ok(arti.warns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xCBF43926);
+    }
+}
@@ -6,7 +6,7 @@ use std::process;
 
 use crate::hir::Set;
 use crate::ty::codeobj::{CodeObj, CodeObjFlags, MakeFunctionFlags};
-use crate::ty::value::GenTypeObj;
+use crate::ty::value::{EnumTypeObj, GenTypeObj, TypeObj};
 use erg_common::cache::CacheSet;
 use erg_common::config::ErgConfig;
 use erg_common::env::erg_std_path;
@@ -39,6 +39,7 @@ use crate::hir::{
     Literal, NonDefaultParamSignature, Params, PatchDef, PosArg, ReDef, Record, Signature,
     SubrSignature, Tuple, UnaryOp, VarSignature, HIR,
 };
+use crate::ty::constructors::{func1, mono};
 use crate::ty::value::ValueObj;
 use crate::ty::{HasType, Type, TypeCode, TypePair, VisibilityModifier};
 use crate::varinfo::VarInfo;
@@ -89,8 +90,16 @@ fn escape_name(name: &str, vis: &VisibilityModifier, def_line: u32, def_col: u32
 
 fn escape_ident(ident: Identifier) -> Str {
     let vis = ident.vis();
-    if &ident.inspect()[..] == "Self" {
-        let Ok(ty) = <&Type>::try_from(ident.vi.t.singleton_value().unwrap()) else {
+    if &ident.inspect()[..] == "Self" || &ident.inspect()[..] == "Self!" {
+        // e.g. `Self!: ({T}, {N}) -> {Queue!(T, N)}` (polymorphic class)
+        let value = ident.vi.t.singleton_value().or_else(|| {
+            ident
+                .vi
+                .t
+                .return_t()
+                .and_then(|ret_t| ret_t.singleton_value())
+        });
+        let Ok(ty) = <&Type>::try_from(value.unwrap()) else {
             unreachable!()
         };
         escape_name(
@@ -956,7 +965,7 @@ impl PyCodeGenerator {
                 fn_name!(),
                 line!(),
             );
-            err.write_to_stderr();
+            err.write_to_stderr_with(&self.cfg.error_format);
             process::exit(1);
         }
     }
@@ -1001,6 +1010,10 @@ impl PyCodeGenerator {
     fn emit_acc(&mut self, acc: Accessor) {
         log!(info "entered {} ({acc})", fn_name!());
         match acc {
+            // e.g. `Array!(*, 0)`: the type arguments are erased at runtime
+            Accessor::Ident(ident) if ident.vis().is_private() && &ident.inspect()[..] == "*" => {
+                self.emit_load_const(ValueObj::None);
+            }
             Accessor::Ident(ident) => {
                 self.emit_load_name_instr(ident);
             }
@@ -1119,11 +1132,18 @@ impl PyCodeGenerator {
     // class T(metaclass=ABCMeta):
     //    def f(): pass
     fn emit_trait_block(&mut self, kind: DefKind, sig: &Signature, mut block: Block) -> CodeObj {
-        debug_assert_eq!(kind, DefKind::Trait);
+        debug_assert!(matches!(kind, DefKind::Trait | DefKind::StructuralTrait));
         let name = sig.ident().inspect().clone();
         let Expr::Call(mut trait_call) = block.remove(0) else {
             unreachable!()
         };
+        // `Structural Trait {...}` => `Trait {...}`
+        if kind == DefKind::StructuralTrait {
+            let Some(Expr::Call(inner)) = trait_call.args.remove_left_or_key("Type") else {
+                unreachable!()
+            };
+            trait_call = inner;
+        }
         let req = if let Some(Expr::Record(req)) = trait_call.args.remove_left_or_key("Requirement")
         {
             req.attrs.into_iter()
@@ -1169,7 +1189,7 @@ impl PyCodeGenerator {
                 block_id,
                 fn_name_full!(),
             )
-            .write_to_stderr();
+            .write_to_stderr_with(&self.cfg.error_format);
             self.crash("error in emit_trait_block: invalid stack size");
         }
         // flagging
@@ -1271,11 +1291,89 @@ impl PyCodeGenerator {
         self.write_arg(0);
         self.emit_load_const(ident.inspect().clone());
         // LOAD subclasses
-        let subclasses_len = self.emit_require_type(obj, require_or_sup);
+        let subclasses_len = self.emit_require_type(obj.clone(), require_or_sup);
         self.emit_call_instr(2 + subclasses_len, Name);
         self.stack_dec_n((1 + 2 + subclasses_len) - 1);
-        self.emit_store_instr(ident, Name);
+        self.emit_store_instr(ident.clone(), Name);
         self.stack_dec();
+        if let GenTypeObj::Enum(enum_) = obj {
+            self.emit_enum_variants(ident, enum_);
+        }
+    }
+
+    /// ```erg
+    /// E = Enum { .A = Int; .B = {x = Int} }
+    /// ```
+    /// ↓
+    /// ```python
+    /// class E: ...
+    /// class A(E):
+    ///     def __init__(self, _0): self._0 = _0
+    /// E.A = A
+    /// class B(E):
+    ///     def __init__(self, x): self.x = x.x
+    /// E.B = B
+    /// ```
+    fn emit_enum_variants(&mut self, enum_ident: Identifier, enum_: EnumTypeObj) {
+        log!(info "entered {} ({enum_ident})", fn_name!());
+        let Type::Record(variants) = enum_.variants.typ() else {
+            return;
+        };
+        // the methods of the enum may have been emitted after its definition
+        let line = enum_ident
+            .ln_begin()
+            .unwrap_or(0)
+            .max(self.cur_block().prev_lineno);
+        let enum_obj = GenTypeObj::Enum(enum_.clone());
+        // sorted so that the output does not depend on the order of the hash map
+        let mut variants = variants.iter().collect::<Vec<_>>();
+        variants.sort_by(|(l, _), (r, _)| l.symbol.cmp(&r.symbol));
+        for (field, payload) in variants {
+            let variant_t = mono(format!("{}.{}", enum_.t.qual_name(), field.symbol));
+            let gen = GenTypeObj::inherited(
+                variant_t.clone(),
+                TypeObj::Generated(enum_obj.clone()),
+                None,
+                Some(TypeObj::builtin_type(payload.clone())),
+            );
+            let variant_ident = if field.vis.is_public() {
+                Identifier::public_with_line(DOT, field.symbol.clone(), line)
+            } else {
+                Identifier::private_with_line(field.symbol.clone(), line)
+            };
+            let sig = Signature::Var(VarSignature::new(variant_ident.clone(), None));
+            let sup = Expr::Accessor(Accessor::Ident(enum_ident.clone()));
+            let variant = ClassDef::new(
+                gen.clone(),
+                sig,
+                Some(sup.clone()),
+                true,
+                func1(payload.clone(), variant_t),
+                Block::empty(),
+            );
+            self.emit_push_null();
+            self.write_instr(LOAD_BUILD_CLASS);
+            self.write_arg(0);
+            self.stack_inc();
+            let code = self.emit_class_block(variant);
+            self.emit_load_const(code);
+            if self.py_version.minor < Some(11) {
+                self.emit_load_const(field.symbol.clone());
+            } else {
+                self.stack_inc();
+            }
+            self.write_instr(MAKE_FUNCTION);
+            self.write_arg(0);
+            self.emit_load_const(field.symbol.clone());
+            let subclasses_len = self.emit_require_type(gen, Some(sup));
+            self.emit_call_instr(2 + subclasses_len, Name);
+            self.stack_dec_n((1 + 2 + subclasses_len) - 1);
+            self.store_acc(Accessor::attr(
+                Expr::Accessor(Accessor::Ident(enum_ident.clone())),
+                variant_ident,
+            ));
+            self.stack_dec();
+        }
     }
 
     fn emit_patch_def(&mut self, patch_def: PatchDef) {
@@ -1306,7 +1404,7 @@ impl PyCodeGenerator {
     fn emit_require_type(&mut self, obj: GenTypeObj, require_or_sup: Option<Expr>) -> usize {
         log!(info "entered {} ({obj}, {require_or_sup:?})", fn_name!());
         match obj {
-            GenTypeObj::Class(_) => 0,
+            GenTypeObj::Class(_) | GenTypeObj::Enum(_) => 0,
             GenTypeObj::Subclass(_) => {
                 self.emit_expr(require_or_sup.unwrap());
                 1 // TODO: not always 1
@@ -1338,6 +1436,7 @@ impl PyCodeGenerator {
     fn emit_subr_def(&mut self, class_name: Option<&str>, sig: SubrSignature, body: DefBody) {
         log!(info "entered {} ({sig} = {})", fn_name!(), body.block);
         let name = sig.ident.inspect().clone();
+        let is_async = sig.is_async();
         let mut make_function_flag = 0;
         let params = self.gen_param_names(&sig.params);
         if !sig.params.defaults.is_empty() {
@@ -1351,11 +1450,16 @@ impl PyCodeGenerator {
             self.stack_dec_n(defaults_len - 1);
             make_function_flag += MakeFunctionFlags::Defaults as usize;
         }
-        let flags = if sig.params.var_params.is_some() {
+        let mut flags = if sig.params.var_params.is_some() {
             CodeObjFlags::VarArgs as u32
         } else {
             0
         };
+        if is_async {
+            flags += CodeObjFlags::Coroutine as u32;
+        } else if body.block.yields(&name) {
+            flags += CodeObjFlags::Generator as u32;
+        }
         let code = self.emit_block(body.block, Some(name.clone()), params, flags);
         // code.flags += CodeObjFlags::Optimized as u32;
         self.register_cellvars(&mut make_function_flag);
@@ -1525,7 +1629,7 @@ impl PyCodeGenerator {
                     &unary.op.inspect().clone(),
                     String::from(unary.op.content),
                 )
-                .write_to_stderr();
+                .write_to_stderr_with(&self.cfg.error_format);
                 NOT_IMPLEMENTED
             }
         };
@@ -1638,7 +1742,7 @@ impl PyCodeGenerator {
                     &binop.inspect().clone(),
                     String::from(binop.content),
                 )
-                .write_to_stderr();
+                .write_to_stderr_with(&self.cfg.error_format);
                 Opcode310::NOT_IMPLEMENTED
             }
         };
@@ -1713,7 +1817,7 @@ impl PyCodeGenerator {
                     &binop.inspect().clone(),
                     String::from(binop.content),
                 )
-                .write_to_stderr();
+                .write_to_stderr_with(&self.cfg.error_format);
                 Opcode311::NOT_IMPLEMENTED
             }
         };
@@ -2428,6 +2532,7 @@ impl PyCodeGenerator {
             "discard" => self.emit_discard_instr(args),
             "for" | "for!" => self.emit_for_instr(args),
             "while!" => self.emit_while_instr(args),
+            "await!" => self.emit_await_instr(args),
             "if" | "if!" => self.emit_if_instr(args),
             "match" | "match!" => self.emit_match_instr(args, true),
             "with!" => match self.py_version.minor {
@@ -2651,6 +2756,43 @@ impl PyCodeGenerator {
         }
         self.write_instr(YIELD_VALUE);
         self.write_arg(0);
+        if self.py_version.minor >= Some(11) {
+            self.write_instr(Opcode311::RESUME);
+            self.write_arg(1);
+        }
+    }
+
+    /// `await! coro` => `GET_AWAITABLE; LOAD_CONST None; (SEND ... | YIELD_FROM)`
+    fn emit_await_instr(&mut self, mut args: Args) {
+        log!(info "entered {}", fn_name!());
+        let init_stack_len = self.stack_len();
+        self.emit_expr(args.remove(0));
+        if self.py_version.minor >= Some(11) {
+            self.write_instr(Opcode311::GET_AWAITABLE);
+        } else {
+            self.write_instr(Opcode310::GET_AWAITABLE);
+        }
+        self.write_arg(0);
+        self.emit_load_const(ValueObj::None);
+        if self.py_version.minor >= Some(11) {
+            // SEND: jump to the end when the awaitable is exhausted
+            self.write_instr(Opcode311::SEND);
+            self.write_arg(3);
+            self.write_instr(YIELD_VALUE);
+            self.write_arg(0);
+            // 3: resumed after `await`
+            self.write_instr(Opcode311::RESUME);
+            self.write_arg(3);
+            // jump back to SEND
+            self.write_instr(Opcode311::JUMP_BACKWARD_NO_INTERRUPT);
+            self.write_arg(4);
+        } else {
+            self.write_instr(Opcode310::YIELD_FROM);
+            self.write_arg(0);
+        }
+        // <awaitable> + None -> <result>
+        self.stack_dec();
+        debug_assert_eq!(self.stack_len(), init_stack_len + 1);
     }
 
     /// 1.abs() => abs(1)
@@ -2966,7 +3108,7 @@ impl PyCodeGenerator {
                     fn_name_full!(),
                     line!(),
                 )
-                .write_to_stderr();
+                .write_to_stderr_with(&self.cfg.error_format);
                 self.crash("codegen failed: invalid bytecode format");
             }
         }
@@ -3136,9 +3278,25 @@ impl PyCodeGenerator {
         self.emit_store_instr(Identifier::public("__module__"), Name);
         self.emit_load_const(name);
         self.emit_store_instr(Identifier::public("__qualname__"), Name);
-        self.emit_init_method(&class.sig, class.__new__.clone());
+        // enum variants with a non-record payload store it as `_0`
+        let is_variant = matches!(
+            &class.obj,
+            GenTypeObj::Subclass(sub)
+                if matches!(sub.sup.as_ref(), TypeObj::Generated(GenTypeObj::Enum(_)))
+        );
+        self.emit_init_method(&class.sig, class.__new__.clone(), is_variant);
         if class.need_to_gen_new {
-            self.emit_new_func(&class.sig, class.__new__);
+            let class_expr = if is_variant {
+                // E.A
+                let enum_ = class.require_or_sup.as_ref().unwrap().as_ref().clone();
+                enum_.attr_expr(class.sig.ident().clone())
+            } else {
+                Expr::Accessor(Accessor::Ident(class.sig.ident().clone()))
+            };
+            self.emit_new_func(&class.sig, class_expr, class.__new__);
+        }
+        if !class.obj.typ().is_monomorphic() {
+            self.emit_class_getitem_func(&class.sig);
         }
         if !class.methods.is_empty() {
             self.emit_frameless_block(class.methods, vec![]);
@@ -3158,7 +3316,7 @@ impl PyCodeGenerator {
                 block_id,
                 fn_name_full!(),
             )
-            .write_to_stderr();
+            .write_to_stderr_with(&self.cfg.error_format);
             self.crash("error in emit_class_block: invalid stack size");
         }
         // flagging
@@ -3179,7 +3337,7 @@ impl PyCodeGenerator {
         unit.codeobj
     }
 
-    fn emit_init_method(&mut self, sig: &Signature, __new__: Type) {
+    fn emit_init_method(&mut self, sig: &Signature, __new__: Type, is_variant: bool) {
         log!(info "entered {}", fn_name!());
         let new_first_param = __new__.non_default_params().unwrap().first();
         let line = sig.ln_begin().unwrap_or(0);
@@ -3225,7 +3383,9 @@ impl PyCodeGenerator {
             //   => self.x = %x.x; self.y = %x.y
             // () => pass
             Some(Type::Record(rec)) => {
-                for field in rec.keys() {
+                let mut fields = rec.keys().collect::<Vec<_>>();
+                fields.sort_by(|l, r| l.symbol.cmp(&r.symbol));
+                for field in fields {
                     let obj =
                         Expr::Accessor(Accessor::private_with_line(Str::from(&param_name), line));
                     let ident = erg_parser::ast::Identifier::public(field.symbol.clone());
@@ -3245,12 +3405,16 @@ impl PyCodeGenerator {
                     attrs.push(Expr::ReDef(redef));
                 }
             }
-            // self::base = %x
+            // self::base = %x (self._0 = %x for enum variants)
             Some(_) => {
                 let expr =
                     Expr::Accessor(Accessor::private_with_line(Str::from(&param_name), line));
                 let obj = Expr::Accessor(Accessor::private_with_line(Str::ever("self"), line));
-                let attr = obj.attr(Identifier::private_with_line(Str::ever("base"), line));
+                let attr = if is_variant {
+                    obj.attr(Identifier::public_with_line(DOT, Str::ever("_0"), line))
+                } else {
+                    obj.attr(Identifier::private_with_line(Str::ever("base"), line))
+                };
                 let redef = ReDef::new(attr, Block::new(vec![expr]));
                 attrs.push(Expr::ReDef(redef));
             }
@@ -3268,12 +3432,11 @@ impl PyCodeGenerator {
     ///     # __new__ => __call__
     ///     def new(x): return C.__call__(x)
     /// ```
-    fn emit_new_func(&mut self, sig: &Signature, __new__: Type) {
+    fn emit_new_func(&mut self, sig: &Signature, class: Expr, __new__: Type) {
         log!(info "entered {}", fn_name!());
         let class_ident = sig.ident();
         let line = sig.ln_begin().unwrap_or(0);
         let mut ident = Identifier::public_with_line(DOT, Str::ever("new"), line);
-        let class = Expr::Accessor(Accessor::Ident(class_ident.clone()));
         let mut new_ident = Identifier::private_with_line(Str::ever("__new__"), line);
         new_ident.vi.py_name = Some(Str::ever("__call__"));
         let class_new = class.attr_expr(new_ident);
@@ -3313,6 +3476,30 @@ impl PyCodeGenerator {
         }
     }
 
+    /// The type arguments are erased at runtime.
+    /// ```python
+    /// def __class_getitem__(cls, _): return cls
+    /// ```
+    fn emit_class_getitem_func(&mut self, sig: &Signature) {
+        log!(info "entered {}", fn_name!());
+        let class_ident = sig.ident();
+        let line = sig.ln_begin().unwrap_or(0);
+        let ident = Identifier::public_with_line(DOT, Str::ever("__class_getitem__"), line);
+        let param = |name: &'static str| {
+            let vi = VarInfo::nd_parameter(Type::Obj, ident.vi.def_loc.clone(), "?".into());
+            let raw = erg_parser::ast::NonDefaultParamSignature::new(
+                ParamPattern::VarName(VarName::from_str_and_line(Str::ever(name), line)),
+                None,
+            );
+            NonDefaultParamSignature::new(raw, vi, None)
+        };
+        let params = Params::new(vec![param("cls"), param("_")], None, vec![], None);
+        let sig = SubrSignature::new(ident, TypeBoundSpecs::empty(), params, None);
+        let cls = Expr::Accessor(Accessor::private_with_line(Str::ever("cls"), line));
+        let body = DefBody::new(EQUAL, Block::new(vec![cls]), DefId(0));
+        self.emit_subr_def(Some(class_ident.inspect()), sig, body);
+    }
+
     fn emit_block(
         &mut self,
         block: Block,
@@ -3340,14 +3527,28 @@ impl PyCodeGenerator {
             firstlineno,
             flags,
         ));
+        // 0: generator, 1: coroutine, 2: async generator
+        let gen_kind = if flags & CodeObjFlags::Coroutine as u32 != 0 {
+            Some(1)
+        } else if flags & CodeObjFlags::Generator as u32 != 0 {
+            Some(0)
+        } else {
+            None
+        };
         let idx_copy_free_vars = if self.py_version.minor >= Some(11) {
             let idx_copy_free_vars = self.lasti();
             self.write_instr(Opcode311::COPY_FREE_VARS);
             self.write_arg(0);
+            if let Some(kind) = gen_kind {
+                self.emit_gen_start(kind);
+            }
             self.write_instr(Opcode311::RESUME);
             self.write_arg(0);
             idx_copy_free_vars
         } else {
+            if let Some(kind) = gen_kind {
+                self.emit_gen_start(kind);
+            }
             0
         };
         let init_stack_len = self.stack_len();
@@ -3373,7 +3574,7 @@ impl PyCodeGenerator {
                 block_id,
                 fn_name_full!(),
             )
-            .write_to_stderr();
+            .write_to_stderr_with(&self.cfg.error_format);
             self.crash("error in emit_block: invalid stack size");
         }
         self.write_instr(RETURN_VALUE);
@@ -3411,6 +3612,27 @@ impl PyCodeGenerator {
         unit.codeobj
     }
 
+    /// Creates a coroutine object from the current frame.
+    /// When the coroutine is started, the first sent value (`None`) is discarded.
+    fn emit_gen_start(&mut self, kind: usize) {
+        match self.py_version.minor {
+            Some(11) => {
+                self.write_instr(Opcode311::RETURN_GENERATOR);
+                self.write_arg(0);
+                self.stack_inc();
+                self.emit_pop_top();
+            }
+            Some(10) => {
+                self.write_instr(Opcode310::GEN_START);
+                self.write_arg(kind);
+                self.stack_inc();
+                self.stack_dec();
+            }
+            // <= 3.9: no prologue is needed
+            _ => {}
+        }
+    }
+
     fn load_prelude(&mut self) {
         // NOTE: Integers need to be used in IMPORT_NAME
         // but `Int` are called before importing it, so they need to be no_std mode
@@ -3572,7 +3794,7 @@ impl PyCodeGenerator {
                 block_id,
                 fn_name_full!(),
             )
-            .write_to_stderr();
+            .write_to_stderr_with(&self.cfg.error_format);
             self.crash("error in emit: invalid stack size");
         }
         self.write_instr(RETURN_VALUE);
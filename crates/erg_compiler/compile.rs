@@ -1,19 +1,24 @@
 //! defines `Compiler`.
 //!
 //! コンパイラーを定義する
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use erg_common::config::ErgConfig;
 use erg_common::dict::Dict;
 use erg_common::error::MultiErrorDisplay;
+use erg_common::io::Input;
 use erg_common::log;
+use erg_common::style::Highlight;
 use erg_common::traits::{ExitStatus, Runnable, Stream};
 use erg_parser::ast::VarName;
+use erg_parser::highlight::highlight;
 
 use crate::artifact::{CompleteArtifact, ErrorArtifact};
 use crate::context::{Context, ContextProvider};
 use crate::optimize::HIROptimizer;
+use crate::peephole::PeepholeOptimizer;
 use crate::ty::codeobj::CodeObj;
+use crate::ty::Type;
 
 use crate::build_hir::HIRBuilder;
 use crate::codegen::PyCodeGenerator;
@@ -131,12 +136,7 @@ impl Runnable for Compiler {
 
     fn new(cfg: ErgConfig) -> Self {
         let shared = SharedCompilerResource::new(cfg.copy());
-        Self {
-            shared: shared.clone(),
-            builder: HIRBuilder::new_with_cache(cfg.copy(), "<module>", shared),
-            code_generator: PyCodeGenerator::new(cfg.copy()),
-            cfg,
-        }
+        Self::new_with_cache(cfg, shared)
     }
 
     #[inline]
@@ -168,21 +168,60 @@ impl Runnable for Compiler {
         let warns = self
             .compile_and_dump_as_pyc(path, src, "exec")
             .map_err(|eart| {
-                eart.warns.write_all_stderr();
+                eart.warns.write_all_stderr_with(&self.cfg().error_format);
                 eart.errors
             })?;
-        warns.write_all_stderr();
+        warns.write_all_stderr_with(&self.cfg().error_format);
         Ok(ExitStatus::compile_passed(warns.len()))
     }
 
     fn eval(&mut self, src: String) -> Result<String, CompileErrors> {
         let arti = self.compile(src, "eval").map_err(|eart| {
-            eart.warns.write_all_stderr();
+            eart.warns.write_all_stderr_with(&self.cfg().error_format);
             eart.errors
         })?;
-        arti.warns.write_all_stderr();
+        arti.warns.write_all_stderr_with(&self.cfg().error_format);
         Ok(arti.object.code_info(Some(self.code_generator.py_version)))
     }
+
+    fn type_of(&mut self, src: String) -> Option<Result<String, Self::Errs>> {
+        Some(self.infer_type(src).map(|t| t.to_string()))
+    }
+
+    fn doc_of(&self, name: &str) -> Option<String> {
+        self.signature_and_doc(name)
+    }
+
+    fn dir_of(&self, receiver: Option<&str>) -> Option<Vec<String>> {
+        self.dir_lines(receiver)
+    }
+
+    fn complete(&self, word: &str) -> Vec<String> {
+        self.completion_candidates(word)
+    }
+
+    fn highlight(&self, line: &str, cursor: usize) -> Vec<Highlight> {
+        highlight(line, cursor)
+    }
+
+    fn pre_check(&mut self, src: String) -> Result<(), Self::Errs> {
+        self.builder.pre_check(src)
+    }
+
+    fn eval_file(&mut self, path: PathBuf, src: String) -> Result<String, Self::Errs> {
+        let input = self.replace_input(Input::file(path));
+        let res = self.eval(src);
+        self.replace_input(input);
+        res
+    }
+
+    fn reload(&mut self) -> Option<Result<Vec<String>, Self::Errs>> {
+        let (names, src) = self.unload_modified_modules();
+        if names.is_empty() {
+            return Some(Ok(names));
+        }
+        Some(self.eval(src).map(|_| names))
+    }
 }
 
 impl ContextProvider for Compiler {
@@ -200,15 +239,29 @@ impl ContextProvider for Compiler {
 }
 
 impl Compiler {
+    pub fn new_with_cache(cfg: ErgConfig, shared: SharedCompilerResource) -> Self {
+        Self {
+            shared: shared.clone(),
+            builder: HIRBuilder::new_with_cache(cfg.copy(), "<module>", shared),
+            code_generator: PyCodeGenerator::new(cfg.copy()),
+            cfg,
+        }
+    }
+
     pub fn compile_and_dump_as_pyc<P: AsRef<Path>>(
         &mut self,
         pyc_path: P,
         src: String,
         mode: &str,
     ) -> Result<CompileWarnings, ErrorArtifact> {
-        let arti = self.compile(src, mode)?;
+        let arti = self.compile(src.clone(), mode)?;
         arti.object
-            .dump_as_pyc(pyc_path, self.cfg.py_magic_num)
+            .dump_as_pyc_with_mode(
+                pyc_path,
+                self.cfg.py_magic_num,
+                self.cfg.invalidation_mode,
+                &src,
+            )
             .expect("failed to dump a .pyc file (maybe permission denied)");
         Ok(arti.warns)
     }
@@ -219,10 +272,15 @@ impl Compiler {
         src: String,
         mode: &str,
     ) -> Result<CompleteArtifact<Option<Expr>>, ErrorArtifact> {
-        let arti = self.eval_compile(src, mode)?;
+        let arti = self.eval_compile(src.clone(), mode)?;
         let (code, last) = arti.object;
-        code.dump_as_pyc(pyc_path, self.cfg.py_magic_num)
-            .expect("failed to dump a .pyc file (maybe permission denied)");
+        code.dump_as_pyc_with_mode(
+            pyc_path,
+            self.cfg.py_magic_num,
+            self.cfg.invalidation_mode,
+            &src,
+        )
+        .expect("failed to dump a .pyc file (maybe permission denied)");
         Ok(CompleteArtifact::new(last, arti.warns))
     }
 
@@ -234,6 +292,7 @@ impl Compiler {
         log!(info "the compiling process has started.");
         let arti = self.build_link_desugar_optimize(src, mode)?;
         let codeobj = self.code_generator.emit(arti.object);
+        let codeobj = self.optimize_bytecode(codeobj);
         log!(info "code object:\n{}", codeobj.code_info(Some(self.code_generator.py_version)));
         log!(info "the compiling process has completed");
         Ok(CompleteArtifact::new(codeobj, arti.warns))
@@ -253,6 +312,7 @@ impl Compiler {
         let arti = self.build_link_desugar_optimize(src, mode)?;
         let last = arti.object.module.last().cloned();
         let codeobj = self.code_generator.emit(arti.object);
+        let codeobj = self.optimize_bytecode(codeobj);
         log!(info "code object:\n{}", codeobj.code_info(Some(self.code_generator.py_version)));
         log!(info "the compiling process has completed");
         Ok(CompleteArtifact::new((codeobj, last), arti.warns))
@@ -271,7 +331,42 @@ impl Compiler {
         Ok(CompleteArtifact::new(hir, artifact.warns))
     }
 
+    fn optimize_bytecode(&self, codeobj: CodeObj) -> CodeObj {
+        PeepholeOptimizer::optimize(self.cfg.clone(), self.code_generator.py_version, codeobj)
+    }
+
     pub fn initialize_generator(&mut self) {
         self.code_generator.initialize();
     }
+
+    /// Infers the type of the last expression of `src` (see `HIRBuilder::infer_type`).
+    pub fn infer_type(&mut self, src: String) -> Result<Type, CompileErrors> {
+        self.builder.infer_type(src)
+    }
+
+    /// see `HIRBuilder::pre_check`
+    pub fn pre_check(&mut self, src: String) -> Result<(), CompileErrors> {
+        self.builder.pre_check(src)
+    }
+
+    /// see `HIRBuilder::unload_modified_modules`
+    pub fn unload_modified_modules(&mut self) -> (Vec<String>, String) {
+        self.builder.unload_modified_modules()
+    }
+
+    /// The input of the type checker (differs from `cfg.input` while replaced by `replace_input`).
+    pub fn checker_input(&self) -> &Input {
+        &self.builder.cfg().input
+    }
+
+    /// Replaces the input of the type checker and returns the old one.
+    /// The code generator still treats the code as the original input.
+    pub fn replace_input(&mut self, input: Input) -> Input {
+        self.builder.replace_input(input)
+    }
+
+    /// see `Context::register_external`
+    pub fn register_external(&mut self, name: &str, t: Type) {
+        self.builder.register_external(name, t)
+    }
 }
@@ -3,6 +3,7 @@ use std::option::Option; // conflicting to Type::Option
 
 use erg_common::consts::DEBUG_MODE;
 use erg_common::dict::Dict;
+use erg_common::set::Set;
 use erg_common::style::colors::DEBUG_ERROR;
 use erg_common::traits::StructuralEq;
 use erg_common::{assume_unreachable, log};
@@ -14,7 +15,9 @@ use crate::ty::free::{Constraint, FreeKind, FreeTyVar};
 use crate::ty::typaram::{TyParam, TyParamOrdering};
 use crate::ty::value::ValueObj;
 use crate::ty::value::ValueObj::Inf;
-use crate::ty::{Field, GuardType, Predicate, RefinementType, SubrKind, SubrType, Type};
+use crate::ty::{
+    CastTarget, Field, GuardType, Predicate, RefinementType, SubrKind, SubrType, Type,
+};
 use Predicate as Pred;
 
 use TyParamOrdering::*;
@@ -530,6 +533,15 @@ impl Context {
                 true
             }
             (Bool, Guard { .. }) => true,
+            // {x#0 in Int} :> {x in Int} (is_int(x: Obj): {x in Int} = isinstance(x, Int))
+            (Guard(l), Guard(r)) => {
+                l.target.is_same_target(&r.target) && self.same_type_of(&l.to, &r.to)
+            }
+            // e.g. Norm :> P (Norm = Structural Trait {.norm = (self: Self) -> Int}; P.norm: (self: P) -> Nat)
+            (Mono(_), r) if !matches!(r, Or(_, _)) && self.is_structural_trait(lhs) => {
+                let (missing, incompatible) = self.structural_trait_mismatches(lhs, r);
+                missing.is_empty() && incompatible.is_empty()
+            }
             (Mono(n), NamedTuple(_)) => &n[..] == "GenericNamedTuple" || &n[..] == "GenericTuple",
             (Mono(n), Record(_)) => &n[..] == "Record",
             (Type, Subr(subr)) => self.supertype_of(&Type, &subr.return_t),
@@ -696,6 +708,18 @@ impl Context {
             }
             // not Nat :> not Int == true
             (Not(l), Not(r)) => self.subtype_of(l, r),
+            // (E.A or E.B) :> E == true (if `E = Enum { .A = ...; .B = ... }`)
+            // (each variant is compared with the union members to avoid infinite recursion via `E.A <: E`)
+            (Or(_, _), Mono(_))
+                if self.get_enum_variants(rhs).is_some_and(|variants| {
+                    let members = lhs.union_types();
+                    variants
+                        .iter()
+                        .all(|v| members.iter().any(|m| self.supertype_of(m, v)))
+                }) =>
+            {
+                true
+            }
             // (Int or Str) :> Nat == Int :> Nat || Str :> Nat == true
             // (Num or Show) :> Show == Num :> Show || Show :> Num == true
             (Or(l_or, r_or), rhs) => self.supertype_of(l_or, rhs) || self.supertype_of(r_or, rhs),
@@ -711,6 +735,10 @@ impl Context {
                 (self.supertype_of(l_1, r_1) && self.supertype_of(l_2, r_2))
                     || (self.supertype_of(l_1, r_2) && self.supertype_of(l_2, r_1))
             }
+            // ((Int -> Int) and (Str -> Str)) :> (Int or Str -> Int or Str) == true
+            (Overloaded(overloads), rhs) => overloads.iter().all(|l| self.supertype_of(l, rhs)),
+            // (Int -> Int) :> ((Int -> Int) and (Str -> Str)) == true
+            (lhs, Overloaded(overloads)) => overloads.iter().any(|r| self.supertype_of(lhs, r)),
             // (Num and Show) :> Show == false
             (And(l_and, r_and), rhs) => {
                 self.supertype_of(l_and, rhs) && self.supertype_of(r_and, rhs)
@@ -780,6 +808,31 @@ impl Context {
         }
     }
 
+    /// Returns the members of the structural trait `trait_` that `rhs` does not have,
+    /// and the members whose types are incompatible (`(field, required, found)`).
+    /// `Self` in the requirements is replaced with `rhs`.
+    pub(crate) fn structural_trait_mismatches(
+        &self,
+        trait_: &Type,
+        rhs: &Type,
+    ) -> (Vec<Field>, Vec<(Field, Type, Type)>) {
+        let mut missing = vec![];
+        let mut incompatible = vec![];
+        let r_fields = self.fields(rhs);
+        for (l_field, l_ty) in self.fields(trait_) {
+            let l_ty = l_ty.replace(trait_, rhs);
+            match r_fields.get_key_value(&l_field) {
+                Some((r_field, r_ty)) if r_field.vis == l_field.vis => {
+                    if !self.supertype_of(&l_ty, r_ty) {
+                        incompatible.push((l_field, l_ty, r_ty.clone()));
+                    }
+                }
+                _ => missing.push(l_field),
+            }
+        }
+        (missing, incompatible)
+    }
+
     pub fn fields(&self, t: &Type) -> Dict<Field, Type> {
         match t {
             Type::FreeVar(fv) if fv.is_linked() => self.fields(&fv.crack()),
@@ -890,16 +943,30 @@ impl Context {
                 true
             }
             // {Int: Str} :> {Int: Str, Bool: Int}
+            // {"a": Int, "b": NotRequired(Str)} :> {"a": Int}
             (TyParam::Dict(sup_d), TyParam::Dict(sub_d)) => {
-                if sup_d.len() > sub_d.len() {
-                    return false;
+                for (sup_k, sup_v) in sup_d.iter() {
+                    if sup_v.not_required_inner().is_some() {
+                        continue;
+                    }
+                    if !sub_d.keys().any(|sub_k| {
+                        sub_k == sup_k || self.supertype_of_tp(sup_k, sub_k, Variance::Covariant)
+                    }) {
+                        return false;
+                    }
                 }
                 for (sub_k, sub_v) in sub_d.iter() {
                     if let Some(sup_v) = sup_d
                         .get(sub_k)
                         .or_else(|| sub_tpdict_get(sup_d, sub_k, self))
                     {
-                        if !self.supertype_of_tp(sup_v, sub_v, variance) {
+                        let sup_v = match (sup_v.not_required_inner(), sub_v.not_required_inner()) {
+                            (Some(inner), None) => inner,
+                            // an optional key cannot be a required key
+                            (None, Some(_)) => return false,
+                            _ => sup_v.clone(),
+                        };
+                        if !self.supertype_of_tp(&sup_v, sub_v, variance) {
                             return false;
                         }
                     } else {
@@ -928,10 +995,21 @@ impl Context {
                     self.subtype_of(&fv_t, &sub_t)
                 } else if variance == Variance::Covariant {
                     self.supertype_of(&fv_t, &sub_t)
+                } else if matches!(sub_p, TyParam::Value(v) if !v.is_type()) {
+                    // e.g. `?N(: Nat) :> 1` (`2 - 1: Int`)
+                    self.supertype_of(&fv_t, &sub_t)
                 } else {
                     self.same_type_of(&fv_t, &sub_t) || self.same_type_of(&fv_t, &sub_t.derefine())
                 }
             }
+            // e.g. `0 :> ?N(: Nat)` (`0: Nat`)
+            (TyParam::Value(v), TyParam::FreeVar(fv)) if fv.is_unbound() && !v.is_type() => {
+                let Some(fv_t) = fv.get_type() else {
+                    return false;
+                };
+                self.get_tp_t(sup_p)
+                    .is_ok_and(|sup_t| self.supertype_of(&fv_t, &sup_t))
+            }
             (TyParam::Value(sup), _) => {
                 if let Ok(sup) = Self::convert_value_into_tp(sup.clone()) {
                     self.supertype_of_tp(&sup, sub_p, variance)
@@ -1498,9 +1576,107 @@ impl Context {
         }
     }
 
+    /// Returns the negation of a condition type (`None` if it has no guards).
+    /// ```erg
+    /// negate_guard({x in Int}) == Some({x in not Int})
+    /// negate_guard({x in Int} and Bool) == Some({x in not Int} or Bool)
+    /// negate_guard(Bool) == None
+    /// ```
+    pub(crate) fn negate_guard(&self, cond_t: &Type) -> Option<Type> {
+        if !cond_t.is_guard() {
+            return None;
+        }
+        match cond_t {
+            FreeVar(fv) if fv.is_linked() => self.negate_guard(&fv.crack()),
+            Guard(_) => Some(self.complement(cond_t)),
+            And(l, r) => Some(or(
+                self.negate_guard(l).unwrap_or(Bool),
+                self.negate_guard(r).unwrap_or(Bool),
+            )),
+            Or(l, r) => Some(and(
+                self.negate_guard(l).unwrap_or(Bool),
+                self.negate_guard(r).unwrap_or(Bool),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Returns the guards that hold when a condition of type `cond_t` is `True`
+    /// (or `False` if `negated`).
+    /// ```erg
+    /// guards_of({x in Int} and {y in Str}, false) == [{x in Int}, {y in Str}]
+    /// guards_of({x in Int} and {y in Str}, true) == []
+    /// guards_of({x in Int} or {x in Str}, false) == [{x in Int or Str}]
+    /// guards_of({x in Int} or Bool, true) == [{x in not Int}]
+    /// ```
+    pub(crate) fn guards_of(&self, cond_t: &Type, negated: bool) -> Vec<GuardType> {
+        match cond_t {
+            FreeVar(fv) if fv.is_linked() => self.guards_of(&fv.crack(), negated),
+            Guard(guard) if negated => {
+                let guard = GuardType::new(
+                    guard.namespace.clone(),
+                    guard.target.clone(),
+                    self.complement(&guard.to),
+                );
+                // narrowing to the complement is best-effort
+                if self.is_castable(&guard) {
+                    vec![guard]
+                } else {
+                    vec![]
+                }
+            }
+            Guard(guard) => vec![guard.clone()],
+            // not (A and B) == not A or not B
+            And(l, r) if negated => {
+                self.union_guards(self.guards_of(l, negated), self.guards_of(r, negated))
+            }
+            And(l, r) => {
+                let mut guards = self.guards_of(l, negated);
+                guards.extend(self.guards_of(r, negated));
+                guards
+            }
+            // not (A or B) == not A and not B
+            Or(l, r) if negated => {
+                let mut guards = self.guards_of(l, negated);
+                guards.extend(self.guards_of(r, negated));
+                guards
+            }
+            Or(l, r) => self.union_guards(self.guards_of(l, negated), self.guards_of(r, negated)),
+            _ => vec![],
+        }
+    }
+
+    /// Returns `false` if the current type of the target cannot be narrowed by `guard`.
+    fn is_castable(&self, guard: &GuardType) -> bool {
+        let CastTarget::Var { name, .. } = &guard.target else {
+            return true;
+        };
+        let Some((_, vi)) = self.get_var_kv(name) else {
+            return true;
+        };
+        self.recover_typarams(&vi.t, guard).is_ok()
+    }
+
+    /// Only targets narrowed on both sides can be narrowed (to the union).
+    fn union_guards(&self, lhs: Vec<GuardType>, rhs: Vec<GuardType>) -> Vec<GuardType> {
+        let mut guards = vec![];
+        for l in lhs.into_iter() {
+            if let Some(r) = rhs
+                .iter()
+                .find(|r| r.namespace == l.namespace && r.target.is_same_target(&l.target))
+            {
+                let to = self.union(&l.to, &r.to);
+                guards.push(GuardType::new(l.namespace, l.target, to));
+            }
+        }
+        guards
+    }
+
     /// Returns difference of two types (`A - B` == `A and not B`).
     /// ```erg
     /// (A or B).diff(B) == A
+    /// {1, 2, 3}.diff({1}) == {2, 3}
+    /// E.diff(E.A) == E.B # E = Enum { .A = ...; .B = ... }
     /// ```
     pub fn diff(&self, lhs: &Type, rhs: &Type) -> Type {
         match (self.supertype_of(lhs, rhs), self.subtype_of(lhs, rhs)) {
@@ -1512,6 +1688,31 @@ impl Context {
             Type::FreeVar(fv) if fv.is_linked() => self.diff(&fv.crack(), rhs),
             // Type::And(l, r) => self.intersection(&self.diff(l, rhs), &self.diff(r, rhs)),
             Type::Or(l, r) => self.union(&self.diff(l, rhs), &self.diff(r, rhs)),
+            Type::Refinement(refine) if refine.pred.consist_of_equal() => {
+                let mut rest = Set::new();
+                for pred in refine.pred.ors() {
+                    let Pred::Equal { rhs: tp, .. } = pred else {
+                        return lhs.clone();
+                    };
+                    if !self
+                        .subtype_of(&constructors::singleton(*refine.t.clone(), tp.clone()), rhs)
+                    {
+                        rest.insert(tp.clone());
+                    }
+                }
+                if rest.is_empty() {
+                    Type::Never
+                } else {
+                    constructors::tp_enum(*refine.t.clone(), rest)
+                }
+            }
+            Type::Mono(_) => match self.get_enum_variants(lhs) {
+                Some(variants) => variants
+                    .into_iter()
+                    .filter(|variant| !self.subtype_of(variant, rhs))
+                    .fold(Type::Never, |acc, variant| self.union(&acc, &variant)),
+                None => lhs.clone(),
+            },
             _ => lhs.clone(),
         }
     }
@@ -27,7 +27,7 @@ use crate::ty::free::{Constraint, HasLevel};
 use crate::ty::typaram::{OpKind, TyParam};
 use crate::ty::value::{GenTypeObj, TypeObj, ValueObj};
 use crate::ty::{
-    ConstSubr, HasType, Predicate, SubrKind, Type, UserConstSubr, ValueArgs, Visibility,
+    ConstSubr, HasType, Predicate, SubrKind, SubrType, Type, UserConstSubr, ValueArgs, Visibility,
 };
 
 use crate::context::instantiate_spec::ParamKind;
@@ -38,6 +38,21 @@ use crate::varinfo::VarInfo;
 use super::instantiate::TyVarCache;
 use Type::{Failure, Never, Subr};
 
+/// The maximum number of user-defined const subroutine calls in one compile-time evaluation.
+const CONST_EVAL_STEP_LIMIT: usize = 2000;
+/// The maximum nesting depth of user-defined const subroutine calls.
+const CONST_EVAL_DEPTH_LIMIT: usize = 64;
+
+/// The state of a compile-time evaluation of a user-defined const subroutine.
+/// All the nested calls are evaluated in the context created by the outermost call,
+/// and the arguments of each call are bound in a frame instead of a new context.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ConstEvalState {
+    steps: Shared<usize>,
+    /// the innermost frame is the last one
+    frames: Shared<Vec<Dict<VarName, ValueObj>>>,
+}
+
 macro_rules! feature_error {
     ($ctx: expr, $loc: expr, $name: expr) => {
         $crate::feature_error!(EvalErrors, EvalError, $ctx, $loc, $name)
@@ -457,7 +472,11 @@ impl Context {
     }
 
     fn eval_const_ident(&self, ident: &Identifier) -> EvalResult<ValueObj> {
-        if let Some(val) = self.rec_get_const_obj(ident.inspect()) {
+        if let Some(val) = self.consts.get(ident.inspect()) {
+            Ok(val.clone())
+        } else if let Some(val) = self.get_const_eval_bind(ident.inspect()) {
+            Ok(val)
+        } else if let Some(val) = self.rec_get_const_obj(ident.inspect()) {
             Ok(val.clone())
         } else if self.kind.is_subr() {
             feature_error!(self, ident.loc(), "const parameters")
@@ -554,6 +573,12 @@ impl Context {
     fn tp_eval_const_call(&self, call: &Call) -> EvalResult<TyParam> {
         if let Expr::Accessor(acc) = call.obj.as_ref() {
             match acc {
+                Accessor::Ident(ident) if &ident.inspect()[..] == "if" => {
+                    self.eval_const_if(call).map(TyParam::value)
+                }
+                Accessor::Ident(ident) if &ident.inspect()[..] == "match" => {
+                    self.eval_const_match(call).map(TyParam::value)
+                }
                 Accessor::Ident(ident) => {
                     let obj = self.rec_get_const_obj(ident.inspect()).ok_or_else(|| {
                         EvalError::no_var_error(
@@ -619,27 +644,23 @@ impl Context {
     fn call(&self, subr: ConstSubr, args: ValueArgs, loc: Location) -> EvalResult<TyParam> {
         match subr {
             ConstSubr::User(user) => {
-                // HACK: should avoid cloning
-                let mut subr_ctx = Context::instant(
-                    user.name.clone(),
-                    self.cfg.clone(),
-                    2,
-                    self.shared.clone(),
-                    self.clone(),
-                );
-                // TODO: var_args
-                for (arg, sig) in args
-                    .pos_args
-                    .into_iter()
-                    .zip(user.params.non_defaults.iter())
-                {
-                    let name = VarName::from_str(sig.inspect().unwrap().clone());
-                    subr_ctx.consts.insert(name, arg);
-                }
-                for (name, arg) in args.kw_args.into_iter() {
-                    subr_ctx.consts.insert(VarName::from_str(name), arg);
-                }
-                subr_ctx.eval_const_block(&user.block()).map(TyParam::value)
+                let res = if let Some(state) = self.get_const_eval_state() {
+                    self.eval_const_subr_call(state, user, args, loc)
+                } else {
+                    // the caller is cloned only once per top-level evaluation
+                    let mut eval_ctx = Context::instant(
+                        user.name.clone(),
+                        self.cfg.clone(),
+                        0,
+                        self.shared.clone(),
+                        self.clone(),
+                    );
+                    let state = ConstEvalState::default();
+                    eval_ctx.const_eval = Some(state.clone());
+                    eval_ctx.eval_const_subr_call(&state, user, args, loc)
+                };
+                res.map(TyParam::value)
+                    .map_err(|errs| self.relocate_errors(errs, loc))
             }
             ConstSubr::Builtin(builtin) => builtin.call(args, self).map_err(|mut e| {
                 if e.0.loc.is_unknown() {
@@ -664,6 +685,444 @@ impl Context {
         }
     }
 
+    fn get_const_eval_state(&self) -> Option<&ConstEvalState> {
+        self.const_eval
+            .as_ref()
+            .or_else(|| self.get_outer()?.get_const_eval_state())
+    }
+
+    /// Returns the value bound to `name` in the innermost frame of the evaluation in progress.
+    fn get_const_eval_bind(&self, name: &str) -> Option<ValueObj> {
+        let state = self.get_const_eval_state()?;
+        let frames = state.frames.borrow();
+        frames.last()?.get(name).cloned()
+    }
+
+    /// Count a call of a user-defined const subroutine.
+    fn enter_const_call(
+        &self,
+        state: &ConstEvalState,
+        callee_name: &str,
+        loc: Location,
+    ) -> EvalResult<()> {
+        *state.steps.borrow_mut() += 1;
+        if *state.steps.borrow() > CONST_EVAL_STEP_LIMIT {
+            return Err(EvalErrors::from(EvalError::recursion_limit_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                loc,
+                self.caused_by(),
+                callee_name,
+                CONST_EVAL_STEP_LIMIT,
+            )));
+        }
+        if state.frames.borrow().len() >= CONST_EVAL_DEPTH_LIMIT {
+            return Err(EvalErrors::from(EvalError::recursion_limit_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                loc,
+                self.caused_by(),
+                callee_name,
+                CONST_EVAL_DEPTH_LIMIT,
+            )));
+        }
+        Ok(())
+    }
+
+    fn eval_const_subr_call(
+        &self,
+        state: &ConstEvalState,
+        subr: UserConstSubr,
+        args: ValueArgs,
+        loc: Location,
+    ) -> EvalResult<ValueObj> {
+        self.enter_const_call(state, &subr.name, loc)?;
+        let frame = self.bind_const_args(&subr, args, loc)?;
+        self.eval_const_in_frame(state, frame, &subr.block())
+    }
+
+    fn eval_const_in_frame(
+        &self,
+        state: &ConstEvalState,
+        frame: Dict<VarName, ValueObj>,
+        block: &Block,
+    ) -> EvalResult<ValueObj> {
+        state.frames.borrow_mut().push(frame);
+        let res = self.eval_const_frame_block(state, block);
+        state.frames.borrow_mut().pop();
+        res
+    }
+
+    /// Evaluate a block in the innermost frame (local definitions are bound in the frame).
+    fn eval_const_frame_block(
+        &self,
+        state: &ConstEvalState,
+        block: &Block,
+    ) -> EvalResult<ValueObj> {
+        for chunk in block.iter().rev().skip(1).rev() {
+            self.eval_const_frame_chunk(state, chunk)?;
+        }
+        self.eval_const_frame_chunk(state, block.last().unwrap())
+    }
+
+    fn eval_const_frame_chunk(&self, state: &ConstEvalState, expr: &Expr) -> EvalResult<ValueObj> {
+        match expr {
+            Expr::Def(def) => match &def.sig {
+                Signature::Var(var) if def.is_const() && var.ident().is_some() => {
+                    let obj = self.eval_const_frame_block(state, &def.body.block)?;
+                    let name = VarName::from_str(var.inspect().unwrap().clone());
+                    if let Some(frame) = state.frames.borrow_mut().last_mut() {
+                        frame.insert(name, obj);
+                    }
+                    Ok(ValueObj::None)
+                }
+                _ => Err(EvalErrors::from(EvalError::not_const_expr(
+                    self.cfg.input.clone(),
+                    line!() as usize,
+                    def.loc(),
+                    self.caused_by(),
+                ))),
+            },
+            other => self.eval_const_expr(other),
+        }
+    }
+
+    /// Errors that occur in the body of a const subroutine are reported at the call site.
+    fn relocate_errors(&self, errs: EvalErrors, loc: Location) -> EvalErrors {
+        errs.into_iter()
+            .map(|mut err| {
+                err.core.loc = loc;
+                for sub in err.core.sub_messages.iter_mut() {
+                    sub.loc = loc;
+                }
+                err.caused_by = self.caused_by();
+                err
+            })
+            .collect()
+    }
+
+    /// Bind the arguments to the parameters of `subr` (the result is the frame of the call).
+    ///
+    /// e.g. `F X, *Ys, Z := X = ...`, `F(1, 2, 3)` ==> `X = 1, Ys = [2, 3], Z = 1`
+    fn bind_const_args(
+        &self,
+        subr: &UserConstSubr,
+        args: ValueArgs,
+        loc: Location,
+    ) -> EvalResult<Dict<VarName, ValueObj>> {
+        let mut binds = dict! {};
+        let param_ts = <&SubrType>::try_from(&subr.sig_t).ok();
+        let ValueArgs {
+            pos_args,
+            mut kw_args,
+        } = args;
+        let pos_args_len = pos_args.len();
+        let kw_args_len = kw_args.len();
+        let mut pos_args = pos_args.into_iter();
+        let mut missing = vec![];
+        for (i, sig) in subr.params.non_defaults.iter().enumerate() {
+            let name = sig.inspect();
+            let arg = pos_args
+                .next()
+                .or_else(|| name.and_then(|name| kw_args.remove(name)));
+            let Some(arg) = arg else {
+                missing.push(name.cloned().unwrap_or(Str::ever("_")));
+                continue;
+            };
+            let param_t = param_ts.and_then(|subr_t| subr_t.non_default_params.get(i));
+            self.bind_const_arg(sig, param_t.map(|pt| pt.typ()), arg, &mut binds)?;
+        }
+        let mut rest = pos_args.collect::<Vec<_>>();
+        if let Some(var_params) = subr.params.var_params.as_deref() {
+            let arg = ValueObj::Array(ArcArray::from(mem::take(&mut rest)));
+            self.bind_const_arg(var_params, None, arg, &mut binds)?;
+        }
+        let mut rest = rest.into_iter();
+        for (i, sig) in subr.params.defaults.iter().enumerate() {
+            let name = sig.sig.inspect();
+            let arg = rest
+                .next()
+                .or_else(|| name.and_then(|name| kw_args.remove(name)));
+            let arg = match arg {
+                Some(arg) => arg,
+                None => self.eval_const_expr(&sig.default_val)?,
+            };
+            let param_t = param_ts.and_then(|subr_t| subr_t.default_params.get(i));
+            self.bind_const_arg(&sig.sig, param_t.map(|pt| pt.typ()), arg, &mut binds)?;
+        }
+        if rest.next().is_some() {
+            return Err(EvalErrors::from(EvalError::too_many_args_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                loc,
+                &subr.name,
+                self.caused_by(),
+                subr.params.len(),
+                pos_args_len,
+                kw_args_len,
+            )));
+        }
+        if let Some((name, _)) = kw_args.into_iter().next() {
+            return Err(EvalErrors::from(EvalError::unexpected_kw_arg_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                loc,
+                &subr.name,
+                self.caused_by(),
+                &name,
+                None,
+            )));
+        }
+        if !missing.is_empty() {
+            return Err(EvalErrors::from(EvalError::args_missing_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                loc,
+                &subr.name,
+                self.caused_by(),
+                missing,
+            )));
+        }
+        Ok(binds)
+    }
+
+    fn bind_const_arg(
+        &self,
+        sig: &NonDefaultParamSignature,
+        param_t: Option<&Type>,
+        arg: ValueObj,
+        binds: &mut Dict<VarName, ValueObj>,
+    ) -> EvalResult<()> {
+        if let Some(param_t) = param_t.filter(|t| !t.has_qvar() && !t.has_unbound_var()) {
+            let arg_t = v_enum(set! { arg.clone() });
+            if !self.subtype_of(&arg_t, param_t) {
+                return Err(EvalErrors::from(EvalError::type_mismatch_error(
+                    self.cfg.input.clone(),
+                    line!() as usize,
+                    sig.loc(),
+                    self.caused_by(),
+                    sig.inspect().map_or("_", |name| &name[..]),
+                    None,
+                    param_t,
+                    &arg_t,
+                    None,
+                    None,
+                )));
+            }
+        }
+        if self.match_const_pattern(&sig.pat, &arg, binds)? {
+            Ok(())
+        } else {
+            Err(EvalErrors::from(EvalError::no_matching_arm_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                sig.loc(),
+                self.caused_by(),
+                &arg.to_string(),
+            )))
+        }
+    }
+
+    /// Evaluate the body of a lambda as a branch of `if` or `match`.
+    fn eval_const_branch_body(
+        &self,
+        body: &Block,
+        binds: Dict<VarName, ValueObj>,
+    ) -> EvalResult<ValueObj> {
+        if binds.is_empty() && body.len() == 1 && !matches!(body[0], Expr::Def(_)) {
+            return self.eval_const_expr(&body[0]);
+        }
+        if let Some(state) = self.get_const_eval_state() {
+            let mut frame = state.frames.borrow().last().cloned().unwrap_or_default();
+            frame.extend(binds);
+            return self.eval_const_in_frame(state, frame, body);
+        }
+        // HACK: should avoid cloning
+        let mut lambda_ctx = Context::instant(
+            Str::ever("<lambda>"),
+            self.cfg.clone(),
+            binds.len(),
+            self.shared.clone(),
+            self.clone(),
+        );
+        lambda_ctx.consts.extend(binds);
+        lambda_ctx.eval_const_block(body)
+    }
+
+    fn eval_const_branch(&self, branch: &Expr) -> EvalResult<ValueObj> {
+        match branch {
+            Expr::Lambda(lambda) if lambda.sig.params.is_empty() => {
+                self.eval_const_branch_body(&lambda.body, dict! {})
+            }
+            other => match self.eval_const_expr(other)? {
+                ValueObj::Subr(subr) => {
+                    let args = ValueArgs::new(vec![], dict! {});
+                    let tp = self.call(subr, args, other.loc())?;
+                    ValueObj::try_from(tp).map_err(|_| {
+                        EvalErrors::from(EvalError::not_const_expr(
+                            self.cfg.input.clone(),
+                            line!() as usize,
+                            other.loc(),
+                            self.caused_by(),
+                        ))
+                    })
+                }
+                obj => Ok(obj),
+            },
+        }
+    }
+
+    /// `if cond, do x, do y`
+    fn eval_const_if(&self, call: &Call) -> EvalResult<ValueObj> {
+        let cond = call.args.get_left_or_key("cond").ok_or_else(|| {
+            EvalErrors::from(EvalError::args_missing_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                call.loc(),
+                "if",
+                self.caused_by(),
+                vec![Str::ever("cond")],
+            ))
+        })?;
+        let then = call.args.nth_or_key(1, "then");
+        let else_ = call.args.nth_or_key(2, "else");
+        match self.eval_const_expr(cond)? {
+            ValueObj::Bool(true) => then.map_or(Ok(ValueObj::None), |b| self.eval_const_branch(b)),
+            ValueObj::Bool(false) => {
+                else_.map_or(Ok(ValueObj::None), |b| self.eval_const_branch(b))
+            }
+            other => Err(EvalErrors::from(EvalError::type_mismatch_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                cond.loc(),
+                self.caused_by(),
+                "cond",
+                None,
+                &Type::Bool,
+                &other.t(),
+                None,
+                None,
+            ))),
+        }
+    }
+
+    /// `match x, (1 -> a), (_ -> b)`
+    fn eval_const_match(&self, call: &Call) -> EvalResult<ValueObj> {
+        let mut args = call.args.pos_args().iter().map(|arg| &arg.expr);
+        let Some(obj) = args.next() else {
+            return Err(EvalErrors::from(EvalError::args_missing_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                call.loc(),
+                "match",
+                self.caused_by(),
+                vec![Str::ever("obj")],
+            )));
+        };
+        let val = self.eval_const_expr(obj)?;
+        for arm in args {
+            let Expr::Lambda(lambda) = arm else {
+                return Err(EvalErrors::from(EvalError::not_const_expr(
+                    self.cfg.input.clone(),
+                    line!() as usize,
+                    arm.loc(),
+                    self.caused_by(),
+                )));
+            };
+            let [param] = &lambda.sig.params.non_defaults[..] else {
+                return Err(EvalErrors::from(EvalError::not_const_expr(
+                    self.cfg.input.clone(),
+                    line!() as usize,
+                    arm.loc(),
+                    self.caused_by(),
+                )));
+            };
+            let mut binds = dict! {};
+            if self.match_const_param(param, &val, &mut binds)? {
+                return self.eval_const_branch_body(&lambda.body, binds);
+            }
+        }
+        Err(EvalErrors::from(EvalError::no_matching_arm_error(
+            self.cfg.input.clone(),
+            line!() as usize,
+            call.loc(),
+            self.caused_by(),
+            &val.to_string(),
+        )))
+    }
+
+    fn match_const_param(
+        &self,
+        sig: &NonDefaultParamSignature,
+        val: &ValueObj,
+        binds: &mut Dict<VarName, ValueObj>,
+    ) -> EvalResult<bool> {
+        if let Some(spec) = sig.t_spec.as_ref() {
+            let mut dummy_tv_cache = TyVarCache::new(self.level, self);
+            let spec_t = self.instantiate_typespec_full(
+                &spec.t_spec,
+                None,
+                &mut dummy_tv_cache,
+                RegistrationMode::Normal,
+                false,
+            )?;
+            if !self.subtype_of(&v_enum(set! { val.clone() }), &spec_t) {
+                return Ok(false);
+            }
+        }
+        self.match_const_pattern(&sig.pat, val, binds)
+    }
+
+    fn match_const_pattern(
+        &self,
+        pat: &ParamPattern,
+        val: &ValueObj,
+        binds: &mut Dict<VarName, ValueObj>,
+    ) -> EvalResult<bool> {
+        match pat {
+            ParamPattern::Discard(_) => Ok(true),
+            ParamPattern::VarName(name) | ParamPattern::Ref(name) | ParamPattern::RefMut(name) => {
+                binds.insert(name.clone(), val.clone());
+                Ok(true)
+            }
+            ParamPattern::Lit(lit) => {
+                let lit = self.eval_lit(lit)?;
+                Ok(lit.try_eq(val.clone()) == Some(ValueObj::Bool(true)))
+            }
+            ParamPattern::Array(arr) => match val {
+                ValueObj::Array(elems) => self.match_const_params(&arr.elems, elems, binds),
+                _ => Ok(false),
+            },
+            ParamPattern::Tuple(tup) => match val {
+                ValueObj::Tuple(elems) => self.match_const_params(&tup.elems, elems, binds),
+                _ => Ok(false),
+            },
+            other => feature_error!(self, other.loc(), &format!("const pattern: {other}")),
+        }
+    }
+
+    fn match_const_params(
+        &self,
+        params: &Params,
+        elems: &[ValueObj],
+        binds: &mut Dict<VarName, ValueObj>,
+    ) -> EvalResult<bool> {
+        let len = params.non_defaults.len();
+        if elems.len() < len || (params.var_params.is_none() && elems.len() != len) {
+            return Ok(false);
+        }
+        for (sig, elem) in params.non_defaults.iter().zip(elems.iter()) {
+            if !self.match_const_param(sig, elem, binds)? {
+                return Ok(false);
+            }
+        }
+        if let Some(var_params) = params.var_params.as_deref() {
+            let rest = ValueObj::Array(ArcArray::from(&elems[len..]));
+            return self.match_const_param(var_params, &rest, binds);
+        }
+        Ok(true)
+    }
+
     fn eval_const_def(&mut self, def: &Def) -> EvalResult<ValueObj> {
         if def.is_const() {
             let __name__ = def.sig.ident().unwrap().inspect();
@@ -1562,6 +2021,18 @@ impl Context {
                 };
                 Ok(self.intersection(&l, &r))
             }
+            Type::Overloaded(overloads) => {
+                let mut new_overloads = Vec::with_capacity(overloads.len());
+                for t in overloads.into_iter() {
+                    match self.eval_t_params(t, level, t_loc) {
+                        Ok(t) => new_overloads.push(t),
+                        Err((_, errs)) => {
+                            return Err((Failure, errs));
+                        }
+                    }
+                }
+                Ok(Type::Overloaded(new_overloads))
+            }
             Type::Or(l, r) => {
                 let l = match self.eval_t_params(*l, level, t_loc) {
                     Ok(l) => l,
@@ -1869,12 +2340,19 @@ impl Context {
                 Ok(array_t(union, len))
             }
             ValueObj::Set(set) => Ok(v_enum(set)),
+            // e.g. `{"a": Int}` == `{{"a"}: Int}`
             ValueObj::Dict(dic) => {
-                let dic = dic
-                    .into_iter()
-                    .map(|(k, v)| (TyParam::Value(k), TyParam::Value(v)))
-                    .collect();
-                Ok(dict_t(TyParam::Dict(dic)))
+                let mut new_dic = dict! {};
+                for (k, v) in dic.into_iter() {
+                    let k = self
+                        .convert_value_into_type(k)
+                        .unwrap_or_else(|k| v_enum(set! { k }));
+                    let v = self
+                        .convert_value_into_type(v)
+                        .map_or_else(TyParam::Value, TyParam::t);
+                    new_dic.insert(TyParam::t(k), v);
+                }
+                Ok(dict_t(TyParam::Dict(new_dic)))
             }
             ValueObj::Subr(subr) => subr.as_type(self).ok_or(ValueObj::Subr(subr)),
             other => Err(other),
@@ -2487,6 +2965,27 @@ impl Context {
                         )
                     }
                 }
+                // e.g. `N + 1: Nat`, `N - 1: Int` (N: Nat)
+                OpKind::Add | OpKind::Sub | OpKind::Mul => {
+                    let lhs = self.get_tp_t(&lhs)?.derefine();
+                    let rhs = self.get_tp_t(&rhs)?.derefine();
+                    if op != OpKind::Sub
+                        && self.subtype_of(&lhs, &Type::Nat)
+                        && self.subtype_of(&rhs, &Type::Nat)
+                    {
+                        Ok(Type::Nat)
+                    } else if self.subtype_of(&lhs, &Type::Int) && self.subtype_of(&rhs, &Type::Int)
+                    {
+                        Ok(Type::Int)
+                    } else {
+                        let op_name = op_to_name(op);
+                        feature_error!(
+                            self,
+                            Location::Unknown,
+                            &format!("get type: {op_name}({lhs}, {rhs})")
+                        )
+                    }
+                }
                 _ => {
                     let op_name = op_to_name(op);
                     feature_error!(
@@ -268,6 +268,12 @@ impl Generalizer {
                 // not `self.intersection` because types are generalized
                 and(l, r)
             }
+            Overloaded(overloads) => Overloaded(
+                overloads
+                    .into_iter()
+                    .map(|t| self.generalize_t(t, uninit))
+                    .collect(),
+            ),
             Or(l, r) => {
                 let l = self.generalize_t(*l, uninit);
                 let r = self.generalize_t(*r, uninit);
@@ -710,6 +716,13 @@ impl<'c, 'q, 'l, L: Locational> Dereferencer<'c, 'q, 'l, L> {
                 let r = self.deref_tyvar(*r)?;
                 Ok(self.ctx.intersection(&l, &r))
             }
+            Type::Overloaded(overloads) => {
+                let overloads = overloads
+                    .into_iter()
+                    .map(|t| self.deref_tyvar(t))
+                    .collect::<TyCheckResult<_>>()?;
+                Ok(Type::Overloaded(overloads))
+            }
             Type::Or(l, r) => {
                 let l = self.deref_tyvar(*l)?;
                 let r = self.deref_tyvar(*r)?;
@@ -864,11 +877,12 @@ impl<'c, 'q, 'l, L: Locational> Dereferencer<'c, 'q, 'l, L> {
     //     ?T -> ?T
     //     ?T -> K(?T)
     //     ?T -> ?U(:> ?T)
+    //     K(?T) -> Int (K is invariant)
     fn eliminate_needless_quant(&mut self, subr: Type) -> TyCheckResult<Type> {
         let Ok(mut subr) = SubrType::try_from(subr) else {
             unreachable!()
         };
-        let essential_qnames = subr.essential_qnames();
+        let essential_qnames = self.ctx.essential_qnames(&subr);
         let mut _self = Dereferencer::new(
             self.ctx,
             self.variance,
@@ -918,6 +932,55 @@ impl Context {
         }
     }
 
+    /// Returns the names of the quantified variables which appear as invariant type parameters.
+    /// e.g. `Queue!(T, N)` ==> `{T, N}` (if `Queue!` is invariant)
+    fn invariant_qnames(&self, t: &Type) -> Set<Str> {
+        match t {
+            FreeVar(fv) if fv.is_linked() => self.invariant_qnames(&fv.crack()),
+            Ref(t) => self.invariant_qnames(t),
+            RefMut { before, after } => {
+                let mut qnames = self.invariant_qnames(before);
+                if let Some(after) = after {
+                    qnames.extend(self.invariant_qnames(after));
+                }
+                qnames
+            }
+            Poly { params, .. } => {
+                let variances = self
+                    .get_nominal_type_ctx(t)
+                    .map_or(vec![], |(_, ctx)| ctx.type_params_variance());
+                let mut qnames = set! {};
+                for (param, variance) in params.iter().zip(variances) {
+                    match param {
+                        _ if variance == Invariant => {
+                            qnames.extend(param.qvars().into_iter().map(|(name, _)| name));
+                        }
+                        TyParam::Type(t) => qnames.extend(self.invariant_qnames(t)),
+                        _ => {}
+                    }
+                }
+                qnames
+            }
+            _ => set! {},
+        }
+    }
+
+    /// `SubrType::essential_qnames` + the quantified variables which appear as invariant type parameters
+    /// e.g. `() -> Queue!(T, 0)` ==> `{T}` (`T` cannot be determined as `Never`)
+    pub(crate) fn essential_qnames(&self, subr: &SubrType) -> Set<Str> {
+        let mut qnames = subr.essential_qnames();
+        for pt in subr
+            .non_default_params
+            .iter()
+            .chain(subr.var_params.as_deref())
+            .chain(subr.default_params.iter())
+        {
+            qnames.extend(self.invariant_qnames(pt.typ()));
+        }
+        qnames.extend(self.invariant_qnames(&subr.return_t));
+        qnames
+    }
+
     pub fn readable_type(&self, t: Type) -> Type {
         let qnames = set! {};
         let mut dereferencer = Dereferencer::new(self, Covariant, false, &qnames, &());
@@ -1242,7 +1305,7 @@ impl Context {
                     let Ok(subr) = <&SubrType>::try_from(quant.as_ref()) else {
                         unreachable!()
                     };
-                    subr.essential_qnames()
+                    self.essential_qnames(subr)
                 } else {
                     qnames.clone()
                 };
@@ -1262,7 +1325,7 @@ impl Context {
                     let Ok(subr) = <&SubrType>::try_from(quant.as_ref()) else {
                         unreachable!()
                     };
-                    subr.essential_qnames()
+                    self.essential_qnames(subr)
                 } else {
                     qnames.clone()
                 };
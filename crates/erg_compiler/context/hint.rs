@@ -116,6 +116,35 @@ impl Context {
                     return Some(hint.to_string());
                 }
             }
+            (Type::Mono(_), found) if self.is_structural_trait(&expected) => {
+                let (missing, incompatible) = self.structural_trait_mismatches(&expected, found);
+                let mut msgs = vec![];
+                if !missing.is_empty() {
+                    let missing = missing
+                        .iter()
+                        .map(|field| format!("`{field}`"))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    msgs.push(switch_lang!(
+                        "japanese" => format!("{found}には{missing}がありません"),
+                        "simplified_chinese" => format!("{found}缺少{missing}"),
+                        "traditional_chinese" => format!("{found}缺少{missing}"),
+                        "english" => format!("{found} has no {missing}"),
+                    ));
+                }
+                for (field, required, found) in incompatible.iter() {
+                    msgs.push(switch_lang!(
+                        "japanese" => format!("`{field}`は{required}型である必要がありますが、{found}型です"),
+                        "simplified_chinese" => format!("`{field}`应为{required}类型，但找到了{found}"),
+                        "traditional_chinese" => format!("`{field}`應為{required}型別，但找到了{found}"),
+                        "english" => format!("`{field}` should be of type {required}, but found {found}"),
+                    ));
+                }
+                if !msgs.is_empty() {
+                    hint.push_str(&msgs.join("; "));
+                    return Some(hint.to_string());
+                }
+            }
             (Type::And(l, r), found) => {
                 let left = self.readable_type(l.as_ref().clone());
                 let right = self.readable_type(r.as_ref().clone());
@@ -1425,15 +1425,18 @@ impl Context {
         );
         // __getitem__: |T, N|(self: [T; N], _: {I: Nat | I <= N}) -> T
         //              and (self: [T; N], _: Range(Int)) -> [T; _]
-        let array_getitem_t = (fn1_kw_met(
-            array_t(T.clone(), N.clone()),
-            anon(input.clone()),
-            T.clone(),
-        ) & fn1_kw_met(
-            array_t(T.clone(), N.clone()),
-            anon(poly(RANGE, vec![ty_tp(Int)])),
-            unknown_len_array_t(T.clone()),
-        ))
+        let array_getitem_t = Type::Overloaded(vec![
+            fn1_kw_met(
+                array_t(T.clone(), N.clone()),
+                anon(input.clone()),
+                T.clone(),
+            ),
+            fn1_kw_met(
+                array_t(T.clone(), N.clone()),
+                anon(poly(RANGE, vec![ty_tp(Int)])),
+                unknown_len_array_t(T.clone()),
+            ),
+        ])
         .quantify();
         let get_item = ValueObj::Subr(ConstSubr::Builtin(BuiltinConstSubr::new(
             FUNDAMENTAL_GETITEM,
@@ -1770,16 +1773,23 @@ impl Context {
             None,
         )));
         dict_.register_builtin_const(ITEMS, Visibility::BUILTIN_PUBLIC, items);
+        // __dict_get__: _: T -> D[T] (`NotRequired` keys are allowed)
+        let dict_get_out = proj_call(D.clone(), DICT_GET, vec![ty_tp(T.clone())]);
+        let dict_get_t = fn1_met(dict_t.clone(), T.clone(), dict_get_out.clone()).quantify();
+        let dict_get = ValueObj::Subr(ConstSubr::Builtin(BuiltinConstSubr::new(
+            DICT_GET,
+            __dict_get__,
+            dict_get_t,
+            None,
+        )));
+        dict_.register_builtin_const(DICT_GET, Visibility::BUILTIN_PRIVATE, dict_get);
         let Def = type_q("Default");
         let get_t = fn_met(
             dict_t.clone(),
             vec![kw("key", T.clone())],
             None,
             vec![kw_default("default", Def.clone(), NoneType)],
-            or(
-                proj_call(D.clone(), FUNDAMENTAL_GETITEM, vec![ty_tp(T.clone())]),
-                Def,
-            ),
+            or(dict_get_out, Def),
         )
         .quantify();
         dict_.register_py_builtin(FUNC_GET, get_t, Some(FUNC_GET), 9);
@@ -1796,12 +1806,14 @@ impl Context {
             Str,
         );
         bytes.register_py_builtin(FUNC_DECODE, decode_t, Some(FUNC_DECODE), 6);
-        let bytes_getitem_t = fn1_kw_met(mono(BYTES), kw(KW_IDX, Nat), Int)
-            & fn1_kw_met(
+        let bytes_getitem_t = Type::Overloaded(vec![
+            fn1_kw_met(mono(BYTES), kw(KW_IDX, Nat), Int),
+            fn1_kw_met(
                 mono(BYTES),
                 kw(KW_IDX, poly(RANGE, vec![ty_tp(Int)])),
                 mono(BYTES),
-            );
+            ),
+        ]);
         bytes.register_builtin_erg_impl(
             FUNDAMENTAL_GETITEM,
             bytes_getitem_t,
@@ -2012,7 +2024,7 @@ impl Context {
         )));
         generic_named_tuple.register_builtin_const(FUNC_UNION, Visibility::BUILTIN_PUBLIC, union);
         /* Or (true or type) */
-        let or_t = poly(OR, vec![ty_tp(L), ty_tp(R)]);
+        let or_t = poly(OR, vec![ty_tp(L), ty_tp(R.clone())]);
         let mut or = Self::builtin_poly_class(OR, vec![PS::t_nd(TY_L), PS::t_nd(TY_R)], 2);
         or.register_superclass(Obj, &obj);
         /* Iterators */
@@ -2716,15 +2728,58 @@ impl Context {
             Immutable,
             Visibility::BUILTIN_PUBLIC,
         );
-        let mut g_generator = Self::builtin_mono_class(GENERIC_GENERATOR, 2);
-        g_generator.register_superclass(mono(GENERIC_CALLABLE), &g_callable);
-        let t_yield = fn1_met(mono(GENERIC_GENERATOR), Obj, Never).quantify();
-        g_generator.register_builtin_erg_impl(
+        // (f: ?T -> Generator(?Y, ?S, ?R)).yield: (self: GenericCallable, arg: Obj) -> ?S
+        let S = type_q(TY_S);
+        let t_yield = fn1_met(mono(GENERIC_CALLABLE), Obj, S.clone()).quantify();
+        g_callable.register_builtin_erg_impl(
             FUNC_YIELD,
             t_yield,
             Immutable,
             Visibility::BUILTIN_PUBLIC,
         );
+        /* Generator */
+        let generator_t = generator(T.clone(), S.clone(), R.clone());
+        let mut generator_ = Self::builtin_poly_class(
+            GENERATOR,
+            vec![PS::t_nd(TY_T), PS::t_nd(TY_S), PS::t_nd(TY_R)],
+            2,
+        );
+        generator_.register_superclass(Obj, &obj);
+        generator_
+            .register_marker_trait(self, poly(ITERATOR, vec![ty_tp(T.clone())]))
+            .unwrap();
+        generator_
+            .register_marker_trait(self, poly(INPUT, vec![ty_tp(S.clone())]))
+            .unwrap();
+        generator_
+            .register_marker_trait(self, poly(OUTPUT, vec![ty_tp(R.clone())]))
+            .unwrap();
+        let t_send = pr1_kw_met(
+            ref_mut(generator_t.clone(), None),
+            kw(KW_VALUE, S),
+            T.clone(),
+        )
+        .quantify();
+        generator_.register_builtin_py_impl(
+            PROC_SEND,
+            t_send,
+            Immutable,
+            Visibility::BUILTIN_PUBLIC,
+            Some(FUNC_SEND),
+        );
+        /* Coroutine */
+        let mut coroutine = Self::builtin_poly_class(COROUTINE, vec![PS::t_nd(TY_T)], 2);
+        coroutine.register_superclass(Obj, &obj);
+        coroutine
+            .register_marker_trait(self, poly(OUTPUT, vec![ty_tp(T.clone())]))
+            .unwrap();
+        /* NotRequired */
+        // e.g. `{"name": Str, "age": NotRequired(Nat)}`
+        let mut not_required_ = Self::builtin_poly_class(NOT_REQUIRED, vec![PS::t_nd(TY_T)], 1);
+        not_required_.register_superclass(Obj, &obj);
+        not_required_
+            .register_marker_trait(self, poly(OUTPUT, vec![ty_tp(T.clone())]))
+            .unwrap();
         /* Proc */
         let mut proc = Self::builtin_mono_class(PROC, 2);
         proc.register_superclass(mono(GENERIC_CALLABLE), &g_callable);
@@ -2896,7 +2951,7 @@ impl Context {
             Some(FUNC_REVERSED),
         );
         self.register_builtin_type(
-            poly(ZIP, vec![ty_tp(T), ty_tp(U)]),
+            poly(ZIP, vec![ty_tp(T.clone()), ty_tp(U)]),
             zip,
             Visibility::BUILTIN_PRIVATE,
             Const,
@@ -2927,12 +2982,20 @@ impl Context {
             Const,
             Some(CALLABLE),
         );
+        self.register_builtin_type(generator_t, generator_, vis.clone(), Const, Some(GENERATOR));
+        self.register_builtin_type(
+            poly(COROUTINE, vec![ty_tp(T.clone())]),
+            coroutine,
+            vis.clone(),
+            Const,
+            Some(COROUTINE),
+        );
         self.register_builtin_type(
-            mono(GENERIC_GENERATOR),
-            g_generator,
+            not_required(T),
+            not_required_,
             vis.clone(),
             Const,
-            Some(GENERATOR),
+            Some(NOT_REQUIRED),
         );
         self.register_builtin_type(mono(PROC), proc, vis.clone(), Const, Some(PROC));
         self.register_builtin_type(mono(FUNC), func, vis.clone(), Const, Some(FUNC));
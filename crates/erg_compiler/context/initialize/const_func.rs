@@ -8,7 +8,7 @@ use erg_common::{dict, enum_unwrap, set};
 
 use crate::context::Context;
 use crate::feature_error;
-use crate::ty::constructors::{and, mono, tuple_t, v_enum};
+use crate::ty::constructors::{and, mono, not_required, tuple_t, v_enum};
 use crate::ty::value::{EvalValueError, EvalValueResult, GenTypeObj, TypeObj, ValueObj};
 use crate::ty::{TyParam, Type, ValueArgs};
 use erg_common::error::{ErrorCore, ErrorKind, Location, SubMessage};
@@ -41,6 +41,22 @@ fn no_key(slf: impl Display, key: impl Display) -> EvalValueError {
     .into()
 }
 
+fn not_required_key(slf: impl Display, key: impl Display) -> EvalValueError {
+    let hint = "use `.get` instead".to_string();
+    ErrorCore::new(
+        vec![SubMessage::ambiguous_new(
+            Location::Unknown,
+            vec![],
+            Some(hint),
+        )],
+        format!("{key} may not be a key of {slf} (it is not required)"),
+        line!() as usize,
+        ErrorKind::KeyError,
+        Location::Unknown,
+    )
+    .into()
+}
+
 fn type_mismatch(expected: impl Display, got: impl Display, param: &str) -> EvalValueError {
     let got = StyledString::new(format!("{got}"), Some(ERR), None);
     let param = StyledStr::new(param, Some(WARN), None);
@@ -175,6 +191,54 @@ pub(crate) fn structural_func(mut args: ValueArgs, ctx: &Context) -> EvalValueRe
     Ok(ValueObj::gen_t(GenTypeObj::structural(t, base)).into())
 }
 
+/// typename: Str, fields: {Str: Type}, total := Bool -> Type
+///
+/// `TypedDict("Person", {"name": Str, "age": Nat}, total := False) == {"name": NotRequired(Str), "age": NotRequired(Nat)}`
+pub(crate) fn typed_dict_func(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<TyParam> {
+    let _typename = args
+        .remove_left_or_key("typename")
+        .ok_or_else(|| not_passed("typename"))?;
+    let fields = args
+        .remove_left_or_key("fields")
+        .ok_or_else(|| not_passed("fields"))?;
+    let total = args
+        .remove_left_or_key("total")
+        .is_none_or(|total| total != ValueObj::Bool(false));
+    let ValueObj::Dict(fields) = fields else {
+        return Err(type_mismatch("dict", fields, "fields"));
+    };
+    let mut new_fields = dict! {};
+    for (key, val) in fields.into_iter() {
+        let Ok(t) = ctx.convert_value_into_type(val.clone()) else {
+            return Err(type_mismatch("type", val, "fields"));
+        };
+        let t = if total || t.not_required_inner().is_some() {
+            t
+        } else {
+            not_required(t)
+        };
+        new_fields.insert(key, ValueObj::builtin_type(t));
+    }
+    Ok(ValueObj::Dict(new_fields).into())
+}
+
+/// Variants: Type -> ClassType
+///
+/// `Variants` must be a record type, e.g. `{.Lit = Int; .Add = {lhs = Expr; rhs = Expr}}`.
+pub(crate) fn enum_func(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<TyParam> {
+    let variants = args
+        .remove_left_or_key("Variants")
+        .ok_or_else(|| not_passed("Variants"))?;
+    let Some(variants) = variants.as_type(ctx) else {
+        return Err(type_mismatch("type", variants, "Variants"));
+    };
+    if !variants.typ().is_record() {
+        return Err(type_mismatch("record type", variants, "Variants"));
+    }
+    let t = mono(ctx.name.clone());
+    Ok(ValueObj::gen_t(GenTypeObj::enum_(t, variants)).into())
+}
+
 pub(crate) fn __array_getitem__(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<TyParam> {
     let slf = args
         .remove_left_or_key("Self")
@@ -269,7 +333,12 @@ pub(crate) fn sub_tpdict_get<'d>(
     None
 }
 
-pub(crate) fn __dict_getitem__(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<TyParam> {
+/// `allow_not_required`: whether the value type of a `NotRequired` key can be got (e.g. `Dict.get`)
+fn dict_get_value(
+    mut args: ValueArgs,
+    ctx: &Context,
+    allow_not_required: bool,
+) -> EvalValueResult<TyParam> {
     let slf = args
         .remove_left_or_key("Self")
         .ok_or_else(|| not_passed("Self"))?;
@@ -278,18 +347,42 @@ pub(crate) fn __dict_getitem__(mut args: ValueArgs, ctx: &Context) -> EvalValueR
         .remove_left_or_key("Index")
         .ok_or_else(|| not_passed("Index"))?;
     if let Some(v) = slf.get(&index).or_else(|| sub_vdict_get(&slf, &index, ctx)) {
+        if let Some(inner) = <&Type>::try_from(v).ok().and_then(Type::not_required_inner) {
+            // `{"a": NotRequired(Int)}["a"]` may raise a KeyError at runtime
+            // (the key is not determined yet if `Index` is an unbound type variable)
+            let key_determined = match &index {
+                ValueObj::Type(t) => !t.typ().lower_bounded().has_unbound_var(),
+                _ => true,
+            };
+            if key_determined && !allow_not_required {
+                return Err(not_required_key(slf, coerce_index(index, ctx)));
+            }
+            return Ok(ValueObj::builtin_type(inner).into());
+        }
         Ok(v.clone().into())
     } else {
-        let index = if let ValueObj::Type(t) = &index {
-            let derefed = ctx.coerce(t.typ().clone(), &()).unwrap_or(t.typ().clone());
-            ValueObj::builtin_type(derefed)
-        } else {
-            index
-        };
-        Err(no_key(slf, index))
+        Err(no_key(slf, coerce_index(index, ctx)))
+    }
+}
+
+fn coerce_index(index: ValueObj, ctx: &Context) -> ValueObj {
+    if let ValueObj::Type(t) = &index {
+        let derefed = ctx.coerce(t.typ().clone(), &()).unwrap_or(t.typ().clone());
+        ValueObj::builtin_type(derefed)
+    } else {
+        index
     }
 }
 
+pub(crate) fn __dict_getitem__(args: ValueArgs, ctx: &Context) -> EvalValueResult<TyParam> {
+    dict_get_value(args, ctx, false)
+}
+
+/// `{"a": NotRequired(Int)}.__dict_get__("a") == Int`
+pub(crate) fn __dict_get__(args: ValueArgs, ctx: &Context) -> EvalValueResult<TyParam> {
+    dict_get_value(args, ctx, true)
+}
+
 /// `{Str: Int, Int: Float}.keys() == Str or Int`
 pub(crate) fn dict_keys(mut args: ValueArgs, ctx: &Context) -> EvalValueResult<TyParam> {
     let slf = args
@@ -1,4 +1,5 @@
 use erg_common::consts::{DEBUG_MODE, ERG_MODE, PYTHON_MODE};
+use erg_common::dict;
 #[allow(unused_imports)]
 use erg_common::log;
 
@@ -340,16 +341,17 @@ impl Context {
         self.register_builtin_py_impl(FUNC_QUIT, t_quit, Immutable, vis.clone(), Some(FUNC_QUIT));
         let MAX = mono_q_tp("MAX", instanceof(Int));
         let MIN = mono_q_tp("MIN", instanceof(Int));
-        let t_range = nd_func(
-            vec![kw(KW_START, singleton(Int, MAX.clone()))],
-            None,
-            poly(
-                RANGE,
-                vec![ty_tp((TyParam::value(0u64)..MAX.clone()).into())],
-            ),
-        )
-        .quantify()
-            & nd_func(
+        let t_range = Type::Overloaded(vec![
+            nd_func(
+                vec![kw(KW_START, singleton(Int, MAX.clone()))],
+                None,
+                poly(
+                    RANGE,
+                    vec![ty_tp((TyParam::value(0u64)..MAX.clone()).into())],
+                ),
+            )
+            .quantify(),
+            nd_func(
                 vec![
                     kw(KW_START, singleton(Int, MIN.clone())),
                     kw(KW_STOP, singleton(Int, MAX.clone())),
@@ -357,8 +359,8 @@ impl Context {
                 None,
                 poly(RANGE, vec![ty_tp((MIN.clone()..MAX.clone()).into())]),
             )
-            .quantify()
-            & nd_func(
+            .quantify(),
+            nd_func(
                 vec![
                     kw(KW_START, singleton(Int, MIN.clone())),
                     kw(KW_STOP, singleton(Int, MAX.clone())),
@@ -367,18 +369,19 @@ impl Context {
                 None,
                 poly(RANGE, vec![ty_tp((MIN..MAX).into())]),
             )
-            .quantify()
-            & nd_func(vec![kw(KW_START, Int)], None, poly(RANGE, vec![ty_tp(Int)]))
-            & nd_func(
+            .quantify(),
+            nd_func(vec![kw(KW_START, Int)], None, poly(RANGE, vec![ty_tp(Int)])),
+            nd_func(
                 vec![kw(KW_START, Int), kw(KW_STOP, Int)],
                 None,
                 poly(RANGE, vec![ty_tp(Int)]),
-            )
-            & nd_func(
+            ),
+            nd_func(
                 vec![kw(KW_START, Int), kw(KW_STOP, Int), kw(KW_STEP, Int)],
                 None,
                 poly(RANGE, vec![ty_tp(Int)]),
-            );
+            ),
+        ]);
         self.register_builtin_py_impl(
             FUNC_RANGE,
             t_range,
@@ -579,6 +582,30 @@ impl Context {
             None,
         ));
         self.register_builtin_const(STRUCTURAL, vis.clone(), ValueObj::Subr(structural));
+        let enum_t = func(vec![kw(KW_VARIANTS, Type)], None, vec![], ClassType);
+        let enum_ = ConstSubr::Builtin(BuiltinConstSubr::new(ENUM, enum_func, enum_t, None));
+        self.register_builtin_const(ENUM, vis.clone(), ValueObj::Subr(enum_));
+        let typed_dict_t = func(
+            vec![
+                kw(KW_TYPENAME, Str),
+                kw(KW_FIELDS, dict! {Str => Type}.into()),
+            ],
+            None,
+            vec![kw_default(KW_TOTAL, Bool, Bool)],
+            Type,
+        );
+        let typed_dict = ConstSubr::Builtin(BuiltinConstSubr::new(
+            TYPED_DICT,
+            typed_dict_func,
+            typed_dict_t,
+            None,
+        ));
+        self.register_py_builtin_const(
+            TYPED_DICT,
+            vis.clone(),
+            ValueObj::Subr(typed_dict),
+            Some(TYPED_DICT),
+        );
         // decorators
         let inheritable_t = func1(ClassType, ClassType);
         let inheritable = ConstSubr::Builtin(BuiltinConstSubr::new(
@@ -779,21 +806,25 @@ impl Context {
         self.register_builtin_py_impl(OP_GE, op_t, Const, Visibility::BUILTIN_PRIVATE, Some("ge"));
         let T = type_q(TY_T);
         let U = type_q(TY_U);
-        let or_t = bin_op(Bool, Bool, Bool)
-            & bin_op(
+        let or_t = Type::Overloaded(vec![
+            bin_op(Bool, Bool, Bool),
+            bin_op(
                 tp_enum(Type, set! { ty_tp(T.clone()) }),
                 tp_enum(Type, set! { ty_tp(U.clone()) }),
                 tp_enum(Type, set! { ty_tp(T.clone() | U.clone()) }),
             )
-            .quantify();
+            .quantify(),
+        ]);
         self.register_builtin_py_impl(OP_OR, or_t, Const, Visibility::BUILTIN_PRIVATE, Some("or_"));
-        let and_t = bin_op(Bool, Bool, Bool)
-            & bin_op(
+        let and_t = Type::Overloaded(vec![
+            bin_op(Bool, Bool, Bool),
+            bin_op(
                 tp_enum(Type, set! { ty_tp(T.clone()) }),
                 tp_enum(Type, set! { ty_tp(U.clone()) }),
                 tp_enum(Type, set! { ty_tp(T & U) }),
             )
-            .quantify();
+            .quantify(),
+        ]);
         self.register_builtin_py_impl(
             OP_AND,
             and_t,
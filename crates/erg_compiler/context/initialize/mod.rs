@@ -298,9 +298,12 @@ const FUNC_INVERT: &str = "invert";
 const PROC_INVERT: &str = "invert!";
 const RANGE: &str = "Range";
 const GENERIC_CALLABLE: &str = "GenericCallable";
-const GENERIC_GENERATOR: &str = "GenericGenerator";
+const COROUTINE: &str = "Coroutine";
+const NOT_REQUIRED: &str = "NotRequired";
 const FUNC_RETURN: &str = "return";
 const FUNC_YIELD: &str = "yield";
+const FUNC_SEND: &str = "send";
+const PROC_SEND: &str = "send!";
 const PROC: &str = "Proc";
 const NAMED_PROC: &str = "NamedProc";
 const NAMED_FUNC: &str = "NamedFunc";
@@ -381,6 +384,8 @@ const INHERITABLE: &str = "Inheritable";
 const DEL: &str = "Del";
 const PATCH: &str = "Patch";
 const STRUCTURAL: &str = "Structural";
+const ENUM: &str = "Enum";
+const TYPED_DICT: &str = "TypedDict";
 const KEYS: &str = "keys";
 const VALUES: &str = "values";
 const ITEMS: &str = "items";
@@ -446,6 +451,7 @@ const FUNDAMENTAL_REPR: &str = "__repr__";
 const FUNDAMENTAL_DICT: &str = "__dict__";
 const FUNDAMENTAL_BYTES: &str = "__bytes__";
 const FUNDAMENTAL_GETITEM: &str = "__getitem__";
+const DICT_GET: &str = "__dict_get__";
 const FUNDAMENTAL_TUPLE_GETITEM: &str = "__Tuple_getitem__";
 const FUNDAMENTAL_SETITEM: &str = "__setitem__";
 const PROC_FUNDAMENTAL_SETITEM: &str = "__setitem__!";
@@ -545,6 +551,10 @@ const KW_REQUIREMENT: &str = "Requirement";
 const KW_IMPL: &str = "Impl";
 const KW_ADDITIONAL: &str = "Additional";
 const KW_SUPER: &str = "Super";
+const KW_VARIANTS: &str = "Variants";
+const KW_TYPENAME: &str = "typename";
+const KW_FIELDS: &str = "fields";
+const KW_TOTAL: &str = "total";
 const KW_MAXSPLIT: &str = "maxsplit";
 const KW_SUB: &str = "sub";
 const KW_OFFSET: &str = "offset";
@@ -901,7 +911,8 @@ impl Context {
             .quantify();
             let subr = move |data: ClosureData, args, _ctx: &Context| {
                 let passed = Vec::<TyParam>::from(args);
-                let lack = data.nd_params.len() + data.d_params.len() - passed.len();
+                let lack =
+                    (data.nd_params.len() + data.d_params.len()).saturating_sub(passed.len());
                 let erased = data
                     .d_params
                     .clone()
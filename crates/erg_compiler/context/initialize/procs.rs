@@ -103,6 +103,7 @@ impl Context {
             mono("File!"),
         )
         .quantify();
+        let t_await = nd_proc(vec![kw("coro", coroutine(T.clone()))], None, T.clone()).quantify();
         let C = if PYTHON_MODE {
             mono("ContextManager").structuralize()
         } else {
@@ -131,6 +132,7 @@ impl Context {
         self.register_builtin_py_impl("locals!", t_locals, Immutable, vis.clone(), Some("locals"));
         self.register_builtin_py_impl("next!", t_next, Immutable, vis.clone(), Some("next"));
         self.register_py_builtin("open!", t_open, Some("open"), 198);
+        self.register_builtin_erg_impl("await!", t_await, Immutable, vis.clone());
         let name = if PYTHON_MODE { "if" } else { "if__" };
         self.register_builtin_py_impl("if!", t_if, Immutable, vis.clone(), Some(name));
         let name = if PYTHON_MODE { "for" } else { "for__" };
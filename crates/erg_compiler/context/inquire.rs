@@ -18,7 +18,7 @@ use erg_parser::ast::{self, Identifier, VarName};
 use erg_parser::token::Token;
 
 use crate::ty::constructors::{anon, fn_met, free_var, func, mono, poly, proc, proj, ref_, subr_t};
-use crate::ty::free::{Constraint, FreeTyParam};
+use crate::ty::free::{CanbeFree, Constraint, FreeTyParam};
 use crate::ty::typaram::TyParam;
 use crate::ty::value::{GenTypeObj, TypeObj, ValueObj};
 use crate::ty::{
@@ -185,6 +185,16 @@ impl Context {
                 self.get_singular_ctxs_by_ident(&ident.raw, namespace)
             }
             hir::Expr::Accessor(hir::Accessor::Attr(attr)) => {
+                // e.g. attr.t: {E.A} (an enum variant)
+                if let Type::Refinement(refine) = attr.ref_t() {
+                    if let Predicate::Equal { rhs, .. } = refine.pred.as_ref() {
+                        if let Ok(t) = <&Type>::try_from(rhs) {
+                            if let Some(ctxs) = self.get_nominal_super_type_ctxs(t) {
+                                return Ok(ctxs);
+                            }
+                        }
+                    }
+                }
                 // REVIEW: 両方singularとは限らない?
                 let mut ctxs = vec![];
                 for ctx in self.get_singular_ctxs_by_hir_expr(&attr.obj, namespace)? {
@@ -193,6 +203,22 @@ impl Context {
                 Ok(ctxs)
             }
             hir::Expr::TypeAsc(tasc) => self.get_singular_ctxs_by_hir_expr(&tasc.expr, namespace),
+            // e.g. call.t: {Box(Int)}
+            hir::Expr::Call(call) => {
+                if let Some(Ok(t)) = call.ref_t().singleton_value().map(<&Type>::try_from) {
+                    if let Some(ctxs) = self.get_nominal_super_type_ctxs(t) {
+                        return Ok(ctxs);
+                    }
+                }
+                Err(TyCheckError::no_var_error(
+                    self.cfg.input.clone(),
+                    line!() as usize,
+                    obj.loc(),
+                    self.caused_by(),
+                    &obj.to_string(),
+                    None,
+                ))
+            }
             // TODO: change error
             _ => Err(TyCheckError::no_var_error(
                 self.cfg.input.clone(),
@@ -212,6 +238,14 @@ impl Context {
     ) -> SingleTyCheckResult<Vec<&Context>> {
         self.get_mod(ident.inspect())
             .map(|ctx| vec![ctx])
+            .or_else(|| {
+                // enum variants: `E.A`
+                let (typ, _) = self
+                    .methods_list
+                    .iter()
+                    .find_map(|(_, methods)| methods.mono_types.get(&ident.inspect()[..]))?;
+                self.get_nominal_super_type_ctxs(typ)
+            })
             .or_else(|| {
                 let (typ, _) = self.get_type_and_ctx(ident.inspect())?;
                 self.get_nominal_super_type_ctxs(typ)
@@ -368,7 +402,11 @@ impl Context {
                 )));
             }
         }
-        let match_target_expr_t = pos_args[0].expr.ref_t();
+        // `ref self` is matched by its content
+        let match_target_expr_t = match pos_args[0].expr.ref_t() {
+            Type::Ref(t) | Type::RefMut { before: t, .. } => t.as_ref(),
+            t => t,
+        };
         // Never or T => T
         let mut union_pat_t = Type::Never;
         let mut arm_ts = vec![];
@@ -419,6 +457,7 @@ impl Context {
                 match_target_expr_t,
                 &union_pat_t,
                 arm_ts,
+                &self.diff(match_target_expr_t, &union_pat_t),
             )));
         }
         let branch_ts = pos_args
@@ -463,14 +502,14 @@ impl Context {
         input: &Input,
         namespace: &Context,
     ) -> Triple<VarInfo, TyCheckError> {
-        if ident.inspect() == "Self" {
+        if ident.inspect() == "Self" || ident.inspect() == "Self!" {
             if let Some(self_t) = self.rec_get_self_t() {
-                return self.rec_get_var_info(
-                    &Identifier::auto(self_t.local_name()),
-                    acc_kind,
-                    input,
-                    namespace,
-                );
+                let name = if ident.inspect() == "Self!" {
+                    mutable_name(self_t.local_name())
+                } else {
+                    self_t.local_name()
+                };
+                return self.rec_get_var_info(&Identifier::auto(name), acc_kind, input, namespace);
             }
         }
         if let Some(vi) = self.get_current_scope_var(&ident.name) {
@@ -636,7 +675,10 @@ impl Context {
             for ctx in singular_ctxs {
                 match ctx.rec_get_var_info(ident, AccessKind::UnboundAttr, input, namespace) {
                     Triple::Ok(vi) => {
-                        return Triple::Ok(vi);
+                        return match self.specialize_unbound_attr(obj, vi) {
+                            Ok(vi) => Triple::Ok(vi),
+                            Err(e) => Triple::Err(e),
+                        };
                     }
                     Triple::Err(e) => {
                         return Triple::Err(e);
@@ -658,6 +700,16 @@ impl Context {
                             return Triple::Err(e);
                         }
                     }
+                } else if vi.t.has_qvar() {
+                    // e.g. `Box(Int).value: T` ==> `Box(Int).value: Int`
+                    let obj_t = match obj.ref_t().derefine() {
+                        t if t.is_unbound_var() => t.get_super().unwrap_or(t),
+                        t => t,
+                    };
+                    if let Some((gen_t, _)) = self.get_nominal_type_ctx(&obj_t) {
+                        let t = vi.t.clone().replace(gen_t, &obj_t);
+                        return Triple::Ok(VarInfo { t, ..vi });
+                    }
                 }
                 return Triple::Ok(vi);
             }
@@ -707,6 +759,34 @@ impl Context {
         Triple::None
     }
 
+    /// e.g. `Box(Int)::__new__: |T| {.value = T} -> Box(T)` ==> `{.value = Int} -> Box(Int)`
+    fn specialize_unbound_attr(
+        &self,
+        obj: &hir::Expr,
+        vi: VarInfo,
+    ) -> SingleTyCheckResult<VarInfo> {
+        let hir::Expr::Call(_) = obj else {
+            return Ok(vi);
+        };
+        let Some(Ok(applied)) = obj.ref_t().singleton_value().map(<&Type>::try_from) else {
+            return Ok(vi);
+        };
+        if !vi.t.is_quantified_subr() {
+            return Ok(vi);
+        }
+        let t = self
+            .instantiate_dummy(vi.t.clone())
+            .map_err(|mut errs| errs.remove(0))?;
+        if let Some(return_t) = t
+            .return_t()
+            .filter(|ret| ret.qual_name() == applied.qual_name())
+        {
+            self.sub_unify(return_t, applied, obj, None)
+                .map_err(|mut errs| errs.remove(0))?;
+        }
+        Ok(VarInfo { t, ..vi })
+    }
+
     fn get_bound_attr_from_nominal_t(
         &self,
         obj: &hir::Expr,
@@ -952,6 +1032,7 @@ impl Context {
                     .collect(),
                 Obj,
             );
+            let mut best: Option<&Type> = None;
             for ty in intersecs.iter() {
                 match (ty.is_method(), input_t.is_method()) {
                     (true, false) => {
@@ -970,9 +1051,18 @@ impl Context {
                     _ => {}
                 }
                 if self.subtype_of(ty, &input_t) {
-                    return Ok(ty.clone());
+                    // comparing further candidates may constrain type variables, so the first match wins
+                    if ty.has_qvar() || ty.has_unbound_var() || input_t.has_unbound_var() {
+                        return Ok(best.unwrap_or(ty).clone());
+                    }
+                    if best.is_none_or(|best| self.is_more_specific_overload(ty, best)) {
+                        best = Some(ty);
+                    }
                 }
             }
+            if let Some(best) = best {
+                return Ok(best.clone());
+            }
             let Type::Subr(subr_t) = input_t else {
                 unreachable!()
             };
@@ -988,6 +1078,24 @@ impl Context {
         }
     }
 
+    /// `(x: Nat) -> Nat` is more specific than `(x: Int) -> Int`.
+    /// If neither is more specific, the former declared overload wins.
+    fn is_more_specific_overload(&self, lhs: &Type, rhs: &Type) -> bool {
+        let (Some(l_params), Some(r_params)) = (lhs.non_default_params(), rhs.non_default_params())
+        else {
+            return false;
+        };
+        l_params.len() == r_params.len()
+            && l_params
+                .iter()
+                .zip(r_params.iter())
+                .all(|(l, r)| self.subtype_of(l.typ(), r.typ()))
+            && l_params
+                .iter()
+                .zip(r_params.iter())
+                .any(|(l, r)| !self.subtype_of(r.typ(), l.typ()))
+    }
+
     pub(crate) fn get_same_name_context(&self, name: &str) -> Option<&Context> {
         if &self.name[..] == name {
             Some(self)
@@ -1106,7 +1214,7 @@ impl Context {
                     .or_else(|| ctx.decls.get(attr_name.inspect()))
                 {
                     self.validate_visibility(attr_name, vi, input, namespace)?;
-                    return Ok(vi.clone());
+                    return self.specialize_unbound_attr(obj, vi.clone());
                 }
                 for (_, method_ctx) in ctx.methods_list.iter() {
                     if let Some(vi) = method_ctx
@@ -1115,7 +1223,7 @@ impl Context {
                         .or_else(|| method_ctx.decls.get(attr_name.inspect()))
                     {
                         self.validate_visibility(attr_name, vi, input, namespace)?;
-                        return Ok(vi.clone());
+                        return self.specialize_unbound_attr(obj, vi.clone());
                     }
                 }
             }
@@ -1251,7 +1359,7 @@ impl Context {
                     .or_else(|| ctx.decls.get(attr_name.inspect()))
                 {
                     self.validate_visibility(attr_name, vi, input, namespace)?;
-                    return Ok(vi.clone());
+                    return self.specialize_unbound_attr(obj, vi.clone());
                 }
                 for (_, method_ctx) in ctx.methods_list.iter() {
                     if let Some(vi) = method_ctx
@@ -1260,7 +1368,7 @@ impl Context {
                         .or_else(|| method_ctx.decls.get(attr_name.inspect()))
                     {
                         self.validate_visibility(attr_name, vi, input, namespace)?;
-                        return Ok(vi.clone());
+                        return self.specialize_unbound_attr(obj, vi.clone());
                     }
                 }
             }
@@ -1498,6 +1606,7 @@ impl Context {
                 } = self_t
                 {
                     log!(info "~> {after}\n");
+                    self.validate_transition(after, receiver)?;
                     *self_t = *after.clone();
                     if let hir::Expr::Accessor(hir::Accessor::Ident(ident)) = receiver {
                         if let Some(vi) = self.rec_get_mut_var_info(&ident.raw, AccessKind::Name) {
@@ -1512,6 +1621,40 @@ impl Context {
         }
     }
 
+    /// Check that the state after the transition satisfies the type parameter constraints.
+    /// e.g. `Queue!(Int, 0).dequeue!()` ==> `Queue!(Int, -1)` (`N: Nat` is violated)
+    fn validate_transition(&self, after: &Type, receiver: &hir::Expr) -> TyCheckResult<()> {
+        let Some((_, ctx)) = self.get_nominal_type_ctx(after) else {
+            return Ok(());
+        };
+        for (i, (tp, (name, param_vi))) in
+            after.typarams().iter().zip(ctx.params.iter()).enumerate()
+        {
+            // e.g. `N - 1` (`N` is not determined yet)
+            if tp.has_unbound_var() || tp.has_qvar() {
+                continue;
+            }
+            let Ok(tp_t) = self.get_tp_t(tp) else {
+                continue;
+            };
+            if !self.subtype_of(&tp_t, &param_vi.t) {
+                return Err(TyCheckErrors::from(TyCheckError::type_mismatch_error(
+                    self.cfg.input.clone(),
+                    line!() as usize,
+                    receiver.loc(),
+                    self.caused_by(),
+                    name.as_ref().map_or("", |n| &n.inspect()[..]),
+                    Some(i + 1),
+                    &param_vi.t,
+                    &tp_t,
+                    None,
+                    None,
+                )));
+            }
+        }
+        Ok(())
+    }
+
     fn not_callable_error(
         &self,
         obj: &hir::Expr,
@@ -1795,10 +1938,14 @@ impl Context {
         pos_args: &[hir::PosArg],
         kw_args: &[hir::KwArg],
     ) -> TyCheckResult<SubstituteResult> {
-        let ctxs = self
-            .get_singular_ctxs_by_hir_expr(obj, self)
-            .ok()
-            .unwrap_or(vec![]);
+        let ctxs = match obj {
+            // e.g. `Array() [1, 2]` (the result of a type application is not callable)
+            hir::Expr::Call(_) => vec![],
+            _ => self
+                .get_singular_ctxs_by_hir_expr(obj, self)
+                .ok()
+                .unwrap_or(vec![]),
+        };
         let one = attr_name
             .as_ref()
             .map(|attr| {
@@ -2487,6 +2634,10 @@ impl Context {
                     self.get_nominal_super_type_ctxs(&Type)
                 }
             }
+            // the nominal types of overloads are all subroutine types
+            Type::Overloaded(overloads) => overloads
+                .first()
+                .and_then(|t| self.get_nominal_super_type_ctxs(t)),
             Type::And(l, r) => {
                 match (
                     self.get_nominal_super_type_ctxs(l),
@@ -2851,6 +3002,10 @@ impl Context {
             if let Some(ty) = self.rec_get_self_t() {
                 return self.rec_get_const_obj(&ty.local_name());
             }
+        } else if name == "Self!" {
+            if let Some(ty) = self.rec_get_self_t() {
+                return self.rec_get_const_obj(&mutable_name(ty.local_name()));
+            }
         }
         if let Some(val) = self.consts.get(name) {
             return Some(val);
@@ -2880,21 +3035,47 @@ impl Context {
     // TODO: poly type
     pub(crate) fn rec_get_self_t(&self) -> Option<Type> {
         if self.kind.is_method_def() || self.kind.is_type() {
+            // e.g. `Box(T).` => `Box(T)`
+            if let Some((t, _)) = self
+                .get_outer()
+                .and_then(|outer| outer.get_poly_type(&self.name))
+                .filter(|(t, _)| t.qual_name() == self.name)
+            {
+                return Some(t.clone());
+            }
             Some(mono(self.name.clone()))
         } else if let ContextKind::PatchMethodDefs(t) = &self.kind {
             Some(t.clone())
         } else if let Some(outer) = self.get_outer() {
-            outer.rec_get_self_t()
+            let self_t = outer.rec_get_self_t()?;
+            let Some(tv_cache) = self
+                .tv_cache
+                .as_ref()
+                .filter(|_| matches!(self_t, Type::Poly { .. }) && self_t.has_qvar())
+            else {
+                return Some(self_t);
+            };
+            // e.g. `Box(T).get|T|(self)` => `self: Box(T)` (`T` is bound in `get`)
+            let params = self_t
+                .typarams()
+                .into_iter()
+                .map(|tp| {
+                    let Some(name) = tp.unbound_name() else {
+                        return tp;
+                    };
+                    if let Some(t) = tv_cache.get_tyvar(&name) {
+                        TyParam::t(t.clone())
+                    } else {
+                        tv_cache.get_typaram(&name).cloned().unwrap_or(tp)
+                    }
+                })
+                .collect();
+            Some(poly(self_t.qual_name(), params))
         } else {
             None
         }
     }
 
-    pub(crate) fn gen_type(&self, ident: &ast::Identifier) -> Type {
-        let vis = ident.vis.display_as_accessor();
-        mono(format!("{}{vis}{}", self.name, ident.inspect()))
-    }
-
     pub(crate) fn get_namespace_path(&self, namespace: &Str) -> Option<PathBuf> {
         // get the true name
         let namespace = if let Some((_, vi)) = self.get_var_info(namespace) {
@@ -2939,6 +3120,23 @@ impl Context {
             return Some((t, ctx));
         }
         let typ = Type::Mono(Str::rc(name));
+        // enum variants (`E.A`) are defined in the methods of `E`
+        // this must be checked before the local name (`A`) is looked up, since it may shadow a builtin type
+        let namespace = typ.namespace();
+        if !namespace.is_empty() {
+            let enum_ctx = if self.name == namespace {
+                Some(self)
+            } else {
+                self.get_mono_type(&namespace).map(|(_, ctx)| ctx)
+            };
+            if let Some(ctx) = enum_ctx {
+                for (_, methods) in ctx.methods_list.iter() {
+                    if let Some((t, ctx)) = methods.mono_types.get(&typ.local_name()[..]) {
+                        return Some((t, ctx));
+                    }
+                }
+            }
+        }
         if self.name.starts_with(&typ.namespace()[..]) {
             if let Some((t, ctx)) = self.rec_local_get_mono_type(&typ.local_name()) {
                 return Some((t, ctx));
@@ -2952,6 +3150,42 @@ impl Context {
         None
     }
 
+    /// Returns the variant type `name` of the enum `t` (e.g. `E.A` of `E = Enum { .A = Int }`)
+    pub(crate) fn get_enum_variant(&self, t: &Type, name: &str) -> Option<Type> {
+        let (_, ctx) = self.get_nominal_type_ctx(t)?;
+        ctx.methods_list.iter().find_map(|(_, methods)| {
+            let ValueObj::Type(TypeObj::Generated(GenTypeObj::Subclass(variant))) =
+                methods.consts.get(name)?
+            else {
+                return None;
+            };
+            match variant.sup.as_ref() {
+                TypeObj::Generated(GenTypeObj::Enum(enum_)) if &enum_.t == t => {
+                    Some(variant.t.clone())
+                }
+                _ => None,
+            }
+        })
+    }
+
+    /// Returns the variant types of `t` if `t` is an enum (`Enum { .A = ...; .B = ... }`).
+    pub(crate) fn get_enum_variants(&self, t: &Type) -> Option<Vec<Type>> {
+        let (_, ctx) = self.get_nominal_type_ctx(t)?;
+        let mut variants = vec![];
+        for (_, methods) in ctx.methods_list.iter() {
+            for val in methods.consts.values() {
+                if let ValueObj::Type(TypeObj::Generated(GenTypeObj::Subclass(variant))) = val {
+                    if let TypeObj::Generated(GenTypeObj::Enum(enum_)) = variant.sup.as_ref() {
+                        if &enum_.t == t {
+                            variants.push(variant.t.clone());
+                        }
+                    }
+                }
+            }
+        }
+        (!variants.is_empty()).then_some(variants)
+    }
+
     /// you should use `get_mono_type` instead of this
     pub(crate) fn rec_local_get_mono_type(&self, name: &str) -> Option<(&Type, &Context)> {
         #[cfg(feature = "py_compat")]
@@ -2982,6 +3216,23 @@ impl Context {
             return Some((t, ctx));
         }
         let typ = Type::Mono(Str::rc(name));
+        // enum variants (`E.A`) are defined in the methods of `E`
+        // this must be checked before the local name (`A`) is looked up, since it may shadow a builtin type
+        let namespace = typ.namespace();
+        if !namespace.is_empty() {
+            let enum_ctx = if self.name == namespace {
+                Some(self)
+            } else {
+                self.get_mono_type(&namespace).map(|(_, ctx)| ctx)
+            };
+            if let Some(ctx) = enum_ctx {
+                for (_, methods) in ctx.methods_list.iter() {
+                    if let Some((t, ctx)) = methods.mono_types.get(&typ.local_name()[..]) {
+                        return Some((t, ctx));
+                    }
+                }
+            }
+        }
         if self.name.starts_with(&typ.namespace()[..]) {
             if let Some((t, ctx)) = self.rec_local_get_poly_type(&typ.local_name()) {
                 return Some((t, ctx));
@@ -3039,6 +3290,23 @@ impl Context {
             return Some((t, ctx));
         }
         let typ = Type::Mono(Str::rc(name));
+        // enum variants (`E.A`) are defined in the methods of `E`
+        // this must be checked before the local name (`A`) is looked up, since it may shadow a builtin type
+        let namespace = typ.namespace();
+        if !namespace.is_empty() {
+            let enum_ctx = if self.name == namespace {
+                Some(self)
+            } else {
+                self.get_mono_type(&namespace).map(|(_, ctx)| ctx)
+            };
+            if let Some(ctx) = enum_ctx {
+                for (_, methods) in ctx.methods_list.iter() {
+                    if let Some((t, ctx)) = methods.mono_types.get(&typ.local_name()[..]) {
+                        return Some((t, ctx));
+                    }
+                }
+            }
+        }
         if self.name.starts_with(&typ.namespace()[..]) {
             if let Some((t, ctx)) = self.rec_local_get_type(&typ.local_name()) {
                 return Some((t, ctx));
@@ -3319,6 +3587,14 @@ impl Context {
         }
     }
 
+    /// `T = Structural Trait {...}`
+    pub(crate) fn is_structural_trait(&self, typ: &Type) -> bool {
+        matches!(typ, Type::Mono(_))
+            && self
+                .get_nominal_type_ctx(typ)
+                .is_some_and(|(_, ctx)| ctx.kind == ContextKind::StructuralTrait)
+    }
+
     pub(crate) fn is_trait(&self, typ: &Type) -> bool {
         match typ {
             Type::Never => false,
@@ -3514,3 +3790,12 @@ impl Context {
         }
     }
 }
+
+/// e.g. `Foo` ==> `Foo!`, `Foo!` ==> `Foo!`
+fn mutable_name(name: Str) -> Str {
+    if name.ends_with('!') {
+        name
+    } else {
+        Str::from(format!("{name}!"))
+    }
+}
@@ -4,7 +4,6 @@ use std::option::Option; // conflicting to Type::Option
 
 use erg_common::consts::DEBUG_MODE;
 use erg_common::dict::Dict;
-use erg_common::enum_unwrap;
 #[allow(unused)]
 use erg_common::log;
 use erg_common::set::Set;
@@ -813,6 +812,13 @@ impl Context {
                 let r = self.instantiate_t_inner(*r, tmp_tv_cache, loc)?;
                 Ok(self.intersection(&l, &r))
             }
+            Overloaded(overloads) => {
+                let overloads = overloads
+                    .into_iter()
+                    .map(|t| self.instantiate_t_inner(t, tmp_tv_cache, loc))
+                    .collect::<TyCheckResult<_>>()?;
+                Ok(Overloaded(overloads))
+            }
             Or(l, r) => {
                 let l = self.instantiate_t_inner(*l, tmp_tv_cache, loc)?;
                 let r = self.instantiate_t_inner(*r, tmp_tv_cache, loc)?;
@@ -835,6 +841,13 @@ impl Context {
                 let rhs = self.instantiate(*rhs, callee)?;
                 Ok(lhs & rhs)
             }
+            Overloaded(overloads) => {
+                let overloads = overloads
+                    .into_iter()
+                    .map(|t| self.instantiate(t, callee))
+                    .collect::<TyCheckResult<_>>()?;
+                Ok(Overloaded(overloads))
+            }
             Quantified(quant) => {
                 let mut tmp_tv_cache = TyVarCache::new(self.level, self);
                 let ty = self.instantiate_t_inner(*quant, &mut tmp_tv_cache, callee)?;
@@ -878,6 +891,18 @@ impl Context {
                             )?;
                         }
                     }
+                    Type::Overloaded(overloads) => {
+                        for t in overloads.iter() {
+                            if let Some(self_t) = t.self_t() {
+                                self.sub_unify(
+                                    callee.ref_t(),
+                                    self_t,
+                                    callee,
+                                    Some(&Str::ever("self")),
+                                )?;
+                            }
+                        }
+                    }
                     other => unreachable!("{other}"),
                 }
                 Ok(t)
@@ -895,6 +920,13 @@ impl Context {
                 let rhs = self.instantiate_dummy(*rhs)?;
                 Ok(lhs & rhs)
             }
+            Overloaded(overloads) => {
+                let overloads = overloads
+                    .into_iter()
+                    .map(|t| self.instantiate_dummy(t))
+                    .collect::<TyCheckResult<_>>()?;
+                Ok(Overloaded(overloads))
+            }
             Quantified(quant) => {
                 let mut tmp_tv_cache = TyVarCache::new(self.level, self);
                 let ty = self.instantiate_t_inner(*quant, &mut tmp_tv_cache, &())?;
@@ -904,9 +936,7 @@ impl Context {
                 Ok(ty)
             }
             Refinement(refine) if refine.t.is_quantified_subr() => {
-                let quant = enum_unwrap!(*refine.t, Type::Quantified);
-                let mut tmp_tv_cache = TyVarCache::new(self.level, self);
-                self.instantiate_t_inner(*quant, &mut tmp_tv_cache, &())
+                self.instantiate_dummy(*refine.t)
             }
             _other => unreachable_error!(TyCheckErrors, TyCheckError, self),
         }
@@ -20,7 +20,7 @@ use crate::ty::free::{CanbeFree, Constraint};
 use crate::ty::typaram::{IntervalOp, OpKind, TyParam, TyParamLambda, TyParamOrdering};
 use crate::ty::value::ValueObj;
 use crate::ty::{constructors::*, Predicate, RefinementType, VisibilityModifier};
-use crate::ty::{Field, HasType, ParamTy, SubrKind, SubrType, Type};
+use crate::ty::{CastTarget, ConstSubr, Field, HasType, ParamTy, SubrKind, SubrType, Type};
 use crate::type_feature_error;
 use TyParamOrdering::*;
 use Type::*;
@@ -136,9 +136,8 @@ impl Context {
                     TokenKind::SupertypeOf => Constraint::new_supertype_of(
                         self.instantiate_typespec_full(&spec.t_spec, None, tv_cache, mode, true)?,
                     ),
-                    TokenKind::Colon => Constraint::new_type_of(self.instantiate_typespec_full(
+                    TokenKind::Colon => Constraint::new_type_of(self.instantiate_typaram_t(
                         &spec.t_spec,
-                        None,
                         tv_cache,
                         mode,
                         true,
@@ -162,6 +161,20 @@ impl Context {
         }
     }
 
+    /// Instantiates the type of a type parameter.
+    /// Type parameters themselves are immutable, so `N: Nat!` is instantiated as `N: Nat`.
+    /// The changes are expressed by state transitions (e.g. `ref! self(T, N ~> N+1)`).
+    pub(crate) fn instantiate_typaram_t(
+        &self,
+        t_spec: &TypeSpec,
+        tv_cache: &mut TyVarCache,
+        mode: RegistrationMode,
+        not_found_is_qvar: bool,
+    ) -> TyCheckResult<Type> {
+        let t = self.instantiate_typespec_full(t_spec, None, tv_cache, mode, not_found_is_qvar)?;
+        Ok(t.immutate().unwrap_or(t))
+    }
+
     pub(crate) fn instantiate_ty_bounds(
         &self,
         bounds: &TypeBoundSpecs,
@@ -198,6 +211,37 @@ impl Context {
         Ok(tv_cache)
     }
 
+    /// Bind the parameter names of a methods block header to the type parameters of the class.
+    /// ```erg
+    /// Box T = Class {.value = T}
+    /// Box(T). # Box(T) == <module>::Box(T)
+    /// ```
+    pub(crate) fn instantiate_class_params(&self, class_spec: &TypeSpec) -> TyVarCache {
+        let mut tv_cache = TyVarCache::new(self.level, self);
+        let TypeSpec::PreDeclTy(PreDeclTypeSpec::Poly(poly_spec)) = class_spec else {
+            return tv_cache;
+        };
+        let Some((class, _)) = self.get_type_and_ctx(&poly_spec.ident()) else {
+            return tv_cache;
+        };
+        for (arg, param) in poly_spec.args.pos_args().zip(class.typarams()) {
+            let ConstExpr::Accessor(ast::ConstAccessor::Local(ident)) = &arg.expr else {
+                continue;
+            };
+            if self.rec_get_const_obj(ident.inspect()).is_some() {
+                continue;
+            }
+            match param {
+                TyParam::Type(t) if t.has_qvar() => {
+                    tv_cache.push_or_init_tyvar(&ident.name, &t, self)
+                }
+                tp if tp.has_qvar() => tv_cache.push_or_init_typaram(&ident.name, &tp, self),
+                _ => {}
+            }
+        }
+        tv_cache
+    }
+
     pub(crate) fn instantiate_var_sig_t(
         &self,
         t_spec: Option<&TypeSpec>,
@@ -303,16 +347,27 @@ impl Context {
             }
         }
         let spec_return_t = if let Some(t_spec) = sig.return_t_spec.as_ref() {
-            let opt_decl_t = opt_decl_sig_t
-                .as_ref()
-                .map(|subr| ParamTy::Pos(subr.return_t.as_ref().clone()));
-            match self.instantiate_typespec_full(
-                &t_spec.t_spec,
-                opt_decl_t.as_ref(),
-                &mut tmp_tv_cache,
-                mode,
-                false,
-            ) {
+            let opt_decl_t = opt_decl_sig_t.as_ref().map(|subr| {
+                let return_t = if sig.is_async() {
+                    subr.return_t.coroutine_output()
+                } else {
+                    None
+                };
+                ParamTy::Pos(return_t.unwrap_or_else(|| subr.return_t.as_ref().clone()))
+            });
+            let param_names = sig.params.non_defaults.iter().map(|p| p.inspect());
+            let res = self
+                .instantiate_guard_type_spec(&t_spec.t_spec, param_names, &mut tmp_tv_cache, false)
+                .unwrap_or_else(|| {
+                    self.instantiate_typespec_full(
+                        &t_spec.t_spec,
+                        opt_decl_t.as_ref(),
+                        &mut tmp_tv_cache,
+                        mode,
+                        false,
+                    )
+                });
+            match res {
                 Ok(ty) => ty,
                 Err(es) => {
                     errs.extend(es);
@@ -328,6 +383,12 @@ impl Context {
             };
             free_var(level, Constraint::new_type_of(Type))
         };
+        // `@Async` subroutines return a coroutine of the specified type
+        let spec_return_t = if sig.is_async() {
+            coroutine(spec_return_t)
+        } else {
+            spec_return_t
+        };
         // tmp_tv_cache.warn_isolated_vars(self);
         let typ = if sig.ident.is_procedural() {
             proc(non_defaults, var_args, defaults, spec_return_t)
@@ -341,6 +402,42 @@ impl Context {
         }
     }
 
+    /// Instantiates the return type spec of a user-defined type guard.
+    /// Returns `None` if `t_spec` is not a guard type spec.
+    /// ```erg
+    /// is_int(x: Obj): {x in Int} = isinstance(x, Int) # return type: {x#0 in Int}
+    /// ```
+    fn instantiate_guard_type_spec<'a>(
+        &self,
+        t_spec: &TypeSpec,
+        mut param_names: impl Iterator<Item = Option<&'a Str>>,
+        tmp_tv_cache: &mut TyVarCache,
+        not_found_is_qvar: bool,
+    ) -> Option<TyCheckResult<Type>> {
+        let TypeSpec::Enum(set) = t_spec else {
+            return None;
+        };
+        let mut elems = set.pos_args();
+        let (Some(elem), None) = (elems.next(), elems.next()) else {
+            return None;
+        };
+        let ConstExpr::BinOp(bin) = &elem.expr else {
+            return None;
+        };
+        let ConstExpr::Accessor(ast::ConstAccessor::Local(ident)) = bin.lhs.as_ref() else {
+            return None;
+        };
+        if bin.op.kind != TokenKind::InOp {
+            return None;
+        }
+        let nth = param_names.position(|name| name == Some(ident.inspect()))?;
+        let target = CastTarget::param(nth, ident.inspect().clone(), ident.loc());
+        Some(
+            self.instantiate_const_expr_as_type(&bin.rhs, None, tmp_tv_cache, not_found_is_qvar)
+                .map(|to| guard(self.name.clone(), target, to)),
+        )
+    }
+
     /// spec_t == Noneかつリテラル推論が不可能なら型変数を発行する
     pub(crate) fn instantiate_param_sig_t(
         &self,
@@ -360,13 +457,45 @@ impl Context {
             free_var(level, Constraint::new_type_of(Type))
         };
         let spec_t = if let Some(spec_with_op) = &sig.t_spec {
-            self.instantiate_typespec_full(
-                &spec_with_op.t_spec,
-                opt_decl_t,
-                tmp_tv_cache,
-                mode,
-                not_found_is_qvar,
-            )?
+            match (&sig.pat, &spec_with_op.t_spec) {
+                // e.g. `ref! self(T, N ~> N+1)` ==> `self: RefMut(Self(T, N), after: Self(T, N+1))`
+                (
+                    ast::ParamPattern::RefMut(_),
+                    TypeSpec::PreDeclTy(PreDeclTypeSpec::Poly(poly_spec)),
+                ) if poly_spec.split_transition().is_some() => {
+                    let (before, after) = poly_spec.split_transition().unwrap();
+                    let before = self.instantiate_poly_t(
+                        &before,
+                        opt_decl_t,
+                        tmp_tv_cache,
+                        not_found_is_qvar,
+                    )?;
+                    let after = self.instantiate_poly_t(
+                        &after,
+                        opt_decl_t,
+                        tmp_tv_cache,
+                        not_found_is_qvar,
+                    )?;
+                    ref_mut(before, Some(after))
+                }
+                (ast::ParamPattern::RefMut(_), t_spec) => {
+                    let t = self.instantiate_typespec_full(
+                        t_spec,
+                        opt_decl_t,
+                        tmp_tv_cache,
+                        mode,
+                        not_found_is_qvar,
+                    )?;
+                    ref_mut(t, None)
+                }
+                (_, t_spec) => self.instantiate_typespec_full(
+                    t_spec,
+                    opt_decl_t,
+                    tmp_tv_cache,
+                    mode,
+                    not_found_is_qvar,
+                )?,
+            }
         } else {
             match &sig.pat {
                 ast::ParamPattern::Lit(lit) => v_enum(set![self.eval_lit(lit)?]),
@@ -470,6 +599,9 @@ impl Context {
                         tmp_tv_cache,
                         not_found_is_qvar,
                     ) {
+                        if let Some(variant) = self.get_enum_variant(&receiver_t, t.inspect()) {
+                            return Ok(variant);
+                        }
                         return self.eval_proj(
                             receiver_t,
                             t.inspect().clone(),
@@ -480,6 +612,11 @@ impl Context {
                 }
                 let ctxs = self.get_singular_ctxs(namespace.as_ref(), self)?;
                 for ctx in ctxs {
+                    if let Some(dict @ ValueObj::Dict(_)) = ctx.rec_get_const_obj(t.inspect()) {
+                        if let Ok(typ) = ctx.convert_value_into_type(dict.clone()) {
+                            return Ok(typ);
+                        }
+                    }
                     if let Some((typ, _)) = ctx.rec_local_get_type(t.inspect()) {
                         // TODO: visibility check
                         return Ok(typ.clone());
@@ -550,6 +687,16 @@ impl Context {
                         return Ok(t);
                     }
                 }
+                // e.g. `Person = {"name": Str}`
+                // `get_type_and_ctx` returns the generic `Dict` type for this
+                if let Some(dict @ ValueObj::Dict(_)) = self.rec_get_const_obj(ident.inspect()) {
+                    if let Ok(typ) = self.convert_value_into_type(dict.clone()) {
+                        if let Some((_, vi)) = self.get_var_info(ident.inspect()) {
+                            self.inc_ref(ident.inspect(), vi, ident, self);
+                        }
+                        return Ok(typ);
+                    }
+                }
                 if let Some((typ, _)) = self.get_type_and_ctx(ident.inspect()) {
                     if let Some((_, vi)) = self.get_var_info(ident.inspect()) {
                         self.inc_ref(ident.inspect(), vi, ident, self);
@@ -727,7 +874,14 @@ impl Context {
                 Ok(Type::NamedTuple(ts))
             }
             other => {
-                let Some((typ, ctx)) = self.get_type_and_ctx(&Str::rc(other)) else {
+                // e.g. `Self(T, N)` in `Queue!(T, N).`
+                let typ_ctx = if other == "Self" {
+                    self.rec_get_self_t()
+                        .and_then(|self_t| self.get_nominal_type_ctx(&self_t))
+                } else {
+                    self.get_type_and_ctx(&Str::rc(other))
+                };
+                let Some((typ, ctx)) = typ_ctx else {
                     return Err(TyCheckErrors::from(TyCheckError::no_type_error(
                         self.cfg.input.clone(),
                         line!() as usize,
@@ -772,7 +926,9 @@ impl Context {
                             err
                         })
                         .unwrap_or(Obj);
-                    if self.subtype_of(&arg_t, &param_vi.t) {
+                    // e.g. `N-1: Int` can be checked after `N` is determined
+                    let deferred = param.has_unbound_var() && self.subtype_of(&param_vi.t, &arg_t);
+                    if self.subtype_of(&arg_t, &param_vi.t) || deferred {
                         new_params.push(param);
                     } else {
                         return Err(TyCheckErrors::from(TyCheckError::type_mismatch_error(
@@ -873,6 +1029,21 @@ impl Context {
         )))
     }
 
+    /// e.g. `Fact(3)` (`Fact N = ...`)
+    fn is_user_const_subr_call(&self, expr: &ast::ConstExpr) -> bool {
+        let ast::ConstExpr::App(app) = expr else {
+            return false;
+        };
+        let ast::ConstExpr::Accessor(ast::ConstAccessor::Local(ident)) = app.obj.as_ref() else {
+            return false;
+        };
+        app.attr_name.is_none()
+            && matches!(
+                self.rec_get_const_obj(ident.inspect()),
+                Some(ValueObj::Subr(ConstSubr::User(_)))
+            )
+    }
+
     /// erased_index:
     /// e.g. `instantiate_const_expr(Array(Str, _), Some((self, 1))) => Array(Str, _: Nat)`
     pub(crate) fn instantiate_const_expr(
@@ -882,8 +1053,15 @@ impl Context {
         tmp_tv_cache: &mut TyVarCache,
         not_found_is_qvar: bool,
     ) -> TyCheckResult<TyParam> {
-        if let Ok(value) = self.eval_const_expr(&expr.clone().downgrade()) {
-            return Ok(TyParam::Value(value));
+        match self.eval_const_expr(&expr.clone().downgrade()) {
+            Ok(value) => {
+                return Ok(TyParam::Value(value));
+            }
+            // user-defined const subroutines cannot be left unevaluated
+            Err(errs) if self.is_user_const_subr_call(expr) => {
+                return Err(errs);
+            }
+            Err(_) => {}
         }
         match expr {
             ast::ConstExpr::Lit(lit) => Ok(TyParam::Value(self.eval_lit(lit)?)),
@@ -1125,6 +1303,9 @@ impl Context {
                 )?;
                 Ok(TyParam::bin(op, lhs, rhs))
             }
+            // type parameters are immutable (see `instantiate_typaram_t`), so `!2` == `2`
+            ast::ConstExpr::UnaryOp(unary) if unary.op.is(TokenKind::Mutate) => self
+                .instantiate_const_expr(&unary.expr, erased_idx, tmp_tv_cache, not_found_is_qvar),
             ast::ConstExpr::UnaryOp(unary) => {
                 let Some(op) = token_kind_to_op_kind(unary.op.kind) else {
                     return type_feature_error!(
@@ -1452,12 +1633,23 @@ impl Context {
                     mode,
                     not_found_is_qvar,
                 )?;
+                // `[T; !N]` == `Array!(T, N)`
+                let (len, mutable) = match &arr.len {
+                    ConstExpr::UnaryOp(unary) if unary.op.is(TokenKind::Mutate) => {
+                        (unary.expr.as_ref(), true)
+                    }
+                    len => (len, false),
+                };
                 let mut len =
-                    self.instantiate_const_expr(&arr.len, None, tmp_tv_cache, not_found_is_qvar)?;
+                    self.instantiate_const_expr(len, None, tmp_tv_cache, not_found_is_qvar)?;
                 if let TyParam::Erased(t) = &mut len {
                     *t.as_mut() = Type::Nat;
                 }
-                Ok(array_t(elem_t, len))
+                if mutable {
+                    Ok(array_mut(elem_t, len))
+                } else {
+                    Ok(array_t(elem_t, len))
+                }
             }
             TypeSpec::SetWithLen(set) => {
                 let elem_t = self.instantiate_typespec_full(
@@ -1527,6 +1719,19 @@ impl Context {
             }
             // TODO: エラー処理(リテラルでない)はパーサーにやらせる
             TypeSpec::Enum(set) => {
+                // the return type spec of a type guard, instantiated in the subroutine's context
+                let param_names = self
+                    .params
+                    .iter()
+                    .map(|(name, _)| name.as_ref().map(|n| n.inspect()));
+                if let Some(res) = self.instantiate_guard_type_spec(
+                    t_spec,
+                    param_names,
+                    tmp_tv_cache,
+                    not_found_is_qvar,
+                ) {
+                    return res;
+                }
                 let mut new_set = set! {};
                 for arg in set.pos_args() {
                     new_set.insert(self.instantiate_const_expr(
@@ -1609,13 +1814,26 @@ impl Context {
                 })?
                 .into_iter()
                 .collect();
-                let return_t = self.instantiate_typespec_full(
-                    &subr.return_t,
-                    opt_decl_t,
-                    tmp_tv_ctx,
-                    mode,
-                    not_found_is_qvar,
-                )?;
+                let param_names = subr
+                    .non_defaults
+                    .iter()
+                    .map(|p| p.name.as_ref().map(|n| n.inspect()));
+                let return_t = self
+                    .instantiate_guard_type_spec(
+                        &subr.return_t,
+                        param_names,
+                        tmp_tv_ctx,
+                        not_found_is_qvar,
+                    )
+                    .unwrap_or_else(|| {
+                        self.instantiate_typespec_full(
+                            &subr.return_t,
+                            opt_decl_t,
+                            tmp_tv_ctx,
+                            mode,
+                            not_found_is_qvar,
+                        )
+                    })?;
                 // no quantification at this point (in `generalize_t`)
                 Ok(subr_t(
                     SubrKind::from(subr.arrow.kind),
@@ -34,6 +34,7 @@ use erg_parser::ast;
 use erg_parser::ast::Def;
 use erg_parser::token::Token;
 
+use crate::context::eval::ConstEvalState;
 use crate::context::instantiate::TyVarCache;
 use crate::context::instantiate_spec::ConstTemplate;
 use crate::error::{TyCheckError, TyCheckErrors};
@@ -53,6 +54,72 @@ pub trait ContextProvider {
     fn dir(&self) -> Dict<&VarName, &VarInfo>;
     fn get_receiver_ctx(&self, receiver_name: &str) -> Option<&Context>;
     fn get_var_info(&self, name: &str) -> Option<(&VarName, &VarInfo)>;
+
+    /// `name: type` lines of the user-defined variables (or the attributes of `receiver`), sorted by name.
+    fn dir_lines(&self, receiver: Option<&str>) -> Option<Vec<String>> {
+        let vars = if let Some(receiver) = receiver {
+            let ctx = self.get_receiver_ctx(receiver)?;
+            ctx.type_dir(ctx)
+        } else {
+            self.dir()
+                .into_iter()
+                .filter(|(_, vi)| !vi.kind.is_builtin())
+                .collect()
+        };
+        let mut lines = vars
+            .into_iter()
+            .filter(|(name, _)| !name.inspect().starts_with('%'))
+            .map(|(name, vi)| format!("{name}: {}", vi.t))
+            .collect::<Vec<_>>();
+        lines.sort();
+        Some(lines)
+    }
+
+    /// The signature of `name` (`x` or `receiver.attr`) followed by its doc comment.
+    fn signature_and_doc(&self, name: &str) -> Option<String> {
+        let (name, vi) = if let Some((receiver, attr)) = name.rsplit_once('.') {
+            let ctx = self.get_receiver_ctx(receiver)?;
+            ctx.type_dir(ctx)
+                .into_iter()
+                .find(|(name, _)| &name.inspect()[..] == attr)?
+        } else {
+            self.get_var_info(name)?
+        };
+        let mut doc = format!("{name}: {}", vi.t);
+        if let Some(comment) = vi.def_loc.doc_comment() {
+            doc.push_str("\n\n");
+            doc.push_str(&comment);
+        }
+        Some(doc)
+    }
+
+    /// Names (or attributes, if `word` is like `obj.at`) that start with `word`.
+    fn completion_candidates(&self, word: &str) -> Vec<String> {
+        let (receiver, prefix) = match word.rsplit_once('.') {
+            Some((receiver, prefix)) => (Some(receiver), prefix),
+            None => (None, word),
+        };
+        let vars = if let Some(receiver) = receiver {
+            let Some(ctx) = self.get_receiver_ctx(receiver) else {
+                return vec![];
+            };
+            ctx.type_dir(ctx)
+        } else {
+            self.dir()
+        };
+        let mut candidates = vars
+            .into_iter()
+            .map(|(name, _)| name.inspect())
+            .filter(|name| name.starts_with(prefix) && !name.starts_with('%'))
+            .map(|name| match receiver {
+                Some(receiver) => format!("{receiver}.{name}"),
+                None => name.to_string(),
+            })
+            .collect::<Vec<_>>();
+        candidates.sort();
+        candidates.dedup();
+        candidates
+    }
 }
 
 const BUILTINS: &Str = &Str::ever("<builtins>");
@@ -505,6 +572,8 @@ pub struct Context {
     pub(crate) higher_order_caller: Vec<Str>,
     pub(crate) guards: Vec<GuardType>,
     pub(crate) erg_to_py_names: Dict<Str, Str>,
+    /// The state of the compile-time evaluation in progress (only in the context created for the evaluation).
+    pub(crate) const_eval: Option<ConstEvalState>,
     pub(crate) level: usize,
 }
 
@@ -603,6 +672,22 @@ impl Context {
             .remove(name)
             .or_else(|| self.locals.remove(name))
     }
+
+    /// Registers `name: t`, whose value is bound from outside of Erg (e.g. by an application embedding Erg).
+    /// The generated code refers to it as `name` without mangling.
+    pub fn register_external(&mut self, name: &str, t: Type) {
+        let vi = VarInfo::new(
+            t,
+            Mutability::Immutable,
+            Visibility::private(self.name.clone()),
+            VarKind::Declared,
+            None,
+            None,
+            Some(Str::rc(name)),
+            AbsLocation::unknown(),
+        );
+        self.locals.insert(VarName::from_str(Str::rc(name)), vi);
+    }
 }
 
 impl Context {
@@ -704,6 +789,7 @@ impl Context {
             higher_order_caller: vec![],
             guards: vec![],
             erg_to_py_names: Dict::default(),
+            const_eval: None,
             level,
         }
     }
@@ -1009,6 +1095,12 @@ impl Context {
         self.cfg.input.path()
     }
 
+    /// Returns `true` if `self` is defined in an Erg script (not builtin nor declared in a `.d.er` file).
+    pub(crate) fn is_user_defined(&self) -> bool {
+        // builtin types do not have a shared compiler resource
+        self.shared.is_some() && !self.module_path().to_string_lossy().ends_with(".d.er")
+    }
+
     pub(crate) fn absolutize(&self, loc: Location) -> AbsLocation {
         AbsLocation::new(Some(PathBuf::from(self.module_path())), loc)
     }
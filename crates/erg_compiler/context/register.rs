@@ -7,7 +7,7 @@ use std::process::{Command, Stdio};
 use std::thread::sleep;
 use std::time::{Duration, SystemTime};
 
-use erg_common::config::ErgMode;
+use erg_common::config::{ErgConfig, ErgMode};
 use erg_common::consts::{ERG_MODE, PYTHON_MODE};
 use erg_common::dict::Dict;
 use erg_common::env::{is_pystd_main_module, is_std_decl_path};
@@ -23,19 +23,22 @@ use erg_common::triple::Triple;
 use erg_common::{dict, get_hash, log, set, unique_in_place, Str};
 
 use ast::{
-    ConstIdentifier, Decorator, DefId, Identifier, OperationKind, PolyTypeSpec, PreDeclTypeSpec,
-    VarName,
+    ConstIdentifier, Decorator, DefId, DefKind, Identifier, OperationKind, PolyTypeSpec,
+    PreDeclTypeSpec, VarName,
 };
 use erg_parser::ast;
+use erg_parser::Parser;
 
 use crate::ty::constructors::{
-    free_var, func, func0, func1, proc, ref_, ref_mut, tp_enum, unknown_len_array_t, v_enum,
+    free_var, func, func0, func1, instanceof, mono, mono_q, mono_q_tp, poly, proc, ref_, ref_mut,
+    tp_enum, unknown_len_array_t, v_enum,
 };
 use crate::ty::free::{Constraint, HasLevel};
 use crate::ty::typaram::TyParam;
 use crate::ty::value::{GenTypeObj, TypeObj, ValueObj};
 use crate::ty::{
-    CastTarget, Field, GuardType, HasType, ParamTy, SubrType, Type, Visibility, VisibilityModifier,
+    CastTarget, ConstSubr, Field, GuardType, HasType, ParamTy, SubrType, Type, UserConstSubr,
+    Visibility, VisibilityModifier,
 };
 
 use crate::build_hir::HIRBuilder;
@@ -45,8 +48,9 @@ use crate::error::{
     CompileError, CompileErrors, CompileResult, TyCheckError, TyCheckErrors, TyCheckResult,
 };
 use crate::hir::Literal;
+use crate::module::{disk_cache, SharedCompilerResource};
 use crate::varinfo::{AbsLocation, AliasInfo, Mutability, VarInfo, VarKind};
-use crate::{feature_error, hir};
+use crate::{feature_error, hir, unreachable_error};
 use Mutability::*;
 use RegistrationMode::*;
 
@@ -781,7 +785,13 @@ impl Context {
         let non_default_params = t.non_default_params().unwrap_or(&empty);
         let var_args = t.var_params();
         let default_params = t.default_params().unwrap_or(&empty);
-        if let Some(spec_ret_t) = t.return_t() {
+        // the body of an `@Async` subroutine is checked against `T` of `Coroutine(T)`
+        let spec_ret_t = if sig.is_async() {
+            t.return_t().and_then(|ret_t| ret_t.coroutine_output())
+        } else {
+            t.return_t().cloned()
+        };
+        if let Some(spec_ret_t) = spec_ret_t.as_ref() {
             let unify_result = if let Some(t_spec) = sig.return_t_spec.as_ref() {
                 self.sub_unify(body_t, spec_ret_t, t_spec, None)
             } else {
@@ -1062,7 +1072,7 @@ impl Context {
     fn preregister_type(&mut self, var: &ast::VarSignature, call: &ast::Call) -> TyCheckResult<()> {
         match call.obj.as_ref() {
             ast::Expr::Accessor(ast::Accessor::Ident(ident)) => match &ident.inspect()[..] {
-                "Class" => {
+                "Class" | "Enum" => {
                     let ident = var.ident().unwrap();
                     let t = Type::Mono(format!("{}{ident}", self.name).into());
                     let class = GenTypeObj::class(t, None, None, false);
@@ -1083,10 +1093,89 @@ impl Context {
         }
     }
 
+    /// Register a class with type/value parameters.
+    /// ```erg
+    /// Box T = Class {.value = T}
+    /// Queue! T: Type, N: Nat = Class {.payload = Array!(T, N)}
+    /// ```
+    /// Parameters typed as `Type` become type variables, others become value (dependent) parameters.
+    fn register_poly_class_def(
+        &mut self,
+        sig: &ast::SubrSignature,
+        body: &ast::DefBody,
+    ) -> TyCheckResult<()> {
+        let Some(ast::Expr::Call(call)) = body.block.first() else {
+            return unreachable_error!(TyCheckErrors, TyCheckError, self);
+        };
+        if !sig.params.defaults.is_empty() || sig.params.var_params.is_some() {
+            return feature_error!(
+                TyCheckErrors,
+                TyCheckError,
+                self,
+                sig.params.loc(),
+                "default/variable-length type parameters"
+            );
+        }
+        let mut tv_cache = TyVarCache::new(self.level, self);
+        let mut params = vec![];
+        for param in sig.params.non_defaults.iter() {
+            let Some(name) = param.name() else {
+                return feature_error!(
+                    TyCheckErrors,
+                    TyCheckError,
+                    self,
+                    param.loc(),
+                    "type parameter patterns"
+                );
+            };
+            let param_t = if let Some(spec) = param.t_spec.as_ref() {
+                self.instantiate_typaram_t(&spec.t_spec, &mut tv_cache, Normal, false)?
+            } else {
+                Type::Type
+            };
+            if self.subtype_of(&param_t, &Type::Type) {
+                let tv = mono_q(name.inspect().clone(), instanceof(param_t));
+                tv_cache.push_or_init_tyvar(name, &tv, self);
+                params.push(TyParam::t(tv));
+            } else {
+                let tp = mono_q_tp(name.inspect().clone(), instanceof(param_t));
+                tv_cache.push_or_init_typaram(name, &tp, self);
+                params.push(tp);
+            }
+        }
+        let mut instantiate_arg = |ctx: &Self, key: &'static str| {
+            let Some(expr) = call.args.get_left_or_key(key) else {
+                return Ok(None);
+            };
+            let spec = Parser::expr_to_type_spec(expr.clone()).map_err(|_| {
+                TyCheckErrors::from(TyCheckError::not_a_type_error(
+                    ctx.cfg.input.clone(),
+                    line!() as usize,
+                    expr.loc(),
+                    ctx.caused_by(),
+                    &expr.to_string(),
+                ))
+            })?;
+            let t = ctx.instantiate_typespec_full(&spec, None, &mut tv_cache, Normal, false)?;
+            Ok::<_, TyCheckErrors>(Some(TypeObj::builtin_type(t)))
+        };
+        let base = instantiate_arg(self, "Base")?;
+        let impls = instantiate_arg(self, "Impl")?;
+        let t = poly(format!("{}{}", self.name, sig.ident), params);
+        let class = GenTypeObj::class(t, base, impls, true);
+        self.register_gen_type(&sig.ident, class)
+    }
+
     pub(crate) fn register_const_def(&mut self, def: &ast::Def) -> TyCheckResult<()> {
         let id = Some(def.body.id);
         let __name__ = def.sig.ident().map(|i| i.inspect()).unwrap_or(UBAR);
         match &def.sig {
+            ast::Signature::Subr(sig) if sig.is_const() && def.def_kind() == DefKind::Class => {
+                self.register_poly_class_def(sig, &def.body)?;
+            }
+            ast::Signature::Subr(sig) if sig.is_const() && def.def_kind().is_other() => {
+                self.register_const_subr_def(sig, &def.body)?;
+            }
             ast::Signature::Subr(sig) => {
                 if sig.is_const() {
                     let tv_cache = self.instantiate_ty_bounds(&sig.bounds, PreRegister)?;
@@ -1173,6 +1262,39 @@ impl Context {
         Ok(())
     }
 
+    /// Register a user-defined const subroutine.
+    /// The body is not evaluated here, but each time the subroutine is called at compile time.
+    ///
+    /// e.g. `Fact N: Int = if N <= 1, do 1, do N * Fact(N - 1)`
+    fn register_const_subr_def(
+        &mut self,
+        sig: &ast::SubrSignature,
+        body: &ast::DefBody,
+    ) -> TyCheckResult<()> {
+        let block = Parser::validate_const_block(body.block.clone()).map_err(|_| {
+            TyCheckErrors::from(TyCheckError::not_const_expr(
+                self.cfg.input.clone(),
+                line!() as usize,
+                body.block.loc(),
+                self.caused_by(),
+            ))
+        })?;
+        self.declare_sub(sig, Some(body.id))?;
+        let sig_t = self
+            .decls
+            .get(&sig.ident.name)
+            .map_or(Type::Failure, |vi| vi.t.clone());
+        let subr = ConstSubr::User(UserConstSubr::new(
+            sig.ident.inspect().clone(),
+            sig.params.clone(),
+            block,
+            sig_t,
+        ));
+        self.consts
+            .insert(sig.ident.name.clone(), ValueObj::Subr(subr));
+        Ok(())
+    }
+
     /// e.g. .new
     fn register_auto_impl(
         &mut self,
@@ -1614,6 +1736,35 @@ impl Context {
                     )
                 }
             }
+            // `Structural Trait {...}`: registered like a nominal trait,
+            // but types are compared with it structurally (no explicit `Impl` is needed)
+            GenTypeObj::Structural(structural) => match *structural.base {
+                TypeObj::Generated(gen @ GenTypeObj::Trait(_)) if gen.typ().is_monomorphic() => {
+                    let mut ctx = Self::mono_trait(
+                        gen.typ().qual_name(),
+                        self.cfg.clone(),
+                        self.shared.clone(),
+                        2,
+                        self.level,
+                    );
+                    ctx.kind = ContextKind::StructuralTrait;
+                    if let Some(TypeObj::Builtin {
+                        t: Type::Record(req),
+                        ..
+                    }) = gen.base_or_sup()
+                    {
+                        self.register_instance_attrs(&mut ctx, req)?;
+                    }
+                    self.register_gen_mono_type(ident, gen, ctx, Const)
+                }
+                other => feature_error!(
+                    CompileErrors,
+                    CompileError,
+                    self,
+                    ident.loc(),
+                    &format!("Structural {other} definition")
+                ),
+            },
             GenTypeObj::Patch(_) => {
                 if gen.typ().is_monomorphic() {
                     let Some(TypeObj::Builtin { t: base, .. }) = gen.base_or_sup() else {
@@ -1638,6 +1789,27 @@ impl Context {
                     )
                 }
             }
+            GenTypeObj::Enum(_) => {
+                if gen.typ().is_monomorphic() {
+                    let ctx = Self::mono_class(
+                        gen.typ().qual_name(),
+                        self.cfg.clone(),
+                        self.shared.clone(),
+                        2,
+                        self.level,
+                    );
+                    let ctx = self.register_enum_variants(&gen, ctx)?;
+                    self.register_gen_mono_type(ident, gen, ctx, Const)
+                } else {
+                    feature_error!(
+                        CompileErrors,
+                        CompileError,
+                        self,
+                        ident.loc(),
+                        "polymorphic enum definition"
+                    )
+                }
+            }
             other => feature_error!(
                 CompileErrors,
                 CompileError,
@@ -1648,6 +1820,99 @@ impl Context {
         }
     }
 
+    /// `E = Enum { .A = Int; .B = {x = Int} }`
+    /// => `E.A <: E` (with `E.A::__new__: Int -> E.A` and a `_0` attribute), `E.B <: E` (with `E.B::__new__: {x = Int} -> E.B`)
+    ///
+    /// Each variant is a class whose type is `E.{Variant}`, registered as a constant attribute of `E`.
+    fn register_enum_variants(&self, gen: &GenTypeObj, mut ctx: Context) -> CompileResult<Context> {
+        let GenTypeObj::Enum(enum_) = gen else {
+            return unreachable_error!(CompileErrors, CompileError, self);
+        };
+        let Type::Record(variants) = enum_.variants.typ() else {
+            return unreachable_error!(CompileErrors, CompileError, self);
+        };
+        let mut variant_defs =
+            Self::methods(None, self.cfg.clone(), self.shared.clone(), 2, self.level);
+        for (field, payload) in variants.iter() {
+            let variant_t = mono(format!("{}.{}", gen.typ().qual_name(), field.symbol));
+            let mut variant_ctx = Self::mono_class(
+                variant_t.qual_name(),
+                self.cfg.clone(),
+                self.shared.clone(),
+                2,
+                self.level,
+            );
+            variant_ctx.register_superclass(gen.typ().clone(), &ctx);
+            // record fields keep their visibility (private ones are accessible from the methods of the enum),
+            // a non-record payload is stored in the public `_0` so that `E.A(x)` patterns can be matched anywhere
+            let fields = match payload {
+                Type::Record(rec) => rec.clone(),
+                other => dict! { Field::public("_0".into()) => other.clone() },
+            };
+            for (field, t) in fields.iter() {
+                let vi = VarInfo::instance_attr(field.clone(), t.clone(), None, ctx.name.clone());
+                variant_ctx
+                    .decls
+                    .insert(VarName::from_str(field.symbol.clone()), vi);
+            }
+            let mut methods =
+                Self::methods(None, self.cfg.clone(), self.shared.clone(), 2, self.level);
+            let new_t = func1(payload.clone(), variant_t.clone());
+            methods.register_fixed_auto_impl(
+                "__new__",
+                new_t.clone(),
+                Immutable,
+                Visibility::BUILTIN_PRIVATE,
+                Some("__call__".into()),
+            )?;
+            // variants can be called directly: `E.A(1)`
+            methods.register_fixed_auto_impl(
+                "__call__",
+                new_t.clone(),
+                Immutable,
+                Visibility::BUILTIN_PRIVATE,
+                Some("__call__".into()),
+            )?;
+            methods.register_auto_impl(
+                "new",
+                new_t,
+                Immutable,
+                Visibility::BUILTIN_PUBLIC,
+                None,
+            )?;
+            variant_ctx
+                .methods_list
+                .push((ClassDefType::Simple(variant_t.clone()), methods));
+            let variant = GenTypeObj::inherited(
+                variant_t.clone(),
+                TypeObj::Generated(gen.clone()),
+                None,
+                Some(TypeObj::builtin_type(payload.clone())),
+            );
+            let val = ValueObj::Type(TypeObj::Generated(variant));
+            let name = VarName::from_str(field.symbol.clone());
+            let id = DefId(get_hash(&(&variant_t, &name)));
+            let vi = VarInfo::new(
+                v_enum(set! { val.clone() }),
+                Const,
+                Visibility::new(field.vis.clone(), ctx.name.clone()),
+                VarKind::Defined(id),
+                None,
+                Some(gen.typ().clone()),
+                None,
+                AbsLocation::unknown(),
+            );
+            variant_defs.decls.insert(name.clone(), vi);
+            variant_defs.consts.insert(name.clone(), val);
+            variant_defs
+                .mono_types
+                .insert(name, (variant_t, variant_ctx));
+        }
+        ctx.methods_list
+            .push((ClassDefType::Simple(gen.typ().clone()), variant_defs));
+        Ok(ctx)
+    }
+
     fn register_instance_attrs(
         &self,
         ctx: &mut Context,
@@ -1680,6 +1945,12 @@ impl Context {
                     ..
                 } => {
                     self.register_instance_attrs(ctx, rec)?;
+                    // e.g. `Queue!(T, N).__new__: {.payload = [T; N]} -> Queue!(T, N)` (`.payload: [T; !N]`)
+                    let rec = rec
+                        .iter()
+                        .map(|(field, t)| (field.clone(), immutate_container(t)))
+                        .collect();
+                    func1(Type::Record(rec), gen.typ().clone())
                 }
                 other => {
                     methods.register_fixed_auto_impl(
@@ -1689,12 +1960,18 @@ impl Context {
                         Visibility::BUILTIN_PRIVATE,
                         None,
                     )?;
+                    func1(other.typ().clone(), gen.typ().clone())
                 }
             }
-            func1(base.typ().clone(), gen.typ().clone())
         } else {
             func0(gen.typ().clone())
         };
+        // e.g. `Box(T).__new__: |T| {.value = T} -> Box(T)`
+        let new_t = if new_t.has_qvar() {
+            new_t.quantify()
+        } else {
+            new_t
+        };
         if ERG_MODE {
             methods.register_fixed_auto_impl(
                 "__new__",
@@ -2109,14 +2386,23 @@ impl Context {
         let _path = path.clone();
         let shared = self.shared.as_ref().unwrap().inherit(path.clone());
         let run = move || {
-            let mut builder = HIRBuilder::new_with_cache(cfg, name, shared.clone());
-            match builder.build(src, "exec") {
+            let use_cache = disk_cache::is_available(cfg.mode, false);
+            if use_cache {
+                if let Some(ctx) = Self::load_cached_mod(&cfg, &name, &shared, &src) {
+                    shared.mod_cache.register(_path, None, ctx);
+                    return;
+                }
+            }
+            let mut builder = HIRBuilder::new_with_cache(cfg.copy(), name.clone(), shared.clone());
+            match builder.build(src.clone(), "exec") {
                 Ok(artifact) => {
-                    shared.mod_cache.register(
-                        _path.clone(),
-                        Some(artifact.object),
-                        builder.pop_mod_ctx().unwrap(),
-                    );
+                    let ctx = builder.pop_mod_ctx().unwrap();
+                    if use_cache {
+                        disk_cache::try_store(&cfg, &name, &shared, &src, &ctx);
+                    }
+                    shared
+                        .mod_cache
+                        .register(_path.clone(), Some(artifact.object), ctx);
                     shared.warns.extend(artifact.warns);
                 }
                 Err(artifact) => {
@@ -2315,14 +2601,22 @@ impl Context {
             .input
             .try_read()
             .map_err(|_| self.import_err(line!(), __name__, loc))?;
-        let mut builder = HIRBuilder::new_with_cache(
-            cfg,
-            self.mod_name(&path),
-            self.shared.as_ref().unwrap().clone(),
-        );
-        match builder.build(src, "declare") {
+        let name = self.mod_name(&path);
+        let shared = self.shared.as_ref().unwrap();
+        let use_cache = disk_cache::is_available(cfg.mode, true);
+        if use_cache {
+            if let Some(ctx) = Self::load_cached_mod(&cfg, &name, shared, &src) {
+                py_mod_cache.register(path.clone(), None, ctx);
+                return Ok(path);
+            }
+        }
+        let mut builder = HIRBuilder::new_with_cache(cfg.copy(), name.clone(), shared.clone());
+        match builder.build(src.clone(), "declare") {
             Ok(artifact) => {
                 let ctx = builder.pop_mod_ctx().unwrap();
+                if use_cache {
+                    disk_cache::try_store(&cfg, &name, shared, &src, &ctx);
+                }
                 py_mod_cache.register(path.clone(), Some(artifact.object), ctx);
                 Ok(path)
             }
@@ -2335,6 +2629,22 @@ impl Context {
         }
     }
 
+    /// Builds the module context from the on-disk cache (see `module::disk_cache`).
+    /// Returns `None` if there is no valid cache.
+    fn load_cached_mod(
+        cfg: &ErgConfig,
+        mod_name: &Str,
+        shared: &SharedCompilerResource,
+        src: &str,
+    ) -> Option<ModuleContext> {
+        let path = cfg.input.path();
+        let decls = disk_cache::load(path, src)?;
+        // built as the module itself so that the re-exported modules are resolved in the same way
+        let mut builder = HIRBuilder::new_with_cache(cfg.copy(), mod_name.clone(), shared.clone());
+        builder.build(decls, "declare").ok()?;
+        builder.pop_mod_ctx()
+    }
+
     pub fn del(&mut self, ident: &hir::Identifier) -> CompileResult<()> {
         let is_const = self
             .rec_get_var_info(&ident.raw, crate::AccessKind::Name, &self.cfg.input, self)
@@ -2595,3 +2905,14 @@ impl Context {
         }
     }
 }
+
+/// Mutable containers are plain Python containers at runtime,
+/// so a field of `Array!(T, N)` can be initialized with a fresh `Array(T, N)` value.
+fn immutate_container(t: &Type) -> Type {
+    match t {
+        Type::Poly { name, params } if matches!(&name[..], "Array!" | "Set!" | "Dict!") => {
+            poly(name.trim_end_matches('!').to_string(), params.clone())
+        }
+        other => other.clone(),
+    }
+}
@@ -156,6 +156,18 @@ impl<'c, 'l, 'u, L: Locational> Unifier<'c, 'l, 'u, L> {
                 self.occur_inner(l, rhs)?;
                 self.occur_inner(r, rhs)
             }
+            (lhs, Overloaded(overloads)) => {
+                for t in overloads.iter() {
+                    self.occur_inner(lhs, t)?;
+                }
+                Ok(())
+            }
+            (Overloaded(overloads), rhs) => {
+                for t in overloads.iter() {
+                    self.occur_inner(t, rhs)?;
+                }
+                Ok(())
+            }
             _ => Ok(()),
         }
     }
@@ -275,16 +287,70 @@ impl<'c, 'l, 'u, L: Locational> Unifier<'c, 'l, 'u, L> {
                 self.occur_inner(l, rhs)?;
                 self.occur_inner(r, rhs)
             }
+            (lhs, Overloaded(overloads)) => {
+                for t in overloads.iter() {
+                    self.occur_inner(lhs, t)?;
+                }
+                Ok(())
+            }
+            (Overloaded(overloads), rhs) => {
+                for t in overloads.iter() {
+                    self.occur_inner(t, rhs)?;
+                }
+                Ok(())
+            }
             _ => Ok(()),
         }
     }
 
+    /// e.g. `Box(?T) <: Box(Int)` ==> `?T == Int` (if `Box` is invariant)
+    fn sub_unify_with_variance(
+        &self,
+        maybe_sub: &Type,
+        maybe_sup: &Type,
+        variance: Option<Variance>,
+    ) -> TyCheckResult<()> {
+        // e.g. `Queue!(?T, 0) <: Queue!(?T, ?N)` (the `?T`s are instantiated from different `T`s)
+        if let (FreeVar(sub_fv), FreeVar(sup_fv)) = (maybe_sub, maybe_sup) {
+            if variance == Some(Variance::Invariant)
+                && sub_fv.is_unbound()
+                && sup_fv.is_unbound()
+                && !sub_fv.addr_eq(sup_fv)
+                && maybe_sub == maybe_sup
+            {
+                if let (Some((lsub, lsup)), Some((rsub, rsup))) =
+                    (sub_fv.get_subsup(), sup_fv.get_subsup())
+                {
+                    let sub = self.ctx.union(&lsub, &rsub);
+                    let sup = self.ctx.intersection(&lsup, &rsup);
+                    let new_constraint = Constraint::new_sandwiched(sub, sup);
+                    if sub_fv.level() < sup_fv.level() {
+                        maybe_sub.update_constraint(new_constraint, self.undoable, false);
+                        maybe_sup.link(maybe_sub, self.undoable);
+                    } else {
+                        maybe_sup.update_constraint(new_constraint, self.undoable, false);
+                        maybe_sub.link(maybe_sup, self.undoable);
+                    }
+                    return Ok(());
+                }
+            }
+        }
+        self.sub_unify(maybe_sub, maybe_sup)?;
+        if variance == Some(Variance::Invariant)
+            && ((maybe_sub.is_unbound_var() && maybe_sup.has_no_unbound_var())
+                || (maybe_sup.is_unbound_var() && maybe_sub.has_no_unbound_var()))
+        {
+            self.sub_unify(maybe_sup, maybe_sub)?;
+        }
+        Ok(())
+    }
+
     /// allow_divergence = trueにすると、Num型変数と±Infの単一化を許す
     fn sub_unify_tp(
         &self,
         maybe_sub: &TyParam,
         maybe_sup: &TyParam,
-        _variance: Option<Variance>,
+        variance: Option<Variance>,
         allow_divergence: bool,
     ) -> TyCheckResult<()> {
         if maybe_sub.has_no_unbound_var()
@@ -294,15 +360,17 @@ impl<'c, 'l, 'u, L: Locational> Unifier<'c, 'l, 'u, L> {
             return Ok(());
         }
         match (maybe_sub, maybe_sup) {
-            (TyParam::Type(sub), TyParam::Type(sup)) => self.sub_unify(sub, sup),
+            (TyParam::Type(sub), TyParam::Type(sup)) => {
+                self.sub_unify_with_variance(sub, sup, variance)
+            }
             (TyParam::Value(ValueObj::Type(sub)), TyParam::Type(sup)) => {
-                self.sub_unify(sub.typ(), sup)
+                self.sub_unify_with_variance(sub.typ(), sup, variance)
             }
             (TyParam::Type(sub), TyParam::Value(ValueObj::Type(sup))) => {
-                self.sub_unify(sub, sup.typ())
+                self.sub_unify_with_variance(sub, sup.typ(), variance)
             }
             (TyParam::Value(ValueObj::Type(sub)), TyParam::Value(ValueObj::Type(sup))) => {
-                self.sub_unify(sub.typ(), sup.typ())
+                self.sub_unify_with_variance(sub.typ(), sup.typ(), variance)
             }
             (TyParam::FreeVar(sub_fv), TyParam::FreeVar(sup_fv))
                 if sub_fv.is_unbound() && sup_fv.is_unbound() =>
@@ -320,7 +388,7 @@ impl<'c, 'l, 'u, L: Locational> Unifier<'c, 'l, 'u, L> {
             (TyParam::FreeVar(sub_fv), sup_tp) => {
                 match &*sub_fv.borrow() {
                     FreeKind::Linked(l) | FreeKind::UndoableLinked { t: l, .. } => {
-                        return self.sub_unify_tp(l, sup_tp, _variance, allow_divergence);
+                        return self.sub_unify_tp(l, sup_tp, variance, allow_divergence);
                     }
                     FreeKind::Unbound { .. } | FreeKind::NamedUnbound { .. } => {}
                 } // &fv is dropped
@@ -360,7 +428,7 @@ impl<'c, 'l, 'u, L: Locational> Unifier<'c, 'l, 'u, L> {
             (sub_tp, TyParam::FreeVar(sup_fv)) => {
                 match &*sup_fv.borrow() {
                     FreeKind::Linked(l) | FreeKind::UndoableLinked { t: l, .. } => {
-                        return self.sub_unify_tp(l, sub_tp, _variance, allow_divergence);
+                        return self.sub_unify_tp(l, sub_tp, variance, allow_divergence);
                     }
                     FreeKind::Unbound { .. } | FreeKind::NamedUnbound { .. } => {}
                 } // &fv is dropped
@@ -399,7 +467,7 @@ impl<'c, 'l, 'u, L: Locational> Unifier<'c, 'l, 'u, L> {
             (TyParam::UnaryOp { op: lop, val: lval }, TyParam::UnaryOp { op: rop, val: rval })
                 if lop == rop =>
             {
-                self.sub_unify_tp(lval, rval, _variance, allow_divergence)
+                self.sub_unify_tp(lval, rval, variance, allow_divergence)
             }
             (
                 TyParam::BinOp { op: lop, lhs, rhs },
@@ -409,8 +477,8 @@ impl<'c, 'l, 'u, L: Locational> Unifier<'c, 'l, 'u, L> {
                     rhs: rhs2,
                 },
             ) if lop == rop => {
-                self.sub_unify_tp(lhs, lhs2, _variance, allow_divergence)?;
-                self.sub_unify_tp(rhs, rhs2, _variance, allow_divergence)
+                self.sub_unify_tp(lhs, lhs2, variance, allow_divergence)?;
+                self.sub_unify_tp(rhs, rhs2, variance, allow_divergence)
             }
             (sub, TyParam::Erased(t)) => {
                 let sub_t = self.ctx.get_tp_t(sub)?;
@@ -471,7 +539,7 @@ impl<'c, 'l, 'u, L: Locational> Unifier<'c, 'l, 'u, L> {
             (TyParam::Array(sub), TyParam::Array(sup))
             | (TyParam::Tuple(sub), TyParam::Tuple(sup)) => {
                 for (l, r) in sub.iter().zip(sup.iter()) {
-                    self.sub_unify_tp(l, r, _variance, allow_divergence)?;
+                    self.sub_unify_tp(l, r, variance, allow_divergence)?;
                 }
                 Ok(())
             }
@@ -481,8 +549,14 @@ impl<'c, 'l, 'u, L: Locational> Unifier<'c, 'l, 'u, L> {
                         .get(sub_k)
                         .or_else(|| sub_tpdict_get(sup, sub_k, self.ctx))
                     {
-                        // self.sub_unify_tp(sub_k, sup_k, _variance, loc, allow_divergence)?;
-                        self.sub_unify_tp(sub_v, sup_v, _variance, allow_divergence)?;
+                        // self.sub_unify_tp(sub_k, sup_k, variance, loc, allow_divergence)?;
+                        // e.g. {"a": Int} <: {"a": NotRequired(Int)}
+                        let sup_v = if sub_v.not_required_inner().is_none() {
+                            sup_v.not_required_inner().unwrap_or_else(|| sup_v.clone())
+                        } else {
+                            sup_v.clone()
+                        };
+                        self.sub_unify_tp(sub_v, &sup_v, variance, allow_divergence)?;
                     } else {
                         log!(err "{sup} does not have key {sub_k}");
                         // TODO:
@@ -504,9 +578,9 @@ impl<'c, 'l, 'u, L: Locational> Unifier<'c, 'l, 'u, L> {
                 },
             ) => {
                 if attr == a2 {
-                    self.sub_unify_tp(obj, o2, _variance, allow_divergence)?;
+                    self.sub_unify_tp(obj, o2, variance, allow_divergence)?;
                     for (l, r) in args.iter().zip(args2.iter()) {
-                        self.sub_unify_tp(l, r, _variance, allow_divergence)?;
+                        self.sub_unify_tp(l, r, variance, allow_divergence)?;
                     }
                     Ok(())
                 } else {
@@ -527,8 +601,11 @@ impl<'c, 'l, 'u, L: Locational> Unifier<'c, 'l, 'u, L> {
     fn sub_unify_pred(&self, sub_pred: &Predicate, sup_pred: &Predicate) -> TyCheckResult<()> {
         match (sub_pred, sup_pred) {
             (Pred::Value(_), Pred::Value(_)) | (Pred::Const(_), Pred::Const(_)) => Ok(()),
-            (Pred::Equal { rhs, .. }, Pred::Equal { rhs: rhs2, .. })
-            | (Pred::GreaterEqual { rhs, .. }, Pred::GreaterEqual { rhs: rhs2, .. })
+            // sub_unify_pred(I == Int, I == ?T) ==> ?T == Int
+            (Pred::Equal { rhs, .. }, Pred::Equal { rhs: rhs2, .. }) => {
+                self.sub_unify_tp(rhs, rhs2, Some(Variance::Invariant), false)
+            }
+            (Pred::GreaterEqual { rhs, .. }, Pred::GreaterEqual { rhs: rhs2, .. })
             | (Pred::LessEqual { rhs, .. }, Pred::LessEqual { rhs: rhs2, .. })
             | (Pred::NotEqual { rhs, .. }, Pred::NotEqual { rhs: rhs2, .. }) => {
                 self.sub_unify_tp(rhs, rhs2, None, false)
@@ -1225,8 +1302,16 @@ impl<'c, 'l, 'u, L: Locational> Unifier<'c, 'l, 'u, L> {
                 if ln != rn {
                     self.nominal_sub_unify(maybe_sub, maybe_sup, rps)?;
                 } else {
-                    for (l_maybe_sub, r_maybe_sup) in lps.iter().zip(rps.iter()) {
-                        self.sub_unify_tp(l_maybe_sub, r_maybe_sup, None, false)?;
+                    // builtin and declared types may be covariant without declaring `Output`,
+                    // so variance is only taken into account for user-defined types
+                    let variances = self
+                        .ctx
+                        .get_nominal_type_ctx(maybe_sub)
+                        .filter(|(_, ctx)| ctx.is_user_defined())
+                        .map_or(vec![], |(_, ctx)| ctx.type_params_variance());
+                    for (i, (l_maybe_sub, r_maybe_sup)) in lps.iter().zip(rps.iter()).enumerate() {
+                        let variance = variances.get(i).copied();
+                        self.sub_unify_tp(l_maybe_sub, r_maybe_sup, variance, false)?;
                     }
                 }
             }
@@ -1261,6 +1346,20 @@ impl<'c, 'l, 'u, L: Locational> Unifier<'c, 'l, 'u, L> {
                 self.sub_unify(maybe_sub, l)?;
                 self.sub_unify(maybe_sub, r)?;
             }
+            // X <: ((Y -> Y) and (Z -> Z)) is valid when X <: (Y -> Y) and X <: (Z -> Z)
+            (_, Overloaded(overloads)) => {
+                for t in overloads.iter() {
+                    self.sub_unify(maybe_sub, t)?;
+                }
+            }
+            // ((X -> X) and (Y -> Y)) <: Z is valid when the first matching overload <: Z
+            (Overloaded(overloads), _) => {
+                if let Some(t) = overloads.iter().find(|t| self.ctx.subtype_of(t, maybe_sup)) {
+                    self.sub_unify(t, maybe_sup)?;
+                } else if let Some(t) = overloads.last() {
+                    self.sub_unify(t, maybe_sup)?;
+                }
+            }
             // (X and Y) <: Z is valid when X <: Z or Y <: Z
             (And(l, r), _) => {
                 if self.ctx.subtype_of(l, maybe_sup) {
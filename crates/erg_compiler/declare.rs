@@ -10,7 +10,7 @@ use erg_parser::desugar::Desugarer;
 use crate::context::instantiate::TyVarCache;
 use crate::context::{ClassDefType, Context, MethodPair, TraitImpl};
 use crate::lower::ASTLowerer;
-use crate::ty::constructors::{array_t, mono, mono_q_tp, poly, v_enum};
+use crate::ty::constructors::{array_t, mono, mono_q_tp, overloaded, poly, v_enum};
 use crate::ty::free::{Constraint, HasLevel};
 use crate::ty::value::{GenTypeObj, TypeObj, ValueObj};
 use crate::ty::{HasType, TyParam, Type, Visibility};
@@ -21,6 +21,18 @@ use crate::hir::HIR;
 use crate::varinfo::{Mutability, VarInfo, VarKind};
 use crate::{feature_error, hir};
 
+/// `.abs: ((x: Int) -> Nat) and ((x: Complex) -> Float)` declares an overload set
+fn overload_set(t: Type) -> Type {
+    if t.is_intersection_type() && t.is_subr() {
+        t.intersection_types()
+            .into_iter()
+            .reduce(overloaded)
+            .unwrap_or(t)
+    } else {
+        t
+    }
+}
+
 impl ASTLowerer {
     fn declare_var(
         &mut self,
@@ -562,7 +574,7 @@ impl ASTLowerer {
                     .context
                     .instantiate_typespec(&tasc.t_spec.t_spec)?;
                 t.lift();
-                let t = self.module.context.generalize_t(t);
+                let t = overload_set(self.module.context.generalize_t(t));
                 match kind {
                     AscriptionKind::TypeOf | AscriptionKind::AsCast => {
                         self.declare_instance(&ident, &t, py_name.clone())?;
@@ -607,10 +619,11 @@ impl ASTLowerer {
                 } else {
                     TyVarCache::new(self.module.context.level, &self.module.context)
                 };
-                let t = self
-                    .module
-                    .context
-                    .instantiate_typespec_with_tv_cache(&tasc.t_spec.t_spec, &mut tv_cache)?;
+                let t = overload_set(
+                    self.module
+                        .context
+                        .instantiate_typespec_with_tv_cache(&tasc.t_spec.t_spec, &mut tv_cache)?,
+                );
                 let impl_trait = if let ast::Expr::Accessor(ast::Accessor::TypeApp(tapp)) =
                     attr.obj.as_ref()
                 {
@@ -725,7 +738,7 @@ impl ASTLowerer {
                     .context
                     .instantiate_typespec_with_tv_cache(&tasc.t_spec.t_spec, &mut tv_cache)?;
                 t.lift();
-                let t = self.module.context.generalize_t(t);
+                let t = overload_set(self.module.context.generalize_t(t));
                 match kind {
                     AscriptionKind::TypeOf | AscriptionKind::AsCast => {
                         self.declare_instance(&ident, &t, py_name)?;
@@ -875,6 +888,19 @@ impl ASTLowerer {
             ast::Expr::Accessor(acc) if allow_acc => {
                 Ok(hir::Expr::Accessor(self.lower_acc(acc, None)?))
             }
+            // e.g. `.Person = {"name": Str, "age": NotRequired(Nat)}`
+            ast::Expr::Dict(dict) if allow_acc => Ok(hir::Expr::Dict(self.lower_dict(dict, None)?)),
+            // e.g. `.Person = typing.TypedDict("Person", {"name": Str})`
+            ast::Expr::Call(call)
+                if allow_acc
+                    && self
+                        .module
+                        .context
+                        .eval_const_expr(&ast::Expr::Call(call.clone()))
+                        .is_ok() =>
+            {
+                Ok(hir::Expr::Call(self.lower_call(call, None)?))
+            }
             ast::Expr::Def(def) => Ok(hir::Expr::Def(self.declare_def(def)?)),
             ast::Expr::TypeAscription(tasc) => Ok(hir::Expr::TypeAsc(self.declare_ident(tasc)?)),
             ast::Expr::Call(call)
@@ -9,7 +9,7 @@ use erg_common::Str;
 use erg_parser::token::TokenKind;
 
 use crate::error::{EffectError, EffectErrors};
-use crate::hir::{Array, Def, Dict, Expr, Params, Set, Signature, Tuple, HIR};
+use crate::hir::{Array, Call, Def, Dict, Expr, Params, Set, Signature, Tuple, HIR};
 use crate::ty::{HasType, Visibility};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -20,6 +20,7 @@ enum BlockKind {
     ConstInstant, // e.g. Type definition
     // allow side effects
     Proc,
+    AsyncProc,
     Instant,
     Module,
 }
@@ -73,8 +74,8 @@ impl SideEffectChecker {
             self.block_stack.last().unwrap(),
         ) {
             (_, Func | ConstInstant) => false,
-            (_, Proc) => true,
-            (Proc | Module | Instant, Instant) => true,
+            (_, Proc | AsyncProc) => true,
+            (Proc | AsyncProc | Module | Instant, Instant) => true,
             _ => false,
         }
     }
@@ -107,6 +108,7 @@ impl SideEffectChecker {
                     }
                 }
                 Expr::Call(call) => {
+                    self.check_await(expr, call);
                     for parg in call.args.pos_args.iter() {
                         self.check_expr(&parg.expr);
                     }
@@ -252,7 +254,11 @@ impl SideEffectChecker {
                 panic!("user-defined constant procedures are not allowed");
             }
             (true, true, false) => {
-                self.block_stack.push(Proc);
+                if matches!(&def.sig, Signature::Subr(sig) if sig.is_async()) {
+                    self.block_stack.push(AsyncProc);
+                } else {
+                    self.block_stack.push(Proc);
+                }
             }
             (_, false, false) => {
                 self.block_stack.push(Instant);
@@ -385,6 +391,7 @@ impl SideEffectChecker {
                 other => todo!("{other}"),
             },
             Expr::Call(call) => {
+                self.check_await(expr, call);
                 if (call.obj.t().is_procedure()
                     || call
                         .attr_name
@@ -463,6 +470,21 @@ impl SideEffectChecker {
         }
     }
 
+    /// `await!` suspends the enclosing coroutine, so it must be in an `@Async` procedure
+    fn check_await(&mut self, expr: &Expr, call: &Call) {
+        if call.attr_name.is_none()
+            && call.obj.local_name() == Some("await!")
+            && !self.block_stack.contains(&AsyncProc)
+        {
+            self.errs.push(EffectError::await_outside_async_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                expr,
+                self.full_path(),
+            ));
+        }
+    }
+
     pub(crate) fn is_impure(expr: &Expr) -> bool {
         match expr {
             Expr::Call(call) => {
@@ -47,4 +47,55 @@ impl EvalError {
             caused_by,
         )
     }
+
+    pub fn recursion_limit_error(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        caused_by: String,
+        callee_name: &str,
+        limit: usize,
+    ) -> Self {
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::only_loc(loc)],
+                switch_lang!(
+                    "japanese" => format!("{callee_name}のコンパイル時評価が上限({limit})を超えました"),
+                    "simplified_chinese" => format!("{callee_name}的编译时求值超过了上限({limit})"),
+                    "traditional_chinese" => format!("{callee_name}的編譯時求值超過了上限({limit})"),
+                    "english" => format!("the compile-time evaluation of {callee_name} exceeded the limit ({limit})"),
+                ),
+                errno,
+                RecursionError,
+                loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
+
+    pub fn no_matching_arm_error(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        caused_by: String,
+        value: &str,
+    ) -> Self {
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::only_loc(loc)],
+                switch_lang!(
+                    "japanese" => format!("{value}にマッチするパターンがありません"),
+                    "simplified_chinese" => format!("没有与{value}匹配的模式"),
+                    "traditional_chinese" => format!("沒有與{value}匹配的模式"),
+                    "english" => format!("no pattern matches {value}"),
+                ),
+                errno,
+                PatternError,
+                loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
 }
@@ -1184,4 +1184,35 @@ impl LowerWarning {
             caused_by,
         )
     }
+
+    pub fn unreachable_arm_warning(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        caused_by: String,
+        arm_t: &Type,
+    ) -> Self {
+        let hint = switch_lang!(
+            "japanese" => "前のアームで既に網羅されています",
+            "simplified_chinese" => "已被前面的分支涵盖",
+            "traditional_chinese" => "已被前面的分支涵蓋",
+            "english" => "already covered by the preceding arms",
+        );
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(loc, vec![], Some(hint.into()))],
+                switch_lang!(
+                    "japanese" => format!("このアーム(: {arm_t})には到達しません"),
+                    "simplified_chinese" => format!("此分支(: {arm_t})永远不会被执行"),
+                    "traditional_chinese" => format!("此分支(: {arm_t})永遠不會被執行"),
+                    "english" => format!("this arm (: {arm_t}) is unreachable"),
+                ),
+                errno,
+                UnusedWarning,
+                loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
 }
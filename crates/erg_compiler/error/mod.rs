@@ -5,7 +5,7 @@ pub mod tycheck;
 use std::fmt;
 
 use erg_common::error::{
-    ErrorCore, ErrorDisplay, ErrorKind::*, Location, MultiErrorDisplay, SubMessage,
+    ErrorCore, ErrorDisplay, ErrorKind, ErrorKind::*, Location, MultiErrorDisplay, SubMessage,
 };
 use erg_common::io::Input;
 use erg_common::style::{Attribute, Color, StyledStr, StyledString, StyledStrings, Theme, THEME};
@@ -336,6 +336,28 @@ caused from: {fn_name}"),
             "".to_owned(),
         )
     }
+
+    /// An exception raised while running the code (in the REPL server).
+    /// The frames of `traceback` are shown below the code at `loc`.
+    pub fn runtime_error(
+        input: Input,
+        kind: ErrorKind,
+        msg: String,
+        traceback: Vec<String>,
+        loc: Location,
+    ) -> Self {
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(loc, traceback, None)],
+                msg,
+                0,
+                kind,
+                loc,
+            ),
+            input,
+            "<module>".to_owned(),
+        )
+    }
 }
 
 pub type EffectError = TyCheckError;
@@ -412,6 +434,30 @@ impl EffectError {
         )
     }
 
+    pub fn await_outside_async_error(
+        input: Input,
+        errno: usize,
+        expr: &Expr,
+        caused_by: String,
+    ) -> Self {
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::only_loc(expr.loc())],
+                switch_lang!(
+                    "japanese" => "await!は@Asyncなプロシージャの中でのみ使用できます",
+                    "simplified_chinese" => "await!只能在@Async过程中使用",
+                    "traditional_chinese" => "await!只能在@Async過程中使用",
+                    "english" => "await! can only be used in @Async procedures",
+                ),
+                errno,
+                HasEffect,
+                expr.loc(),
+            ),
+            input,
+            caused_by,
+        )
+    }
+
     pub fn touch_mut_error(input: Input, errno: usize, expr: &Expr, caused_by: String) -> Self {
         Self::new(
             ErrorCore::new(
@@ -414,6 +414,7 @@ impl TyCheckError {
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn match_error(
         input: Input,
         errno: usize,
@@ -422,6 +423,7 @@ impl TyCheckError {
         expr_t: &Type,
         union_pat_t: &Type,
         arm_ts: Vec<Type>,
+        uncovered: &Type,
     ) -> Self {
         let arms = arm_ts
             .into_iter()
@@ -433,10 +435,10 @@ impl TyCheckError {
             ErrorCore::new(
                 vec![SubMessage::only_loc(loc)],
                 switch_lang!(
-                    "japanese" => format!("{expr_t}型の全パターンを網羅していません\nunion type: {union_pat_t}\n{arms}"),
-                    "simplified_chinese" => format!("并非所有{expr_t}类型的模式都被涵盖\nunion type: {union_pat_t}\n{arms}"),
-                    "traditional_chinese" => format!("並非所有{expr_t}類型的模式都被涵蓋\nunion type: {union_pat_t}\n{arms}"),
-                    "english" => format!("not all patterns of type {expr_t} are covered\nunion type: {union_pat_t}\n{arms}"),
+                    "japanese" => format!("{expr_t}型の全パターンを網羅していません\nunion type: {union_pat_t}\n{arms}網羅されていないパターン: {uncovered}"),
+                    "simplified_chinese" => format!("并非所有{expr_t}类型的模式都被涵盖\nunion type: {union_pat_t}\n{arms}未涵盖的模式: {uncovered}"),
+                    "traditional_chinese" => format!("並非所有{expr_t}類型的模式都被涵蓋\nunion type: {union_pat_t}\n{arms}未涵蓋的模式: {uncovered}"),
+                    "english" => format!("not all patterns of type {expr_t} are covered\nunion type: {union_pat_t}\n{arms}uncovered: {uncovered}"),
                 ),
                 errno,
                 TypeError,
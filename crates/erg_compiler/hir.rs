@@ -1517,6 +1517,13 @@ impl NoTypeDisplay for Block {
 impl_display_from_nested!(Block);
 impl_stream!(Block, Expr);
 
+impl Block {
+    /// Whether this block contains `{name}.yield` (i.e. `name` is a generator)
+    pub fn yields(&self, name: &str) -> bool {
+        self.0.iter().any(|expr| expr.yields(name))
+    }
+}
+
 impl Locational for Block {
     fn loc(&self) -> Location {
         Location::stream(&self.0)
@@ -1921,6 +1928,15 @@ impl SubrSignature {
         self.ident.is_procedural()
     }
 
+    /// e.g. `@Async\nfetch! url = ...`
+    pub fn is_async(&self) -> bool {
+        self.ident
+            .vi
+            .comptime_decos
+            .as_ref()
+            .is_some_and(|decos| decos.contains("Async"))
+    }
+
     pub const fn name(&self) -> &VarName {
         &self.ident.raw.name
     }
@@ -2141,7 +2157,7 @@ impl Def {
     pub fn def_kind(&self) -> DefKind {
         match self.body.block.first().unwrap() {
             Expr::Call(call) => match call.obj.show_acc().as_ref().map(|n| &n[..]) {
-                Some("Class") => DefKind::Class,
+                Some("Class" | "Enum") => DefKind::Class,
                 Some("Inherit") => DefKind::Inherit,
                 Some("Trait") => DefKind::Trait,
                 Some("Subsume") => DefKind::Subsume,
@@ -2156,6 +2172,16 @@ impl Def {
                         DefKind::Other
                     }
                 }
+                Some("Structural") => {
+                    if let Some(Expr::Call(inner)) = call.args.get_left_or_key("Type") {
+                        match inner.obj.show_acc().as_ref().map(|n| &n[..]) {
+                            Some("Trait") => DefKind::StructuralTrait,
+                            _ => DefKind::Other,
+                        }
+                    } else {
+                        DefKind::Other
+                    }
+                }
                 Some("Patch") => DefKind::Patch,
                 Some("import") => DefKind::ErgImport,
                 Some("pyimport") | Some("__import__") => DefKind::PyImport,
@@ -2620,6 +2646,41 @@ impl Expr {
         }
     }
 
+    /// Whether this expression contains `{name}.yield`.
+    /// Nested subroutine/class definitions are not searched.
+    pub fn yields(&self, name: &str) -> bool {
+        let args_yield = |args: &Args| {
+            args.pos_args.iter().any(|arg| arg.expr.yields(name))
+                || args
+                    .var_args
+                    .as_ref()
+                    .is_some_and(|arg| arg.expr.yields(name))
+                || args.kw_args.iter().any(|arg| arg.expr.yields(name))
+        };
+        match self {
+            Expr::Call(call) => {
+                (call.additional_operation() == Some(OperationKind::Yield)
+                    && call.obj.local_name() == Some(name))
+                    || call.obj.yields(name)
+                    || args_yield(&call.args)
+            }
+            Expr::Accessor(Accessor::Attr(attr)) => attr.obj.yields(name),
+            Expr::Array(Array::Normal(arr)) => args_yield(&arr.elems),
+            Expr::Tuple(Tuple::Normal(tup)) => args_yield(&tup.elems),
+            Expr::Set(Set::Normal(set)) => args_yield(&set.elems),
+            Expr::Record(rec) => rec.attrs.iter().any(|def| def.body.block.yields(name)),
+            Expr::BinOp(bin) => bin.lhs.yields(name) || bin.rhs.yields(name),
+            Expr::UnaryOp(unary) => unary.expr.yields(name),
+            // lambdas passed to `for!`, `while!`, etc. are inlined
+            Expr::Lambda(lambda) => lambda.body.yields(name),
+            Expr::Def(def) if !def.sig.is_subr() => def.body.block.yields(name),
+            Expr::ReDef(redef) => redef.block.yields(name),
+            Expr::TypeAsc(tasc) => tasc.expr.yields(name),
+            Expr::Code(block) | Expr::Compound(block) => block.yields(name),
+            _ => false,
+        }
+    }
+
     pub const fn name(&self) -> &'static str {
         match self {
             Self::Literal(_) => "literal",
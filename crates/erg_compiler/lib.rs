@@ -6,6 +6,8 @@ pub extern crate erg_parser;
 
 pub mod artifact;
 pub mod build_hir;
+pub mod build_package;
+pub mod bundle;
 mod compile;
 pub use compile::*;
 mod codegen;
@@ -22,10 +24,13 @@ pub mod lower;
 pub mod module;
 pub mod optimize;
 pub mod ownercheck;
+pub mod peephole;
 pub mod transpile;
 pub mod ty;
 pub mod varinfo;
 
 pub use build_hir::HIRBuilder;
+pub use build_package::PackageBuilder;
+pub use bundle::Bundler;
 pub use erg_parser::build_ast::ASTBuilder;
 pub use transpile::Transpiler;
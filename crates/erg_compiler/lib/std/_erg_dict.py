@@ -1,2 +1,28 @@
 class Dict(dict):
     pass
+
+
+# e.g. {"name": Str, "age": NotRequired(Nat)}
+class NotRequired:
+    def __init__(self, t):
+        self.t = t
+
+    def __class_getitem__(cls, t):
+        return cls(t)
+
+    def __repr__(self):
+        return f"NotRequired({self.t!r})"
+
+
+# e.g. TypedDict("Person", {"name": Str, "age": NotRequired(Nat)})
+class TypedDict:
+    def __new__(cls, typename, fields, total=True):
+        if total:
+            return fields
+        return {
+            k: v if isinstance(v, NotRequired) else NotRequired(v)
+            for k, v in fields.items()
+        }
+
+    def __class_getitem__(cls, args):
+        return cls(*args)
@@ -3,7 +3,7 @@ use std::mem::{replace, take};
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
-use erg_common::config::ErgConfig;
+use erg_common::config::{ErgConfig, ErgMode};
 use erg_common::dict::Dict as Dic;
 use erg_common::fresh::SharedFreshNameGenerator;
 use erg_common::pathutil::squash;
@@ -389,7 +389,8 @@ impl<'a> HIRLinker<'a> {
             return;
         }
         // In the case of REPL, entries cannot be used up
-        let hir_cfg = if self.cfg.input.is_repl() {
+        // The package builder also links the same module into all the modules that import it
+        let hir_cfg = if self.cfg.input.is_repl() || self.cfg.mode == ErgMode::Build {
             self.mod_cache
                 .get(path.as_path())
                 .and_then(|entry| entry.hir.clone().map(|hir| (hir, entry.cfg().clone())))
@@ -161,6 +161,44 @@ impl ASTLowerer {
         }
     }
 
+    /// Warns arms that can never be reached, e.g.
+    /// ```erg
+    /// match x:
+    ///     _ -> ...
+    ///     (i: Int) -> ... # unreachable
+    /// ```
+    pub(crate) fn warn_unreachable_match_arms(&mut self, call: &hir::Call) {
+        let mut covered = Type::Never;
+        for arg in call.args.pos_args.iter().skip(1) {
+            let Some(arm_t) = arg
+                .expr
+                .ref_t()
+                .non_default_params()
+                .and_then(|params| params.first())
+                .map(|pt| pt.typ().clone())
+            else {
+                return;
+            };
+            if arm_t.has_unbound_var() {
+                return;
+            }
+            if covered != Type::Never && self.module.context.subtype_of(&arm_t, &covered) {
+                let loc = match &arg.expr {
+                    hir::Expr::Lambda(lambda) => lambda.params.loc(),
+                    other => other.loc(),
+                };
+                self.warns.push(LowerWarning::unreachable_arm_warning(
+                    self.cfg().input.clone(),
+                    line!() as usize,
+                    loc,
+                    self.module.context.caused_by(),
+                    &arm_t,
+                ));
+            }
+            covered = self.module.context.union(&covered, &arm_t);
+        }
+    }
+
     pub(crate) fn inc_ref<L: Locational>(&self, name: &Str, vi: &VarInfo, loc: &L) {
         self.module
             .context
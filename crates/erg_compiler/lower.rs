@@ -2,6 +2,7 @@
 //!
 //! ASTLowerer(ASTからHIRへの変換器)を実装
 use std::mem;
+use std::path::PathBuf;
 
 use erg_common::config::{ErgConfig, ErgMode};
 use erg_common::consts::{ELS, ERG_MODE, PYTHON_MODE};
@@ -9,17 +10,20 @@ use erg_common::dict;
 use erg_common::dict::Dict;
 use erg_common::error::{Location, MultiErrorDisplay};
 use erg_common::fresh::FreshNameGenerator;
+use erg_common::io::Input;
 use erg_common::set;
 use erg_common::set::Set;
+use erg_common::style::Highlight;
 use erg_common::traits::OptionalTranspose;
 use erg_common::traits::{ExitStatus, Locational, NoTypeDisplay, Runnable, Stream};
 use erg_common::triple::Triple;
 use erg_common::{fmt_option, fn_name, log, switch_lang, Str};
 
 use erg_parser::ast::{self, AscriptionKind, VisModifierSpec};
-use erg_parser::ast::{OperationKind, TypeSpecWithOp, VarName, AST};
+use erg_parser::ast::{DefKind, OperationKind, TypeSpecWithOp, VarName, AST};
 use erg_parser::build_ast::ASTBuilder;
 use erg_parser::desugar::Desugarer;
+use erg_parser::highlight::highlight;
 use erg_parser::token::{Token, TokenKind};
 use erg_parser::Parser;
 
@@ -27,17 +31,19 @@ use crate::artifact::{CompleteArtifact, IncompleteArtifact};
 use crate::context::instantiate::TyVarCache;
 use crate::module::SharedCompilerResource;
 use crate::ty::constructors::{
-    array_t, free_var, func, guard, mono, poly, proc, refinement, set_t, singleton, ty_tp, v_enum,
+    and, array_t, free_var, func, func0, generator, guard, mono, or, poly, proc, refinement, set_t,
+    singleton, ty_tp, v_enum,
 };
 use crate::ty::free::Constraint;
 use crate::ty::typaram::TyParam;
 use crate::ty::value::{GenTypeObj, TypeObj, ValueObj};
 use crate::ty::{
-    CastTarget, GuardType, HasType, ParamTy, Predicate, SubrType, Type, VisibilityModifier,
+    CastTarget, GuardType, HasType, ParamTy, Predicate, SubrType, Type, Visibility,
+    VisibilityModifier,
 };
 
 use crate::context::{
-    ClassDefType, Context, ContextKind, ContextProvider, ControlKind, ModuleContext,
+    ClassDefType, Context, ContextKind, ContextProvider, ControlKind, DefaultInfo, ModuleContext,
     RegistrationMode, TraitImpl,
 };
 use crate::error::{
@@ -47,7 +53,7 @@ use crate::error::{
 use crate::hir;
 use crate::hir::HIR;
 use crate::link_ast::ASTLinker;
-use crate::varinfo::{VarInfo, VarKind};
+use crate::varinfo::{Mutability, VarInfo, VarKind};
 use crate::AccessKind;
 use crate::{feature_error, unreachable_error};
 
@@ -124,11 +130,15 @@ impl Runnable for ASTLowerer {
         let artifact = ast_builder
             .build(self.cfg.input.read())
             .map_err(|artifact| artifact.errors)?;
-        artifact.warns.write_all_to(&mut self.cfg.output);
+        artifact
+            .warns
+            .write_all_to_with(&mut self.cfg.output, &self.cfg.error_format);
         let artifact = self
             .lower(artifact.ast, "exec")
             .map_err(|artifact| artifact.errors)?;
-        artifact.warns.write_all_to(&mut self.cfg.output);
+        artifact
+            .warns
+            .write_all_to_with(&mut self.cfg.output, &self.cfg.error_format);
         use std::io::Write;
         write!(self.cfg.output, "{}", artifact.object).unwrap();
         Ok(ExitStatus::compile_passed(artifact.warns.len()))
@@ -137,13 +147,40 @@ impl Runnable for ASTLowerer {
     fn eval(&mut self, src: String) -> Result<String, Self::Errs> {
         let mut ast_builder = ASTBuilder::new(self.cfg.copy());
         let artifact = ast_builder.build(src).map_err(|artifact| artifact.errors)?;
-        artifact.warns.write_all_stderr();
+        artifact
+            .warns
+            .write_all_stderr_with(&self.cfg().error_format);
         let artifact = self
             .lower(artifact.ast, "eval")
             .map_err(|artifact| artifact.errors)?;
-        artifact.warns.write_all_stderr();
+        artifact
+            .warns
+            .write_all_stderr_with(&self.cfg().error_format);
         Ok(format!("{}", artifact.object))
     }
+
+    fn doc_of(&self, name: &str) -> Option<String> {
+        self.signature_and_doc(name)
+    }
+
+    fn dir_of(&self, receiver: Option<&str>) -> Option<Vec<String>> {
+        self.dir_lines(receiver)
+    }
+
+    fn complete(&self, word: &str) -> Vec<String> {
+        self.completion_candidates(word)
+    }
+
+    fn highlight(&self, line: &str, cursor: usize) -> Vec<Highlight> {
+        highlight(line, cursor)
+    }
+
+    fn eval_file(&mut self, path: PathBuf, src: String) -> Result<String, Self::Errs> {
+        let input = self.replace_input(Input::file(path));
+        let res = self.eval(src);
+        self.replace_input(input);
+        res
+    }
 }
 
 impl ContextProvider for ASTLowerer {
@@ -223,6 +260,16 @@ impl ASTLowerer {
     pub fn unregister(&mut self, name: &str) -> Option<VarInfo> {
         self.module.context.unregister(name)
     }
+
+    pub fn register_external(&mut self, name: &str, t: Type) {
+        self.module.context.register_external(name, t)
+    }
+
+    /// Replaces the input (of the lowerer and the module context) and returns the old one.
+    pub fn replace_input(&mut self, input: Input) -> Input {
+        self.module.context.cfg.input = input.clone();
+        mem::replace(&mut self.cfg.input, input)
+    }
 }
 
 impl ASTLowerer {
@@ -599,7 +646,11 @@ impl ASTLowerer {
         }
     }
 
-    fn lower_dict(&mut self, dict: ast::Dict, expect: Option<&Type>) -> LowerResult<hir::Dict> {
+    pub(crate) fn lower_dict(
+        &mut self,
+        dict: ast::Dict,
+        expect: Option<&Type>,
+    ) -> LowerResult<hir::Dict> {
         log!(info "enter {}({dict})", fn_name!());
         match dict {
             ast::Dict::Normal(set) => Ok(hir::Dict::Normal(self.lower_normal_dict(set, expect)?)),
@@ -630,7 +681,12 @@ impl ASTLowerer {
                     .ok()
             })
             .map(|dict| dict.into_iter().collect::<Vec<_>>());
-        let expect_kvs = expect.transpose(dict.kvs.len());
+        let mut expect_kvs = expect.transpose(dict.kvs.len());
+        // e.g. `{Str: Int}` is expected for each entry of `{"a": 1, "b": 2}`
+        if expect_kvs.len() == 1 {
+            expect_kvs = vec![expect_kvs[0].clone(); dict.kvs.len()];
+        }
+        expect_kvs.resize(dict.kvs.len(), None);
         for (kv, expect) in dict.kvs.into_iter().zip(expect_kvs) {
             let key = self.lower_expr(kv.key, expect.as_ref().map(|(k, _)| k))?;
             let value = self.lower_expr(kv.value, expect.as_ref().map(|(_, v)| v))?;
@@ -790,6 +846,19 @@ impl ASTLowerer {
                 },
                 None,
             )
+        } else if ident.vis.is_private() && &ident.inspect()[..] == "*" {
+            // e.g. `Array!(*, 0)`: the type argument is inferred
+            let t = free_var(
+                self.module.context.level,
+                Constraint::new_type_of(Type::Type),
+            );
+            (
+                VarInfo {
+                    t: singleton(Type::Type, ty_tp(t)),
+                    ..VarInfo::default()
+                },
+                None,
+            )
         } else {
             let res = match self.module.context.rec_get_var_info(
                 &ident,
@@ -916,11 +985,19 @@ impl ASTLowerer {
             self.errs.extend(errs);
             hir::Expr::Dummy(hir::Dummy::new(vec![]))
         });
+        // `rhs` of `and` (`or`) is evaluated only if `lhs` is `True` (`False`)
+        let rhs_guards = match bin.op.kind {
+            TokenKind::AndOp => self.module.context.guards_of(lhs.ref_t(), false),
+            TokenKind::OrOp => self.module.context.guards_of(lhs.ref_t(), true),
+            _ => vec![],
+        };
         let lhs = hir::PosArg::new(lhs);
-        let rhs = self.lower_expr(rhs, None).unwrap_or_else(|errs| {
-            self.errs.extend(errs);
-            hir::Expr::Dummy(hir::Dummy::new(vec![]))
-        });
+        let rhs = self
+            .lower_expr_with_guards(rhs, rhs_guards)
+            .unwrap_or_else(|errs| {
+                self.errs.extend(errs);
+                hir::Expr::Dummy(hir::Dummy::new(vec![]))
+            });
         let rhs = hir::PosArg::new(rhs);
         let args = [lhs, rhs];
         let mut vi = self
@@ -931,6 +1008,17 @@ impl ASTLowerer {
                 self.errs.extend(errs);
                 VarInfo::ILLEGAL
             });
+        let guard = guard.or_else(|| {
+            let (lhs, rhs) = (args[0].expr.ref_t(), args[1].expr.ref_t());
+            if !lhs.is_guard() && !rhs.is_guard() {
+                return None;
+            }
+            match bin.op.kind {
+                TokenKind::AndOp => Some(and(lhs.clone(), rhs.clone())),
+                TokenKind::OrOp => Some(or(lhs.clone(), rhs.clone())),
+                _ => None,
+            }
+        });
         if let Some(guard) = guard {
             if let Some(return_t) = vi.t.mut_return_t() {
                 debug_assert!(
@@ -1012,11 +1100,13 @@ impl ASTLowerer {
             .map_or(vec![None; pos_args.len()], |params| {
                 params.take(pos_args.len()).collect()
             });
+        let mut cond_t = None;
         for (nth, (arg, param)) in pos_args.into_iter().zip(pos_params).enumerate() {
             match self.lower_expr(arg.expr, param) {
                 Ok(expr) => {
                     if let Some(kind) = self.module.context.control_kind() {
-                        self.push_guard(nth, kind, expr.ref_t());
+                        let cond_t = cond_t.get_or_insert_with(|| expr.t());
+                        self.push_guard(nth, kind, cond_t);
                     }
                     hir_args.pos_args.push(hir::PosArg::new(expr))
                 }
@@ -1051,28 +1141,80 @@ impl ASTLowerer {
         hir_args
     }
 
-    fn push_guard(&mut self, nth: usize, kind: ControlKind, t: &Type) {
-        match t {
-            Type::Guard(guard) => match nth {
-                0 if kind.is_conditional() => {
-                    self.module.context.guards.push(guard.clone());
-                }
-                1 if kind.is_if() => {
-                    let guard = GuardType::new(
-                        guard.namespace.clone(),
-                        guard.target.clone(),
-                        self.module.context.complement(&guard.to),
-                    );
-                    self.module.context.guards.push(guard);
-                }
-                _ => {}
-            },
-            Type::And(lhs, rhs) => {
-                self.push_guard(nth, kind, lhs);
-                self.push_guard(nth, kind, rhs);
+    /// `cond_t`: the type of the condition (the first argument) of the control flow.
+    /// The guards pushed here are consumed by the next lambda (the `nth + 1`-th argument).
+    /// ```erg
+    /// if x == None:
+    ///     do ... # x: {None}
+    ///     do ... # x: not {None}
+    /// ```
+    fn push_guard(&mut self, nth: usize, kind: ControlKind, cond_t: &Type) {
+        let negated = match nth {
+            0 if kind.is_conditional() => false,
+            1 if kind.is_if() => true,
+            _ => return,
+        };
+        let guards = self.module.context.guards_of(cond_t, negated);
+        self.module.context.guards.extend(guards);
+    }
+
+    /// Lowers `expr` with the variables narrowed by `guards`.
+    /// The narrowing only applies to `expr` (e.g. the rhs of `isinstance(x, Int) and x >= 0`).
+    fn lower_expr_with_guards(
+        &mut self,
+        expr: ast::Expr,
+        guards: Vec<GuardType>,
+    ) -> LowerResult<hir::Expr> {
+        if guards.is_empty() {
+            return self.lower_expr(expr, None);
+        }
+        let n_guards = self.module.context.guards.len();
+        let mut shadowed = vec![];
+        for guard in guards.into_iter() {
+            if let CastTarget::Var { name, .. } = &guard.target {
+                let name = VarName::from_str(name.clone());
+                let prev = self.module.context.locals.get(&name).cloned();
+                shadowed.push((name, prev));
+            }
+            if let Err(errs) = self.module.context.cast(guard, &mut vec![]) {
+                self.errs.extend(errs);
+            }
+        }
+        let res = self.lower_expr(expr, None);
+        for (name, prev) in shadowed.into_iter().rev() {
+            if let Some(vi) = prev {
+                self.module.context.locals.insert(name, vi);
+            } else {
+                self.module.context.locals.remove(&name);
             }
-            _ => {}
         }
+        self.module.context.guards.truncate(n_guards);
+        res
+    }
+
+    /// `f(x: Obj): {x in T}`: `f(y)` => `{y in T}`
+    fn get_arg_guard_type(&self, subr_t: &Type, args: &hir::Args) -> Option<Type> {
+        let Type::Guard(GuardType { target, to, .. }) = subr_t.return_t()? else {
+            return None;
+        };
+        let nth = match target {
+            CastTarget::Param { nth, .. } => *nth,
+            // inferred from the body (e.g. `is_int x = isinstance x, Int`)
+            CastTarget::Var { name, .. } => subr_t
+                .non_default_params()?
+                .iter()
+                .position(|pt| pt.name() == Some(name))?,
+            CastTarget::Expr(_) => return None,
+        };
+        let hir::Expr::Accessor(hir::Accessor::Ident(ident)) = &args.pos_args.get(nth)?.expr else {
+            return None;
+        };
+        let target = CastTarget::Var {
+            name: ident.inspect().clone(),
+            loc: ident.loc(),
+        };
+        let namespace = self.module.context.name.clone();
+        Some(guard(namespace, target, *to.clone()))
     }
 
     /// returning `Ok(call)` does not mean the call is valid, just means it is syntactically valid
@@ -1139,6 +1281,16 @@ impl ASTLowerer {
         if let Err(es) = self.module.context.propagate(&mut vi.t, &obj) {
             errs.extend(es);
         }
+        let guard = guard.or_else(|| {
+            if call.attr_name.is_some() {
+                None
+            } else if obj.show_acc().is_some_and(|name| name == "not") {
+                let arg = hir_args.pos_args.first()?;
+                self.module.context.negate_guard(arg.expr.ref_t())
+            } else {
+                self.get_arg_guard_type(&vi.t, &hir_args)
+            }
+        });
         if let Some(guard) = guard {
             debug_assert!(
                 self.module
@@ -1154,7 +1306,9 @@ impl ASTLowerer {
             }
         }
         let attr_name = if let Some(attr_name) = call.attr_name {
-            self.inc_ref(attr_name.inspect(), &vi, &attr_name.name);
+            // the index records the declared type, not the resolved overload
+            let decl_vi = opt_vi.as_ref().unwrap_or(&vi);
+            self.inc_ref(attr_name.inspect(), decl_vi, &attr_name.name);
             Some(hir::Identifier::new(attr_name, None, vi))
         } else {
             if let hir::Expr::Call(call) = &obj {
@@ -1175,6 +1329,14 @@ impl ASTLowerer {
         self.module.context.higher_order_caller.pop();
         if errs.is_empty() {
             self.exec_additional_op(&mut call)?;
+            if call.attr_name.is_none()
+                && call
+                    .obj
+                    .show_acc()
+                    .is_some_and(|name| name == "match" || name == "match!")
+            {
+                self.warn_unreachable_match_arms(&call);
+            }
         }
         self.errs.extend(errs);
         Ok(call)
@@ -1199,7 +1361,7 @@ impl ASTLowerer {
                     )))
                 }
             },
-            Some(OperationKind::Return | OperationKind::Yield) => {
+            Some(op @ (OperationKind::Return | OperationKind::Yield)) => {
                 // (f: ?T -> ?U).return: (self: GenericCallable, arg: Obj) -> Never
                 let callable_t = call.obj.ref_t();
                 let ret_t = match callable_t {
@@ -1213,14 +1375,23 @@ impl ASTLowerer {
                     }
                 };
                 let arg_t = call.args.get(0).unwrap().ref_t();
-                self.module.context.sub_unify(arg_t, &ret_t, call, None)?;
+                if op == OperationKind::Yield {
+                    // (f: ?T -> Generator(?Y, ?S, ?R)).yield: (self: GenericCallable, arg: ?Y) -> ?S
+                    let (yield_t, send_t) = self.generator_params_of(&ret_t, call)?;
+                    self.module.context.sub_unify(arg_t, &yield_t, call, None)?;
+                    self.module
+                        .context
+                        .sub_unify(&send_t, call.ref_t(), call, None)?;
+                } else {
+                    self.module.context.sub_unify(arg_t, &ret_t, call, None)?;
+                }
                 Ok(())
             }
             Some(OperationKind::Assert) => {
-                if let Some(Type::Guard(guard)) =
-                    call.args.get_left_or_key("test").map(|exp| exp.ref_t())
-                {
-                    self.module.context.cast(guard.clone(), &mut vec![])?;
+                if let Some(test) = call.args.get_left_or_key("test") {
+                    for guard in self.module.context.guards_of(test.ref_t(), false) {
+                        self.module.context.cast(guard, &mut vec![])?;
+                    }
                 }
                 Ok(())
             }
@@ -1237,6 +1408,26 @@ impl ASTLowerer {
         }
     }
 
+    /// Returns the yield type and the send type of a generator whose return type is `ret_t`.
+    /// If `ret_t` is not determined yet, it is inferred as `Generator(?Y, ?S, ?R)`.
+    fn generator_params_of(&mut self, ret_t: &Type, call: &hir::Call) -> LowerResult<(Type, Type)> {
+        if let Some((yield_t, send_t, _)) = ret_t.generator_params() {
+            return Ok((yield_t, send_t));
+        }
+        let level = self.module.context.level;
+        let yield_t = free_var(level, Constraint::new_type_of(Type::Type));
+        let send_t = free_var(level, Constraint::new_type_of(Type::Type));
+        let return_t = free_var(level, Constraint::new_type_of(Type::Type));
+        let gen_t = generator(yield_t.clone(), send_t.clone(), return_t);
+        if ret_t.is_unbound_var() {
+            ret_t.destructive_link(&gen_t);
+        } else {
+            // e.g. `g!(): Iterator(Int) = ...`
+            self.module.context.sub_unify(&gen_t, ret_t, call, None)?;
+        }
+        Ok((yield_t, send_t))
+    }
+
     fn lower_pack(
         &mut self,
         pack: ast::DataPack,
@@ -1595,11 +1786,16 @@ impl ASTLowerer {
             ));
         }
         let kind = ContextKind::from(&def);
+        let def_kind = def.def_kind();
         let vis = self
             .module
             .context
             .instantiate_vis_modifier(def.sig.vis())?;
         let res = match def.sig {
+            ast::Signature::Subr(sig) if sig.is_const() && def_kind == DefKind::Class => {
+                self.module.context.grow(&name, kind, vis, None);
+                self.lower_poly_class_def(sig, def.body)
+            }
             ast::Signature::Subr(sig) => {
                 let tv_cache = self
                     .module
@@ -1620,6 +1816,45 @@ impl ASTLowerer {
         res
     }
 
+    /// `Box T = Class {.value = T}` is lowered as `Box = Class {.value = T}` where `T: Type`
+    fn lower_poly_class_def(
+        &mut self,
+        sig: ast::SubrSignature,
+        body: ast::DefBody,
+    ) -> LowerResult<hir::Def> {
+        log!(info "entered {}({sig})", fn_name!());
+        for param in sig.params.non_defaults.iter() {
+            let Some(name) = param.name() else {
+                continue;
+            };
+            let param_t = if let Some(spec) = param.t_spec.as_ref() {
+                let mut dummy_tv_cache =
+                    TyVarCache::new(self.module.context.level, &self.module.context);
+                self.module.context.instantiate_typaram_t(
+                    &spec.t_spec,
+                    &mut dummy_tv_cache,
+                    RegistrationMode::Normal,
+                    false,
+                )?
+            } else {
+                Type::Type
+            };
+            let vi = VarInfo::new(
+                param_t,
+                Mutability::Const,
+                Visibility::private(self.module.context.name.clone()),
+                VarKind::parameter(body.id, false, DefaultInfo::NonDefault),
+                None,
+                None,
+                None,
+                self.module.context.absolutize(name.loc()),
+            );
+            self.module.context.locals.insert(name.clone(), vi);
+        }
+        let sig = ast::VarSignature::new(ast::VarPattern::Ident(sig.ident), None);
+        self.lower_var_def(sig, body)
+    }
+
     fn lower_var_def(
         &mut self,
         sig: ast::VarSignature,
@@ -1720,6 +1955,7 @@ impl ASTLowerer {
             .unwrap_or(Type::Failure);
         match registered_t {
             Type::Subr(subr_t) => {
+                let registered_ret_t = *subr_t.return_t.clone();
                 let mut params = self.lower_params(sig.params.clone())?;
                 if let Err(errs) = self.module.context.assign_params(&mut params, Some(subr_t)) {
                     self.errs.extend(errs);
@@ -1730,6 +1966,21 @@ impl ASTLowerer {
                 match self.lower_block(body.block, None) {
                     Ok(block) => {
                         let found_body_t = self.module.context.squash_tyvar(block.t());
+                        // the body of a generator is checked against `Generator(Y, S, R)`
+                        let found_body_t = if block.yields(sig.ident.inspect()) {
+                            let (yield_t, send_t, _) =
+                                registered_ret_t.generator_params().unwrap_or_else(|| {
+                                    let level = self.module.context.level;
+                                    (
+                                        free_var(level, Constraint::new_type_of(Type::Type)),
+                                        free_var(level, Constraint::new_type_of(Type::Type)),
+                                        Type::Failure,
+                                    )
+                                });
+                            generator(yield_t, send_t, found_body_t)
+                        } else {
+                            found_body_t
+                        };
                         let vi = match self.module.context.outer.as_mut().unwrap().assign_subr(
                             &sig,
                             body.id,
@@ -1817,10 +2068,17 @@ impl ASTLowerer {
 
     fn lower_class_def(&mut self, class_def: ast::ClassDef) -> LowerResult<hir::ClassDef> {
         log!(info "entered {}({class_def})", fn_name!());
+        let class_params = match &class_def.def.sig {
+            ast::Signature::Subr(sig) => sig.params.non_defaults.clone(),
+            ast::Signature::Var(_) => vec![],
+        };
         let mut hir_def = self.lower_def(class_def.def)?;
         let mut hir_methods = hir::Block::empty();
         for mut methods in class_def.methods_list.into_iter() {
-            let (class, impl_trait) = self.get_class_and_impl_trait(&methods.class)?;
+            self.bind_class_params(&mut methods, &class_params)?;
+            let mut tv_cache = self.module.context.instantiate_class_params(&methods.class);
+            let (class, impl_trait) =
+                self.get_class_and_impl_trait(&methods.class, &mut tv_cache)?;
             // assume the class has implemented the trait, regardless of whether the implementation is correct
             if let Some((trait_, trait_loc)) = &impl_trait {
                 self.register_trait_impl(&class, trait_, *trait_loc)?;
@@ -1922,16 +2180,6 @@ impl ASTLowerer {
             }
             self.check_collision_and_push(class, impl_trait.map(|(t, _)| t));
         }
-        let class = self.module.context.gen_type(&hir_def.sig.ident().raw);
-        let Some((_, class_ctx)) = self.module.context.get_nominal_type_ctx(&class) else {
-            return Err(LowerErrors::from(LowerError::type_not_found(
-                self.cfg.input.clone(),
-                line!() as usize,
-                hir_def.sig.loc(),
-                self.module.context.caused_by(),
-                &class,
-            )));
-        };
         let Some(class_type) = self
             .module
             .context
@@ -1942,16 +2190,31 @@ impl ASTLowerer {
         let ValueObj::Type(TypeObj::Generated(type_obj)) = class_type else {
             return unreachable_error!(LowerErrors, LowerError, self);
         };
+        let class = type_obj.typ().clone();
+        let Some((_, class_ctx)) = self.module.context.get_nominal_type_ctx(&class) else {
+            return Err(LowerErrors::from(LowerError::type_not_found(
+                self.cfg.input.clone(),
+                line!() as usize,
+                hir_def.sig.loc(),
+                self.module.context.caused_by(),
+                &class,
+            )));
+        };
         let Some(hir::Expr::Call(call)) = hir_def.body.block.first() else {
             return unreachable_error!(LowerErrors, LowerError, self);
         };
         if let Some(sup_type) = call.args.get_left_or_key("Super") {
             Self::check_inheritable(&self.cfg, &mut self.errs, type_obj, sup_type, &hir_def.sig);
         }
-        let Some(__new__) = class_ctx
+        // enums cannot be instantiated directly (use the variant constructors)
+        let __new__ = if let GenTypeObj::Enum(_) = type_obj {
+            func0(class.clone())
+        } else if let Some(__new__) = class_ctx
             .get_current_scope_var(&VarName::from_static("__new__"))
             .or(class_ctx.get_current_scope_var(&VarName::from_static("__call__")))
-        else {
+        {
+            __new__.t.clone()
+        } else {
             return unreachable_error!(LowerErrors, LowerError, self);
         };
         let need_to_gen_new = class_ctx
@@ -1963,16 +2226,85 @@ impl ASTLowerer {
             hir_def.sig,
             require_or_sup,
             need_to_gen_new,
-            __new__.t.clone(),
+            __new__,
             hir_methods,
         ))
     }
 
+    /// The parameters of a methods block header are bound in each method.
+    /// ```erg
+    /// Box(T).
+    ///     get(self): T = self.value
+    /// # ↓
+    /// Box(T).
+    ///     get|T|(self: Box(T)): T = self.value
+    /// ```
+    fn bind_class_params(
+        &self,
+        methods: &mut ast::Methods,
+        class_params: &[ast::NonDefaultParamSignature],
+    ) -> LowerResult<()> {
+        let ast::TypeSpec::PreDeclTy(ast::PreDeclTypeSpec::Poly(poly)) = &methods.class else {
+            return Ok(());
+        };
+        let mut bounds = vec![];
+        for (arg, param) in poly.args.pos_args().zip(class_params) {
+            let ast::ConstExpr::Accessor(ast::ConstAccessor::Local(ident)) = &arg.expr else {
+                continue;
+            };
+            if self
+                .module
+                .context
+                .rec_get_const_obj(ident.inspect())
+                .is_some()
+            {
+                continue;
+            }
+            if param.inspect() != Some(ident.inspect()) {
+                return feature_error!(
+                    LowerErrors,
+                    LowerError,
+                    self.module.context,
+                    ident.loc(),
+                    "renaming class parameters in a methods block"
+                );
+            }
+            // `T: Type` ==> `|T|`, `N: Nat` ==> `|N: Nat|`
+            let bound = match &param.t_spec {
+                Some(spec)
+                    if !matches!(
+                        self.module.context.instantiate_typespec(&spec.t_spec)?,
+                        Type::Type | Type::ClassType | Type::TraitType
+                    ) =>
+                {
+                    ast::TypeBoundSpec::non_default(ident.name.clone(), spec.clone())
+                }
+                _ => ast::TypeBoundSpec::Omitted(ident.name.clone()),
+            };
+            bounds.push(bound);
+        }
+        for attr in methods.attrs.iter_mut() {
+            let ast::ClassAttr::Def(ast::Def {
+                sig: ast::Signature::Subr(sig),
+                ..
+            }) = attr
+            else {
+                continue;
+            };
+            for bound in bounds.iter() {
+                if sig.bounds.iter().all(|b| b.lhs() != bound.lhs()) {
+                    sig.bounds.push(bound.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn get_class_and_impl_trait<'c>(
         &mut self,
         class_spec: &'c ast::TypeSpec,
+        tv_cache: &mut TyVarCache,
     ) -> LowerResult<(Type, Option<(Type, &'c TypeSpecWithOp)>)> {
-        let mut dummy_tv_cache = TyVarCache::new(self.module.context.level, &self.module.context);
         match class_spec {
             ast::TypeSpec::TypeApp { spec, args } => {
                 match &args.args {
@@ -1983,7 +2315,7 @@ impl ASTLowerer {
                                 self.module.context.instantiate_typespec_full(
                                     &tasc.t_spec.t_spec,
                                     None,
-                                    &mut dummy_tv_cache,
+                                    tv_cache,
                                     RegistrationMode::Normal,
                                     false,
                                 )?,
@@ -2004,7 +2336,7 @@ impl ASTLowerer {
                             self.module.context.instantiate_typespec_full(
                                 spec,
                                 None,
-                                &mut dummy_tv_cache,
+                                tv_cache,
                                 RegistrationMode::Normal,
                                 false,
                             )?,
@@ -2015,7 +2347,7 @@ impl ASTLowerer {
                         let impl_trait = self.module.context.instantiate_typespec_full(
                             &trait_spec.t_spec,
                             None,
-                            &mut dummy_tv_cache,
+                            tv_cache,
                             RegistrationMode::Normal,
                             false,
                         )?;
@@ -2023,7 +2355,7 @@ impl ASTLowerer {
                             self.module.context.instantiate_typespec_full(
                                 spec,
                                 None,
-                                &mut dummy_tv_cache,
+                                tv_cache,
                                 RegistrationMode::Normal,
                                 false,
                             )?,
@@ -2036,7 +2368,7 @@ impl ASTLowerer {
                 self.module.context.instantiate_typespec_full(
                     other,
                     None,
-                    &mut dummy_tv_cache,
+                    tv_cache,
                     RegistrationMode::Normal,
                     false,
                 )?,
@@ -2458,6 +2790,7 @@ impl ASTLowerer {
                     Self::get_require_or_sup_or_base(call.args.remove_left_or_key("Class").unwrap())
                 }
                 Some("Structural") => call.args.remove_left_or_key("Type"),
+                Some("Enum") => call.args.remove_left_or_key("Variants"),
                 Some("Patch") => call.args.remove_left_or_key("Base"),
                 _ => todo!(),
             },
@@ -2680,11 +3013,47 @@ impl ASTLowerer {
                     hir::Expr::Dummy(hir::Dummy::new(vec![]))
                 }
             };
+            self.narrow_after_early_exit(&chunk);
             hir_block.push(chunk);
         }
         Ok(hir::Block::new(hir_block))
     }
 
+    /// ```erg
+    /// f(x: Int or NoneType) =
+    ///     if x == None, do:
+    ///         f.return 0
+    ///     x + 1 # x: Int
+    /// ```
+    fn narrow_after_early_exit(&mut self, chunk: &hir::Expr) {
+        let hir::Expr::Call(call) = chunk else {
+            return;
+        };
+        if call.attr_name.is_some()
+            || !call
+                .obj
+                .show_acc()
+                .is_some_and(|name| name == "if" || name == "if!")
+        {
+            return;
+        }
+        let Some(cond) = call.args.get(0) else {
+            return;
+        };
+        let exits = |branch: Option<&hir::Expr>| matches!(branch, Some(hir::Expr::Lambda(lambda)) if lambda.body.ref_t() == &Type::Never);
+        // if both branches exit, the rest is unreachable
+        let negated = match (exits(call.args.get(1)), exits(call.args.get(2))) {
+            (true, false) => true,
+            (false, true) => false,
+            _ => return,
+        };
+        for guard in self.module.context.guards_of(cond.ref_t(), negated) {
+            if let Err(errs) = self.module.context.cast(guard, &mut vec![]) {
+                self.errs.extend(errs);
+            }
+        }
+    }
+
     fn lower_dummy(
         &mut self,
         ast_dummy: ast::Dummy,
@@ -3,6 +3,7 @@ extern crate erg_compiler;
 extern crate erg_parser;
 
 use erg_common::config::{ErgConfig, ErgMode::*};
+use erg_common::explain;
 use erg_common::spawn::exec_new_thread;
 use erg_common::traits::{ExitStatus, Runnable};
 
@@ -10,13 +11,14 @@ use erg_compiler::build_hir::HIRBuilder;
 use erg_compiler::lower::ASTLowerer;
 use erg_compiler::transpile::Transpiler;
 use erg_compiler::ty::deserialize::Deserializer;
-use erg_compiler::Compiler;
+use erg_compiler::{Bundler, Compiler, PackageBuilder};
 
 use erg_parser::lex::LexerRunner;
 use erg_parser::ParserRunner;
 
 fn run() {
     let cfg = ErgConfig::parse();
+    let error_format = cfg.error_format.clone();
     let stat = match cfg.mode {
         Lex => LexerRunner::run(cfg),
         Parse => ParserRunner::run(cfg),
@@ -24,12 +26,16 @@ fn run() {
         FullCheck => HIRBuilder::run(cfg),
         Transpile => Transpiler::run(cfg),
         Compile | Execute => Compiler::run(cfg),
+        Build => PackageBuilder::run(cfg),
+        Bundle => Bundler::run(cfg),
+        Explain => explain::run(cfg),
         Read => Deserializer::run(cfg),
         other => {
             println!("invalid mode: {other}");
             ExitStatus::ERR1
         }
     };
+    error_format.flush_sarif_log(&mut std::io::stderr());
     std::process::exit(stat.code);
 }
 
@@ -3,6 +3,7 @@ use std::fmt;
 use std::hash::Hash;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::SystemTime;
 
 use erg_common::config::ErgConfig;
 use erg_common::dict::Dict;
@@ -36,6 +37,8 @@ pub struct ModuleEntry {
     pub id: ModId, // builtin == 0, __main__ == 1
     pub hir: Option<HIR>,
     pub module: Arc<ModuleContext>,
+    /// when the module was checked
+    pub timestamp: SystemTime,
 }
 
 impl fmt::Display for ModuleEntry {
@@ -54,6 +57,7 @@ impl ModuleEntry {
             id,
             hir,
             module: Arc::new(ctx),
+            timestamp: SystemTime::now(),
         }
     }
 
@@ -62,12 +66,23 @@ impl ModuleEntry {
             id: ModId::builtin(),
             hir: None,
             module: Arc::new(ctx),
+            timestamp: SystemTime::now(),
         }
     }
 
     pub fn cfg(&self) -> &ErgConfig {
         &self.module.context.cfg
     }
+
+    /// Returns `true` if the source file has been modified since the module was checked.
+    pub fn is_modified(&self) -> bool {
+        self.cfg()
+            .input
+            .path()
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .is_ok_and(|modified| modified > self.timestamp)
+    }
 }
 
 /// Caches checked modules.
@@ -0,0 +1,372 @@
+//! Persists the public interface of checked modules to `__pycache__`,
+//! so that the next process does not have to re-typecheck the whole import tree.
+//!
+//! The cache file is a declaration file (`.d.er`) with a status header, like the one generated by pylyzer.
+//! Each header line is `##[<key>] <value>`, and the value (which may be a path containing spaces) is the rest of the line.
+//! The source hashes of the modules that the module depends on (see `ModuleGraph`) are also recorded,
+//! so that the cache is invalidated when one of them is modified.
+//! ```erg
+//! ##[erg] 0.6.21
+//! ##[file] /path/to/foo.er
+//! ##[hash] 1234567890
+//! ##[dep] 9876543210 /path/to/bar.er
+//! .f: (x: Int) -> Int
+//! .x: {1}
+//! .C: ClassType
+//! .C.
+//!     .new: (_: {.x = Int}) -> .C
+//!     .f: (self: .C) -> Int
+//! .bar = import "bar"
+//! ```
+use std::fmt;
+use std::fs::{create_dir_all, read_to_string, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use erg_common::config::{ErgConfig, ErgMode};
+use erg_common::traits::LimitedDisplay;
+use erg_common::{get_hash, Str};
+
+use crate::context::{Context, ModuleContext};
+use crate::module::SharedCompilerResource;
+use crate::ty::value::ValueObj;
+use crate::ty::{ParamTy, TyParam, Type};
+
+/// The cache is invalidated when the compiler version changes.
+pub const CACHE_VERSION: &str = env!("CARGO_PKG_VERSION");
+const MAX_TYPE_DEPTH: isize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheStatus {
+    pub version: Str,
+    pub file: PathBuf,
+    pub hash: usize,
+    /// (path, source hash) of the modules that the module depends on, sorted by path
+    pub deps: Vec<(PathBuf, usize)>,
+}
+
+impl fmt::Display for CacheStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "##[erg] {}", self.version)?;
+        writeln!(f, "##[file] {}", self.file.display())?;
+        write!(f, "##[hash] {}", self.hash)?;
+        for (dep, hash) in self.deps.iter() {
+            write!(f, "\n##[dep] {hash} {}", dep.display())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for CacheStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut version = None;
+        let mut file = None;
+        let mut hash = None;
+        let mut deps = vec![];
+        for line in s.lines() {
+            let (key, value) = line
+                .strip_prefix("##[")
+                .and_then(|line| line.split_once("] "))
+                .ok_or_else(|| format!("invalid header: {line}"))?;
+            match key {
+                "erg" => version = Some(Str::rc(value)),
+                "file" => file = Some(PathBuf::from(value)),
+                "hash" => hash = Some(parse_hash(value)?),
+                "dep" => {
+                    let (hash, dep) = value.split_once(' ').ok_or("no dependency path")?;
+                    deps.push((PathBuf::from(dep), parse_hash(hash)?));
+                }
+                _ => return Err(format!("unknown key: {key}")),
+            }
+        }
+        Ok(CacheStatus {
+            version: version.ok_or("no version")?,
+            file: file.ok_or("no file")?,
+            hash: hash.ok_or("no hash")?,
+            deps,
+        })
+    }
+}
+
+fn parse_hash(hash: &str) -> Result<usize, String> {
+    hash.parse().map_err(|e| format!("hash parse error: {e}"))
+}
+
+fn source_hash(path: &Path) -> Option<usize> {
+    read_to_string(path).ok().map(|src| get_hash(&src))
+}
+
+impl CacheStatus {
+    /// Returns `None` if one of the dependencies cannot be read.
+    pub fn new(file: PathBuf, src: &str, deps: impl IntoIterator<Item = PathBuf>) -> Option<Self> {
+        let mut deps = deps
+            .into_iter()
+            .map(|dep| source_hash(&dep).map(|hash| (dep, hash)))
+            .collect::<Option<Vec<_>>>()?;
+        deps.sort();
+        Some(Self {
+            version: Str::ever(CACHE_VERSION),
+            file,
+            hash: get_hash(&src),
+            deps,
+        })
+    }
+
+    /// Whether the cache was generated from `src` (at `file`) and the current dependencies.
+    pub fn is_fresh(&self, file: &Path, src: &str) -> bool {
+        &self.version[..] == CACHE_VERSION
+            && self.file == file
+            && self.hash == get_hash(&src)
+            && self
+                .deps
+                .iter()
+                .all(|(dep, hash)| source_hash(dep) == Some(*hash))
+    }
+}
+
+/// Whether the cached context can be used instead of checking the module.
+/// Erg modules are cached without their HIR, so the cache cannot be used when the HIR is linked
+/// (`compile`, `exec`, ...), and the language server needs the original definition locations.
+pub fn is_available(mode: ErgMode, is_decl: bool) -> bool {
+    match mode {
+        ErgMode::TypeCheck | ErgMode::FullCheck => true,
+        ErgMode::LanguageServer => false,
+        _ => is_decl,
+    }
+}
+
+/// e.g.
+/// * `foo/bar.er` -> `foo/__pycache__/bar.erg-0.6.21.d.er`
+/// * `foo/bar.d.er` -> `foo/__pycache__/bar.d.erg-0.6.21.d.er`
+pub fn cache_path(path: &Path) -> PathBuf {
+    let mut cache = path.to_path_buf();
+    let file_name = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .trim_end_matches(".er")
+        .to_string();
+    cache.pop();
+    cache.push("__pycache__");
+    cache.push(format!("{file_name}.erg-{CACHE_VERSION}.d.er"));
+    cache
+}
+
+/// Returns the cached declarations of the module if the cache is up to date.
+pub fn load(path: &Path, src: &str) -> Option<String> {
+    let cache = read_to_string(cache_path(path)).ok()?;
+    let mut header_len = 0;
+    for line in cache.split_inclusive('\n') {
+        if !line.starts_with("##[") {
+            break;
+        }
+        header_len += line.len();
+    }
+    let (header, decls) = cache.split_at(header_len);
+    let status = header.parse::<CacheStatus>().ok()?;
+    if status.is_fresh(path, src) {
+        Some(decls.to_string())
+    } else {
+        None
+    }
+}
+
+/// Stores the declarations of the module at `path`.
+/// `deps` are the paths of the modules that the module depends on.
+pub fn store(
+    path: &Path,
+    src: &str,
+    deps: impl IntoIterator<Item = PathBuf>,
+    decls: &str,
+) -> std::io::Result<()> {
+    let invalid_data = || std::io::Error::from(std::io::ErrorKind::InvalidData);
+    let status = CacheStatus::new(path.to_path_buf(), src, deps).ok_or_else(invalid_data)?;
+    // the header cannot contain paths with newlines
+    if status.to_string().lines().count() != 3 + status.deps.len() {
+        return Err(invalid_data());
+    }
+    let cache = cache_path(path);
+    if let Some(dir) = cache.parent() {
+        create_dir_all(dir)?;
+    }
+    let mut file = File::create(cache)?;
+    writeln!(file, "{status}")?;
+    file.write_all(decls.as_bytes())
+}
+
+/// Converts a type to the declaration syntax.
+/// * types defined in the module are referred to as `.C`
+/// * anonymous type variables (`%1`) are renamed to `T_1`
+///
+/// Returns `None` if the type is too deeply nested (or recursive) to be declared,
+/// or if it refers to a type defined in another module (e.g. `bar.C`), which cannot be resolved in the declarations.
+fn fmt_type(t: &Type, mod_name: &str) -> Option<String> {
+    let prefix = format!("{mod_name}.");
+    let mut displayed = String::new();
+    name_params(t)
+        .limited_fmt(&mut displayed, MAX_TYPE_DEPTH)
+        .ok()?;
+    if displayed.contains("...") {
+        return None;
+    }
+    let mut code = String::with_capacity(displayed.len());
+    let mut rest = &displayed[..];
+    let mut prev = ' ';
+    // the first character of the current identifier (or number)
+    let mut head = ' ';
+    let mut in_str = false;
+    while let Some(c) = rest.chars().next() {
+        let is_ident_char = |c: char| c.is_alphanumeric() || c == '_' || c == '!';
+        if in_str {
+            in_str = c != '"' || prev == '\\';
+        } else if c == '"' {
+            in_str = true;
+        } else if !is_ident_char(prev) && rest.starts_with(&prefix) {
+            code.push('.');
+            rest = &rest[prefix.len()..];
+            prev = '.';
+            head = '.';
+            continue;
+        } else if c == '.'
+            && is_ident_char(prev)
+            && !head.is_ascii_digit()
+            && rest[1..].starts_with(is_ident_char)
+        {
+            return None;
+        }
+        if !is_ident_char(prev) {
+            head = c;
+        }
+        if !in_str && c == '%' && rest[1..].starts_with(|c: char| c.is_ascii_digit()) {
+            code.push_str("T_");
+        } else {
+            code.push(c);
+        }
+        rest = &rest[c.len_utf8()..];
+        prev = c;
+    }
+    Some(code)
+}
+
+/// Nameless parameters are declared as `_: T`, because e.g. `({.x = Int}) -> .C` cannot be parsed.
+fn name_params(t: &Type) -> Type {
+    match t {
+        Type::FreeVar(fv) if fv.is_linked() => name_params(&fv.crack()),
+        Type::Subr(subr) => {
+            let mut subr = subr.clone();
+            for pt in subr.non_default_params.iter_mut() {
+                if let ParamTy::Pos(ty) = pt {
+                    *pt = ParamTy::Kw {
+                        name: Str::ever("_"),
+                        ty: ty.clone(),
+                    };
+                }
+            }
+            Type::Subr(subr)
+        }
+        Type::Quantified(quant) => Type::Quantified(Box::new(name_params(quant))),
+        Type::Overloaded(ts) => Type::Overloaded(ts.iter().map(name_params).collect()),
+        _ => t.clone(),
+    }
+}
+
+fn fmt_str_lit(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// e.g. `.C: ClassType\n.C <: .Base\n.C.\n    .f: (self: .C) -> Int\n`
+fn gen_type_decl(name: &str, typ: &Context, mod_name: &str) -> Option<String> {
+    let kind = if typ.kind.is_trait() {
+        "TraitType"
+    } else {
+        "ClassType"
+    };
+    let mut code = format!(".{name}: {kind}\n");
+    for sup in typ.super_classes.iter().chain(typ.super_traits.iter()) {
+        if sup != &Type::Obj {
+            code += &format!(".{name} <: {}\n", fmt_type(sup, mod_name)?);
+        }
+    }
+    let mut attrs = vec![];
+    // the declarations in the type itself take precedence over the automatically implemented methods
+    let defs = [typ]
+        .into_iter()
+        .chain(typ.methods_list.iter().map(|(_, methods)| methods));
+    for ctx in defs {
+        for (attr, vi) in ctx.locals.iter().chain(ctx.decls.iter()) {
+            if !vi.vis.is_public() {
+                continue;
+            }
+            if attrs.iter().all(|(a, _)| a != attr.inspect()) {
+                let decl = format!("    .{attr}: {}\n", fmt_type(&vi.t, mod_name)?);
+                attrs.push((attr.inspect().clone(), decl));
+            }
+        }
+    }
+    if !attrs.is_empty() {
+        attrs.sort_by(|(l, _), (r, _)| l.cmp(r));
+        code += &format!(".{name}.\n");
+        code.extend(attrs.into_iter().map(|(_, decl)| decl));
+    }
+    Some(code)
+}
+
+/// Generates the declarations of the public interface of the module.
+/// The declarations are sorted by name, so that the same context produces the same code.
+///
+/// Re-exported modules are imported again by the same names,
+/// so the declarations have to be built as the module itself (not as the cache file).
+/// Returns `None` if the module has a public item that cannot be declared (polymorphic types, etc.).
+pub fn gen_decl(module: &ModuleContext, mod_name: &str) -> Option<String> {
+    let ctx = &module.context;
+    let mut items = vec![];
+    for (name, vi) in ctx.locals.iter().chain(ctx.decls.iter()) {
+        if !vi.vis.is_public() || items.iter().any(|(n, _)| n == name.inspect()) {
+            continue;
+        }
+        let code = if let Some((_, typ)) = ctx.mono_types.get(name) {
+            gen_type_decl(name.inspect(), typ, mod_name)?
+        } else if ctx.poly_types.get(name).is_some() {
+            return None;
+        } else if vi.t.is_module() {
+            let tps = vi.t.typarams();
+            let Some(TyParam::Value(ValueObj::Str(__name__))) = tps.first() else {
+                return None;
+            };
+            let import = if vi.t.is_py_module() {
+                "pyimport"
+            } else {
+                "import"
+            };
+            format!(".{name} = {import} {}\n", fmt_str_lit(__name__))
+        } else {
+            format!(".{name}: {}\n", fmt_type(&vi.t, mod_name)?)
+        };
+        items.push((name.inspect().clone(), code));
+    }
+    items.sort_by(|(l, _), (r, _)| l.cmp(r));
+    Some(items.into_iter().map(|(_, code)| code).collect())
+}
+
+/// Stores the declarations of `module` with the modules it depends on (taken from the module graph).
+/// Modules whose interface cannot be represented by the declarations are not cached.
+pub fn try_store(
+    cfg: &ErgConfig,
+    mod_name: &Str,
+    shared: &SharedCompilerResource,
+    src: &str,
+    module: &ModuleContext,
+) {
+    let path = cfg.input.path();
+    let Some(decls) = gen_decl(module, mod_name) else {
+        return;
+    };
+    let deps = shared
+        .graph
+        .ancestors(path)
+        .into_iter()
+        .map(|dep| dep.to_path_buf());
+    let _ = store(path, src, deps, &decls);
+}
@@ -60,6 +60,8 @@ impl ModuleIndexValue {
 #[derive(Debug, Clone, Default)]
 pub struct ModuleIndex {
     members: Dict<AbsLocation, ModuleIndexValue>,
+    /// module path -> the referees defined or referred in the module
+    paths: Dict<PathBuf, Set<AbsLocation>>,
 }
 
 impl fmt::Display for ModuleIndex {
@@ -72,11 +74,36 @@ impl ModuleIndex {
     pub fn new() -> Self {
         Self {
             members: Dict::new(),
+            paths: Dict::new(),
         }
     }
 
+    fn index_path(&mut self, loc: &AbsLocation, referee: &AbsLocation) {
+        if let Some(path) = loc.module.as_ref() {
+            if let Some(referees) = self.paths.get_mut(path) {
+                referees.insert(referee.clone());
+            } else {
+                self.paths.insert(path.clone(), set! {referee.clone()});
+            }
+        }
+    }
+
+    fn reindex_paths(&mut self) {
+        self.paths.clear();
+        let members = std::mem::take(&mut self.members);
+        for (referee, value) in members.iter() {
+            self.index_path(referee, referee);
+            for referrer in value.referrers.iter() {
+                self.index_path(referrer, referee);
+            }
+        }
+        self.members = members;
+    }
+
     pub fn inc_ref(&mut self, name: &Str, vi: &VarInfo, referrer: AbsLocation) {
         let referee = vi.def_loc.clone();
+        self.index_path(&referee, &referee);
+        self.index_path(&referrer, &referee);
         if let Some(referrers) = self.members.get_mut(&referee) {
             referrers.push_ref(referrer);
         } else {
@@ -90,6 +117,7 @@ impl ModuleIndex {
             return;
         }
         let referee = vi.def_loc.clone();
+        self.index_path(&referee, &referee);
         let value = ModuleIndexValue::new(name, vi.clone(), set! {});
         self.members.insert(referee, value);
     }
@@ -98,8 +126,14 @@ impl ModuleIndex {
         self.members.get(referee)
     }
 
+    /// Returns the referees defined or referred in `path` (without scanning all members).
+    pub fn get_referees_in(&self, path: &Path) -> Option<&Set<AbsLocation>> {
+        self.paths.get(path)
+    }
+
     pub fn initialize(&mut self) {
         self.members.clear();
+        self.paths.clear();
     }
 
     pub fn remove_path(&mut self, path: &Path) {
@@ -109,6 +143,10 @@ impl ModuleIndex {
                 .retain(|ref_loc| ref_loc.module.as_deref() != Some(path));
             loc.module.as_deref() != Some(path)
         });
+        self.paths.remove(path);
+        for referees in self.paths.values_mut() {
+            referees.retain(|referee| referee.module.as_deref() != Some(path));
+        }
     }
 
     pub fn rename_path(&mut self, old: &Path, new: PathBuf) {
@@ -142,6 +180,7 @@ impl ModuleIndex {
             }
         }
         self.members = new_members;
+        self.reindex_paths();
     }
 }
 
@@ -180,6 +219,11 @@ impl SharedModuleIndex {
         }
     }
 
+    /// The set is copied so that `get_refs` can be called while iterating over it.
+    pub fn get_referees_in(&self, path: &Path) -> Option<Set<AbsLocation>> {
+        self.0.borrow().get_referees_in(path).cloned()
+    }
+
     pub fn members(&self) -> Members {
         Members(RwLockReadGuard::map(self.0.borrow(), |mi| &mi.members))
     }
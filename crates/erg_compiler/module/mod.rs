@@ -1,4 +1,5 @@
 pub mod cache;
+pub mod disk_cache;
 pub mod errors;
 pub mod global;
 pub mod graph;
@@ -0,0 +1,929 @@
+//! defines `PeepholeOptimizer` (a bytecode-level optimizer).
+//!
+//! バイトコードレベルの最適化(のぞき穴最適化)を行う
+use erg_common::config::ErgConfig;
+#[allow(unused_imports)]
+use erg_common::log;
+use erg_common::opcode::CommonOpcode;
+use erg_common::opcode308::Opcode308;
+use erg_common::opcode309::Opcode309;
+use erg_common::opcode310::Opcode310;
+use erg_common::opcode311::Opcode311;
+use erg_common::python_util::PythonVersion;
+
+use crate::ty::codeobj::{CodeObj, CodeObjFlags};
+use crate::ty::value::ValueObj;
+
+/// How the arg of a jump instruction is interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JumpKind {
+    /// the arg is the offset of the target
+    Absolute,
+    /// the arg is the distance from the next instruction to the target
+    Forward,
+    /// the arg is the distance from the target to the next instruction (3.11)
+    Backward,
+}
+
+/// A decoded instruction. `EXTENDED_ARG`s are already applied to `arg`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Instr {
+    op: u8,
+    arg: usize,
+    /// the number of `CACHE` entries following the instruction (3.11)
+    caches: usize,
+    /// the index of the jump target in the instruction list
+    target: Option<usize>,
+    lineno: i64,
+    removed: bool,
+}
+
+impl Instr {
+    const fn new(op: u8, arg: usize, lineno: i64) -> Self {
+        Self {
+            op,
+            arg,
+            caches: 0,
+            target: None,
+            lineno,
+            removed: false,
+        }
+    }
+}
+
+/// Returns the index of the first instruction that is not removed, at or after `idx`.
+fn next_alive(instrs: &[Instr], idx: usize) -> Option<usize> {
+    (idx..instrs.len()).find(|&i| !instrs[i].removed)
+}
+
+/// The number of `EXTENDED_ARG`s needed to encode `arg`.
+const fn extended_args(arg: usize) -> usize {
+    match arg {
+        0..=0xFF => 0,
+        0x100..=0xFFFF => 1,
+        0x1_0000..=0xFF_FFFF => 2,
+        _ => 3,
+    }
+}
+
+/// Optimizes `CodeObj`s emitted by `PyCodeGenerator`.
+/// This is enabled when `opt_level` is 2 or higher.
+///
+/// * jumps to unconditional jumps are redirected to the final target
+/// * `LOAD_CONST; POP_TOP` pairs are removed
+/// * `LOAD_CONST`s followed by `BUILD_TUPLE` are folded into a constant tuple
+/// * unreachable instructions (e.g. after `RETURN_VALUE`) are removed
+///
+/// After that, the jump args, `lnotab` and `stacksize` are recomputed.
+/// If the code object contains something the optimizer does not understand (unknown opcodes, exception tables, etc.),
+/// it is left as it is.
+#[derive(Debug)]
+pub struct PeepholeOptimizer {
+    minor: u8,
+}
+
+impl PeepholeOptimizer {
+    pub fn optimize(cfg: ErgConfig, py_ver: PythonVersion, code: CodeObj) -> CodeObj {
+        let minor = py_ver.minor.unwrap_or_default();
+        if cfg.opt_level < 2 || cfg.input.is_repl() || !(7..=11).contains(&minor) {
+            return code;
+        }
+        let optimizer = PeepholeOptimizer { minor };
+        optimizer.optimize_code(code)
+    }
+
+    fn optimize_code(&self, mut code: CodeObj) -> CodeObj {
+        code.consts = code
+            .consts
+            .into_iter()
+            .map(|c| match c {
+                ValueObj::Code(c) => ValueObj::Code(Box::new(self.optimize_code(*c))),
+                other => other,
+            })
+            .collect();
+        let Some(mut instrs) = self.decode(&code) else {
+            return code;
+        };
+        let mut changed = false;
+        loop {
+            let mut cont = self.fold_tuples(&mut instrs, &mut code.consts);
+            cont |= self.remove_const_pops(&mut instrs);
+            cont |= self.thread_jumps(&mut instrs);
+            cont |= self.remove_unreachable(&mut instrs);
+            cont |= self.remove_jumps_to_next(&mut instrs);
+            if !cont {
+                break;
+            }
+            changed = true;
+        }
+        if !changed {
+            return code;
+        }
+        match self.encode(&instrs, code.firstlineno) {
+            Some((bytes, lnotab)) => {
+                code.code = bytes;
+                code.lnotab = lnotab;
+                if let Some(stacksize) = self.stack_depth(&instrs, code.flags) {
+                    code.stacksize = stacksize;
+                }
+            }
+            // the code is left as it is (the folded tuples remain in `consts`, but they are harmless)
+            None => {
+                log!(err "failed to encode the optimized code: {}", code.name);
+            }
+        }
+        code
+    }
+
+    fn jump_kind(&self, op: u8) -> Option<JumpKind> {
+        match self.minor {
+            ..=8 => match Opcode308::try_from_u8(op)? {
+                Opcode308::FOR_ITER
+                | Opcode308::JUMP_FORWARD
+                | Opcode308::SETUP_FINALLY
+                | Opcode308::SETUP_WITH
+                | Opcode308::CALL_FINALLY => Some(JumpKind::Forward),
+                Opcode308::JUMP_ABSOLUTE
+                | Opcode308::POP_JUMP_IF_FALSE
+                | Opcode308::POP_JUMP_IF_TRUE
+                | Opcode308::JUMP_IF_FALSE_OR_POP
+                | Opcode308::JUMP_IF_TRUE_OR_POP => Some(JumpKind::Absolute),
+                _ => None,
+            },
+            9 => match Opcode309::try_from_u8(op)? {
+                Opcode309::FOR_ITER | Opcode309::JUMP_FORWARD | Opcode309::SETUP_WITH => {
+                    Some(JumpKind::Forward)
+                }
+                Opcode309::JUMP_ABSOLUTE
+                | Opcode309::POP_JUMP_IF_FALSE
+                | Opcode309::POP_JUMP_IF_TRUE
+                | Opcode309::JUMP_IF_FALSE_OR_POP
+                | Opcode309::JUMP_IF_TRUE_OR_POP => Some(JumpKind::Absolute),
+                _ => None,
+            },
+            10 => match Opcode310::try_from_u8(op)? {
+                Opcode310::FOR_ITER | Opcode310::JUMP_FORWARD | Opcode310::SETUP_WITH => {
+                    Some(JumpKind::Forward)
+                }
+                Opcode310::JUMP_ABSOLUTE
+                | Opcode310::POP_JUMP_IF_FALSE
+                | Opcode310::POP_JUMP_IF_TRUE
+                | Opcode310::JUMP_IF_FALSE_OR_POP
+                | Opcode310::JUMP_IF_TRUE_OR_POP => Some(JumpKind::Absolute),
+                _ => None,
+            },
+            _ => match Opcode311::try_from_u8(op)? {
+                Opcode311::FOR_ITER
+                | Opcode311::JUMP_FORWARD
+                | Opcode311::POP_JUMP_FORWARD_IF_FALSE
+                | Opcode311::POP_JUMP_FORWARD_IF_TRUE
+                | Opcode311::JUMP_IF_FALSE_OR_POP
+                | Opcode311::JUMP_IF_TRUE_OR_POP
+                | Opcode311::SEND => Some(JumpKind::Forward),
+                Opcode311::JUMP_BACKWARD
+                | Opcode311::JUMP_BACKWARD_NO_INTERRUPT
+                | Opcode311::POP_JUMP_BACKWARD_IF_FALSE
+                | Opcode311::POP_JUMP_BACKWARD_IF_TRUE => Some(JumpKind::Backward),
+                _ => None,
+            },
+        }
+    }
+
+    /// `JUMP_FORWARD`, `JUMP_ABSOLUTE` and `JUMP_BACKWARD`
+    fn is_plain_jump(&self, op: u8) -> bool {
+        if self.minor >= 11 {
+            op == Opcode311::JUMP_FORWARD as u8 || op == Opcode311::JUMP_BACKWARD as u8
+        } else {
+            op == Opcode310::JUMP_FORWARD as u8 || op == Opcode310::JUMP_ABSOLUTE as u8
+        }
+    }
+
+    /// Returns true if the control never reaches the next instruction.
+    fn is_terminator(&self, op: u8) -> bool {
+        if op == CommonOpcode::RETURN_VALUE as u8
+            || op == CommonOpcode::RAISE_VARARGS as u8
+            || self.is_plain_jump(op)
+        {
+            return true;
+        }
+        match self.minor {
+            ..=8 => false,
+            9 => op == Opcode309::RERAISE as u8,
+            10 => op == Opcode310::RERAISE as u8,
+            _ => {
+                op == Opcode311::RERAISE as u8 || op == Opcode311::JUMP_BACKWARD_NO_INTERRUPT as u8
+            }
+        }
+    }
+
+    /// Returns the opcode to use when the target of the jump `op` is changed.
+    /// `None` means the jump cannot be redirected (in that direction).
+    fn redirected_op(&self, op: u8, backward: bool) -> Option<u8> {
+        if self.minor >= 11 {
+            let op = match Opcode311::try_from_u8(op)? {
+                Opcode311::JUMP_FORWARD | Opcode311::JUMP_BACKWARD if backward => {
+                    Opcode311::JUMP_BACKWARD
+                }
+                Opcode311::JUMP_FORWARD | Opcode311::JUMP_BACKWARD => Opcode311::JUMP_FORWARD,
+                Opcode311::POP_JUMP_FORWARD_IF_FALSE | Opcode311::POP_JUMP_BACKWARD_IF_FALSE
+                    if backward =>
+                {
+                    Opcode311::POP_JUMP_BACKWARD_IF_FALSE
+                }
+                Opcode311::POP_JUMP_FORWARD_IF_FALSE | Opcode311::POP_JUMP_BACKWARD_IF_FALSE => {
+                    Opcode311::POP_JUMP_FORWARD_IF_FALSE
+                }
+                Opcode311::POP_JUMP_FORWARD_IF_TRUE | Opcode311::POP_JUMP_BACKWARD_IF_TRUE
+                    if backward =>
+                {
+                    Opcode311::POP_JUMP_BACKWARD_IF_TRUE
+                }
+                Opcode311::POP_JUMP_FORWARD_IF_TRUE | Opcode311::POP_JUMP_BACKWARD_IF_TRUE => {
+                    Opcode311::POP_JUMP_FORWARD_IF_TRUE
+                }
+                Opcode311::JUMP_IF_FALSE_OR_POP | Opcode311::JUMP_IF_TRUE_OR_POP if !backward => {
+                    Opcode311::try_from_u8(op)?
+                }
+                _ => return None,
+            };
+            Some(op as u8)
+        } else {
+            // the opcodes of the jump instructions are the same in 3.7~3.10
+            match Opcode310::try_from_u8(op)? {
+                Opcode310::JUMP_FORWARD if backward => Some(Opcode310::JUMP_ABSOLUTE as u8),
+                Opcode310::JUMP_FORWARD
+                | Opcode310::JUMP_ABSOLUTE
+                | Opcode310::POP_JUMP_IF_FALSE
+                | Opcode310::POP_JUMP_IF_TRUE
+                | Opcode310::JUMP_IF_FALSE_OR_POP
+                | Opcode310::JUMP_IF_TRUE_OR_POP => Some(op),
+                _ => None,
+            }
+        }
+    }
+
+    fn is_known_op(&self, op: u8) -> bool {
+        match self.minor {
+            ..=8 => Opcode308::try_from_u8(op).is_some(),
+            9 => Opcode309::try_from_u8(op).is_some(),
+            10 => Opcode310::try_from_u8(op).is_some(),
+            _ => Opcode311::try_from_u8(op).is_some(),
+        }
+    }
+
+    /// `lnotab` is in the format written by `PyCodeGenerator::push_lnotab` (pairs of `sdelta`, `ldelta`).
+    /// Returns the line number of each offset where the line changes.
+    fn decode_lnotab(lnotab: &[u8], firstlineno: u32) -> Option<Vec<(usize, i64)>> {
+        if !lnotab.len().is_multiple_of(2) {
+            return None;
+        }
+        let mut lines = vec![(0, firstlineno as i64)];
+        let mut addr = 0;
+        let mut line = firstlineno as i64;
+        for pair in lnotab.chunks_exact(2) {
+            addr += pair[0] as usize;
+            line += pair[1] as i8 as i64;
+            lines.push((addr, line));
+        }
+        Some(lines)
+    }
+
+    fn decode(&self, code: &CodeObj) -> Option<Vec<Instr>> {
+        if !code.exceptiontable.is_empty() || !code.code.len().is_multiple_of(2) {
+            return None;
+        }
+        let lines = Self::decode_lnotab(&code.lnotab, code.firstlineno)?;
+        let mut lines = lines.into_iter().peekable();
+        let mut lineno = code.firstlineno as i64;
+        let mut instrs: Vec<Instr> = vec![];
+        // (start offset (of the first `EXTENDED_ARG`), offset of the opcode)
+        let mut offsets = vec![];
+        let mut ext_start = None;
+        let mut ext_arg = 0;
+        for (i, unit) in code.code.chunks_exact(2).enumerate() {
+            let (op, offset) = (unit[0], i * 2);
+            if self.minor >= 11 && op == Opcode311::CACHE as u8 {
+                instrs.last_mut()?.caches += 1;
+                continue;
+            }
+            if !self.is_known_op(op) {
+                return None;
+            }
+            let start = *ext_start.get_or_insert(offset);
+            let arg = (ext_arg << 8) | unit[1] as usize;
+            if op == CommonOpcode::EXTENDED_ARG as u8 {
+                ext_arg = arg;
+                continue;
+            }
+            while let Some((_, line)) = lines.next_if(|(addr, _)| *addr <= start) {
+                lineno = line;
+            }
+            instrs.push(Instr::new(op, arg, lineno));
+            offsets.push((start, offset));
+            ext_start = None;
+            ext_arg = 0;
+        }
+        if ext_start.is_some() || instrs.is_empty() {
+            return None;
+        }
+        let unit = if self.minor >= 10 { 2 } else { 1 };
+        for i in 0..instrs.len() {
+            let Some(kind) = self.jump_kind(instrs[i].op) else {
+                continue;
+            };
+            let end = offsets[i].1 + 2 + instrs[i].caches * 2;
+            let target = match kind {
+                JumpKind::Absolute => instrs[i].arg * unit,
+                JumpKind::Forward => end + instrs[i].arg * unit,
+                JumpKind::Backward => end.checked_sub(instrs[i].arg * 2)?,
+            };
+            // a jump to the opcode skipping `EXTENDED_ARG 0`s is also accepted
+            let idx = offsets.iter().position(|(start, op_offset)| {
+                *start == target || (*op_offset == target && instrs[i].arg <= 0xFF)
+            })?;
+            instrs[i].target = Some(idx);
+        }
+        Some(instrs)
+    }
+
+    /// The indices of the instructions that are jumped to.
+    fn jump_targets(instrs: &[Instr]) -> Vec<bool> {
+        let mut targets = vec![false; instrs.len()];
+        for instr in instrs.iter().filter(|i| !i.removed) {
+            if let Some(target) = instr.target.and_then(|t| next_alive(instrs, t)) {
+                targets[target] = true;
+            }
+        }
+        targets
+    }
+
+    /// ```python
+    /// LOAD_CONST 1
+    /// LOAD_CONST 2
+    /// BUILD_TUPLE 2
+    /// ```
+    /// ↓
+    /// ```python
+    /// LOAD_CONST (1, 2)
+    /// ```
+    fn fold_tuples(&self, instrs: &mut [Instr], consts: &mut Vec<ValueObj>) -> bool {
+        let targets = Self::jump_targets(instrs);
+        let mut changed = false;
+        for i in 0..instrs.len() {
+            if instrs[i].removed || instrs[i].op != CommonOpcode::BUILD_TUPLE as u8 {
+                continue;
+            }
+            let mut elems = vec![];
+            let mut idx = i;
+            while elems.len() < instrs[i].arg {
+                let Some(prev) = (0..idx).rev().find(|&j| !instrs[j].removed) else {
+                    break;
+                };
+                // only the first `LOAD_CONST` can be a jump target
+                if instrs[prev].op != CommonOpcode::LOAD_CONST as u8 || targets[idx] {
+                    break;
+                }
+                match consts.get(instrs[prev].arg) {
+                    Some(ValueObj::Code(_)) | None => break,
+                    Some(_) => elems.push(prev),
+                }
+                idx = prev;
+            }
+            if elems.len() != instrs[i].arg {
+                continue;
+            }
+            let tuple = ValueObj::Tuple(
+                elems
+                    .iter()
+                    .rev()
+                    .map(|&j| consts[instrs[j].arg].clone())
+                    .collect(),
+            );
+            // same as `PyCodeGenerator::register_const`
+            let const_idx = consts.iter().position(|c| c == &tuple).unwrap_or_else(|| {
+                consts.push(tuple);
+                consts.len() - 1
+            });
+            for j in elems {
+                instrs[j].removed = true;
+            }
+            instrs[i].op = CommonOpcode::LOAD_CONST as u8;
+            instrs[i].arg = const_idx;
+            changed = true;
+        }
+        changed
+    }
+
+    /// Removes `LOAD_CONST; POP_TOP` (e.g. `None` discarded at the end of a block).
+    fn remove_const_pops(&self, instrs: &mut [Instr]) -> bool {
+        let targets = Self::jump_targets(instrs);
+        let mut changed = false;
+        for i in 0..instrs.len() {
+            if instrs[i].removed || instrs[i].op != CommonOpcode::LOAD_CONST as u8 {
+                continue;
+            }
+            let Some(next) = next_alive(instrs, i + 1) else {
+                continue;
+            };
+            if instrs[next].op == CommonOpcode::POP_TOP as u8 && !targets[next] {
+                instrs[i].removed = true;
+                instrs[next].removed = true;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Redirects jumps to unconditional jumps to their final targets.
+    fn thread_jumps(&self, instrs: &mut [Instr]) -> bool {
+        let mut changed = false;
+        for i in 0..instrs.len() {
+            if instrs[i].removed {
+                continue;
+            }
+            let Some(orig) = instrs[i].target.and_then(|t| next_alive(instrs, t)) else {
+                continue;
+            };
+            let mut target = orig;
+            let mut visited = vec![i];
+            while self.is_plain_jump(instrs[target].op) && !visited.contains(&target) {
+                visited.push(target);
+                let Some(next) = instrs[target].target.and_then(|t| next_alive(instrs, t)) else {
+                    break;
+                };
+                target = next;
+            }
+            if target == orig {
+                continue;
+            }
+            if let Some(op) = self.redirected_op(instrs[i].op, target <= i) {
+                instrs[i].op = op;
+                instrs[i].target = Some(target);
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    fn remove_unreachable(&self, instrs: &mut [Instr]) -> bool {
+        let mut reachable = vec![false; instrs.len()];
+        let mut stack = next_alive(instrs, 0).into_iter().collect::<Vec<_>>();
+        while let Some(i) = stack.pop() {
+            if reachable[i] {
+                continue;
+            }
+            reachable[i] = true;
+            if let Some(target) = instrs[i].target.and_then(|t| next_alive(instrs, t)) {
+                stack.push(target);
+            }
+            if !self.is_terminator(instrs[i].op) {
+                stack.extend(next_alive(instrs, i + 1));
+            }
+        }
+        let mut changed = false;
+        for (instr, reachable) in instrs.iter_mut().zip(reachable) {
+            if !instr.removed && !reachable {
+                instr.removed = true;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    fn remove_jumps_to_next(&self, instrs: &mut [Instr]) -> bool {
+        let mut changed = false;
+        for i in 0..instrs.len() {
+            if instrs[i].removed || !self.is_plain_jump(instrs[i].op) {
+                continue;
+            }
+            let target = instrs[i].target.and_then(|t| next_alive(instrs, t));
+            if target.is_some() && target == next_alive(instrs, i + 1) {
+                instrs[i].removed = true;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Returns the new `code` and `lnotab`.
+    fn encode(&self, instrs: &[Instr], firstlineno: u32) -> Option<(Vec<u8>, Vec<u8>)> {
+        let alive = instrs.iter().filter(|i| !i.removed).collect::<Vec<_>>();
+        // the index in `alive` of each instruction (the next one if removed)
+        let mut new_idx = vec![0; instrs.len() + 1];
+        new_idx[instrs.len()] = alive.len();
+        for i in (0..instrs.len()).rev() {
+            new_idx[i] = new_idx[i + 1] - usize::from(!instrs[i].removed);
+        }
+        let targets = alive
+            .iter()
+            .map(|i| i.target.map(|t| new_idx[t]))
+            .collect::<Vec<_>>();
+        if targets.iter().flatten().any(|&t| t >= alive.len()) {
+            return None;
+        }
+        let unit = if self.minor >= 10 { 2 } else { 1 };
+        let mut args = alive.iter().map(|i| i.arg).collect::<Vec<_>>();
+        let mut exts = args.iter().map(|&a| extended_args(a)).collect::<Vec<_>>();
+        // the jump args depend on the offsets, which depend on the number of `EXTENDED_ARG`s
+        let starts = loop {
+            let mut starts = Vec::with_capacity(alive.len());
+            let mut offset = 0;
+            for (instr, ext) in alive.iter().zip(exts.iter()) {
+                starts.push(offset);
+                offset += (ext + 1 + instr.caches) * 2;
+            }
+            let mut grown = false;
+            for (i, instr) in alive.iter().enumerate() {
+                let Some(target) = targets[i] else {
+                    continue;
+                };
+                let end = starts[i] + (exts[i] + 1 + instr.caches) * 2;
+                let arg = match self.jump_kind(instr.op)? {
+                    JumpKind::Absolute => starts[target] / unit,
+                    JumpKind::Forward => starts[target].checked_sub(end)? / unit,
+                    JumpKind::Backward => end.checked_sub(starts[target])? / 2,
+                };
+                args[i] = arg;
+                // never shrink, so that this loop terminates
+                if extended_args(arg) > exts[i] {
+                    exts[i] = extended_args(arg);
+                    grown = true;
+                }
+            }
+            if !grown {
+                break starts;
+            }
+        };
+        let mut code = vec![];
+        for ((instr, arg), ext) in alive.iter().zip(args).zip(exts) {
+            let bytes = (arg as u32).to_be_bytes();
+            for byte in bytes[4 - ext - 1..3].iter() {
+                code.push(CommonOpcode::EXTENDED_ARG as u8);
+                code.push(*byte);
+            }
+            code.push(instr.op);
+            code.push(bytes[3]);
+            for _ in 0..instr.caches {
+                code.push(Opcode311::CACHE as u8);
+                code.push(0);
+            }
+        }
+        let mut lnotab = vec![];
+        let (mut addr, mut line) = (0, firstlineno as i64);
+        for (instr, start) in alive.iter().zip(starts) {
+            if instr.lineno == line {
+                continue;
+            }
+            let mut sdelta = start - addr;
+            let mut ldelta = instr.lineno - line;
+            while sdelta > 255 {
+                lnotab.extend([255, 0]);
+                sdelta -= 255;
+            }
+            while ldelta > 127 {
+                lnotab.extend([sdelta as u8, 127]);
+                sdelta = 0;
+                ldelta -= 127;
+            }
+            while ldelta < -128 {
+                lnotab.extend([sdelta as u8, -128i8 as u8]);
+                sdelta = 0;
+                ldelta += 128;
+            }
+            lnotab.extend([sdelta as u8, ldelta as i8 as u8]);
+            addr = start;
+            line = instr.lineno;
+        }
+        Some((code, lnotab))
+    }
+
+    /// Computes the maximum stack depth by following the control flow.
+    /// Returns `None` if the depth cannot be determined.
+    fn stack_depth(&self, instrs: &[Instr], flags: u32) -> Option<u32> {
+        let generator = CodeObjFlags::Generator as u32
+            | CodeObjFlags::Coroutine as u32
+            | CodeObjFlags::AsyncGenerator as u32;
+        let first = next_alive(instrs, 0)?;
+        // in 3.10, the first sent value is popped by `GEN_START`
+        let init = if self.minor == 10 && flags & generator != 0 {
+            1
+        } else {
+            0
+        };
+        let mut depths = vec![None; instrs.len()];
+        let mut max_depth = init;
+        let mut stack = vec![(first, init)];
+        while let Some((i, depth)) = stack.pop() {
+            if depths[i].is_some_and(|d| d >= depth) {
+                continue;
+            }
+            // the depth keeps growing (e.g. unbalanced loops)
+            if depth > u16::MAX as i32 {
+                return None;
+            }
+            depths[i] = Some(depth);
+            let instr = &instrs[i];
+            if let Some(target) = instr.target.and_then(|t| next_alive(instrs, t)) {
+                let jumped = depth + self.stack_effect(instr.op, instr.arg, true)?;
+                if jumped < 0 {
+                    return None;
+                }
+                max_depth = max_depth.max(jumped);
+                stack.push((target, jumped));
+            }
+            let next_depth = depth + self.stack_effect(instr.op, instr.arg, false)?;
+            if next_depth < 0 {
+                return None;
+            }
+            max_depth = max_depth.max(next_depth);
+            if !self.is_terminator(instr.op) {
+                stack.extend(next_alive(instrs, i + 1).map(|next| (next, next_depth)));
+            }
+        }
+        u32::try_from(max_depth).ok()
+    }
+
+    /// Same as `dis.stack_effect`. `jump` means the effect when the jump is taken.
+    fn stack_effect(&self, op: u8, arg: usize, jump: bool) -> Option<i32> {
+        match self.minor {
+            ..=8 => Self::stack_effect_308(Opcode308::try_from_u8(op)?, arg as i32, jump),
+            9 => Self::stack_effect_309(Opcode309::try_from_u8(op)?, arg as i32, jump),
+            10 => Self::stack_effect_310(Opcode310::try_from_u8(op)?, arg as i32, jump),
+            _ => Self::stack_effect_311(Opcode311::try_from_u8(op)?, arg as i32, jump),
+        }
+    }
+
+    /// the number of values popped by `MAKE_FUNCTION` for the flags
+    const fn make_function_flags(arg: i32) -> i32 {
+        (arg & 0x0f).count_ones() as i32
+    }
+
+    fn stack_effect_308(op: Opcode308, arg: i32, jump: bool) -> Option<i32> {
+        use Opcode308::*;
+        let effect = match op {
+            NOP | ROT_TWO | ROT_THREE | ROT_FOUR | EXTENDED_ARG => 0,
+            UNARY_POSITIVE | UNARY_NEGATIVE | UNARY_NOT | UNARY_INVERT => 0,
+            GET_ITER | GET_YIELD_FROM_ITER | GET_AITER | GET_AWAITABLE => 0,
+            SETUP_ANNOTATIONS | YIELD_VALUE | POP_BLOCK | DELETE_NAME | DELETE_FAST => 0,
+            LOAD_ATTR | JUMP_FORWARD | JUMP_ABSOLUTE => 0,
+            POP_TOP | PRINT_EXPR | RETURN_VALUE | IMPORT_STAR | YIELD_FROM => -1,
+            BINARY_MATRIX_MULTIPLY
+            | INPLACE_MATRIX_MULTIPLY
+            | BINARY_POWER
+            | BINARY_MULTIPLY
+            | BINARY_MODULO
+            | BINARY_ADD
+            | BINARY_SUBTRACT
+            | BINARY_SUBSCR
+            | BINARY_FLOOR_DIVIDE
+            | BINARY_TRUE_DIVIDE
+            | INPLACE_FLOOR_DIVIDE
+            | INPLACE_TRUE_DIVIDE
+            | INPLACE_ADD
+            | INPLACE_SUBTRACT
+            | INPLACE_MULTIPLY
+            | INPLACE_MODULO
+            | BINARY_AND
+            | BINARY_XOR
+            | BINARY_OR => -1,
+            STORE_NAME | STORE_GLOBAL | STORE_FAST | STORE_DEREF | COMPARE_OP | IMPORT_NAME => -1,
+            POP_JUMP_IF_FALSE | POP_JUMP_IF_TRUE => -1,
+            DUP_TOP | GET_ANEXT | BEFORE_ASYNC_WITH | LOAD_BUILD_CLASS | LOAD_CONST | LOAD_NAME
+            | LOAD_GLOBAL | LOAD_FAST | LOAD_CLOSURE | LOAD_DEREF | LOAD_METHOD | IMPORT_FROM => 1,
+            DUP_TOP2 => 2,
+            STORE_ATTR => -2,
+            STORE_SUBSCR | POP_EXCEPT => -3,
+            // the values are counted as in the CPython compiler to balance the `finally` blocks
+            BEGIN_FINALLY => 6,
+            END_FINALLY | POP_FINALLY => -6,
+            END_ASYNC_FOR => -7,
+            WITH_CLEANUP_START => 2,
+            WITH_CLEANUP_FINISH => -3,
+            SETUP_FINALLY if jump => 6,
+            SETUP_FINALLY => 0,
+            SETUP_WITH if jump => 6,
+            SETUP_WITH => 1,
+            CALL_FINALLY if jump => 1,
+            CALL_FINALLY => 0,
+            FOR_ITER if jump => -1,
+            FOR_ITER => 1,
+            JUMP_IF_FALSE_OR_POP | JUMP_IF_TRUE_OR_POP if jump => 0,
+            JUMP_IF_FALSE_OR_POP | JUMP_IF_TRUE_OR_POP => -1,
+            UNPACK_SEQUENCE => arg - 1,
+            UNPACK_EX => (arg & 0xFF) + (arg >> 8),
+            BUILD_TUPLE | BUILD_LIST | BUILD_SET | BUILD_TUPLE_UNPACK_WITH_CALL => 1 - arg,
+            BUILD_MAP => 1 - 2 * arg,
+            RAISE_VARARGS | CALL_FUNCTION => -arg,
+            CALL_FUNCTION_KW | CALL_METHOD => -arg - 1,
+            CALL_FUNCTION_EX => -1 - (arg & 1),
+            MAKE_FUNCTION => -1 - Self::make_function_flags(arg),
+            _ => return None,
+        };
+        Some(effect)
+    }
+
+    fn stack_effect_309(op: Opcode309, arg: i32, jump: bool) -> Option<i32> {
+        use Opcode309::*;
+        let effect = match op {
+            NOP | ROT_TWO | ROT_THREE | ROT_FOUR | EXTENDED_ARG => 0,
+            UNARY_POSITIVE | UNARY_NEGATIVE | UNARY_NOT | UNARY_INVERT => 0,
+            GET_ITER | GET_YIELD_FROM_ITER | GET_AITER | GET_AWAITABLE => 0,
+            SETUP_ANNOTATIONS | YIELD_VALUE | POP_BLOCK | DELETE_NAME | DELETE_FAST => 0,
+            LOAD_ATTR | JUMP_FORWARD | JUMP_ABSOLUTE => 0,
+            POP_TOP | PRINT_EXPR | RETURN_VALUE | IMPORT_STAR | YIELD_FROM => -1,
+            BINARY_MATRIX_MULTIPLY
+            | INPLACE_MATRIX_MULTIPLY
+            | BINARY_POWER
+            | BINARY_MULTIPLY
+            | BINARY_MODULO
+            | BINARY_ADD
+            | BINARY_SUBTRACT
+            | BINARY_SUBSCR
+            | BINARY_FLOOR_DIVIDE
+            | BINARY_TRUE_DIVIDE
+            | INPLACE_FLOOR_DIVIDE
+            | INPLACE_TRUE_DIVIDE
+            | INPLACE_ADD
+            | INPLACE_SUBTRACT
+            | INPLACE_MULTIPLY
+            | INPLACE_MODULO
+            | BINARY_AND
+            | BINARY_XOR
+            | BINARY_OR => -1,
+            STORE_NAME | STORE_GLOBAL | STORE_FAST | STORE_DEREF | COMPARE_OP | IMPORT_NAME => -1,
+            IS_OP | CONTAINS_OP | POP_JUMP_IF_FALSE | POP_JUMP_IF_TRUE => -1,
+            DUP_TOP | GET_ANEXT | BEFORE_ASYNC_WITH | LOAD_BUILD_CLASS | LOAD_CONST | LOAD_NAME
+            | LOAD_GLOBAL | LOAD_FAST | LOAD_CLOSURE | LOAD_DEREF | LOAD_METHOD | IMPORT_FROM => 1,
+            WITH_EXCEPT_START => 1,
+            DUP_TOP2 => 2,
+            STORE_ATTR => -2,
+            STORE_SUBSCR | POP_EXCEPT | RERAISE => -3,
+            END_ASYNC_FOR => -7,
+            SETUP_WITH if jump => 6,
+            SETUP_WITH => 1,
+            FOR_ITER if jump => -1,
+            FOR_ITER => 1,
+            JUMP_IF_FALSE_OR_POP | JUMP_IF_TRUE_OR_POP if jump => 0,
+            JUMP_IF_FALSE_OR_POP | JUMP_IF_TRUE_OR_POP => -1,
+            UNPACK_SEQUENCE => arg - 1,
+            UNPACK_EX => (arg & 0xFF) + (arg >> 8),
+            BUILD_TUPLE | BUILD_LIST | BUILD_SET | BUILD_TUPLE_UNPACK_WITH_CALL => 1 - arg,
+            BUILD_MAP => 1 - 2 * arg,
+            RAISE_VARARGS | CALL_FUNCTION => -arg,
+            CALL_FUNCTION_KW | CALL_METHOD => -arg - 1,
+            CALL_FUNCTION_EX => -1 - (arg & 1),
+            MAKE_FUNCTION => -1 - Self::make_function_flags(arg),
+            _ => return None,
+        };
+        Some(effect)
+    }
+
+    fn stack_effect_310(op: Opcode310, arg: i32, jump: bool) -> Option<i32> {
+        use Opcode310::*;
+        let effect = match op {
+            NOP | ROT_TWO | ROT_THREE | ROT_FOUR | EXTENDED_ARG => 0,
+            UNARY_POSITIVE | UNARY_NEGATIVE | UNARY_NOT | UNARY_INVERT => 0,
+            GET_ITER | GET_YIELD_FROM_ITER | GET_AITER | GET_AWAITABLE => 0,
+            SETUP_ANNOTATIONS | YIELD_VALUE | POP_BLOCK | DELETE_NAME | DELETE_FAST => 0,
+            LOAD_ATTR | JUMP_FORWARD | JUMP_ABSOLUTE | LIST_TO_TUPLE => 0,
+            POP_TOP | PRINT_EXPR | RETURN_VALUE | IMPORT_STAR | YIELD_FROM | GEN_START => -1,
+            BINARY_MATRIX_MULTIPLY
+            | INPLACE_MATRIX_MULTIPLY
+            | BINARY_POWER
+            | BINARY_MULTIPLY
+            | BINARY_MODULO
+            | BINARY_ADD
+            | BINARY_SUBTRACT
+            | BINARY_SUBSCR
+            | BINARY_FLOOR_DIVIDE
+            | BINARY_TRUE_DIVIDE
+            | INPLACE_FLOOR_DIVIDE
+            | INPLACE_TRUE_DIVIDE
+            | INPLACE_ADD
+            | INPLACE_SUBTRACT
+            | INPLACE_MULTIPLY
+            | INPLACE_MODULO
+            | BINARY_AND
+            | BINARY_XOR
+            | BINARY_OR => -1,
+            STORE_NAME | STORE_GLOBAL | STORE_FAST | STORE_DEREF | COMPARE_OP | IMPORT_NAME => -1,
+            IS_OP | CONTAINS_OP | POP_JUMP_IF_FALSE | POP_JUMP_IF_TRUE | LIST_EXTEND => -1,
+            DUP_TOP | GET_ANEXT | BEFORE_ASYNC_WITH | LOAD_BUILD_CLASS | LOAD_CONST | LOAD_NAME
+            | LOAD_GLOBAL | LOAD_FAST | LOAD_CLOSURE | LOAD_DEREF | LOAD_METHOD | IMPORT_FROM => 1,
+            WITH_EXCEPT_START | LOAD_ASSERTION_ERROR => 1,
+            GET_LEN | MATCH_MAPPING | MATCH_SEQUENCE => 1,
+            DUP_TOP2 | MATCH_KEYS => 2,
+            STORE_ATTR => -2,
+            STORE_SUBSCR | POP_EXCEPT | RERAISE => -3,
+            END_ASYNC_FOR => -7,
+            SETUP_WITH if jump => 6,
+            SETUP_WITH => 1,
+            FOR_ITER if jump => -1,
+            FOR_ITER => 1,
+            JUMP_IF_FALSE_OR_POP | JUMP_IF_TRUE_OR_POP if jump => 0,
+            JUMP_IF_FALSE_OR_POP | JUMP_IF_TRUE_OR_POP => -1,
+            UNPACK_SEQUENCE => arg - 1,
+            UNPACK_EX => (arg & 0xFF) + (arg >> 8),
+            BUILD_TUPLE | BUILD_LIST | BUILD_SET => 1 - arg,
+            BUILD_MAP => 1 - 2 * arg,
+            RAISE_VARARGS | CALL_FUNCTION => -arg,
+            CALL_FUNCTION_KW | CALL_METHOD => -arg - 1,
+            CALL_FUNCTION_EX => -1 - (arg & 1),
+            MAKE_FUNCTION => -1 - Self::make_function_flags(arg),
+            _ => return None,
+        };
+        Some(effect)
+    }
+
+    fn stack_effect_311(op: Opcode311, arg: i32, jump: bool) -> Option<i32> {
+        use Opcode311::*;
+        let effect = match op {
+            CACHE | NOP | EXTENDED_ARG | RESUME | SWAP | KW_NAMES | COPY_FREE_VARS | MAKE_CELL => 0,
+            UNARY_POSITIVE | UNARY_NEGATIVE | UNARY_NOT | UNARY_INVERT => 0,
+            GET_ITER | GET_YIELD_FROM_ITER | GET_AITER | GET_AWAITABLE => 0,
+            SETUP_ANNOTATIONS | YIELD_VALUE | ASYNC_GEN_WRAP | DELETE_NAME | DELETE_FAST => 0,
+            LOAD_ATTR | LIST_TO_TUPLE | CHECK_EXC_MATCH | CHECK_EG_MATCH => 0,
+            JUMP_FORWARD | JUMP_BACKWARD | JUMP_BACKWARD_NO_INTERRUPT => 0,
+            POP_TOP | PRINT_EXPR | RETURN_VALUE | IMPORT_STAR | POP_EXCEPT | RERAISE => -1,
+            BINARY_SUBSCR | BINARY_OP | PREP_RERAISE_STAR | CALL => -1,
+            STORE_NAME | STORE_GLOBAL | STORE_FAST | STORE_DEREF | COMPARE_OP | IMPORT_NAME => -1,
+            IS_OP | CONTAINS_OP | LIST_APPEND | SET_ADD | LIST_EXTEND => -1,
+            POP_JUMP_FORWARD_IF_FALSE
+            | POP_JUMP_FORWARD_IF_TRUE
+            | POP_JUMP_BACKWARD_IF_FALSE
+            | POP_JUMP_BACKWARD_IF_TRUE => -1,
+            PUSH_NULL | GET_ANEXT | BEFORE_ASYNC_WITH | BEFORE_WITH | LOAD_BUILD_CLASS
+            | LOAD_CONST | LOAD_NAME | LOAD_FAST | LOAD_CLOSURE | LOAD_DEREF | LOAD_CLASSDEREF
+            | LOAD_METHOD | IMPORT_FROM | COPY => 1,
+            PUSH_EXC_INFO | WITH_EXCEPT_START | LOAD_ASSERTION_ERROR => 1,
+            GET_LEN | MATCH_MAPPING | MATCH_SEQUENCE | MATCH_KEYS => 1,
+            // `PyCodeGenerator` counts the first sent value here
+            RETURN_GENERATOR => 1,
+            STORE_ATTR | END_ASYNC_FOR | MAP_ADD => -2,
+            STORE_SUBSCR => -3,
+            LOAD_GLOBAL => 1 + (arg & 1),
+            SEND if jump => -1,
+            SEND => 0,
+            FOR_ITER if jump => -1,
+            FOR_ITER => 1,
+            JUMP_IF_FALSE_OR_POP | JUMP_IF_TRUE_OR_POP if jump => 0,
+            JUMP_IF_FALSE_OR_POP | JUMP_IF_TRUE_OR_POP => -1,
+            UNPACK_SEQUENCE => arg - 1,
+            UNPACK_EX => (arg & 0xFF) + (arg >> 8),
+            BUILD_TUPLE | BUILD_LIST | BUILD_SET => 1 - arg,
+            BUILD_MAP => 1 - 2 * arg,
+            RAISE_VARARGS | PRECALL => -arg,
+            CALL_FUNCTION_EX => -2 - (arg & 1),
+            MAKE_FUNCTION => -Self::make_function_flags(arg),
+            _ => return None,
+        };
+        Some(effect)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peephole_309() {
+        use erg_common::Str;
+        let code = CodeObj {
+            code: vec![
+                100, 0, // LOAD_CONST 1
+                100, 1, // LOAD_CONST 2
+                102, 2, // BUILD_TUPLE 2
+                101, 0, // LOAD_NAME x
+                114, 14, // POP_JUMP_IF_FALSE 14
+                100, 2, // LOAD_CONST None
+                1, 0, // POP_TOP
+                113, 18, // JUMP_ABSOLUTE 18
+                1, 0, // POP_TOP (unreachable)
+                83, 0, // RETURN_VALUE
+            ],
+            consts: vec![ValueObj::Nat(1), ValueObj::Nat(2), ValueObj::None],
+            names: vec![Str::ever("x")],
+            stacksize: 3,
+            firstlineno: 1,
+            lnotab: vec![10, 1, 8, 1],
+            ..CodeObj::default()
+        };
+        let optimizer = PeepholeOptimizer { minor: 9 };
+        let code = optimizer.optimize_code(code);
+        assert_eq!(
+            code.code,
+            vec![
+                100, 3, // LOAD_CONST (1, 2)
+                101, 0, // LOAD_NAME x
+                114, 6, // POP_JUMP_IF_FALSE 6
+                83, 0, // RETURN_VALUE
+            ]
+        );
+        assert_eq!(
+            code.consts[3],
+            ValueObj::Tuple([ValueObj::Nat(1), ValueObj::Nat(2)].into())
+        );
+        assert_eq!(code.stacksize, 2);
+        assert_eq!(code.lnotab, vec![6, 2]);
+    }
+}
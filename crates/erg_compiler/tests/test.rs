@@ -1,17 +1,24 @@
 use erg_common::config::ErgConfig;
-use erg_common::error::MultiErrorDisplay;
-use erg_common::io::Output;
+use erg_common::error::{ErrorKind, MultiErrorDisplay};
+use erg_common::io::{Input, Output};
+use erg_common::python_util::opt_which_python;
+use erg_common::serialize::{get_ver_from_magic_num, PycInvalidationMode};
 use erg_common::set;
 use erg_common::spawn::exec_new_thread;
-use erg_common::traits::Runnable;
+use erg_common::traits::{Runnable, Stream};
 
+use erg_compiler::build_hir::HIRBuilder;
 use erg_compiler::context::{Context, ModuleContext};
 use erg_compiler::error::CompileErrors;
 use erg_compiler::lower::ASTLowerer;
+use erg_compiler::module::disk_cache;
+use erg_compiler::ty::disassemble::Disassembly;
+use erg_compiler::ty::value::ValueObj;
+use erg_compiler::{Bundler, Compiler, PackageBuilder};
 
 use erg_compiler::ty::constructors::{
-    array_t, func0, func1, func2, kw, mono, nd_func, nd_proc, or, poly, proc1, subtype_q, ty_tp,
-    type_q, unknown_len_array_mut, unknown_len_array_t, v_enum,
+    and, array_t, func0, func1, func2, kw, mono, nd_func, nd_proc, or, poly, proc1, subtype_q,
+    ty_tp, type_q, unknown_len_array_mut, unknown_len_array_t, v_enum,
 };
 use erg_compiler::ty::Type::*;
 
@@ -79,6 +86,163 @@ fn _test_infer_types() -> Result<(), ()> {
     Ok(())
 }
 
+#[test]
+fn test_disk_cache() -> Result<(), ()> {
+    exec_new_thread(_test_disk_cache, "test_disk_cache")
+}
+
+fn _test_disk_cache() -> Result<(), ()> {
+    let module = load_file("tests/cache.er").map_err(|errs| {
+        errs.write_all_stderr();
+    })?;
+    let decls = disk_cache::gen_decl(&module, "<module>").ok_or(())?;
+    assert_eq!(
+        decls,
+        r#".Norm: TraitType
+.Norm.
+    .norm: (self: .Norm) -> Nat
+.Point: ClassType
+.Point.
+    .new: (_: {.x = Int; .y = Int}) -> .Point
+    .norm: (self: .Point) -> Nat
+    .x: Int
+    .y: Int
+.add: (x: Int, y: Int) -> Int
+.id: |T: Type|(x: T) -> T
+.log!: (x: Obj) => NoneType
+.sub = import "cache_sub"
+.x: {1}
+"#
+    );
+    // the declarations reproduce the same interface
+    let mut cfg = ErgConfig::with_main_path("tests/cache.er".into());
+    cfg.output = Output::Null;
+    let mut builder = HIRBuilder::new(cfg);
+    builder
+        .build(decls.clone(), "declare")
+        .map_err(|artifact| artifact.errors.write_all_stderr())?;
+    let declared = builder.pop_mod_ctx().ok_or(())?;
+    assert_eq!(
+        disk_cache::gen_decl(&declared, "<module>").as_ref(),
+        Some(&decls)
+    );
+    // the header must be able to contain paths with spaces
+    let dir = std::env::temp_dir().join("erg test disk cache");
+    let path = dir.join("cache.er");
+    let dep = dir.join("dep.er");
+    std::fs::create_dir_all(&dir).map_err(|_| ())?;
+    std::fs::write(&dep, ".x = 1").map_err(|_| ())?;
+    disk_cache::store(&path, "src", [dep.clone()], &decls).map_err(|_| ())?;
+    assert_eq!(disk_cache::load(&path, "src"), Some(decls));
+    assert_eq!(disk_cache::load(&path, "modified src"), None);
+    // modifying a dependency invalidates the cache
+    std::fs::write(&dep, ".x = 2").map_err(|_| ())?;
+    assert_eq!(disk_cache::load(&path, "src"), None);
+    Ok(())
+}
+
+#[test]
+fn test_build_package() -> Result<(), ()> {
+    exec_new_thread(_test_build_package, "test_build_package")
+}
+
+fn _test_build_package() -> Result<(), ()> {
+    let root = std::env::temp_dir().join("erg_test_build_package");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).map_err(|_| ())?;
+    let write = |file: &str, src: &str| std::fs::write(root.join(file), src).map_err(|_| ());
+    write("package.er", "name = \"test\"\n")?;
+    write("main.er", "lib = import \"lib\"\nprint! lib.add(1, 2)\n")?;
+    // `app.er` is compiled after `lib.er`, and `lib` is linked into both modules
+    write("app.er", "lib = import \"lib\"\nprint! lib.add(3, 4)\n")?;
+    write("lib.er", ".add x: Int, y: Int = x + y\n")?;
+    let build = || {
+        let cfg = ErgConfig::with_main_path(root.join("main.er"));
+        let mut builder = PackageBuilder::new(cfg).ok_or(())?;
+        builder
+            .build()
+            .map_err(|(errs, _)| errs.write_all_stderr())?;
+        Ok::<_, ()>(builder.out_dir().to_path_buf())
+    };
+    let modified = |pyc: &std::path::Path| {
+        std::fs::metadata(pyc)
+            .and_then(|m| m.modified())
+            .map_err(|_| ())
+    };
+    let out_dir = build()?;
+    let run = |pyc: &str| {
+        let out = std::process::Command::new(opt_which_python().map_err(|_| ())?)
+            .arg(out_dir.join(pyc))
+            .current_dir(&out_dir)
+            .output()
+            .map_err(|_| ())?;
+        Ok::<_, ()>(String::from_utf8_lossy(&out.stdout).to_string())
+    };
+    assert_eq!(run("main.pyc")?, "3\n");
+    assert_eq!(run("app.pyc")?, "7\n");
+    let (main, lib) = (out_dir.join("main.pyc"), out_dir.join("lib.pyc"));
+    let (main_t, lib_t) = (modified(&main)?, modified(&lib)?);
+    // nothing has changed
+    build()?;
+    assert_eq!((modified(&main)?, modified(&lib)?), (main_t, lib_t));
+    // the dependent module is rebuilt too
+    write("lib.er", ".add x: Int, y: Int = x * y\n")?;
+    build()?;
+    assert_ne!(modified(&main)?, main_t);
+    assert_ne!(modified(&lib)?, lib_t);
+    // the output directory cannot be created (it is a file)
+    write("blocked", "")?;
+    let mut cfg = ErgConfig::with_main_path(root.join("main.er"));
+    let blocked = root.join("blocked").to_string_lossy().to_string();
+    cfg.dist_dir = Some(Box::leak(blocked.into_boxed_str()));
+    let mut builder = PackageBuilder::new(cfg).ok_or(())?;
+    let (errs, _) = builder.build().err().ok_or(())?;
+    assert!(!errs.is_empty());
+    assert!(errs.iter().all(|err| err.core.kind == ErrorKind::IoError));
+    Ok(())
+}
+
+#[test]
+fn test_bundle() -> Result<(), ()> {
+    exec_new_thread(_test_bundle, "test_bundle")
+}
+
+fn _test_bundle() -> Result<(), ()> {
+    let root = std::env::temp_dir().join("erg_test_bundle");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).map_err(|_| ())?;
+    let write = |file: &str, src: &str| std::fs::write(root.join(file), src).map_err(|_| ());
+    write(
+        "main.er",
+        "lib = import \"lib\"\nprint! lib.add(1, 2), [1, 2] + [3]\n",
+    )?;
+    write("lib.er", ".add x: Int, y: Int = x + y\n")?;
+    let bundle = || {
+        let cfg = ErgConfig::with_main_path(root.join("main.er"));
+        let mut bundler = Bundler::new(cfg).ok_or(())?;
+        bundler
+            .bundle()
+            .map_err(|(errs, _)| errs.write_all_stderr())?;
+        std::fs::read(bundler.out_path()).map_err(|_| ())
+    };
+    let pyz = bundle()?;
+    assert_eq!(pyz, bundle()?);
+    let out = std::process::Command::new(opt_which_python().map_err(|_| ())?)
+        .arg(root.join("main.pyz"))
+        .current_dir(std::env::temp_dir())
+        .output()
+        .map_err(|_| ())?;
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "3 [1, 2, 3]\n");
+    // the output directory does not exist
+    let mut cfg = ErgConfig::with_main_path(root.join("main.er"));
+    let missing = root.join("missing").to_string_lossy().to_string();
+    cfg.dist_dir = Some(Box::leak(missing.into_boxed_str()));
+    let mut bundler = Bundler::new(cfg).ok_or(())?;
+    let (errs, _) = bundler.bundle().err().ok_or(())?;
+    assert!(errs.iter().all(|err| err.core.kind == ErrorKind::IoError));
+    Ok(())
+}
+
 #[test]
 fn test_refinement_subtyping() -> Result<(), ()> {
     let context = Context::default_with_name("<module>");
@@ -93,6 +257,185 @@ fn test_quant_subtyping() -> Result<(), ()> {
     Ok(())
 }
 
+#[test]
+fn test_reproducible_pyc() -> Result<(), ()> {
+    exec_new_thread(_test_reproducible_pyc, "test_reproducible_pyc")
+}
+
+fn _test_reproducible_pyc() -> Result<(), ()> {
+    let src = "C = Class { x = Int; y = Str; z = Bool }\nE = Enum { .A = Int; .B = Str; .C = C }\nf x = [x, 1]\nc = C.new { x = 1; y = \"a\"; z = True }\nprint! f(2), c, E.A.new(1)";
+    let compile = |mode| {
+        let mut cfg = ErgConfig::default();
+        cfg.py_magic_num = Some(3495); // Python 3.11
+        cfg.output = Output::Null;
+        let mut compiler = Compiler::new(cfg);
+        let arti = compiler.compile(src.to_string(), "exec").map_err(|arti| {
+            arti.errors.write_all_stderr();
+        })?;
+        Ok::<_, ()>(arti.object.into_bytecode_with_mode(Some(3495), mode, src))
+    };
+    let checked = compile(PycInvalidationMode::CheckedHash)?;
+    assert_eq!(checked, compile(PycInvalidationMode::CheckedHash)?);
+    assert_eq!(&checked[4..8], &[3, 0, 0, 0]);
+    let unchecked = compile(PycInvalidationMode::UncheckedHash)?;
+    assert_eq!(&unchecked[4..8], &[1, 0, 0, 0]);
+    assert_eq!(&checked[8..], &unchecked[8..]);
+    Ok(())
+}
+
+#[test]
+fn test_disassemble() -> Result<(), ()> {
+    exec_new_thread(_test_disassemble, "test_disassemble")
+}
+
+fn _test_disassemble() -> Result<(), ()> {
+    let src = "f x = if x > 0:\n    do 1\n    do 2\nprint! f 1";
+    let mut cfg = ErgConfig::default();
+    cfg.py_magic_num = Some(3495); // Python 3.11
+    cfg.output = Output::Null;
+    let mut compiler = Compiler::new(cfg);
+    let arti = compiler.compile(src.to_string(), "exec").map_err(|arti| {
+        arti.errors.write_all_stderr();
+    })?;
+    let dis = Disassembly::new(&arti.object, get_ver_from_magic_num(3495));
+    assert_eq!(dis.instrs[0].opname, "RESUME");
+    // CACHE entries are skipped
+    assert!(dis.instrs.iter().all(|instr| instr.opname != "CACHE"));
+    let f = dis.children.iter().find(|c| &c.name[..] == "f").unwrap();
+    let jump = f.instrs.iter().find(|i| i.jump_target.is_some()).unwrap();
+    let target = jump.jump_target.unwrap();
+    assert_eq!(jump.argrepr, format!("to {target}"));
+    assert!(f
+        .instrs
+        .iter()
+        .any(|i| i.offset == target && i.is_jump_target));
+    assert!(f
+        .instrs
+        .iter()
+        .any(|i| i.opname == "LOAD_CONST" && i.argrepr == "2"));
+    assert!(f.to_string().contains(&format!(">> {target:>4}")));
+    assert!(dis.to_json().starts_with("{\"name\":\"<module>\""));
+    Ok(())
+}
+
+#[test]
+fn test_peephole() -> Result<(), ()> {
+    exec_new_thread(_test_peephole, "test_peephole")
+}
+
+fn _test_peephole() -> Result<(), ()> {
+    let src = "fib n: Int =\n    if n <= 1, do:\n        fib.return n\n    fib(n - 1) + fib(n - 2)\nprint! fib 10";
+    let compile = |opt_level| {
+        let mut cfg = ErgConfig::default();
+        cfg.input = Input::str(src.to_string());
+        cfg.py_magic_num = Some(3495); // Python 3.11
+        cfg.opt_level = opt_level;
+        cfg.output = Output::Null;
+        let mut compiler = Compiler::new(cfg);
+        let arti = compiler.compile(src.to_string(), "exec").map_err(|arti| {
+            arti.errors.write_all_stderr();
+        })?;
+        let fib = arti
+            .object
+            .consts
+            .into_iter()
+            .find_map(|c| match c {
+                ValueObj::Code(c) if &c.name[..] == "fib" => Some(*c),
+                _ => None,
+            })
+            .unwrap();
+        Ok::<_, ()>(fib)
+    };
+    let (orig, opt) = (compile(1)?, compile(2)?);
+    assert!(opt.code.len() < orig.code.len());
+    assert!(opt.stacksize <= orig.stacksize);
+    let dis = Disassembly::new(&opt, get_ver_from_magic_num(3495));
+    assert!(dis
+        .instrs
+        .windows(2)
+        .all(|w| !(w[0].opname == "LOAD_CONST" && w[1].opname == "POP_TOP")));
+    // no jump lands on an unconditional jump
+    for target in dis.instrs.iter().filter_map(|i| i.jump_target) {
+        let instr = dis.instrs.iter().find(|i| i.offset == target).unwrap();
+        assert!(!instr.opname.starts_with("JUMP_"));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_check_without_effects() -> Result<(), ()> {
+    exec_new_thread(_test_check_without_effects, "test_check_without_effects")
+}
+
+fn _test_check_without_effects() -> Result<(), ()> {
+    let mut cfg = ErgConfig::default();
+    cfg.output = Output::Null;
+    let mut compiler = Compiler::new(cfg);
+    compiler
+        .eval_compile("x = ![1]".to_string(), "eval")
+        .map_err(|arti| {
+            arti.errors.write_all_stderr();
+        })?;
+    let before = compiler.infer_type("x".to_string()).map_err(|_| ())?;
+    // `:type x.push! 1`
+    compiler
+        .infer_type("x.push! 1".to_string())
+        .map_err(|_| ())?;
+    assert_eq!(
+        compiler.infer_type("x".to_string()).map_err(|_| ())?,
+        before
+    );
+    compiler
+        .pre_check("x.push! 2".to_string())
+        .map_err(|_| ())?;
+    assert_eq!(
+        compiler.infer_type("x".to_string()).map_err(|_| ())?,
+        before
+    );
+    compiler
+        .eval_compile("x.push! 2".to_string(), "eval")
+        .map_err(|arti| {
+            arti.errors.write_all_stderr();
+        })?;
+    let after = compiler.infer_type("x".to_string()).map_err(|_| ())?;
+    assert_ne!(after, before);
+    assert_eq!(after.typarams()[1].to_string(), "2");
+    Ok(())
+}
+
+#[test]
+fn test_overloaded_type() -> Result<(), ()> {
+    exec_new_thread(_test_overloaded_type, "test_overloaded_type")
+}
+
+fn _test_overloaded_type() -> Result<(), ()> {
+    let mut cfg = ErgConfig::default();
+    cfg.output = Output::Null;
+    let mut compiler = Compiler::new(cfg);
+    compiler
+        .eval_compile("b = pyimport \"builtins\"".to_string(), "eval")
+        .map_err(|arti| {
+            arti.errors.write_all_stderr();
+        })?;
+    let abs_t = compiler.infer_type("b.abs".to_string()).map_err(|_| ())?;
+    assert!(abs_t.is_overloaded());
+    assert_eq!(
+        abs_t.to_string(),
+        "Overloaded((x: Int) -> Nat, (x: Complex) -> Float)"
+    );
+    // overload sets do not change how intersection and union types are displayed
+    assert_eq!(or(Int, Str).to_string(), "Int or Str");
+    assert_eq!(and(Int, Str).to_string(), "Int and Str");
+    assert_eq!(
+        compiler
+            .infer_type("b.abs 1".to_string())
+            .map_err(|_| ())?
+            .to_string(),
+        "Nat"
+    );
+    Ok(())
+}
+
 #[test]
 fn test_instantiation_and_generalization() -> Result<(), ()> {
     let context = Context::default_with_name("<module>");
@@ -1,16 +1,20 @@
 use std::fs::File;
 use std::io::Write;
+use std::path::PathBuf;
 
 use erg_common::config::{ErgConfig, TranspileTarget};
 use erg_common::dict;
 use erg_common::dict::Dict as HashMap;
 use erg_common::error::MultiErrorDisplay;
+use erg_common::io::Input;
 use erg_common::log;
 use erg_common::set::Set as HashSet;
+use erg_common::style::Highlight;
 use erg_common::traits::{ExitStatus, Locational, Runnable, Stream};
 use erg_common::Str;
 
-use erg_parser::ast::{ParamPattern, TypeSpec, VarName};
+use erg_parser::ast::{OperationKind, ParamPattern, TypeSpec, VarName};
+use erg_parser::highlight::highlight;
 use erg_parser::token::TokenKind;
 
 use crate::artifact::{
@@ -28,7 +32,7 @@ use crate::hir::{
 use crate::link_hir::HIRLinker;
 use crate::module::SharedCompilerResource;
 use crate::ty::typaram::OpKind;
-use crate::ty::value::ValueObj;
+use crate::ty::value::{EnumTypeObj, GenTypeObj, TypeObj, ValueObj};
 use crate::ty::{Field, Type};
 use crate::varinfo::{AbsLocation, VarInfo};
 
@@ -192,10 +196,12 @@ impl Runnable for Transpiler {
         let mut path = self.cfg.dump_path();
         let src = self.cfg.input.read();
         let artifact = self.transpile(src, "exec").map_err(|eart| {
-            eart.warns.write_all_stderr();
+            eart.warns.write_all_stderr_with(&self.cfg().error_format);
             eart.errors
         })?;
-        artifact.warns.write_all_stderr();
+        artifact
+            .warns
+            .write_all_stderr_with(&self.cfg().error_format);
         path.set_extension(artifact.object.extension());
         let mut f = File::create(path).unwrap();
         f.write_all(artifact.object.code().as_bytes()).unwrap();
@@ -204,12 +210,45 @@ impl Runnable for Transpiler {
 
     fn eval(&mut self, src: String) -> Result<String, CompileErrors> {
         let artifact = self.transpile(src, "eval").map_err(|eart| {
-            eart.warns.write_all_stderr();
+            eart.warns.write_all_stderr_with(&self.cfg().error_format);
             eart.errors
         })?;
-        artifact.warns.write_all_stderr();
+        artifact
+            .warns
+            .write_all_stderr_with(&self.cfg().error_format);
         Ok(artifact.object.into_code())
     }
+
+    fn type_of(&mut self, src: String) -> Option<Result<String, Self::Errs>> {
+        Some(self.builder.infer_type(src).map(|t| t.to_string()))
+    }
+
+    fn doc_of(&self, name: &str) -> Option<String> {
+        self.signature_and_doc(name)
+    }
+
+    fn dir_of(&self, receiver: Option<&str>) -> Option<Vec<String>> {
+        self.dir_lines(receiver)
+    }
+
+    fn complete(&self, word: &str) -> Vec<String> {
+        self.completion_candidates(word)
+    }
+
+    fn highlight(&self, line: &str, cursor: usize) -> Vec<Highlight> {
+        highlight(line, cursor)
+    }
+
+    fn pre_check(&mut self, src: String) -> Result<(), Self::Errs> {
+        self.builder.pre_check(src)
+    }
+
+    fn eval_file(&mut self, path: PathBuf, src: String) -> Result<String, Self::Errs> {
+        let input = self.builder.replace_input(Input::file(path));
+        let res = self.eval(src);
+        self.builder.replace_input(input);
+        res
+    }
 }
 
 impl ContextProvider for Transpiler {
@@ -321,6 +360,10 @@ pub struct PyScriptGenerator {
     builtin_control_loaded: bool,
     convertors_loaded: bool,
     prelude: String,
+    /// the level of the class body being transpiled (methods are not globals)
+    class_level: Option<usize>,
+    /// qualified type name => Python class name
+    class_names: HashMap<Str, String>,
 }
 
 impl PyScriptGenerator {
@@ -337,6 +380,8 @@ impl PyScriptGenerator {
             builtin_control_loaded: false,
             convertors_loaded: false,
             prelude: String::new(),
+            class_level: None,
+            class_names: HashMap::new(),
         }
     }
 
@@ -657,6 +702,13 @@ impl PyScriptGenerator {
     }
 
     fn transpile_call(&mut self, mut call: Call) -> String {
+        if call.additional_operation() == Some(OperationKind::Yield) {
+            let value = call
+                .args
+                .try_remove(0)
+                .map_or("None".to_string(), |arg| self.transpile_expr(arg));
+            return format!("(yield ({value}))");
+        }
         match call.obj.local_name() {
             Some("assert") => {
                 let mut code = format!("assert {}", self.transpile_expr(call.args.remove(0)));
@@ -666,6 +718,7 @@ impl PyScriptGenerator {
                 code
             }
             Some("not") => format!("(not ({}))", self.transpile_expr(call.args.remove(0))),
+            Some("await!") => format!("(await ({}))", self.transpile_expr(call.args.remove(0))),
             Some("if" | "if!") => self.transpile_if(call),
             Some("for" | "for!") => {
                 let mut code = "for ".to_string();
@@ -797,6 +850,10 @@ impl PyScriptGenerator {
                                     .collect::<Vec<_>>()
                                     .join(" | ");
                                 code += &format!("case ({patterns}) as {param}:\n");
+                            } else if let Some(class) =
+                                self.class_names.get(&target.vi.t.qual_name())
+                            {
+                                code += &format!("case {class}() as {param}:\n");
                             } else {
                                 todo!("{other}")
                             }
@@ -900,7 +957,9 @@ impl PyScriptGenerator {
         let mut code = String::new();
         for non_default in params.non_defaults {
             match non_default.raw.pat {
-                ParamPattern::VarName(param) => {
+                ParamPattern::VarName(param)
+                | ParamPattern::Ref(param)
+                | ParamPattern::RefMut(param) => {
                     code += &format!("{}__,", replace_non_symbolic(&param.into_token().content));
                 }
                 ParamPattern::Discard(_) => {
@@ -974,7 +1033,7 @@ impl PyScriptGenerator {
     // TODO: trait definition
     fn transpile_def(&mut self, mut def: Def) -> String {
         // HACK: allow reference to local variables in tmp functions
-        let mut code = if self.level == 0 {
+        let mut code = if self.level == 0 || self.class_level == Some(self.level) {
             "".to_string()
         } else {
             let name = Self::transpile_ident(def.sig.ident().clone());
@@ -1002,6 +1061,9 @@ impl PyScriptGenerator {
                 }
             }
             Signature::Subr(subr) => {
+                if subr.is_async() {
+                    code += "async ";
+                }
                 code += &format!(
                     "def {}({}):\n",
                     Self::transpile_ident(subr.ident),
@@ -1015,12 +1077,35 @@ impl PyScriptGenerator {
 
     fn transpile_classdef(&mut self, classdef: ClassDef) -> String {
         let class_name = Self::transpile_ident(classdef.sig.into_ident());
+        if let GenTypeObj::Enum(enum_) = &classdef.obj {
+            return self.transpile_enumdef(class_name, enum_, classdef.methods);
+        }
+        self.class_names
+            .insert(classdef.obj.typ().qual_name(), class_name.clone());
         let mut code = format!("class {class_name}():\n");
+        code +=
+            &self.transpile_init_method(classdef.__new__.non_default_params().unwrap()[0].typ());
+        if classdef.need_to_gen_new {
+            code += &"    ".repeat(self.level + 1);
+            code += &format!("def new(x): return {class_name}.__call__(x)\n");
+        }
+        code += &self.transpile_methods(classdef.methods);
+        code
+    }
+
+    fn transpile_methods(&mut self, methods: Block) -> String {
+        let class_level = self.class_level.replace(self.level + 1);
+        let code = self.transpile_block(methods, Discard);
+        self.class_level = class_level;
+        code
+    }
+
+    fn transpile_init_method(&self, payload_t: &Type) -> String {
         let mut init_method = format!(
             "{}def __init__(self, param__):\n",
             "    ".repeat(self.level + 1)
         );
-        match classdef.__new__.non_default_params().unwrap()[0].typ() {
+        match payload_t {
             Type::Record(rec) => {
                 for field in rec.keys() {
                     let vis = if field.vis.is_private() { "__" } else { "" };
@@ -1034,12 +1119,62 @@ impl PyScriptGenerator {
             }
             other => todo!("{other}"),
         }
-        code += &init_method;
-        if classdef.need_to_gen_new {
-            code += &"    ".repeat(self.level + 1);
-            code += &format!("def new(x): return {class_name}.__call__(x)\n");
+        init_method
+    }
+
+    /// ```python
+    /// class E():
+    ///     ... # methods
+    /// class E__A__(E):
+    ///     def __init__(self, param__):
+    ///         self._0 = param__
+    ///     def new(x): return E__A__.__call__(x)
+    /// E.A = E__A__
+    /// ```
+    fn transpile_enumdef(
+        &mut self,
+        enum_name: String,
+        enum_: &EnumTypeObj,
+        methods: Block,
+    ) -> String {
+        let TypeObj::Builtin {
+            t: Type::Record(variants),
+            ..
+        } = enum_.variants.as_ref()
+        else {
+            unreachable!()
+        };
+        self.class_names
+            .insert(enum_.t.qual_name(), enum_name.clone());
+        // variants may be referenced in the methods
+        for field in variants.keys() {
+            self.class_names.insert(
+                format!("{}.{}", enum_.t.qual_name(), field.symbol).into(),
+                format!("{enum_name}__{}__", field.symbol),
+            );
+        }
+        let indent = "    ".repeat(self.level);
+        let mut code = format!("class {enum_name}():\n");
+        if methods.is_empty() {
+            code += &format!("{indent}    pass\n");
+        } else {
+            code += &self.transpile_methods(methods);
+        }
+        for (field, payload) in variants.iter() {
+            let variant_name = format!("{enum_name}__{}__", field.symbol);
+            code += &format!("{indent}class {variant_name}({enum_name}):\n");
+            if let Type::Record(_) = payload {
+                code += &self.transpile_init_method(payload);
+            } else {
+                code += &format!(
+                    "{indent}    def __init__(self, param__):\n{indent}        self._0 = param__\n"
+                );
+            }
+            code += &format!(
+                "{indent}    def new(x): return {variant_name}.__call__(x)\n{indent}{enum_name}.{} = {variant_name}\n",
+                field.symbol
+            );
         }
-        code += &self.transpile_block(classdef.methods, Discard);
         code
     }
 
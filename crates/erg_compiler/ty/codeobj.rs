@@ -9,18 +9,17 @@ use erg_common::config::ErgConfig;
 use erg_common::impl_display_from_debug;
 #[allow(unused_imports)]
 use erg_common::log;
-use erg_common::opcode::CommonOpcode;
-use erg_common::opcode308::Opcode308;
 use erg_common::opcode309::Opcode309;
 use erg_common::opcode310::Opcode310;
-use erg_common::opcode311::{BinOpCode, Opcode311};
+use erg_common::opcode311::Opcode311;
 use erg_common::python_util::{env_magic_number, exec_pyc_code, PythonVersion};
 use erg_common::serialize::*;
 use erg_common::Str;
 
 use super::deserialize::{DeserializeResult, Deserializer};
+use super::disassemble::Disassembly;
 use super::value::ValueObj;
-use super::{HasType, Type, TypePair};
+use super::{HasType, Type};
 
 pub fn consts_into_bytes(consts: Vec<ValueObj>, python_ver: PythonVersion) -> Vec<u8> {
     let mut tuple = vec![];
@@ -308,9 +307,9 @@ impl CodeObj {
         f.read_to_end(v)?;
         let magic_num = get_magic_num_from_bytes(&Deserializer::consume::<4>(v));
         let python_ver = get_ver_from_magic_num(magic_num);
-        let _padding = Deserializer::deserialize_u32(v);
-        let _timestamp = Deserializer::deserialize_u32(v);
-        let _padding = Deserializer::deserialize_u32(v);
+        let _flags = Deserializer::deserialize_u32(v);
+        // timestamp + padding, or the hash of the source
+        let _validation = Deserializer::consume::<8>(v);
         let code = Self::from_bytes(v, python_ver)?;
         Ok((code, python_ver))
     }
@@ -441,21 +440,37 @@ impl CodeObj {
         self,
         path: P,
         py_magic_num: Option<u32>,
+    ) -> std::io::Result<()> {
+        self.dump_as_pyc_with_mode(path, py_magic_num, PycInvalidationMode::Timestamp, "")
+    }
+
+    /// `src` is the source of the code, used by the hash-based modes.
+    pub fn dump_as_pyc_with_mode<P: AsRef<Path>>(
+        self,
+        path: P,
+        py_magic_num: Option<u32>,
+        mode: PycInvalidationMode,
+        src: &str,
     ) -> std::io::Result<()> {
         let mut file = File::create(path)?;
-        let bytes = self.into_bytecode(py_magic_num);
+        let bytes = self.into_bytecode_with_mode(py_magic_num, mode, src);
         file.write_all(&bytes[..])?;
         Ok(())
     }
 
     pub fn into_bytecode(self, py_magic_num: Option<u32>) -> Vec<u8> {
-        let mut bytes = Vec::with_capacity(16);
+        self.into_bytecode_with_mode(py_magic_num, PycInvalidationMode::Timestamp, "")
+    }
+
+    pub fn into_bytecode_with_mode(
+        self,
+        py_magic_num: Option<u32>,
+        mode: PycInvalidationMode,
+        src: &str,
+    ) -> Vec<u8> {
         let py_magic_num = py_magic_num.unwrap_or_else(env_magic_number);
         let python_ver = get_ver_from_magic_num(py_magic_num);
-        bytes.append(&mut get_magic_num_bytes(py_magic_num).to_vec());
-        bytes.append(&mut vec![0; 4]); // padding
-        bytes.append(&mut get_timestamp_bytes().to_vec());
-        bytes.append(&mut vec![0; 4]); // padding
+        let mut bytes = get_header_bytes(py_magic_num, mode, src.as_bytes());
         bytes.append(&mut self.into_bytes(python_ver));
         bytes
     }
@@ -539,304 +554,41 @@ impl CodeObj {
         attrs
     }
 
-    fn instr_info(&self, py_ver: Option<PythonVersion>) -> String {
-        let mut lnotab_iter = self.lnotab.iter();
-        let mut code_iter = self.code.iter();
-        let mut idx = 0usize;
-        let mut line_offset = 0usize;
-        let mut lineno = self.firstlineno;
-        let mut sdelta = lnotab_iter.next().unwrap_or(&0);
-        let mut ldelta = lnotab_iter.next().unwrap_or(&0);
-        let mut instrs = "".to_string();
-        writeln!(instrs, "lnotab: {:?}", self.lnotab).unwrap();
-        if *sdelta != 0 {
-            writeln!(instrs, "{lineno}:").unwrap();
-        }
-        let mut extended_arg = vec![];
-        loop {
-            if *sdelta as usize == line_offset {
-                line_offset = 0;
-                lineno += *ldelta as u32;
-                writeln!(instrs, "{lineno}:").unwrap();
-                sdelta = lnotab_iter.next().unwrap_or(&0);
-                ldelta = lnotab_iter.next().unwrap_or(&0);
-            }
-            if let (Some(op), Some(arg)) = (code_iter.next(), code_iter.next()) {
-                let pushed = if CommonOpcode::try_from(*op) == Ok(CommonOpcode::EXTENDED_ARG) {
-                    extended_arg.push(*arg);
-                    true
-                } else {
-                    false
-                };
-                let arg = if extended_arg.is_empty() || pushed {
-                    *arg as usize
-                } else {
-                    let arg = match extended_arg.len() {
-                        1 => u16::from_be_bytes([extended_arg[0], *arg]) as usize,
-                        3 => u32::from_be_bytes([
-                            extended_arg[0],
-                            extended_arg[1],
-                            extended_arg[2],
-                            *arg,
-                        ]) as usize,
-                        n => unreachable!("{n}"),
-                    };
-                    extended_arg.clear();
-                    arg
-                };
-                match py_ver.and_then(|pv| pv.minor) {
-                    Some(7 | 8) => self.read_instr_308(op, arg, idx, &mut instrs),
-                    Some(9) => self.read_instr_309(op, arg, idx, &mut instrs),
-                    Some(10) => self.read_instr_310(op, arg, idx, &mut instrs),
-                    Some(11) => self.read_instr_311(op, arg, idx, &mut instrs),
-                    _ => {}
-                }
-                idx += 2;
-                line_offset += 2;
-            } else {
-                break;
-            }
-        }
-        instrs
-    }
-
-    fn read_instr_308(&self, op: &u8, arg: usize, idx: usize, instrs: &mut String) {
-        let op308 = Opcode308::from(*op);
-        let s_op = op308.to_string();
-        write!(instrs, "{idx:>15} {s_op:<25}").unwrap();
-        if let Ok(op) = CommonOpcode::try_from(*op) {
-            self.dump_additional_info(op, arg, idx, instrs);
-        }
-        match op308 {
-            Opcode308::STORE_DEREF | Opcode308::LOAD_DEREF => {
-                write!(instrs, "{arg} ({})", self.freevars.get(arg).unwrap()).unwrap();
-            }
-            Opcode308::LOAD_CLOSURE => {
-                write!(instrs, "{arg} ({})", self.cellvars.get(arg).unwrap()).unwrap();
-            }
-            Opcode308::JUMP_ABSOLUTE => {
-                write!(instrs, "{arg} (to {})", arg).unwrap();
-            }
-            Opcode308::JUMP_FORWARD => {
-                write!(instrs, "{arg} (to {})", idx + arg + 2).unwrap();
-            }
-            // REVIEW: *2?
-            Opcode308::POP_JUMP_IF_FALSE | Opcode308::POP_JUMP_IF_TRUE => {
-                write!(instrs, "{arg} (to {})", arg).unwrap();
-            }
-            Opcode308::BINARY_ADD
-            | Opcode308::BINARY_SUBTRACT
-            | Opcode308::BINARY_MULTIPLY
-            | Opcode308::BINARY_TRUE_DIVIDE => {
-                write!(instrs, "{arg} ({:?})", TypePair::from(arg as u8)).unwrap();
-            }
-            Opcode308::CALL_FUNCTION
-            | Opcode308::CALL_FUNCTION_EX
-            | Opcode308::CALL_FUNCTION_KW
-            | Opcode308::CALL_METHOD => {
-                write!(instrs, "{arg}").unwrap();
-            }
-            _ => {}
-        }
-        instrs.push('\n');
-    }
-
-    fn read_instr_309(&self, op: &u8, arg: usize, idx: usize, instrs: &mut String) {
-        let op309 = Opcode309::from(*op);
-        let s_op = op309.to_string();
-        write!(instrs, "{idx:>15} {s_op:<25}").unwrap();
-        if let Ok(op) = CommonOpcode::try_from(*op) {
-            self.dump_additional_info(op, arg, idx, instrs);
-        }
-        match op309 {
-            Opcode309::STORE_DEREF | Opcode309::LOAD_DEREF => {
-                write!(instrs, "{arg} ({})", self.freevars.get(arg).unwrap()).unwrap();
-            }
-            Opcode309::LOAD_CLOSURE => {
-                write!(instrs, "{arg} ({})", self.cellvars.get(arg).unwrap()).unwrap();
-            }
-            Opcode309::JUMP_ABSOLUTE => {
-                write!(instrs, "{arg} (to {})", arg).unwrap();
-            }
-            Opcode309::JUMP_FORWARD => {
-                write!(instrs, "{arg} (to {})", idx + arg + 2).unwrap();
-            }
-            // REVIEW: *2?
-            Opcode309::POP_JUMP_IF_FALSE | Opcode309::POP_JUMP_IF_TRUE => {
-                write!(instrs, "{arg} (to {})", arg).unwrap();
-            }
-            Opcode309::BINARY_ADD
-            | Opcode309::BINARY_SUBTRACT
-            | Opcode309::BINARY_MULTIPLY
-            | Opcode309::BINARY_TRUE_DIVIDE => {
-                write!(instrs, "{arg} ({:?})", TypePair::from(arg as u8)).unwrap();
-            }
-            Opcode309::CALL_FUNCTION
-            | Opcode309::CALL_FUNCTION_EX
-            | Opcode309::CALL_FUNCTION_KW
-            | Opcode309::CALL_METHOD => {
-                write!(instrs, "{arg}").unwrap();
-            }
-            _ => {}
-        }
-        instrs.push('\n');
-    }
-
-    fn read_instr_310(&self, op: &u8, arg: usize, idx: usize, instrs: &mut String) {
-        let op310 = Opcode310::from(*op);
-        let s_op = op310.to_string();
-        write!(instrs, "{idx:>15} {s_op:<25}").unwrap();
-        if let Ok(op) = CommonOpcode::try_from(*op) {
-            self.dump_additional_info(op, arg, idx, instrs);
-        }
-        match op310 {
-            Opcode310::STORE_DEREF | Opcode310::LOAD_DEREF => {
-                write!(
-                    instrs,
-                    "{arg} ({})",
-                    self.freevars
-                        .get(arg)
-                        .or_else(|| self.varnames.get(arg))
-                        .unwrap()
-                )
-                .unwrap();
-            }
-            Opcode310::LOAD_CLOSURE => {
-                write!(instrs, "{arg} ({})", self.cellvars.get(arg).unwrap()).unwrap();
-            }
-            Opcode310::JUMP_ABSOLUTE => {
-                write!(instrs, "{arg} (to {})", arg * 2).unwrap();
-            }
-            Opcode310::JUMP_FORWARD => {
-                write!(instrs, "{arg} (to {})", idx + arg * 2 + 2).unwrap();
-            }
-            Opcode310::POP_JUMP_IF_FALSE | Opcode310::POP_JUMP_IF_TRUE => {
-                write!(instrs, "{arg} (to {})", arg * 2).unwrap();
-            }
-            Opcode310::BINARY_ADD
-            | Opcode310::BINARY_SUBTRACT
-            | Opcode310::BINARY_MULTIPLY
-            | Opcode310::BINARY_TRUE_DIVIDE => {
-                write!(instrs, "{arg} ({:?})", TypePair::from(arg as u8)).unwrap();
-            }
-            Opcode310::CALL_FUNCTION
-            | Opcode310::CALL_FUNCTION_EX
-            | Opcode310::CALL_FUNCTION_KW
-            | Opcode310::CALL_METHOD => {
-                write!(instrs, "{arg}").unwrap();
-            }
-            Opcode310::SETUP_WITH => {
-                write!(instrs, "{arg} (to {})", idx + arg * 2 + 2).unwrap();
-            }
-            _ => {}
-        }
-        instrs.push('\n');
+    /// The names indexed by `LOAD_FAST`, `LOAD_DEREF`, etc. in 3.11 (`co_localsplusnames`).
+    /// The order is the same as `dump_locals`.
+    pub fn localsplus_names(&self) -> Vec<Str> {
+        let varnames = self
+            .varnames
+            .iter()
+            .filter(|n| !self.freevars.contains(n) && !self.cellvars.contains(n));
+        varnames
+            .chain(self.freevars.iter())
+            .chain(self.cellvars.iter())
+            .cloned()
+            .collect()
     }
 
-    fn read_instr_311(&self, op: &u8, arg: usize, idx: usize, instrs: &mut String) {
-        let op311 = Opcode311::from(*op);
-        let s_op = op311.to_string();
-        write!(instrs, "{idx:>15} {s_op:<26}").unwrap();
-        if let Ok(op) = CommonOpcode::try_from(*op) {
-            self.dump_additional_info(op, arg, idx, instrs);
-        }
-        match op311 {
-            Opcode311::STORE_DEREF | Opcode311::LOAD_DEREF => {
-                write!(instrs, "{arg} ({})", self.varnames.get(arg).unwrap()).unwrap();
-            }
-            Opcode311::MAKE_CELL | Opcode311::LOAD_CLOSURE => {
-                write!(instrs, "{arg} ({})", self.cellvars.get(arg).unwrap()).unwrap();
-            }
-            Opcode311::POP_JUMP_FORWARD_IF_FALSE | Opcode311::POP_JUMP_FORWARD_IF_TRUE => {
-                write!(instrs, "{arg} (to {})", idx + arg * 2 + 2).unwrap();
-            }
-            Opcode311::POP_JUMP_BACKWARD_IF_FALSE | Opcode311::POP_JUMP_BACKWARD_IF_TRUE => {
-                write!(instrs, "{arg} (to {})", idx - arg * 2 + 2).unwrap();
-            }
-            Opcode311::JUMP_FORWARD => {
-                write!(instrs, "{arg} (to {})", idx + arg * 2 + 2).unwrap();
-            }
-            Opcode311::JUMP_BACKWARD => {
-                write!(instrs, "{arg} (to {})", idx - arg * 2 + 2).unwrap();
-            }
-            Opcode311::PRECALL
-            | Opcode311::CALL
-            | Opcode311::COPY
-            | Opcode311::SWAP
-            | Opcode311::COPY_FREE_VARS => {
-                write!(instrs, "{arg}").unwrap();
-            }
-            Opcode311::KW_NAMES => {
-                write!(instrs, "{arg} ({})", self.consts.get(arg).unwrap()).unwrap();
-            }
-            Opcode311::BINARY_OP => {
-                write!(instrs, "{arg} ({:?})", BinOpCode::from(arg as u8)).unwrap();
-            }
-            _ => {}
-        }
-        instrs.push('\n');
-    }
-
-    fn dump_additional_info(&self, op: CommonOpcode, arg: usize, idx: usize, instrs: &mut String) {
-        match op {
-            CommonOpcode::COMPARE_OP => {
-                let op = match arg {
-                    0 => "<",
-                    1 => "<=",
-                    2 => "==",
-                    3 => "!=",
-                    4 => ">",
-                    5 => ">=",
-                    _ => "?",
-                };
-                write!(instrs, "{arg} ({op})").unwrap();
-            }
-            CommonOpcode::STORE_NAME
-            | CommonOpcode::LOAD_NAME
-            | CommonOpcode::STORE_GLOBAL
-            | CommonOpcode::LOAD_GLOBAL
-            | CommonOpcode::STORE_ATTR
-            | CommonOpcode::LOAD_ATTR
-            | CommonOpcode::LOAD_METHOD
-            | CommonOpcode::IMPORT_NAME
-            | CommonOpcode::IMPORT_FROM => {
-                write!(instrs, "{arg} ({})", self.names.get(arg).unwrap()).unwrap();
-            }
-            CommonOpcode::STORE_FAST | CommonOpcode::LOAD_FAST => {
-                write!(instrs, "{arg} ({})", self.varnames.get(arg).unwrap()).unwrap();
-            }
-            CommonOpcode::LOAD_CONST => {
-                write!(instrs, "{arg} ({})", self.consts.get(arg).unwrap()).unwrap();
-            }
-            CommonOpcode::FOR_ITER => {
-                write!(instrs, "{arg} (to {})", idx + arg * 2 + 2).unwrap();
-            }
-            CommonOpcode::MAKE_FUNCTION => {
-                let flag = match arg {
-                    8 => "(closure)",
-                    // TODO:
-                    _ => "",
-                };
-                write!(instrs, "{arg} {flag}").unwrap();
-            }
-            other if other.take_arg() => {
-                write!(instrs, "{arg}").unwrap();
-            }
-            _ => {}
-        }
+    /// `py_ver` is needed to decode the instructions. If it is `None`, only the attributes are shown.
+    pub fn code_info(&self, py_ver: Option<PythonVersion>) -> String {
+        let dis = py_ver.map(|ver| Disassembly::new(self, ver));
+        self.code_info_with(dis.as_ref())
     }
 
-    pub fn code_info(&self, py_ver: Option<PythonVersion>) -> String {
+    fn code_info_with(&self, dis: Option<&Disassembly>) -> String {
         let mut info = "".to_string();
         writeln!(info, "Disassembly of {self:?}:").unwrap();
         info += &self.attrs_info();
         info += &self.tables_info();
-        info += &self.instr_info(py_ver);
+        if let Some(dis) = dis {
+            info += &dis.to_string();
+        }
         info.push('\n');
-        for cons in self.consts.iter() {
-            if let ValueObj::Code(c) = cons {
-                info += &c.code_info(py_ver);
-            }
+        let codes = self.consts.iter().filter_map(|c| match c {
+            ValueObj::Code(c) => Some(c),
+            _ => None,
+        });
+        for (i, code) in codes.enumerate() {
+            info += &code.code_info_with(dis.and_then(|dis| dis.children.get(i)));
         }
         info
     }
@@ -170,6 +170,26 @@ pub fn iter(t: Type) -> Type {
     poly("Iter", vec![TyParam::t(t)])
 }
 
+pub fn coroutine(t: Type) -> Type {
+    poly("Coroutine", vec![TyParam::t(t)])
+}
+
+/// `NotRequired(T)`: the value type of an optional key of a typed dict
+pub fn not_required(t: Type) -> Type {
+    poly("NotRequired", vec![TyParam::t(t)])
+}
+
+pub fn generator(yield_t: Type, send_t: Type, return_t: Type) -> Type {
+    poly(
+        "Generator",
+        vec![
+            TyParam::t(yield_t),
+            TyParam::t(send_t),
+            TyParam::t(return_t),
+        ],
+    )
+}
+
 pub fn ref_(t: Type) -> Type {
     Type::Ref(Box::new(t))
 }
@@ -456,6 +476,24 @@ pub fn and(lhs: Type, rhs: Type) -> Type {
     }
 }
 
+/// ```erg
+/// overloaded((Int -> Int), (Str -> Str) and (Nat -> Nat))
+/// == Overloaded(Int -> Int, Str -> Str, Nat -> Nat)
+/// ```
+pub fn overloaded(lhs: Type, rhs: Type) -> Type {
+    let mut overloads = lhs.intersection_types();
+    for t in rhs.intersection_types() {
+        if !overloads.contains(&t) {
+            overloads.push(t);
+        }
+    }
+    if overloads.len() == 1 {
+        overloads.remove(0)
+    } else {
+        Type::Overloaded(overloads)
+    }
+}
+
 pub fn or(lhs: Type, rhs: Type) -> Type {
     match (lhs, rhs) {
         (Type::Or(l, r), other) | (other, Type::Or(l, r)) => {
@@ -7,12 +7,14 @@ use erg_common::dict::Dict;
 use erg_common::error::{ErrorCore, ErrorKind, Location, SubMessage};
 use erg_common::python_util::PythonVersion;
 use erg_common::serialize::DataTypePrefix;
+use erg_common::set::Set;
 use erg_common::traits::ExitStatus;
 use erg_common::{fn_name, switch_lang};
 use erg_common::{ArcArray, Str};
 
 use super::codeobj::{CodeObj, FastKind};
 use super::constructors::array_t;
+use super::disassemble::Disassembly;
 use super::typaram::TyParam;
 use super::value::ValueObj;
 use super::{HasType, Type};
@@ -121,7 +123,11 @@ impl Deserializer {
         let filename = cfg.input.path();
         match CodeObj::from_pyc(filename) {
             Ok((codeobj, ver)) => {
-                println!("{}", codeobj.code_info(Some(ver)));
+                if cfg.json_output {
+                    println!("{}", Disassembly::new(&codeobj, ver).to_json());
+                } else {
+                    println!("{}", codeobj.code_info(Some(ver)));
+                }
                 ExitStatus::OK
             }
             Err(e) => {
@@ -201,6 +207,24 @@ impl Deserializer {
                 }
                 Ok(self.get_cached_arr(&arr))
             }
+            DataTypePrefix::FrozenSet => {
+                let len = Self::deserialize_u32(v);
+                let mut set = Set::with_capacity(len as usize);
+                for _ in 0..len {
+                    set.insert(self.deserialize_const(v, python_ver)?);
+                }
+                Ok(ValueObj::Set(set))
+            }
+            DataTypePrefix::Dict => {
+                let mut dict = Dict::new();
+                while v.first() != Some(&b'0') {
+                    let key = self.deserialize_const(v, python_ver)?;
+                    let value = self.deserialize_const(v, python_ver)?;
+                    dict.insert(key, value);
+                }
+                v.remove(0);
+                Ok(ValueObj::Dict(dict))
+            }
             DataTypePrefix::Code => {
                 v.insert(0, DataTypePrefix::Code as u8);
                 Ok(ValueObj::from(CodeObj::from_bytes(v, python_ver)?))
@@ -303,11 +327,13 @@ impl Deserializer {
             let mut freevars = vec![];
             let mut cellvars = vec![];
             for (name, kind) in names.into_iter().zip(kinds.into_iter()) {
-                match FastKind::try_from(kind) {
-                    Ok(FastKind::Local) => varnames.push(name),
-                    Ok(FastKind::Free) => freevars.push(name),
-                    Ok(FastKind::Cell) => cellvars.push(name),
-                    _ => unreachable!(),
+                // a cell can also be a local (`Local + Cell`), e.g. a captured argument
+                if kind & FastKind::Free as u8 != 0 {
+                    freevars.push(name);
+                } else if kind & FastKind::Cell as u8 != 0 {
+                    cellvars.push(name);
+                } else {
+                    varnames.push(name);
                 }
             }
             Ok((varnames, freevars, cellvars))
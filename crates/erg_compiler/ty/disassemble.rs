@@ -0,0 +1,614 @@
+//! defines `Disassembly` (a `dis`-like view of a code object).
+//!
+//! コードオブジェクトを逆アセンブルする(Pythonの`dis`モジュール相当)
+use std::fmt;
+use std::fmt::Write as _;
+
+use erg_common::error::escape_json;
+use erg_common::opcode308::Opcode308;
+use erg_common::opcode309::Opcode309;
+use erg_common::opcode310::Opcode310;
+use erg_common::opcode311::Opcode311;
+use erg_common::python_util::PythonVersion;
+use erg_common::Str;
+
+use super::codeobj::CodeObj;
+use super::value::ValueObj;
+use super::TypePair;
+
+const COMPARE_OPS: [&str; 6] = ["<", "<=", "==", "!=", ">", ">="];
+/// the args of `BINARY_OP` (3.11), in the same order as `BinOpCode`
+const BINARY_OPS: [&str; 26] = [
+    "+", "&", "//", "<<", "@", "*", "%", "|", "**", ">>", "-", "/", "^", "+=", "&=", "//=", "<<=",
+    "@=", "*=", "%=", "|=", "**=", ">>=", "-=", "/=", "^=",
+];
+/// the bits of the arg of `MAKE_FUNCTION`, see `MakeFunctionFlags`
+const MAKE_FUNCTION_FLAGS: [&str; 4] = ["defaults", "kwdefaults", "annotations", "closure"];
+
+fn json_opt<T: fmt::Display>(value: Option<T>) -> String {
+    value.map_or("null".to_string(), |v| v.to_string())
+}
+
+/// The source range of an instruction.
+/// Only `lineno` and `end_lineno` are available before 3.11.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Positions {
+    pub lineno: Option<u32>,
+    pub end_lineno: Option<u32>,
+    pub col_offset: Option<u32>,
+    pub end_col_offset: Option<u32>,
+}
+
+impl Positions {
+    const fn line(lineno: Option<u32>) -> Self {
+        Self {
+            lineno,
+            end_lineno: lineno,
+            col_offset: None,
+            end_col_offset: None,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"lineno\":{},\"end_lineno\":{},\"col_offset\":{},\"end_col_offset\":{}}}",
+            json_opt(self.lineno),
+            json_opt(self.end_lineno),
+            json_opt(self.col_offset),
+            json_opt(self.end_col_offset)
+        )
+    }
+}
+
+/// Corresponds to `dis.Instruction`. `CACHE` entries (3.11) are not included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub offset: usize,
+    pub opcode: u8,
+    pub opname: String,
+    /// `None` if the instruction does not take an arg (`EXTENDED_ARG`s are already applied)
+    pub arg: Option<usize>,
+    /// e.g. the name of the loaded variable
+    pub argrepr: String,
+    pub jump_target: Option<usize>,
+    /// the line number if the instruction is the first one of the line
+    pub starts_line: Option<u32>,
+    pub is_jump_target: bool,
+    pub positions: Positions,
+}
+
+impl Instruction {
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"offset\":{},\"opcode\":{},\"opname\":{},\"arg\":{},\"argrepr\":{},\"jump_target\":{},\"starts_line\":{},\"is_jump_target\":{},\"positions\":{}}}",
+            self.offset,
+            self.opcode,
+            escape_json(&self.opname),
+            json_opt(self.arg),
+            escape_json(&self.argrepr),
+            json_opt(self.jump_target),
+            json_opt(self.starts_line),
+            self.is_jump_target,
+            self.positions.to_json()
+        )
+    }
+}
+
+/// An entry of the exception table (3.11).
+/// If an exception is raised in `start..end`, the stack is popped to `depth` and the control jumps to `target`.
+/// If `lasti` is true, the offset of the raising instruction is pushed before the exception.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExceptionTableEntry {
+    pub start: usize,
+    pub end: usize,
+    pub target: usize,
+    pub depth: usize,
+    pub lasti: bool,
+}
+
+impl ExceptionTableEntry {
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"start\":{},\"end\":{},\"target\":{},\"depth\":{},\"lasti\":{}}}",
+            self.start, self.end, self.target, self.depth, self.lasti
+        )
+    }
+}
+
+/// The decoded instructions of a code object and (recursively) of the code objects in its constants.
+///
+/// The text form (`Display`) is the same as the `dis` module, but the nested code objects are not included
+/// (`CodeObj::code_info` prints them with their attributes).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disassembly {
+    pub name: Str,
+    pub qualname: Str,
+    pub filename: Str,
+    pub firstlineno: u32,
+    pub instrs: Vec<Instruction>,
+    pub exception_table: Vec<ExceptionTableEntry>,
+    pub children: Vec<Disassembly>,
+}
+
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let max_line = self.instrs.iter().filter_map(|i| i.starts_line).max();
+        let line_width = json_opt(max_line).len().max(3);
+        let max_offset = self.instrs.last().map_or(0, |i| i.offset);
+        let offset_width = max_offset.to_string().len().max(4);
+        for (i, instr) in self.instrs.iter().enumerate() {
+            if i != 0 && instr.starts_line.is_some() {
+                writeln!(f)?;
+            }
+            let line = instr.starts_line.map_or(String::new(), |l| l.to_string());
+            let label = if instr.is_jump_target { ">>" } else { "  " };
+            let mut fields = format!(
+                "{line:>line_width$}    {label} {:>offset_width$} {:<20}",
+                instr.offset, instr.opname
+            );
+            if let Some(arg) = instr.arg {
+                write!(fields, " {arg:>5}")?;
+                if !instr.argrepr.is_empty() {
+                    write!(fields, " ({})", instr.argrepr)?;
+                }
+            }
+            writeln!(f, "{}", fields.trim_end())?;
+        }
+        if !self.exception_table.is_empty() {
+            writeln!(f, "ExceptionTable:")?;
+            for entry in self.exception_table.iter() {
+                let lasti = if entry.lasti { " lasti" } else { "" };
+                // `end` is shown inclusively, as `dis` does
+                writeln!(
+                    f,
+                    "  {} to {} -> {} [{}]{lasti}",
+                    entry.start,
+                    entry.end.saturating_sub(2),
+                    entry.target,
+                    entry.depth
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Disassembly {
+    pub fn new(code: &CodeObj, py_ver: PythonVersion) -> Self {
+        let minor = py_ver.minor.unwrap_or_default();
+        let exception_table = if minor >= 11 {
+            Self::exception_table(&code.exceptiontable)
+        } else {
+            vec![]
+        };
+        let mut instrs = Self::instructions(code, minor);
+        let targets = instrs
+            .iter()
+            .filter_map(|i| i.jump_target)
+            .chain(exception_table.iter().map(|e| e.target))
+            .collect::<Vec<_>>();
+        let positions = Self::positions(code, minor);
+        let mut last_line = None;
+        for instr in instrs.iter_mut() {
+            instr.is_jump_target = targets.contains(&instr.offset);
+            instr.positions = positions.get(instr.offset / 2).copied().unwrap_or_default();
+            if instr.positions.lineno.is_some() && instr.positions.lineno != last_line {
+                instr.starts_line = instr.positions.lineno;
+                last_line = instr.positions.lineno;
+            }
+        }
+        let children = code
+            .consts
+            .iter()
+            .filter_map(|c| match c {
+                ValueObj::Code(c) => Some(Self::new(c, py_ver)),
+                _ => None,
+            })
+            .collect();
+        Self {
+            name: code.name.clone(),
+            qualname: code.qualname.clone(),
+            filename: code.filename.clone(),
+            firstlineno: code.firstlineno,
+            instrs,
+            exception_table,
+            children,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let instrs = self
+            .instrs
+            .iter()
+            .map(|i| i.to_json())
+            .collect::<Vec<_>>()
+            .join(",");
+        let exception_table = self
+            .exception_table
+            .iter()
+            .map(|e| e.to_json())
+            .collect::<Vec<_>>()
+            .join(",");
+        let children = self
+            .children
+            .iter()
+            .map(|c| c.to_json())
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            "{{\"name\":{},\"qualname\":{},\"filename\":{},\"firstlineno\":{},\"instructions\":[{instrs}],\"exception_table\":[{exception_table}],\"children\":[{children}]}}",
+            escape_json(&self.name),
+            escape_json(&self.qualname),
+            escape_json(&self.filename),
+            self.firstlineno
+        )
+    }
+
+    fn opname(minor: u8, op: u8) -> Option<String> {
+        match minor {
+            ..=8 => Opcode308::try_from_u8(op).map(|op| op.to_string()),
+            9 => Opcode309::try_from_u8(op).map(|op| op.to_string()),
+            10 => Opcode310::try_from_u8(op).map(|op| op.to_string()),
+            _ => Opcode311::try_from_u8(op).map(|op| op.to_string()),
+        }
+    }
+
+    fn jump_target(minor: u8, opname: &str, offset: usize, arg: usize) -> Option<usize> {
+        if minor >= 11 {
+            match opname {
+                "FOR_ITER"
+                | "JUMP_FORWARD"
+                | "JUMP_IF_FALSE_OR_POP"
+                | "JUMP_IF_TRUE_OR_POP"
+                | "POP_JUMP_FORWARD_IF_FALSE"
+                | "POP_JUMP_FORWARD_IF_TRUE"
+                | "POP_JUMP_FORWARD_IF_NONE"
+                | "POP_JUMP_FORWARD_IF_NOT_NONE"
+                | "SEND" => Some(offset + 2 + arg * 2),
+                "JUMP_BACKWARD"
+                | "JUMP_BACKWARD_NO_INTERRUPT"
+                | "POP_JUMP_BACKWARD_IF_FALSE"
+                | "POP_JUMP_BACKWARD_IF_TRUE"
+                | "POP_JUMP_BACKWARD_IF_NONE"
+                | "POP_JUMP_BACKWARD_IF_NOT_NONE" => (offset + 2).checked_sub(arg * 2),
+                _ => None,
+            }
+        } else {
+            // jump args are the number of instructions since 3.10, and the number of bytes before
+            let unit = if minor >= 10 { 2 } else { 1 };
+            match opname {
+                "FOR_ITER" | "JUMP_FORWARD" | "SETUP_LOOP" | "SETUP_EXCEPT" | "SETUP_FINALLY"
+                | "SETUP_WITH" | "SETUP_ASYNC_WITH" | "CALL_FINALLY" => {
+                    Some(offset + 2 + arg * unit)
+                }
+                "JUMP_ABSOLUTE"
+                | "POP_JUMP_IF_FALSE"
+                | "POP_JUMP_IF_TRUE"
+                | "JUMP_IF_FALSE_OR_POP"
+                | "JUMP_IF_TRUE_OR_POP"
+                | "JUMP_IF_NOT_EXC_MATCH"
+                | "CONTINUE_LOOP" => Some(arg * unit),
+                _ => None,
+            }
+        }
+    }
+
+    fn const_repr(obj: &ValueObj) -> String {
+        match obj {
+            // without the address, so that the output is reproducible
+            ValueObj::Code(code) => format!(
+                "<code object {}, file \"{}\", line {}>",
+                code.name, code.filename, code.firstlineno
+            ),
+            other => other.to_string(),
+        }
+    }
+
+    fn argrepr(code: &CodeObj, minor: u8, opname: &str, arg: usize) -> Option<String> {
+        match opname {
+            "LOAD_CONST" | "KW_NAMES" => code.consts.get(arg).map(Self::const_repr),
+            "LOAD_GLOBAL" if minor >= 11 => {
+                let name = code.names.get(arg >> 1)?;
+                if arg & 1 == 1 {
+                    Some(format!("NULL + {name}"))
+                } else {
+                    Some(name.to_string())
+                }
+            }
+            "LOAD_NAME" | "STORE_NAME" | "DELETE_NAME" | "LOAD_GLOBAL" | "STORE_GLOBAL"
+            | "DELETE_GLOBAL" | "LOAD_ATTR" | "STORE_ATTR" | "DELETE_ATTR" | "LOAD_METHOD"
+            | "IMPORT_NAME" | "IMPORT_FROM" => code.names.get(arg).map(|n| n.to_string()),
+            "LOAD_FAST" | "STORE_FAST" | "DELETE_FAST" if minor >= 11 => {
+                code.localsplus_names().get(arg).map(|n| n.to_string())
+            }
+            "LOAD_FAST" | "STORE_FAST" | "DELETE_FAST" => {
+                code.varnames.get(arg).map(|n| n.to_string())
+            }
+            "LOAD_DEREF" | "STORE_DEREF" | "DELETE_DEREF" | "LOAD_CLOSURE" | "LOAD_CLASSDEREF"
+            | "MAKE_CELL" => {
+                if minor >= 11 {
+                    code.localsplus_names().get(arg).map(|n| n.to_string())
+                } else {
+                    code.cellvars
+                        .iter()
+                        .chain(code.freevars.iter())
+                        .nth(arg)
+                        .map(|n| n.to_string())
+                }
+            }
+            "COMPARE_OP" => COMPARE_OPS.get(arg).map(|op| op.to_string()),
+            "BINARY_OP" => BINARY_OPS.get(arg).map(|op| op.to_string()),
+            "MAKE_FUNCTION" => {
+                let flags = MAKE_FUNCTION_FLAGS
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| arg & (1 << i) != 0)
+                    .map(|(_, flag)| *flag)
+                    .collect::<Vec<_>>();
+                Some(flags.join(", "))
+            }
+            // Erg stores the operand types in the (unused) arg of the binary operators
+            _ if minor <= 10 && opname.starts_with("BINARY_") => {
+                Some(format!("{:?}", TypePair::from(arg as u8)))
+            }
+            _ => None,
+        }
+    }
+
+    fn instructions(code: &CodeObj, minor: u8) -> Vec<Instruction> {
+        let mut instrs = vec![];
+        let mut extended_arg = 0;
+        for (i, unit) in code.code.chunks(2).enumerate() {
+            let op = unit[0];
+            if minor >= 11 && op == Opcode311::CACHE as u8 {
+                continue;
+            }
+            let offset = i * 2;
+            let raw_arg = unit.get(1).copied().unwrap_or(0) as usize;
+            let opname = Self::opname(minor, op).unwrap_or_else(|| format!("<{op}>"));
+            let takes_arg = (90..220).contains(&op);
+            let arg = (extended_arg << 8) | raw_arg;
+            extended_arg = if opname == "EXTENDED_ARG" { arg } else { 0 };
+            let jump_target = takes_arg
+                .then(|| Self::jump_target(minor, &opname, offset, arg))
+                .flatten();
+            let (arg, argrepr) = if let Some(target) = jump_target {
+                (Some(arg), format!("to {target}"))
+            } else if takes_arg || (minor <= 10 && raw_arg != 0 && opname.starts_with("BINARY_")) {
+                let argrepr = Self::argrepr(code, minor, &opname, arg).unwrap_or_default();
+                (Some(arg), argrepr)
+            } else {
+                (None, String::new())
+            };
+            instrs.push(Instruction {
+                offset,
+                opcode: op,
+                opname,
+                arg,
+                argrepr,
+                jump_target,
+                starts_line: None,
+                is_jump_target: false,
+                positions: Positions::default(),
+            });
+        }
+        instrs
+    }
+
+    /// Returns the positions of each code unit (2 bytes).
+    /// The table is `lnotab` before 3.10, `linetable` in 3.10, and `locationtable` since 3.11.
+    /// See `Objects/lnotab_notes.txt` and `Objects/locations.md` in CPython for details.
+    fn positions(code: &CodeObj, minor: u8) -> Vec<Positions> {
+        let mut positions = vec![Positions::default(); code.code.len() / 2];
+        let mut fill = |start: usize, end: usize, pos: Positions| {
+            for p in positions.iter_mut().take(end / 2).skip(start / 2) {
+                *p = pos;
+            }
+        };
+        let mut line = code.firstlineno as i64;
+        let mut addr = 0;
+        match minor {
+            ..=9 => {
+                for pair in code.lnotab.chunks_exact(2) {
+                    let (sdelta, ldelta) = (pair[0] as usize, pair[1] as i8);
+                    if sdelta != 0 {
+                        fill(
+                            addr,
+                            addr + sdelta,
+                            Positions::line(u32::try_from(line).ok()),
+                        );
+                        addr += sdelta;
+                    }
+                    line += ldelta as i64;
+                }
+                fill(
+                    addr,
+                    code.code.len(),
+                    Positions::line(u32::try_from(line).ok()),
+                );
+            }
+            10 => {
+                for pair in code.lnotab.chunks_exact(2) {
+                    let (sdelta, ldelta) = (pair[0] as usize, pair[1] as i8);
+                    // -128 means that the instructions have no line number
+                    let lineno = if ldelta == -128 {
+                        None
+                    } else {
+                        line += ldelta as i64;
+                        u32::try_from(line).ok()
+                    };
+                    fill(addr, addr + sdelta, Positions::line(lineno));
+                    addr += sdelta;
+                }
+            }
+            _ => {
+                let mut table = code.lnotab.iter().copied();
+                while let Some(first) = table.next() {
+                    let end = addr + ((first & 7) as usize + 1) * 2;
+                    let Some(pos) = Self::read_location((first >> 3) & 15, &mut table, &mut line)
+                    else {
+                        break;
+                    };
+                    fill(addr, end, pos);
+                    addr = end;
+                }
+            }
+        }
+        positions
+    }
+
+    fn read_varint(table: &mut impl Iterator<Item = u8>) -> Option<u64> {
+        let mut val = 0;
+        let mut shift = 0;
+        loop {
+            let byte = table.next()?;
+            val |= ((byte & 63) as u64).checked_shl(shift)?;
+            if byte & 64 == 0 {
+                return Some(val);
+            }
+            shift += 6;
+        }
+    }
+
+    fn read_signed_varint(table: &mut impl Iterator<Item = u8>) -> Option<i64> {
+        let val = Self::read_varint(table)?;
+        if val & 1 == 1 {
+            Some(-((val >> 1) as i64))
+        } else {
+            Some((val >> 1) as i64)
+        }
+    }
+
+    fn read_location(
+        code: u8,
+        table: &mut impl Iterator<Item = u8>,
+        line: &mut i64,
+    ) -> Option<Positions> {
+        let lineno = |line: i64| u32::try_from(line).ok();
+        match code {
+            // no location
+            15 => Some(Positions::default()),
+            // long form
+            14 => {
+                *line += Self::read_signed_varint(table)?;
+                let end_line = *line + Self::read_varint(table)? as i64;
+                // the columns are stored with 1 added (0 means unknown)
+                let col = (Self::read_varint(table)? as u32).checked_sub(1);
+                let end_col = (Self::read_varint(table)? as u32).checked_sub(1);
+                Some(Positions {
+                    lineno: lineno(*line),
+                    end_lineno: lineno(end_line),
+                    col_offset: col,
+                    end_col_offset: end_col,
+                })
+            }
+            // no column info
+            13 => {
+                *line += Self::read_signed_varint(table)?;
+                Some(Positions::line(lineno(*line)))
+            }
+            // one line form
+            10..=12 => {
+                *line += (code - 10) as i64;
+                let col = table.next()? as u32;
+                let end_col = table.next()? as u32;
+                Some(Positions {
+                    col_offset: Some(col),
+                    end_col_offset: Some(end_col),
+                    ..Positions::line(lineno(*line))
+                })
+            }
+            // short form
+            _ => {
+                let second = table.next()? as u32;
+                let col = (code as u32) * 8 + (second >> 4);
+                Some(Positions {
+                    col_offset: Some(col),
+                    end_col_offset: Some(col + (second & 15)),
+                    ..Positions::line(lineno(*line))
+                })
+            }
+        }
+    }
+
+    /// See `Objects/exception_handling_notes.txt` in CPython for details.
+    fn exception_table(table: &[u8]) -> Vec<ExceptionTableEntry> {
+        // unlike the location table, the varints are big-endian
+        fn read_varint(table: &mut impl Iterator<Item = u8>) -> Option<usize> {
+            let mut byte = table.next()?;
+            let mut val = (byte & 63) as usize;
+            while byte & 64 != 0 {
+                byte = table.next()?;
+                val = (val << 6) | (byte & 63) as usize;
+            }
+            Some(val)
+        }
+        let mut table = table.iter().copied();
+        let mut entries = vec![];
+        while let (Some(start), Some(len), Some(target), Some(depth_lasti)) = (
+            read_varint(&mut table),
+            read_varint(&mut table),
+            read_varint(&mut table),
+            read_varint(&mut table),
+        ) {
+            entries.push(ExceptionTableEntry {
+                start: start * 2,
+                end: (start + len) * 2,
+                target: target * 2,
+                depth: depth_lasti >> 1,
+                lasti: depth_lasti & 1 == 1,
+            });
+        }
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_tables() {
+        // `def f(a):\n    try:\n        return a.x\n    except AttributeError:\n        return None` (3.11)
+        let code = CodeObj {
+            code: vec![0; 52],
+            firstlineno: 2,
+            lnotab: vec![
+                128, 0, 240, 2, 3, 5, 20, 216, 15, 16, 140, 115, 136, 10, 248, 221, 11, 25, 240, 0,
+                1, 5, 20, 240, 0, 1, 5, 20, 240, 0, 1, 5, 20, 216, 15, 19, 136, 116, 136, 116, 240,
+                3, 1, 5, 20, 248, 248, 248,
+            ],
+            exceptiontable: vec![130, 6, 9, 0, 137, 10, 23, 3, 150, 1, 23, 3],
+            ..CodeObj::default()
+        };
+        let pos = |ln, end_ln, col, end_col| Positions {
+            lineno: Some(ln),
+            end_lineno: Some(end_ln),
+            col_offset: Some(col),
+            end_col_offset: Some(end_col),
+        };
+        let positions = Disassembly::positions(&code, 11);
+        assert_eq!(positions.len(), 26);
+        assert_eq!(positions[0], pos(2, 2, 0, 0));
+        assert_eq!(positions[1], pos(3, 6, 4, 19));
+        assert_eq!(positions[8], pos(4, 4, 8, 18));
+        assert_eq!(positions[9], Positions::default());
+        assert_eq!(positions[19], pos(6, 6, 15, 19));
+        assert_eq!(positions[22], pos(5, 6, 4, 19));
+        let entry = |start, end, target, depth, lasti| ExceptionTableEntry {
+            start,
+            end,
+            target,
+            depth,
+            lasti,
+        };
+        assert_eq!(
+            Disassembly::exception_table(&code.exceptiontable),
+            vec![
+                entry(4, 16, 18, 0, false),
+                entry(18, 38, 46, 1, true),
+                entry(44, 46, 46, 1, true)
+            ]
+        );
+    }
+}
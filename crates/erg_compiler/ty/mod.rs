@@ -11,6 +11,7 @@ pub mod codeobj;
 pub mod const_subr;
 pub mod constructors;
 pub mod deserialize;
+pub mod disassemble;
 pub mod free;
 pub mod predicate;
 pub mod typaram;
@@ -810,6 +811,19 @@ impl CastTarget {
     pub fn expr(expr: Expr) -> Self {
         Self::Expr(Box::new(expr))
     }
+
+    /// Returns `true` if `self` and `other` refer to the same object (locations are ignored).
+    /// A parameter is the same as the variable bound to it.
+    pub fn is_same_target(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Param { nth: l, .. }, Self::Param { nth: r, .. }) => l == r,
+            (Self::Var { name: l, .. }, Self::Var { name: r, .. })
+            | (Self::Param { name: l, .. }, Self::Var { name: r, .. })
+            | (Self::Var { name: l, .. }, Self::Param { name: r, .. }) => l == r,
+            (Self::Expr(l), Self::Expr(r)) => l == r,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -879,7 +893,6 @@ pub enum Type {
         param_ts: Vec<Type>,
         return_t: Box<Type>,
     },
-    // Overloaded(Vec<Type>),
     Record(Dict<Field, Type>), // e.g. {x = Int}
     // e.g. {T -> T | T: Type}, {I: Int | I > 0}, {S | N: Nat; S: Str N; N > 1}
     // 区間型と列挙型は篩型に変換される
@@ -900,6 +913,9 @@ pub enum Type {
         params: Vec<TyParam>,
     },
     NamedTuple(Vec<(Field, Type)>),
+    // e.g. `((x: Int) -> Int) and ((x: Str) -> Str)`
+    // each element is a (quantified) subroutine type
+    Overloaded(Vec<Type>),
     /* Special types (inference-time types) */
     Proj {
         lhs: Box<Type>,
@@ -1012,6 +1028,7 @@ impl PartialEq for Type {
                 (ll == rl && lr == rr) || (ll == rr && lr == rl)
             }
             (Self::Not(l), Self::Not(r)) => l == r,
+            (Self::Overloaded(l), Self::Overloaded(r)) => l == r,
             (
                 Self::Poly {
                     name: ln,
@@ -1179,6 +1196,16 @@ impl LimitedDisplay for Type {
                 write!(f, " or ")?;
                 rhs.limited_fmt(f, limit - 1)
             }
+            Self::Overloaded(overloads) => {
+                write!(f, "Overloaded(")?;
+                for (i, t) in overloads.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    t.limited_fmt(f, limit - 1)?;
+                }
+                write!(f, ")")
+            }
             Self::Poly { name, params } => {
                 write!(f, "{name}(")?;
                 for (i, tp) in params.iter().enumerate() {
@@ -1448,6 +1475,7 @@ impl HasLevel for Type {
                     .filter_map(|o| *o)
                     .min()
             }
+            Self::Overloaded(overloads) => overloads.iter().filter_map(|t| t.level()).min(),
             Self::And(lhs, rhs) | Self::Or(lhs, rhs) => {
                 let l = lhs
                     .level()
@@ -1540,6 +1568,11 @@ impl HasLevel for Type {
                 lhs.set_level(level);
                 rhs.set_level(level);
             }
+            Self::Overloaded(overloads) => {
+                for t in overloads.iter() {
+                    t.set_level(level);
+                }
+            }
             Self::Not(ty) => ty.set_level(level),
             Self::Record(attrs) => {
                 for t in attrs.values() {
@@ -1686,6 +1719,9 @@ impl StructuralEq for Type {
             }
             (Self::Structural(l), Self::Structural(r)) => l.structural_eq(r),
             (Self::Guard(l), Self::Guard(r)) => l.structural_eq(r),
+            (Self::Overloaded(l), Self::Overloaded(r)) => {
+                l.len() == r.len() && l.iter().zip(r.iter()).all(|(l, r)| l.structural_eq(r))
+            }
             // NG: (l.structural_eq(l2) && r.structural_eq(r2))
             //     || (l.structural_eq(r2) && r.structural_eq(l2))
             (Self::And(_, _), Self::And(_, _)) => {
@@ -1760,10 +1796,31 @@ impl Type {
         }
     }
 
+    /// `Nat!` => `Nat`
+    pub fn immutate(&self) -> Option<Self> {
+        match self {
+            Self::FreeVar(fv) if fv.is_linked() => fv.crack().immutate(),
+            Self::Mono(name) => match &name[..] {
+                "Int!" => Some(Self::Int),
+                "Nat!" => Some(Self::Nat),
+                "Ratio!" => Some(Self::Ratio),
+                "Float!" => Some(Self::Float),
+                "Complex!" => Some(Self::Complex),
+                "Bool!" => Some(Self::Bool),
+                "Str!" => Some(Self::Str),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
     pub fn quantify(self) -> Self {
         debug_assert!(self.is_subr(), "{self} is not subr");
         match self {
             Self::And(lhs, rhs) => lhs.quantify() & rhs.quantify(),
+            Self::Overloaded(overloads) => {
+                Self::Overloaded(overloads.into_iter().map(|t| t.quantify()).collect())
+            }
             other => Self::Quantified(Box::new(other)),
         }
     }
@@ -1833,6 +1890,7 @@ impl Type {
             Self::Subr(subr) if subr.kind == SubrKind::Proc => true,
             Self::Refinement(refine) => refine.t.is_procedure(),
             Self::And(lhs, rhs) => lhs.is_procedure() && rhs.is_procedure(),
+            Self::Overloaded(overloads) => overloads.iter().all(|t| t.is_procedure()),
             _ => false,
         }
     }
@@ -1879,6 +1937,16 @@ impl Type {
         }
     }
 
+    /// `{x in Int}`, `{x in Int} and Bool`, `{x in Int} or {y in Str}`, ...
+    pub fn is_guard(&self) -> bool {
+        match self {
+            Self::FreeVar(fv) if fv.is_linked() => fv.crack().is_guard(),
+            Self::Guard(_) => true,
+            Self::And(lhs, rhs) | Self::Or(lhs, rhs) => lhs.is_guard() || rhs.is_guard(),
+            _ => false,
+        }
+    }
+
     pub fn is_intersection_type(&self) -> bool {
         match self {
             Self::FreeVar(fv) if fv.is_linked() => fv.crack().is_intersection_type(),
@@ -1901,6 +1969,9 @@ impl Type {
             Self::Ref(t) => t.union_size(),
             Self::RefMut { before, after: _ } => before.union_size(),
             Self::And(lhs, rhs) => lhs.union_size().max(rhs.union_size()),
+            Self::Overloaded(overloads) => {
+                overloads.iter().map(|t| t.union_size()).max().unwrap_or(1)
+            }
             Self::Not(ty) => ty.union_size(),
             Self::Callable { param_ts, return_t } => param_ts
                 .iter()
@@ -1987,6 +2058,15 @@ impl Type {
             Self::Subr(subr) => subr.is_method(),
             Self::Quantified(quant) => quant.is_method(),
             Self::And(l, r) => l.is_method() && r.is_method(),
+            Self::Overloaded(overloads) => overloads.iter().all(|t| t.is_method()),
+            _ => false,
+        }
+    }
+
+    pub fn is_overloaded(&self) -> bool {
+        match self {
+            Self::FreeVar(fv) if fv.is_linked() => fv.crack().is_overloaded(),
+            Self::Overloaded(_) => true,
             _ => false,
         }
     }
@@ -1998,6 +2078,7 @@ impl Type {
             Self::Quantified(quant) => quant.is_subr(),
             Self::Refinement(refine) => refine.t.is_subr(),
             Self::And(l, r) => l.is_subr() && r.is_subr(),
+            Self::Overloaded(_) => true,
             _ => false,
         }
     }
@@ -2008,6 +2089,7 @@ impl Type {
             Self::Quantified(_) => true,
             Self::Refinement(refine) => refine.t.is_quantified_subr(),
             Self::And(l, r) => l.is_quantified_subr() && r.is_quantified_subr(),
+            Self::Overloaded(overloads) => overloads.iter().all(|t| t.is_quantified_subr()),
             _ => false,
         }
     }
@@ -2103,6 +2185,7 @@ impl Type {
                 lhs.contains_tvar(target) || args.iter().any(|t| t.contains_tvar(target))
             }
             Self::And(lhs, rhs) => lhs.contains_tvar(target) || rhs.contains_tvar(target),
+            Self::Overloaded(overloads) => overloads.iter().any(|t| t.contains_tvar(target)),
             Self::Or(lhs, rhs) => lhs.contains_tvar(target) || rhs.contains_tvar(target),
             Self::Not(t) => t.contains_tvar(target),
             Self::Ref(t) => t.contains_tvar(target),
@@ -2142,6 +2225,7 @@ impl Type {
                 lhs.contains_type(target) || args.iter().any(|t| t.contains_type(target))
             }
             Self::And(lhs, rhs) => lhs.contains_type(target) || rhs.contains_type(target),
+            Self::Overloaded(overloads) => overloads.iter().any(|t| t.contains_type(target)),
             Self::Or(lhs, rhs) => lhs.contains_type(target) || rhs.contains_type(target),
             Self::Not(t) => t.contains_type(target),
             Self::Ref(t) => t.contains_type(target),
@@ -2175,6 +2259,7 @@ impl Type {
                 lhs.contains_tp(target) || args.iter().any(|t| t.contains_tp(target))
             }
             Self::And(lhs, rhs) => lhs.contains_tp(target) || rhs.contains_tp(target),
+            Self::Overloaded(overloads) => overloads.iter().any(|t| t.contains_tp(target)),
             Self::Or(lhs, rhs) => lhs.contains_tp(target) || rhs.contains_tp(target),
             Self::Not(t) => t.contains_tp(target),
             Self::Ref(t) => t.contains_tp(target),
@@ -2208,6 +2293,7 @@ impl Type {
             Self::And(lhs, rhs) | Self::Or(lhs, rhs) => {
                 lhs.contains_type(self) || rhs.contains_type(self)
             }
+            Self::Overloaded(overloads) => overloads.iter().any(|t| t.contains_type(self)),
             Self::Not(t) => t.contains_type(self),
             Self::Ref(t) => t.contains_type(self),
             Self::RefMut { before, after } => {
@@ -2298,6 +2384,7 @@ impl Type {
             Self::NegInf => Str::ever("NegInf"),
             Self::Mono(name) => name.clone(),
             Self::And(_, _) => Str::ever("And"),
+            Self::Overloaded(_) => Str::ever("Overloaded"),
             Self::Not(_) => Str::ever("Not"),
             Self::Or(_, _) => Str::ever("Or"),
             Self::Ref(_) => Str::ever("Ref"),
@@ -2413,6 +2500,20 @@ impl Type {
         }
     }
 
+    /// e.g. `Int or Str or NoneType` => `[Int, Str, NoneType]`
+    pub fn union_types(&self) -> Vec<Type> {
+        match self {
+            Type::FreeVar(fv) if fv.is_linked() => fv.crack().union_types(),
+            Type::Refinement(refine) => refine.t.union_types(),
+            Type::Or(t1, t2) => {
+                let mut types = t1.union_types();
+                types.extend(t2.union_types());
+                types
+            }
+            _ => vec![self.clone()],
+        }
+    }
+
     pub fn intersection_types(&self) -> Vec<Type> {
         match self {
             Type::FreeVar(fv) if fv.is_linked() => fv.crack().intersection_types(),
@@ -2427,6 +2528,7 @@ impl Type {
                 types.extend(t2.intersection_types());
                 types
             }
+            Type::Overloaded(overloads) => overloads.clone(),
             _ => vec![self.clone()],
         }
     }
@@ -2603,6 +2705,11 @@ impl Type {
                 l.destructive_coerce();
                 r.destructive_coerce();
             }
+            Type::Overloaded(overloads) => {
+                for t in overloads.iter() {
+                    t.destructive_coerce();
+                }
+            }
             Type::Not(l) => l.destructive_coerce(),
             Type::Poly { params, .. } => {
                 for p in params {
@@ -2629,6 +2736,11 @@ impl Type {
                 l.undoable_coerce(list);
                 r.undoable_coerce(list);
             }
+            Type::Overloaded(overloads) => {
+                for t in overloads.iter() {
+                    t.undoable_coerce(list);
+                }
+            }
             Type::Not(l) => l.undoable_coerce(list),
             Type::Poly { params, .. } => {
                 for p in params {
@@ -2667,6 +2779,9 @@ impl Type {
                 .qvars()
                 .concat(after.as_ref().map(|t| t.qvars()).unwrap_or_else(|| set! {})),
             Self::And(lhs, rhs) | Self::Or(lhs, rhs) => lhs.qvars().concat(rhs.qvars()),
+            Self::Overloaded(overloads) => overloads
+                .iter()
+                .fold(set! {}, |acc, t| acc.concat(t.qvars())),
             Self::Not(ty) => ty.qvars(),
             Self::Callable { param_ts, return_t } => param_ts
                 .iter()
@@ -2726,6 +2841,7 @@ impl Type {
                 before.has_qvar() || after.as_ref().map(|t| t.has_qvar()).unwrap_or(false)
             }
             Self::And(lhs, rhs) | Self::Or(lhs, rhs) => lhs.has_qvar() || rhs.has_qvar(),
+            Self::Overloaded(overloads) => overloads.iter().any(|t| t.has_qvar()),
             Self::Not(ty) => ty.has_qvar(),
             Self::Callable { param_ts, return_t } => {
                 param_ts.iter().any(|t| t.has_qvar()) || return_t.has_qvar()
@@ -2780,6 +2896,7 @@ impl Type {
             Self::And(lhs, rhs) | Self::Or(lhs, rhs) => {
                 lhs.has_undoable_linked_var() || rhs.has_undoable_linked_var()
             }
+            Self::Overloaded(overloads) => overloads.iter().any(|t| t.has_undoable_linked_var()),
             Self::Not(ty) => ty.has_undoable_linked_var(),
             Self::Callable { param_ts, return_t } => {
                 param_ts.iter().any(|t| t.has_undoable_linked_var())
@@ -2827,6 +2944,7 @@ impl Type {
             Self::And(lhs, rhs) | Self::Or(lhs, rhs) => {
                 lhs.has_unbound_var() || rhs.has_unbound_var()
             }
+            Self::Overloaded(overloads) => overloads.iter().any(|t| t.has_unbound_var()),
             Self::Not(ty) => ty.has_unbound_var(),
             Self::Callable { param_ts, return_t } => {
                 param_ts.iter().any(|t| t.has_unbound_var()) || return_t.has_unbound_var()
@@ -2873,6 +2991,7 @@ impl Type {
             // REVIEW:
             Self::Ref(_) | Self::RefMut { .. } => Some(1),
             Self::And(_, _) | Self::Or(_, _) => Some(2),
+            Self::Overloaded(overloads) => Some(overloads.len()),
             Self::Not(_) => Some(1),
             Self::Subr(subr) => Some(
                 subr.non_default_params.len()
@@ -2933,6 +3052,7 @@ impl Type {
             Self::And(lhs, rhs) | Self::Or(lhs, rhs) => {
                 vec![TyParam::t(*lhs.clone()), TyParam::t(*rhs.clone())]
             }
+            Self::Overloaded(overloads) => overloads.iter().cloned().map(TyParam::t).collect(),
             Self::Not(t) => vec![TyParam::t(*t.clone())],
             Self::Subr(subr) => subr.typarams(),
             Self::Quantified(quant) => quant.typarams(),
@@ -3067,6 +3187,44 @@ impl Type {
         }
     }
 
+    /// `Coroutine(T)` => `T`
+    pub fn coroutine_output(&self) -> Option<Type> {
+        match self {
+            Self::FreeVar(fv) if fv.is_linked() => fv.crack().coroutine_output(),
+            Self::Refinement(refine) => refine.t.coroutine_output(),
+            Self::Poly { name, params } if &name[..] == "Coroutine" => {
+                <&Type>::try_from(params.first()?).ok().cloned()
+            }
+            _ => None,
+        }
+    }
+
+    /// `NotRequired(T)` => `T`
+    pub fn not_required_inner(&self) -> Option<Type> {
+        match self {
+            Self::FreeVar(fv) if fv.is_linked() => fv.crack().not_required_inner(),
+            Self::Poly { name, params } if &name[..] == "NotRequired" => {
+                <&Type>::try_from(params.first()?).ok().cloned()
+            }
+            _ => None,
+        }
+    }
+
+    /// `Generator(Y, S, R)` => `(Y, S, R)`
+    pub fn generator_params(&self) -> Option<(Type, Type, Type)> {
+        match self {
+            Self::FreeVar(fv) if fv.is_linked() => fv.crack().generator_params(),
+            Self::Refinement(refine) => refine.t.generator_params(),
+            Self::Poly { name, params } if &name[..] == "Generator" => {
+                let yield_t = <&Type>::try_from(params.first()?).ok()?;
+                let send_t = <&Type>::try_from(params.get(1)?).ok()?;
+                let return_t = <&Type>::try_from(params.get(2)?).ok()?;
+                Some((yield_t.clone(), send_t.clone(), return_t.clone()))
+            }
+            _ => None,
+        }
+    }
+
     pub fn derefine(&self) -> Type {
         match self {
             Self::FreeVar(fv) if fv.is_linked() => fv.crack().derefine(),
@@ -3114,6 +3272,9 @@ impl Type {
                 Self::NamedTuple(r)
             }
             Self::And(l, r) => l.derefine() & r.derefine(),
+            Self::Overloaded(overloads) => {
+                Self::Overloaded(overloads.iter().map(|t| t.derefine()).collect())
+            }
             Self::Or(l, r) => l.derefine() | r.derefine(),
             Self::Not(ty) => !ty.derefine(),
             Self::Proj { lhs, rhs } => lhs.derefine().proj(rhs.clone()),
@@ -3271,6 +3432,12 @@ impl Type {
                 after: after.map(|t| Box::new(t._replace(target, to))),
             },
             Self::And(l, r) => l._replace(target, to) & r._replace(target, to),
+            Self::Overloaded(overloads) => Self::Overloaded(
+                overloads
+                    .into_iter()
+                    .map(|t| t._replace(target, to))
+                    .collect(),
+            ),
             Self::Or(l, r) => l._replace(target, to) | r._replace(target, to),
             Self::Not(ty) => !ty._replace(target, to),
             Self::Proj { lhs, rhs } => lhs._replace(target, to).proj(rhs),
@@ -3344,6 +3511,9 @@ impl Type {
                 Self::NamedTuple(r)
             }
             Self::And(l, r) => l.normalize() & r.normalize(),
+            Self::Overloaded(overloads) => {
+                Self::Overloaded(overloads.into_iter().map(|t| t.normalize()).collect())
+            }
             Self::Or(l, r) => l.normalize() | r.normalize(),
             Self::Not(ty) => !ty.normalize(),
             Self::Structural(ty) => ty.normalize().structuralize(),
@@ -3547,6 +3717,9 @@ impl Type {
                 param_ts.iter().flat_map(|t| t.contained_ts()).collect()
             }
             Self::And(l, r) | Self::Or(l, r) => l.contained_ts().union(&r.contained_ts()),
+            Self::Overloaded(overloads) => {
+                overloads.iter().flat_map(|t| t.contained_ts()).collect()
+            }
             Self::Not(t) => t.contained_ts(),
             Self::Bounded { sub, sup } => sub.contained_ts().union(&sup.contained_ts()),
             Self::Quantified(ty) | Self::Structural(ty) => ty.contained_ts(),
@@ -3642,6 +3815,11 @@ impl<'t> ReplaceTable<'t> {
                 self.iterate(l, l2);
                 self.iterate(r, r2);
             }
+            (Type::Overloaded(l), Type::Overloaded(r)) => {
+                for (l, r) in l.iter().zip(r.iter()) {
+                    self.iterate(l, r);
+                }
+            }
             (Type::Or(l, r), Type::Or(l2, r2)) => {
                 self.iterate(l, l2);
                 self.iterate(r, r2);
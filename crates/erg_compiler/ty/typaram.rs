@@ -1348,6 +1348,16 @@ impl TyParam {
         }
     }
 
+    /// `NotRequired(T)` => `T`
+    pub fn not_required_inner(&self) -> Option<TyParam> {
+        match self {
+            Self::Type(t) => t.not_required_inner().map(TyParam::t),
+            Self::FreeVar(fv) if fv.is_linked() => fv.crack().not_required_inner(),
+            Self::Value(ValueObj::Type(t)) => t.typ().not_required_inner().map(TyParam::t),
+            _ => None,
+        }
+    }
+
     pub fn is_erased(&self) -> bool {
         match self {
             Self::FreeVar(fv) if fv.is_linked() => fv.crack().is_erased(),
@@ -183,6 +183,24 @@ impl StructuralTypeObj {
     }
 }
 
+/// `Enum { .A = Int; .B = {x = Int} }`
+///
+/// `variants` is a record type whose fields are the variant names and payload types.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EnumTypeObj {
+    pub t: Type,
+    pub variants: Box<TypeObj>,
+}
+
+impl EnumTypeObj {
+    pub fn new(t: Type, variants: TypeObj) -> Self {
+        Self {
+            t,
+            variants: Box::new(variants),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct PatchObj {
     pub t: Type,
@@ -210,6 +228,7 @@ pub enum GenTypeObj {
     Union(UnionTypeObj),
     Intersection(IntersectionTypeObj),
     Patch(PatchObj),
+    Enum(EnumTypeObj),
 }
 
 impl fmt::Display for GenTypeObj {
@@ -269,6 +288,10 @@ impl GenTypeObj {
         GenTypeObj::Structural(StructuralTypeObj::new(t, type_))
     }
 
+    pub fn enum_(t: Type, variants: TypeObj) -> Self {
+        GenTypeObj::Enum(EnumTypeObj::new(t, variants))
+    }
+
     pub const fn is_inited(&self) -> bool {
         match self {
             Self::Class(class) => class.inited,
@@ -319,7 +342,7 @@ impl GenTypeObj {
 
     pub fn meta_type(&self) -> Type {
         match self {
-            Self::Class(_) | Self::Subclass(_) => Type::ClassType,
+            Self::Class(_) | Self::Subclass(_) | Self::Enum(_) => Type::ClassType,
             Self::Trait(_) | Self::Subtrait(_) => Type::TraitType,
             Self::Patch(_) => Type::Patch,
             Self::Structural(_) => Type::Type,
@@ -337,6 +360,7 @@ impl GenTypeObj {
             Self::Union(union_) => &union_.t,
             Self::Intersection(intersection) => &intersection.t,
             Self::Patch(patch) => &patch.t,
+            Self::Enum(enum_) => &enum_.t,
         }
     }
 
@@ -350,6 +374,7 @@ impl GenTypeObj {
             Self::Union(union_) => &mut union_.t,
             Self::Intersection(intersection) => &mut intersection.t,
             Self::Patch(patch) => &mut patch.t,
+            Self::Enum(enum_) => &mut enum_.t,
         }
     }
 
@@ -363,6 +388,7 @@ impl GenTypeObj {
             Self::Union(union_) => union_.t,
             Self::Intersection(intersection) => intersection.t,
             Self::Patch(patch) => patch.t,
+            Self::Enum(enum_) => enum_.t,
         }
     }
 
@@ -1109,7 +1135,32 @@ impl ValueObj {
                 vec![DataTypePrefix::None as u8]
             }
             Self::Code(c) => c.into_bytes(python_ver),
-            // Dict
+            // The elements are sorted so that the same set (dict) is always serialized into the same bytes
+            Self::Set(st) => {
+                let mut elems = st
+                    .into_iter()
+                    .map(|obj| obj.into_bytes(python_ver))
+                    .collect::<Vec<_>>();
+                elems.sort();
+                let mut bytes = vec![DataTypePrefix::FrozenSet as u8];
+                bytes.append(&mut (elems.len() as u32).to_le_bytes().to_vec());
+                bytes.append(&mut elems.concat());
+                bytes
+            }
+            Self::Dict(dict) => {
+                let mut items = dict
+                    .into_iter()
+                    .map(|(k, v)| (k.into_bytes(python_ver), v.into_bytes(python_ver)))
+                    .collect::<Vec<_>>();
+                items.sort();
+                let mut bytes = vec![DataTypePrefix::Dict as u8];
+                for (mut k, mut v) in items {
+                    bytes.append(&mut k);
+                    bytes.append(&mut v);
+                }
+                bytes.push(b'0'); // NULL
+                bytes
+            }
             other => {
                 panic!(
                     "{}",
@@ -2,8 +2,9 @@ use std::fmt;
 use std::path::PathBuf;
 
 use erg_common::error::Location;
+use erg_common::lang::LanguageCode;
 use erg_common::set::Set;
-use erg_common::{switch_lang, Str};
+use erg_common::{switch_lang, trim_eliminate_top_indent, Str};
 
 use erg_parser::ast::DefId;
 
@@ -185,6 +186,56 @@ impl AbsLocation {
                 })
         })
     }
+
+    /// Returns the doc comments (`'''...'''`) placed just before the definition.
+    /// If there are doc comments for multiple languages, the one for the current language is used.
+    pub fn doc_comment(&self) -> Option<String> {
+        let ln_begin = self.loc.ln_begin()? as usize;
+        let src = std::fs::read_to_string(self.module.as_ref()?).ok()?;
+        let lines = src
+            .lines()
+            .take(ln_begin.saturating_sub(1))
+            .collect::<Vec<_>>();
+        let mut end = lines.len();
+        let mut default = None;
+        let mut code_blocks = vec![];
+        while end > 0 && lines[end - 1].trim().ends_with("'''") {
+            let last = lines[end - 1].trim();
+            let start = if last.len() >= 6 && last.starts_with("'''") {
+                end - 1
+            } else {
+                (0..end - 1).rfind(|&i| lines[i].trim_start().starts_with("'''"))?
+            };
+            let block = lines[start..end].join("\n");
+            let content = block
+                .trim()
+                .trim_start_matches("'''")
+                .trim_end_matches("'''");
+            let (first_line, rest) = content.split_once('\n').unwrap_or((content, ""));
+            match first_line.trim().parse::<LanguageCode>() {
+                Ok(lang) if lang.is_pl() && lang.matches_feature() => {
+                    code_blocks.insert(0, trim_eliminate_top_indent(rest.to_string()));
+                }
+                Ok(lang) if lang.matches_feature() => {
+                    default = Some(trim_eliminate_top_indent(rest.to_string()));
+                }
+                Ok(lang) if lang.is_en() && default.is_none() => {
+                    default = Some(trim_eliminate_top_indent(rest.to_string()));
+                }
+                Err(_) if default.is_none() => {
+                    default = Some(trim_eliminate_top_indent(content.to_string()));
+                }
+                _ => {}
+            }
+            end = start;
+        }
+        let mut doc = default?;
+        for code in code_blocks {
+            doc.push_str("\n\n");
+            doc.push_str(&code);
+        }
+        Some(doc)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
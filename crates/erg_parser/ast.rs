@@ -2346,7 +2346,42 @@ impl PolyTypeSpec {
     }
 
     pub fn ident(&self) -> String {
-        self.acc.to_string()
+        match &self.acc {
+            ConstAccessor::Local(ident) => ident.inspect().to_string(),
+            other => other.to_string(),
+        }
+    }
+
+    /// Split the state transition arguments (`~>`) into the states before and after.
+    /// ```erg
+    /// K(T, N ~> N+1) ==> (K(T, N), K(T, N+1))
+    /// ```
+    pub fn split_transition(&self) -> Option<(Self, Self)> {
+        let is_transition = |arg: &ConstPosArg| matches!(&arg.expr, ConstExpr::BinOp(bin) if bin.op.is(TokenKind::Transition));
+        if !self.args.pos_args().any(is_transition) {
+            return None;
+        }
+        let (pos_args, var_args, kw_args, paren) = self.args.clone().deconstruct();
+        let mut before = vec![];
+        let mut after = vec![];
+        for arg in pos_args.into_iter() {
+            match arg.expr {
+                ConstExpr::BinOp(bin) if bin.op.is(TokenKind::Transition) => {
+                    before.push(ConstPosArg::new(*bin.lhs));
+                    after.push(ConstPosArg::new(*bin.rhs));
+                }
+                expr => {
+                    before.push(ConstPosArg::new(expr.clone()));
+                    after.push(ConstPosArg::new(expr));
+                }
+            }
+        }
+        let before = ConstArgs::new(before, var_args.clone(), kw_args.clone(), paren.clone());
+        let after = ConstArgs::new(after, var_args, kw_args, paren);
+        Some((
+            Self::new(self.acc.clone(), before),
+            Self::new(self.acc.clone(), after),
+        ))
     }
 }
 
@@ -2965,6 +3000,14 @@ impl TypeBoundSpec {
             default,
         }
     }
+
+    pub const fn lhs(&self) -> &VarName {
+        match self {
+            Self::Omitted(name) => name,
+            Self::NonDefault { lhs, .. } => lhs,
+            Self::WithDefault { lhs, .. } => lhs,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -3870,6 +3913,33 @@ impl ParamRecordPattern {
     }
 }
 
+/// e.g. `E.A{x; y}`, `E.B(x)` (== `E.B{_0 = x}`)
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ParamDataPackPattern {
+    pub class: TypeSpec,
+    pub class_as_expr: Box<Expr>,
+    pub args: ParamRecordPattern,
+}
+
+impl NestedDisplay for ParamDataPackPattern {
+    fn fmt_nest(&self, f: &mut fmt::Formatter<'_>, _level: usize) -> fmt::Result {
+        write!(f, "{}{}", self.class, self.args)
+    }
+}
+
+impl_display_from_nested!(ParamDataPackPattern);
+impl_locational!(ParamDataPackPattern, class, args);
+
+impl ParamDataPackPattern {
+    pub const fn new(class: TypeSpec, class_as_expr: Box<Expr>, args: ParamRecordPattern) -> Self {
+        Self {
+            class,
+            class_as_expr,
+            args,
+        }
+    }
+}
+
 /// 関数定義や無名関数で使えるパターン
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum ParamPattern {
@@ -3880,7 +3950,7 @@ pub enum ParamPattern {
     Array(ParamArrayPattern),
     Tuple(ParamTuplePattern),
     Record(ParamRecordPattern),
-    // DataPack(ParamDataPackPattern),
+    DataPack(ParamDataPackPattern),
     Ref(VarName),
     RefMut(VarName),
 }
@@ -3894,6 +3964,7 @@ impl NestedDisplay for ParamPattern {
             Self::Array(array) => write!(f, "{array}"),
             Self::Tuple(tuple) => write!(f, "{tuple}"),
             Self::Record(record) => write!(f, "{record}"),
+            Self::DataPack(pack) => write!(f, "{pack}"),
             Self::Ref(var_name) => write!(f, "ref {var_name}"),
             Self::RefMut(var_name) => write!(f, "ref! {var_name}"),
         }
@@ -3915,7 +3986,7 @@ impl TryFrom<&ParamPattern> for Expr {
 }
 
 impl_display_from_nested!(ParamPattern);
-impl_locational_for_enum!(ParamPattern; Discard, VarName, Lit, Array, Tuple, Record, Ref, RefMut);
+impl_locational_for_enum!(ParamPattern; Discard, VarName, Lit, Array, Tuple, Record, DataPack, Ref, RefMut);
 
 impl ParamPattern {
     pub const fn inspect(&self) -> Option<&Str> {
@@ -4182,6 +4253,13 @@ impl SubrSignature {
         self.ident.is_const()
     }
 
+    /// e.g. `@Async\nfetch! url = ...`
+    pub fn is_async(&self) -> bool {
+        self.decorators.iter().any(|deco| {
+            matches!(deco.expr(), Expr::Accessor(Accessor::Ident(ident)) if &ident.inspect()[..] == "Async")
+        })
+    }
+
     pub fn vis(&self) -> &VisModifierSpec {
         &self.ident.vis
     }
@@ -4507,7 +4585,7 @@ impl DefBody {
     pub fn def_kind(&self) -> DefKind {
         match self.block.first().unwrap() {
             Expr::Call(call) => match call.obj.get_name().map(|n| &n[..]) {
-                Some("Class") => DefKind::Class,
+                Some("Class" | "Enum") => DefKind::Class,
                 Some("Inherit") => DefKind::Inherit,
                 Some("Trait") => DefKind::Trait,
                 Some("Subsume") => DefKind::Subsume,
@@ -4522,6 +4600,16 @@ impl DefBody {
                         DefKind::Other
                     }
                 }
+                Some("Structural") => {
+                    if let Some(Expr::Call(inner)) = call.args.get_left_or_key("Type") {
+                        match inner.obj.get_name().map(|n| &n[..]) {
+                            Some("Trait") => DefKind::StructuralTrait,
+                            _ => DefKind::Other,
+                        }
+                    } else {
+                        DefKind::Other
+                    }
+                }
                 Some("Patch") => DefKind::Patch,
                 Some("import") => DefKind::ErgImport,
                 Some("pyimport") | Some("py") | Some("__import__") => DefKind::PyImport,
@@ -1,10 +1,12 @@
 use erg_common::config::ErgConfig;
+use erg_common::style::Highlight;
 use erg_common::traits::{ExitStatus, Runnable};
 use erg_common::Str;
 
 use crate::ast::AST;
 use crate::desugar::Desugarer;
 use crate::error::{CompleteArtifact, IncompleteArtifact, ParserRunnerError, ParserRunnerErrors};
+use crate::highlight::highlight;
 use crate::parse::ParserRunner;
 
 /// Summarize parsing and desugaring
@@ -54,6 +56,14 @@ impl Runnable for ASTBuilder {
         let artifact = self.build(src).map_err(|iart| iart.errors)?;
         Ok(format!("{}", artifact.ast))
     }
+
+    fn highlight(&self, line: &str, cursor: usize) -> Vec<Highlight> {
+        highlight(line, cursor)
+    }
+
+    fn pre_check(&mut self, src: String) -> Result<(), ParserRunnerErrors> {
+        self.runner.pre_check(src)
+    }
 }
 
 impl ASTBuilder {
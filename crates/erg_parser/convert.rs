@@ -5,7 +5,7 @@ use crate::ast::*;
 use crate::debug_call_info;
 use crate::debug_exit_info;
 use crate::error::{ParseError, ParseResult};
-use crate::token::{Token, TokenKind};
+use crate::token::{Token, TokenKind, COLON};
 use crate::Parser;
 
 impl Parser {
@@ -468,6 +468,22 @@ impl Parser {
                 debug_exit_info!(self);
                 Ok(param)
             }
+            Expr::DataPack(pack) => {
+                let pack = self
+                    .convert_data_pack_to_param_data_pack_pat(pack)
+                    .map_err(|_| self.stack_dec(fn_name!()))?;
+                let param = NonDefaultParamSignature::new(ParamPattern::DataPack(pack), None);
+                debug_exit_info!(self);
+                Ok(param)
+            }
+            Expr::Call(call) if Self::is_variant_pattern(&call) => {
+                let pack = self
+                    .convert_call_to_param_data_pack_pat(call)
+                    .map_err(|_| self.stack_dec(fn_name!()))?;
+                let param = NonDefaultParamSignature::new(ParamPattern::DataPack(pack), None);
+                debug_exit_info!(self);
+                Ok(param)
+            }
             Expr::TypeAscription(tasc) => {
                 let param = self
                     .convert_type_asc_to_param_pattern(tasc, allow_self)
@@ -491,6 +507,14 @@ impl Parser {
                 }
                 TokenKind::RefMutOp => {
                     let var = unary.args.into_iter().next().unwrap();
+                    // e.g. `ref! self(T, N ~> N+1)` ==> `ref! self: Self(T, N ~> N+1)`
+                    if let Expr::Call(call) = *var {
+                        let param = self
+                            .convert_ref_mut_call_to_param(call)
+                            .map_err(|_| self.stack_dec(fn_name!()))?;
+                        debug_exit_info!(self);
+                        return Ok(param);
+                    }
                     let Expr::Accessor(Accessor::Ident(var)) = *var else {
                         let err = ParseError::simple_syntax_error(line!() as usize, var.loc());
                         self.errs.push(err);
@@ -519,6 +543,35 @@ impl Parser {
         }
     }
 
+    fn convert_ref_mut_call_to_param(
+        &mut self,
+        call: Call,
+    ) -> ParseResult<NonDefaultParamSignature> {
+        debug_call_info!(self);
+        if let Some(attr) = &call.attr_name {
+            let err = ParseError::simple_syntax_error(line!() as usize, attr.loc());
+            self.errs.push(err);
+            debug_exit_info!(self);
+            return Err(());
+        }
+        let Expr::Accessor(Accessor::Ident(var)) = *call.obj else {
+            let err = ParseError::simple_syntax_error(line!() as usize, call.obj.loc());
+            self.errs.push(err);
+            debug_exit_info!(self);
+            return Err(());
+        };
+        let self_ty = Identifier::private_with_loc("Self".into(), var.loc());
+        let t_spec_as_expr = Expr::Call(Call::new(self_ty.into(), None, call.args));
+        let t_spec = Self::expr_to_type_spec(t_spec_as_expr.clone()).map_err(|err| {
+            self.errs.push(err);
+            self.stack_dec(fn_name!())
+        })?;
+        let t_spec = TypeSpecWithOp::new(COLON, t_spec, t_spec_as_expr);
+        let param = NonDefaultParamSignature::new(ParamPattern::RefMut(var.name), Some(t_spec));
+        debug_exit_info!(self);
+        Ok(param)
+    }
+
     fn convert_kw_arg_to_default_param(
         &mut self,
         arg: KwArg,
@@ -702,6 +755,15 @@ impl Parser {
                 debug_exit_info!(self);
                 Ok(LambdaSignature::new(params, None, TypeBoundSpecs::empty()))
             }
+            Expr::DataPack(pack) => {
+                let pack = self
+                    .convert_data_pack_to_param_data_pack_pat(pack)
+                    .map_err(|_| self.stack_dec(fn_name!()))?;
+                let param = NonDefaultParamSignature::new(ParamPattern::DataPack(pack), None);
+                let params = Params::single(param);
+                debug_exit_info!(self);
+                Ok(LambdaSignature::new(params, None, TypeBoundSpecs::empty()))
+            }
             Expr::TypeAscription(tasc) => {
                 let sig = self
                     .convert_type_asc_to_lambda_sig(tasc)
@@ -761,6 +823,15 @@ impl Parser {
     }
 
     fn convert_call_to_param_sig(&mut self, call: Call) -> ParseResult<NonDefaultParamSignature> {
+        if Self::is_variant_pattern(&call) {
+            let pack = self
+                .convert_call_to_param_data_pack_pat(call)
+                .map_err(|_| self.stack_dec(fn_name!()))?;
+            return Ok(NonDefaultParamSignature::new(
+                ParamPattern::DataPack(pack),
+                None,
+            ));
+        }
         let predecl = Self::call_to_predecl_type_spec(call.clone()).map_err(|_| ())?;
         let t_spec =
             TypeSpecWithOp::new(Token::DUMMY, TypeSpec::PreDeclTy(predecl), Expr::Call(call));
@@ -770,6 +841,73 @@ impl Parser {
         ))
     }
 
+    /// `E.A(x, _)` (an enum variant pattern) or `E.A(Int)` (a type)
+    fn is_variant_pattern(call: &Call) -> bool {
+        let Some(attr) = &call.attr_name else {
+            return false;
+        };
+        let is_pat = |expr: &Expr| match expr {
+            Expr::Accessor(Accessor::Ident(ident)) => ident
+                .inspect()
+                .chars()
+                .next()
+                .is_some_and(|c| c == '_' || c.is_lowercase()),
+            Expr::Literal(_) => true,
+            _ => false,
+        };
+        attr.inspect()
+            .chars()
+            .next()
+            .is_some_and(char::is_uppercase)
+            && matches!(call.obj.as_ref(), Expr::Accessor(_))
+            && call.args.kw_args().is_empty()
+            && call.args.var_args.is_none()
+            && call.args.pos_args().iter().all(|arg| is_pat(&arg.expr))
+    }
+
+    /// `E.A(x, y)` => `E.A{_0 = x; _1 = y}`
+    fn convert_call_to_param_data_pack_pat(
+        &mut self,
+        call: Call,
+    ) -> ParseResult<ParamDataPackPattern> {
+        debug_call_info!(self);
+        let class_as_expr = Expr::Accessor(call.obj.attr(call.attr_name.unwrap()));
+        let class =
+            Self::expr_to_type_spec(class_as_expr.clone()).map_err(|e| self.errs.push(e))?;
+        let (pos_args, _, _, paren) = call.args.deconstruct();
+        let (l_paren, r_paren) = paren.unwrap_or((Token::DUMMY, Token::DUMMY));
+        let mut attrs = vec![];
+        for (i, arg) in pos_args.into_iter().enumerate() {
+            let lhs =
+                Identifier::public_with_line(Token::DUMMY, format!("_{i}").into(), l_paren.lineno);
+            let rhs = self
+                .convert_pos_arg_to_non_default_param(arg, false)
+                .map_err(|_| self.stack_dec(fn_name!()))?;
+            attrs.push(ParamRecordAttr::new(lhs, rhs));
+        }
+        let args = ParamRecordPattern::new(l_paren, ParamRecordAttrs::new(attrs), r_paren);
+        debug_exit_info!(self);
+        Ok(ParamDataPackPattern::new(
+            class,
+            Box::new(class_as_expr),
+            args,
+        ))
+    }
+
+    /// `E.A{x; y}`
+    fn convert_data_pack_to_param_data_pack_pat(
+        &mut self,
+        pack: DataPack,
+    ) -> ParseResult<ParamDataPackPattern> {
+        debug_call_info!(self);
+        let class = Self::expr_to_type_spec(*pack.class.clone()).map_err(|e| self.errs.push(e))?;
+        let args = self
+            .convert_record_to_param_record_pat(pack.args)
+            .map_err(|_| self.stack_dec(fn_name!()))?;
+        debug_exit_info!(self);
+        Ok(ParamDataPackPattern::new(class, pack.class, args))
+    }
+
     fn convert_tuple_to_params(&mut self, tuple: Tuple) -> ParseResult<Params> {
         debug_call_info!(self);
         match tuple {
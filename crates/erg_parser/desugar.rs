@@ -81,6 +81,7 @@ impl Desugarer {
         let module = Self::desugar_shortened_record(module);
         let module = Self::desugar_acc(module);
         let module = Self::desugar_operator(module);
+        let module = Self::desugar_constructor(module);
         log!(info "AST (desugared):\n{module}");
         log!(info "the desugaring process has completed.");
         module
@@ -1212,22 +1213,26 @@ impl Desugarer {
                 }
                 param.pat = buf_param;
             }
-            /*ParamPattern::DataPack(pack) => {
-                let (buf_name, buf_sig) = self.gen_buf_name_and_sig(
-                    v.ln_begin().unwrap(),
-                    Some(pack.class.clone()), // TODO: これだとvの型指定の意味がなくなる
-                );
-                let buf_def = Def::new(buf_sig, body);
-                new.push(Expr::Def(buf_def));
-                for VarRecordAttr { lhs, rhs } in pack.args.attrs.iter() {
-                    self.desugar_nested_var_pattern(
-                        &mut new,
+            ParamPattern::DataPack(pack) => {
+                let (buf_name, buf_param) = self.gen_buf_nd_param(pack.loc());
+                for ParamRecordAttr { lhs, rhs } in pack.args.elems.iter_mut() {
+                    insertion_idx = self.desugar_nested_param_pattern(
+                        body,
                         rhs,
                         &buf_name,
                         BufIndex::Record(lhs),
+                        insertion_idx,
                     );
                 }
-            }*/
+                // E.A{x; y} -> ...
+                // => %buf: E.A -> (x = %buf.x; y = %buf.y; ...)
+                if param.t_spec.is_none() {
+                    let t_spec =
+                        TypeSpecWithOp::new(COLON, pack.class.clone(), *pack.class_as_expr.clone());
+                    param.t_spec = Some(t_spec);
+                }
+                param.pat = buf_param;
+            }
             _ => {}
         }
     }
@@ -1437,22 +1442,37 @@ impl Desugarer {
                 sig.pat = buf_sig;
                 insertion_idx
             }
-            /*
-            VarPattern::DataPack(pack) => {
-                let (buf_name, buf_sig) =
-                    self.gen_buf_name_and_sig(sig.ln_begin().unwrap(), Some(pack.class.clone()));
-                let buf_def = Def::new(buf_sig, body);
-                new_module.push(Expr::Def(buf_def));
-                for VarRecordAttr { lhs, rhs } in pack.args.attrs.iter() {
-                    self.desugar_nested_var_pattern(
-                        new_module,
+            ParamPattern::DataPack(pack) => {
+                let (buf_name, buf_sig) = self.gen_buf_nd_param(pack.loc());
+                let t_spec = sig.t_spec.clone().unwrap_or(TypeSpecWithOp::new(
+                    COLON,
+                    pack.class.clone(),
+                    *pack.class_as_expr.clone(),
+                ));
+                new_body.insert(
+                    insertion_idx,
+                    Expr::Def(Def::new(
+                        Signature::Var(VarSignature::new(
+                            VarPattern::Ident(Identifier::private(Str::from(&buf_name))),
+                            Some(t_spec.clone()),
+                        )),
+                        body,
+                    )),
+                );
+                insertion_idx += 1;
+                for ParamRecordAttr { lhs, rhs } in pack.args.elems.iter_mut() {
+                    insertion_idx = self.desugar_nested_param_pattern(
+                        new_body,
                         rhs,
                         &buf_name,
                         BufIndex::Record(lhs),
+                        insertion_idx,
                     );
                 }
+                sig.t_spec = Some(t_spec);
+                sig.pat = buf_sig;
+                insertion_idx
             }
-            */
             ParamPattern::VarName(name) => {
                 let ident = Identifier::new(VisModifierSpec::Private, name.clone());
                 let v = VarSignature::new(VarPattern::Ident(ident), sig.t_spec.clone());
@@ -1492,6 +1512,50 @@ impl Desugarer {
         todo!()
     }
 
+    /// `new = C::__new__ {...}` (in a methods block) => `new() = C::__new__ {...}`
+    fn desugar_constructor(module: Module) -> Module {
+        Self::desugar_all_chunks(module, Self::rec_desugar_constructor)
+    }
+
+    fn rec_desugar_constructor(expr: Expr) -> Expr {
+        match expr {
+            Expr::Methods(methods) => {
+                let attrs = methods
+                    .attrs
+                    .into_iter()
+                    .map(|attr| match attr {
+                        ClassAttr::Def(def) => ClassAttr::Def(Self::desugar_constructor_def(def)),
+                        other => other,
+                    })
+                    .collect::<Vec<_>>();
+                Expr::Methods(Methods::new(
+                    methods.class,
+                    *methods.class_as_expr,
+                    methods.vis,
+                    ClassAttrs::from(attrs),
+                ))
+            }
+            expr => Self::perform_desugar(Self::rec_desugar_constructor, expr),
+        }
+    }
+
+    fn desugar_constructor_def(def: Def) -> Def {
+        match def.sig {
+            Signature::Var(VarSignature {
+                pat: VarPattern::Ident(ident),
+                t_spec,
+            }) if &ident.inspect()[..] == "new"
+                && !matches!(def.body.block.first(), Some(Expr::Lambda(_))) =>
+            {
+                let params = Params::new(vec![], None, vec![], None);
+                let sig =
+                    SubrSignature::new(set! {}, ident, TypeBoundSpecs::empty(), params, t_spec);
+                Def::new(Signature::Subr(sig), def.body)
+            }
+            sig => Def::new(sig, def.body),
+        }
+    }
+
     /// x[y] => x.__getitem__(y)
     /// x.0 => x.__Tuple_getitem__(0)
     /// `==`(x, y) => __eq__(x, y)
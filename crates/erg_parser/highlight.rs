@@ -0,0 +1,119 @@
+//! Syntax highlighting for the line editor of the REPL
+use erg_common::style::{Attribute, Color, Highlight};
+
+use crate::lex::Lexer;
+use crate::token::{Token, TokenKind};
+
+use TokenKind::*;
+
+fn token_color(token: &Token) -> Option<Color> {
+    match token.kind {
+        NatLit | IntLit | BinLit | OctLit | HexLit | RatioLit | InfLit => Some(Color::Yellow),
+        StrLit | StrInterpLeft | StrInterpMid | StrInterpRight | DocComment => Some(Color::Green),
+        BoolLit | NoneLit | EllipsisLit => Some(Color::Magenta),
+        InOp | NotInOp | ContainsOp | SubOp | IsOp | IsNotOp | AndOp | OrOp | DotOp | CrossOp
+        | RefOp | RefMutOp | As | Assign | Walrus | FuncArrow | ProcArrow => Some(Color::Magenta),
+        Symbol if token.is_const() => Some(Color::Cyan),
+        Symbol if token.is_procedural() => Some(Color::Blue),
+        Illegal => Some(Color::Red),
+        _ => None,
+    }
+}
+
+const fn closing_bracket(kind: TokenKind) -> Option<TokenKind> {
+    match kind {
+        LParen => Some(RParen),
+        LSqBr => Some(RSqBr),
+        LBrace => Some(RBrace),
+        _ => None,
+    }
+}
+
+/// Returns the highlights of `line` (char indices).
+/// Comments are grayed out, unmatched closing brackets are red,
+/// and the bracket at (or just before) `cursor` is reversed with its pair.
+///
+/// ```
+/// # use erg_common::style::{Attribute, Color};
+/// # use erg_parser::highlight::highlight;
+/// let hls = highlight("f(1)", 4);
+/// assert!(hls.iter().any(|hl| hl.range == (2..3) && hl.color == Some(Color::Yellow)));
+/// assert!(hls.iter().any(|hl| hl.range == (1..2) && hl.attribute == Some(Attribute::Reversed)));
+/// ```
+pub fn highlight(line: &str, cursor: usize) -> Vec<Highlight> {
+    // the lines in a block are entered without the indentation
+    let indent = line.chars().take_while(|c| *c == ' ').count();
+    let mut highlights = vec![];
+    let mut token_ranges = vec![];
+    // (position, expected closing bracket)
+    let mut opened = vec![];
+    let mut pairs = vec![];
+    for token in Lexer::from_str(line[indent..].to_string()) {
+        // the rest of the line is left as it is
+        let Ok(token) = token else {
+            break;
+        };
+        if token.is(EOF) || token.lineno > 1 {
+            break;
+        }
+        if token.content.is_empty() {
+            continue;
+        }
+        let range = indent + token.col_begin as usize..indent + token.col_end as usize;
+        if let Some(closing) = closing_bracket(token.kind) {
+            opened.push((range.start, closing));
+        } else if matches!(token.kind, RParen | RSqBr | RBrace) {
+            match opened.last() {
+                Some((open, closing)) if *closing == token.kind => {
+                    pairs.push((*open, range.start));
+                    opened.pop();
+                }
+                _ => highlights.push(Highlight::new(range.clone(), Some(Color::Red), None)),
+            }
+        }
+        if let Some(color) = token_color(&token) {
+            highlights.push(Highlight::new(range.clone(), Some(color), None));
+        }
+        token_ranges.push(range);
+    }
+    let chars = line.chars().collect::<Vec<_>>();
+    for (i, c) in chars.iter().enumerate() {
+        if *c != '#' || token_ranges.iter().any(|range| range.contains(&i)) {
+            continue;
+        }
+        if highlights
+            .last()
+            .is_some_and(|hl| hl.color == Some(Color::Gray) && hl.range.contains(&i))
+        {
+            continue;
+        }
+        let end = token_ranges
+            .iter()
+            .map(|range| range.start)
+            .find(|start| *start > i)
+            .unwrap_or(chars.len());
+        highlights.push(Highlight::new(i..end, Some(Color::Gray), None));
+    }
+    let matched = pairs
+        .iter()
+        .find(|(open, close)| {
+            cursor
+                .checked_sub(1)
+                .is_some_and(|c| c == *open || c == *close)
+        })
+        .or_else(|| {
+            pairs
+                .iter()
+                .find(|(open, close)| cursor == *open || cursor == *close)
+        });
+    if let Some((open, close)) = matched {
+        for pos in [*open, *close] {
+            highlights.push(Highlight::new(
+                pos..pos + 1,
+                None,
+                Some(Attribute::Reversed),
+            ));
+        }
+    }
+    highlights
+}
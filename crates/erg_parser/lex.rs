@@ -7,11 +7,13 @@ use unicode_xid::UnicodeXID;
 use erg_common::cache::CacheSet;
 use erg_common::config::ErgConfig;
 use erg_common::io::Input;
+use erg_common::style::Highlight;
 use erg_common::traits::DequeStream;
 use erg_common::traits::{Runnable, Stream};
 use erg_common::{debug_power_assert, fn_name_full, normalize_newline, switch_lang};
 
 use crate::error::{LexError, LexErrors, LexResult, LexerRunnerError, LexerRunnerErrors};
+use crate::highlight::highlight;
 use crate::token::{Token, TokenCategory, TokenKind, TokenStream};
 use TokenKind::*;
 
@@ -105,6 +107,10 @@ impl Runnable for LexerRunner {
                 .to_string())
         }
     }
+
+    fn highlight(&self, line: &str, cursor: usize) -> Vec<Highlight> {
+        highlight(line, cursor)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -1331,7 +1337,14 @@ impl Iterator for Lexer /*<'a>*/ {
                     self.accept(Caret, "^")
                 }
             }
-            Some('~') => self.accept(PreBitNot, "~"),
+            Some('~') => {
+                if let Some('>') = self.peek_cur_ch() {
+                    self.consume();
+                    self.accept(Transition, "~>")
+                } else {
+                    self.accept(PreBitNot, "~")
+                }
+            }
             // TODO:
             Some('$') => self.deny_feature("$", "shared variables"),
             Some('@') => self.accept(AtSign, "@"),
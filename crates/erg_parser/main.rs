@@ -4,6 +4,7 @@ extern crate erg_parser;
 use std::process;
 
 use erg_common::config::{ErgConfig, ErgMode::*};
+use erg_common::explain;
 use erg_common::spawn::exec_new_thread;
 use erg_common::traits::{ExitStatus, Runnable};
 
@@ -13,15 +14,18 @@ use erg_parser::ParserRunner;
 
 fn run() {
     let cfg = ErgConfig::parse();
+    let error_format = cfg.error_format.clone();
     let stat = match cfg.mode {
         Lex => LexerRunner::run(cfg),
         Parse => ParserRunner::run(cfg),
         Desugar | Execute => ASTBuilder::run(cfg),
+        Explain => explain::run(cfg),
         other => {
             eprintln!("invalid mode: {other}");
             ExitStatus::ERR1
         }
     };
+    error_format.flush_sarif_log(&mut std::io::stderr());
     process::exit(stat.code);
 }
 
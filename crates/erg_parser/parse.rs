@@ -9,6 +9,7 @@ use erg_common::error::Location;
 use erg_common::io::{Input, InputKind};
 use erg_common::set::Set as HashSet;
 use erg_common::str::Str;
+use erg_common::style::Highlight;
 use erg_common::traits::{DequeStream, ExitStatus, Locational, Runnable, Stream};
 use erg_common::{
     caused_by, debug_power_assert, enum_unwrap, fn_name, impl_display_for_enum,
@@ -21,6 +22,7 @@ use crate::error::{
     CompleteArtifact, IncompleteArtifact, ParseError, ParseErrors, ParseResult, ParserRunnerError,
     ParserRunnerErrors,
 };
+use crate::highlight::highlight;
 use crate::lex::Lexer;
 use crate::token::{Token, TokenCategory, TokenKind, TokenStream};
 
@@ -432,6 +434,14 @@ impl Runnable for ParserRunner {
         let artifact = self.parse(src).map_err(|iart| iart.errors)?;
         Ok(format!("{}", artifact.ast))
     }
+
+    fn highlight(&self, line: &str, cursor: usize) -> Vec<Highlight> {
+        highlight(line, cursor)
+    }
+
+    fn pre_check(&mut self, src: String) -> Result<(), ParserRunnerErrors> {
+        self.parse(src).map(|_| ()).map_err(|iart| iart.errors)
+    }
 }
 
 impl ParserRunner {
@@ -1231,6 +1241,16 @@ impl Parser {
                     }
                 }
             }
+            // e.g. `Array!(*, 0)` (the first argument is inferred)
+            Some(PreStar) if self.nth_is(1, Comma) || self.nth_is(1, RParen) => {
+                let mut star = self.lpop();
+                star.kind = Symbol;
+                let ident = Identifier::private_from_token(star);
+                debug_exit_info!(self);
+                Ok(ArgKind::Pos(PosArg::new(Expr::Accessor(Accessor::Ident(
+                    ident,
+                )))))
+            }
             Some(PreStar) => {
                 self.skip();
                 let expr = self
@@ -2532,6 +2552,38 @@ impl Parser {
                         }
                     }
                 }
+                // e.g. `E.A{x; y}` (data pack of an enum variant)
+                Some(t)
+                    if t.is(LBrace)
+                        && obj.col_end() == t.col_begin()
+                        && matches!(&obj, Expr::Accessor(Accessor::Attr(attr)) if attr.ident.is_const()) =>
+                {
+                    let connector = VisModifierSpec::Public(Token::new_fake(
+                        Dot,
+                        ".",
+                        t.lineno,
+                        t.col_begin,
+                        t.col_begin,
+                    ));
+                    let args = self
+                        .try_reduce_brace_container()
+                        .map_err(|_| self.stack_dec(fn_name!()))?;
+                    match args {
+                        BraceContainer::Record(args) => {
+                            obj = Expr::DataPack(DataPack::new(obj, connector, args));
+                        }
+                        other => {
+                            let err = ParseError::invalid_data_pack_definition(
+                                line!() as usize,
+                                other.loc(),
+                                other.kind(),
+                            );
+                            self.errs.push(err);
+                            debug_exit_info!(self);
+                            return Err(());
+                        }
+                    }
+                }
                 // e.g. l[0].0
                 Some(t) if t.is(RatioLit) && obj.col_end() == t.col_begin() => {
                     let mut token = self.lpop();
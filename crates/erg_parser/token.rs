@@ -136,6 +136,8 @@ pub enum TokenKind {
     FuncArrow,
     /// =>
     ProcArrow,
+    /// ~> (state transition)
+    Transition,
     /// (
     LParen,
     /// )
@@ -283,6 +285,7 @@ impl TokenKind {
             | IsNotOp => 90, // < > <= >= == != in notin contains is isnot
             AndOp => 80,                                              // and
             OrOp => 70,                                               // or
+            Transition => 65,                                         // ~>
             FuncArrow | ProcArrow | Inclusion => 60,                  // -> => <-
             Colon | SupertypeOf | SubtypeOf | As => 50,               // : :> <: as
             Comma => 40,                                              // ,
@@ -132,13 +132,24 @@ impl Parser {
                     ));
                 };*/
                 let attr_name = call.attr_name;
-                let (pos_args, _, _, paren) = call.args.deconstruct();
+                let (pos_args, var_args, kw_args, paren) = call.args.deconstruct();
                 let mut const_pos_args = vec![];
                 for elem in pos_args.into_iter() {
                     let const_expr = Self::validate_const_expr(elem.expr)?;
                     const_pos_args.push(ConstPosArg::new(const_expr));
                 }
-                let args = ConstArgs::pos_only(const_pos_args, paren);
+                let const_var_args = if let Some(var_args) = var_args {
+                    let const_expr = Self::validate_const_expr(var_args.expr)?;
+                    Some(ConstPosArg::new(const_expr))
+                } else {
+                    None
+                };
+                let mut const_kw_args = vec![];
+                for elem in kw_args.into_iter() {
+                    let const_expr = Self::validate_const_expr(elem.expr)?;
+                    const_kw_args.push(ConstKwArg::new(elem.keyword, const_expr));
+                }
+                let args = ConstArgs::new(const_pos_args, const_var_args, const_kw_args, paren);
                 Ok(ConstExpr::App(ConstApp::new(obj, attr_name, args)))
             }
             Expr::Def(def) => Self::validate_const_def(def).map(ConstExpr::Def),
@@ -387,7 +398,11 @@ impl Parser {
             Dict::Normal(dic) => {
                 let mut kvs = vec![];
                 for kv in dic.kvs.into_iter() {
-                    let key = Self::expr_to_type_spec(kv.key)?;
+                    // e.g. `{"name": Str}` == `{{"name"}: Str}`
+                    let key = match kv.key {
+                        Expr::Literal(lit) => TypeSpec::enum_t_spec(vec![lit]),
+                        other => Self::expr_to_type_spec(other)?,
+                    };
                     let value = Self::expr_to_type_spec(kv.value)?;
                     kvs.push((key, value));
                 }
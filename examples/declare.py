@@ -11,3 +11,7 @@ class C:
 
     def f(self, y: int) -> int:
         return self.x + y
+
+
+def greet(p) -> str:
+    return "Hello, " + p["name"]
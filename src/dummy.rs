@@ -1,21 +1,31 @@
 use std::fs::remove_file;
 use std::io::{Read, Write};
+use std::mem;
 use std::net::{Ipv4Addr, SocketAddrV4, TcpListener, TcpStream};
+use std::path::PathBuf;
 use std::process;
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use erg_common::config::ErgConfig;
-use erg_common::error::MultiErrorDisplay;
+use erg_common::error::{ErrorKind, Location, MultiErrorDisplay};
+use erg_common::interrupt::InterruptGuard;
+use erg_common::io::Input;
 use erg_common::python_util::spawn_py;
+use erg_common::style::Highlight;
 use erg_common::traits::{ExitStatus, Runnable, Stream};
 
+use erg_compiler::context::ContextProvider;
 use erg_compiler::hir::Expr;
-use erg_compiler::ty::HasType;
+use erg_compiler::ty::{HasType, Type};
 
 use erg_compiler::error::{CompileError, CompileErrors};
 use erg_compiler::Compiler;
 
+use erg_parser::highlight::highlight;
+
+use crate::value::Value;
+
 pub type EvalError = CompileError;
 pub type EvalErrors = CompileErrors;
 
@@ -35,6 +45,15 @@ enum Inst {
     Exit = 0x05,
     /// Send from client to server. Let the server to execute the code.
     Execute = 0x06,
+    /// Send from server to client. Represents an uncaught exception (see `RuntimeException`).
+    Error = 0x07,
+    /// Send from client to server. Raises `KeyboardInterrupt` in the running code.
+    Interrupt = 0x08,
+    /// Send from client to server. Sets the display mode (see `DisplayMode`).
+    /// Send from server to client. Represents the output and the representations of the result (see `parse_reprs`).
+    Display = 0x09,
+    /// Send from server to client. Represents the output and the result (see `Value::parse`).
+    Value = 0x0a,
     /// Informs that it is not a supported instruction.
     Unknown = 0x00,
 }
@@ -48,6 +67,10 @@ impl From<u8> for Inst {
             0x04 => Inst::Initialize,
             0x05 => Inst::Exit,
             0x06 => Inst::Execute,
+            0x07 => Inst::Error,
+            0x08 => Inst::Interrupt,
+            0x09 => Inst::Display,
+            0x0a => Inst::Value,
             _ => Inst::Unknown,
         }
     }
@@ -132,6 +155,48 @@ impl<T: Read + Write> MessageStream<T> {
     }
 }
 
+impl MessageStream<TcpStream> {
+    /// Waits for the response of the server.
+    /// If Ctrl+C is pressed or the server does not respond within `timeout`,
+    /// sends `Inst::Interrupt` (only once) and keeps waiting for the response.
+    /// Returns `true` as the second value if the evaluation was interrupted because of `timeout`.
+    fn recv_response(&mut self, timeout: Duration) -> Result<(Message, bool), std::io::Error> {
+        let guard = InterruptGuard::new();
+        let start = Instant::now();
+        let mut interrupted_at = None;
+        let mut timed_out = false;
+        self.stream
+            .set_read_timeout(Some(Duration::from_millis(100)))?;
+        loop {
+            match self.stream.peek(&mut [0; 1]) {
+                Ok(_) => break,
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) => {}
+                Err(err) => return Err(err),
+            }
+            match interrupted_at {
+                None if guard.interrupted() || start.elapsed() > timeout => {
+                    timed_out = start.elapsed() > timeout;
+                    self.send_msg(&Message::new(Inst::Interrupt, None))?;
+                    interrupted_at = Some(Instant::now());
+                }
+                Some(at) if at.elapsed() > timeout => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::TimedOut,
+                        "the REPL server does not respond",
+                    ));
+                }
+                _ => {}
+            }
+        }
+        self.stream.set_read_timeout(Some(timeout))?;
+        self.recv_msg().map(|msg| (msg, timed_out))
+    }
+}
+
 #[test]
 fn test_message() {
     use std::collections::VecDeque;
@@ -166,6 +231,144 @@ fn test_message() {
     assert_eq!(std::str::from_utf8(&msg.data.unwrap()).unwrap(), "A");
 }
 
+/// The data of `Inst::Error`: the output before the exception, the exception type, the message,
+/// and the traceback frames (file, line, function), separated by `GROUP_SEP`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RuntimeException {
+    output: String,
+    ty: String,
+    msg: String,
+    frames: Vec<(String, u32, String)>,
+}
+
+const GROUP_SEP: char = '\x1d';
+const RECORD_SEP: char = '\x1e';
+const UNIT_SEP: char = '\x1f';
+
+impl RuntimeException {
+    fn parse(data: &str) -> Self {
+        let mut groups = data.splitn(4, GROUP_SEP);
+        let output = groups.next().unwrap_or_default().to_string();
+        let ty = groups.next().unwrap_or("Exception").to_string();
+        let msg = groups.next().unwrap_or_default().to_string();
+        let frames = groups
+            .next()
+            .unwrap_or_default()
+            .split(RECORD_SEP)
+            .filter_map(|frame| {
+                let mut units = frame.split(UNIT_SEP);
+                let file = units.next()?.to_string();
+                let line = units.next()?.parse().ok()?;
+                let name = units.next()?.to_string();
+                Some((file, line, name))
+            })
+            .collect();
+        Self {
+            output,
+            ty,
+            msg,
+            frames,
+        }
+    }
+
+    /// `file` is the file name of the evaluated code, used to locate the error.
+    fn into_error(self, input: Input, file: &str, timeout: Option<u64>) -> EvalError {
+        let (kind, msg) = match &self.ty[..] {
+            "KeyboardInterrupt" if timeout.is_some() => (
+                ErrorKind::TimeoutError,
+                format!(
+                    "the evaluation has been interrupted because it took more than {} seconds",
+                    timeout.unwrap_or_default()
+                ),
+            ),
+            // Python's `TypeError` (etc.) are not the compile errors of Erg
+            "TypeError" => (ErrorKind::RuntimeTypeError, self.msg),
+            "AttributeError" => (ErrorKind::RuntimeAttributeError, self.msg),
+            "UnicodeError" => (ErrorKind::RuntimeUnicodeError, self.msg),
+            ty => match ErrorKind::from(ty) {
+                ErrorKind::UserError => (ErrorKind::UserError, format!("{ty}: {}", self.msg)),
+                kind => (kind, self.msg),
+            },
+        };
+        let loc = self
+            .frames
+            .iter()
+            .rfind(|(f, line, _)| f == file && *line > 0)
+            .map_or(Location::Unknown, |(_, line, _)| Location::Line(*line));
+        let traceback = self
+            .frames
+            .iter()
+            .map(|(file, line, name)| {
+                if *line > 0 {
+                    format!("File \"{file}\", line {line}, in {name}")
+                } else {
+                    format!("File \"{file}\", in {name}")
+                }
+            })
+            .collect();
+        EvalError::runtime_error(input, kind, msg, traceback, loc)
+    }
+}
+
+#[test]
+fn test_runtime_exception() {
+    let data = "out\x1dZeroDivisionError\x1ddivision by zero\x1d<stdin>\x1f1\x1f<module>\x1ex.py\x1f3\x1ff";
+    let exc = RuntimeException::parse(data);
+    assert_eq!(exc.output, "out");
+    assert_eq!(exc.ty, "ZeroDivisionError");
+    assert_eq!(
+        exc.frames,
+        vec![
+            ("<stdin>".to_string(), 1, "<module>".to_string()),
+            ("x.py".to_string(), 3, "f".to_string())
+        ]
+    );
+    let err = exc.into_error(Input::dummy(), "<stdin>", None);
+    assert_eq!(err.core.kind, ErrorKind::ZeroDivisionError);
+    assert_eq!(err.core.loc, Location::Line(1));
+}
+
+/// Parses the representations (MIME type and data) of the result of `Inst::Display`
+fn parse_reprs(data: &str) -> Vec<(String, String)> {
+    data.split(RECORD_SEP)
+        .filter_map(|rep| rep.split_once(UNIT_SEP))
+        .map(|(mime, data)| (mime.to_string(), data.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_parse_reprs() {
+    assert_eq!(
+        parse_reprs("text/plain\x1f1\x1etext/html\x1f<b>1</b>"),
+        vec![
+            ("text/plain".to_string(), "1".to_string()),
+            ("text/html".to_string(), "<b>1</b>".to_string())
+        ]
+    );
+    assert_eq!(parse_reprs(""), vec![]);
+}
+
+/// How the REPL server sends the results
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DisplayMode {
+    /// `Inst::Print` (the output and the `repr`)
+    Plain,
+    /// `Inst::Display`
+    Rich,
+    /// `Inst::Value`
+    Value,
+}
+
+impl DisplayMode {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Plain => "0",
+            Self::Rich => "1",
+            Self::Value => "2",
+        }
+    }
+}
+
 fn find_available_port() -> u16 {
     let socket = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0);
     TcpListener::bind(socket)
@@ -181,6 +384,7 @@ fn find_available_port() -> u16 {
 pub struct DummyVM {
     compiler: Compiler,
     stream: Option<MessageStream<TcpStream>>,
+    display_mode: DisplayMode,
 }
 
 impl Default for DummyVM {
@@ -246,6 +450,7 @@ impl Runnable for DummyVM {
         Self {
             compiler: Compiler::new(cfg),
             stream,
+            display_mode: DisplayMode::Plain,
         }
     }
 
@@ -284,24 +489,125 @@ impl Runnable for DummyVM {
 
     fn exec(&mut self) -> Result<ExitStatus, Self::Errs> {
         let src = self.cfg_mut().input.read();
+        let error_format = self.cfg().error_format.clone();
         let art = self.compiler.compile(src, "exec").map_err(|eart| {
-            eart.warns.write_all_to(&mut self.cfg_mut().output);
+            eart.warns
+                .write_all_to_with(&mut self.cfg_mut().output, &error_format);
             eart.errors
         })?;
-        art.warns.write_all_to(&mut self.cfg_mut().output);
+        art.warns
+            .write_all_to_with(&mut self.cfg_mut().output, &error_format);
         let stat = art.object.exec(self.cfg()).expect("failed to execute");
         let stat = ExitStatus::new(stat.code().unwrap_or(0), art.warns.len(), 0);
         Ok(stat)
     }
 
     fn eval(&mut self, src: String) -> Result<String, EvalErrors> {
-        let arti = self
-            .compiler
-            .eval_compile(src, "eval")
-            .map_err(|eart| eart.errors)?;
+        self.set_display_mode(DisplayMode::Plain);
+        let (mut res, result) = self.execute(src);
+        let last = match result {
+            Ok((last, _)) => last,
+            Err(errs) => {
+                if !res.is_empty() {
+                    writeln!(self.cfg_mut().output, "{res}").unwrap_or(());
+                    self.cfg_mut().output.flush().unwrap_or(());
+                }
+                return Err(errs);
+            }
+        };
+        // If the result of an expression is None, it will not be displayed in the REPL.
+        if res.ends_with("None") {
+            res.truncate(res.len() - 5);
+        }
+
+        if self.cfg().show_type {
+            res.push_str(": ");
+            res.push_str(
+                &last
+                    .as_ref()
+                    .map(|last| last.t())
+                    .unwrap_or_default()
+                    .to_string(),
+            );
+            if let Some(Expr::Def(def)) = last {
+                res.push_str(&format!(" ({})", def.sig.ident()));
+            }
+        }
+        Ok(res)
+    }
+
+    fn type_of(&mut self, src: String) -> Option<Result<String, Self::Errs>> {
+        Some(self.compiler.infer_type(src).map(|t| t.to_string()))
+    }
+
+    fn doc_of(&self, name: &str) -> Option<String> {
+        self.compiler.signature_and_doc(name)
+    }
+
+    fn dir_of(&self, receiver: Option<&str>) -> Option<Vec<String>> {
+        self.compiler.dir_lines(receiver)
+    }
+
+    fn complete(&self, word: &str) -> Vec<String> {
+        self.compiler.completion_candidates(word)
+    }
+
+    fn highlight(&self, line: &str, cursor: usize) -> Vec<Highlight> {
+        highlight(line, cursor)
+    }
+
+    fn pre_check(&mut self, src: String) -> Result<(), Self::Errs> {
+        self.compiler.pre_check(src)
+    }
+
+    fn eval_file(&mut self, path: PathBuf, src: String) -> Result<String, Self::Errs> {
+        let input = self.compiler.replace_input(Input::file(path));
+        let res = self.eval(src);
+        self.compiler.replace_input(input);
+        res
+    }
+
+    fn reload(&mut self) -> Option<Result<Vec<String>, Self::Errs>> {
+        let (names, src) = self.compiler.unload_modified_modules();
+        if names.is_empty() {
+            return Some(Ok(names));
+        }
+        Some(self.eval(src).map(|_| names))
+    }
+}
+
+impl DummyVM {
+    /// Tells the REPL server how to send the results.
+    fn set_display_mode(&mut self, mode: DisplayMode) {
+        if self.display_mode == mode {
+            return;
+        }
+        let msg = Message::new(Inst::Display, Some(mode.as_str().as_bytes().to_vec()));
+        if let Err(err) = self.stream.as_mut().unwrap().send_msg(&msg) {
+            self.finish();
+            eprintln!("Sending error: {err}");
+            process::exit(1);
+        }
+        self.display_mode = mode;
+    }
+
+    /// Compiles `src` and runs it on the REPL server.
+    /// Returns the warnings and the printed output, and the last expression and the result (see `run_script`) or the errors.
+    #[allow(clippy::type_complexity)]
+    fn execute(&mut self, src: String) -> (String, Result<(Option<Expr>, String), EvalErrors>) {
+        let arti = match self.compiler.eval_compile(src, "eval") {
+            Ok(arti) => arti,
+            Err(eart) => return (String::new(), Err(eart.errors)),
+        };
         let ((code, last), warns) = (arti.object, arti.warns);
-        let mut res = warns.to_string();
+        let script = code.into_script(self.compiler.cfg.py_magic_num);
+        let (out, result) = self.run_script(script);
+        (warns.to_string() + &out, result.map(|data| (last, data)))
+    }
 
+    /// Runs the Python `script` on the REPL server.
+    /// Returns the printed output, and the result (empty in `DisplayMode::Plain`) or the errors.
+    fn run_script(&mut self, script: String) -> (String, Result<String, EvalErrors>) {
         macro_rules! err_handle {
             () => {{
                 self.finish();
@@ -315,71 +621,141 @@ impl Runnable for DummyVM {
         }
 
         // Tell the REPL server to execute the code
-        if let Err(err) = self.stream.as_mut().unwrap().send_msg(&Message::new(
-            Inst::Execute,
-            Some(
-                code.into_script(self.compiler.cfg.py_magic_num)
-                    .into_bytes(),
-            ),
-        )) {
+        if let Err(err) = self
+            .stream
+            .as_mut()
+            .unwrap()
+            .send_msg(&Message::new(Inst::Execute, Some(script.into_bytes())))
+        {
             err_handle!("Sending error: {err}");
         };
 
         // receive data from server
-        let data = match self.stream.as_mut().unwrap().recv_msg() {
-            Result::Ok(msg) => {
+        let timeout = Duration::from_secs(self.cfg().py_server_timeout);
+        match self.stream.as_mut().unwrap().recv_response(timeout) {
+            Result::Ok((msg, timed_out)) => {
                 let s = match msg.inst {
                     Inst::Exception => {
                         debug_assert!(
                             std::str::from_utf8(msg.data.as_ref().unwrap()) == Ok("SystemExit")
                         );
-                        return Err(EvalErrors::from(EvalError::system_exit()));
+                        return (
+                            String::new(),
+                            Err(EvalErrors::from(EvalError::system_exit())),
+                        );
+                    }
+                    Inst::Error => {
+                        self.compiler.initialize_generator();
+                        let data = String::from_utf8_lossy(msg.data.as_deref().unwrap_or_default());
+                        let mut exc = RuntimeException::parse(&data);
+                        let out = mem::take(&mut exc.output);
+                        let file = self.cfg().input.filename();
+                        let timeout = timed_out.then_some(self.cfg().py_server_timeout);
+                        let err =
+                            exc.into_error(self.compiler.checker_input().clone(), &file, timeout);
+                        return (out, Err(EvalErrors::from(err)));
                     }
                     Inst::Initialize => {
                         self.compiler.initialize_generator();
                         String::from_utf8(msg.data.unwrap_or_default())
+                            .map(|out| (out, String::new()))
                     }
-                    Inst::Print => String::from_utf8(msg.data.unwrap_or_default()),
+                    Inst::Print => String::from_utf8(msg.data.unwrap_or_default())
+                        .map(|out| (out, String::new())),
+                    Inst::Display | Inst::Value => String::from_utf8(msg.data.unwrap_or_default())
+                        .map(|data| {
+                            let (out, result) = data.split_once(GROUP_SEP).unwrap_or((&data, ""));
+                            (out.to_string(), result.to_string())
+                        }),
                     Inst::Exit => err_handle!("Receiving inst {:?} from server", msg.inst),
-                    // `load` can only be sent from the client to the server
-                    Inst::Load | Inst::Execute | Inst::Unknown => {
+                    // `load` and `interrupt` can only be sent from the client to the server
+                    Inst::Load | Inst::Execute | Inst::Interrupt | Inst::Unknown => {
                         err_handle!("Receiving unexpected inst {:?} from server", msg.inst)
                     }
                 };
 
-                if let Ok(ss) = s {
-                    ss
+                if let Ok((out, result)) = s {
+                    (out, Ok(result))
                 } else {
                     err_handle!("Failed to parse server response data, error: {:?}", s.err());
                 }
             }
             Result::Err(err) => err_handle!("Received an error: {err}"),
-        };
-
-        res.push_str(&data);
-        // If the result of an expression is None, it will not be displayed in the REPL.
-        if res.ends_with("None") {
-            res.truncate(res.len() - 5);
         }
+    }
 
-        if self.cfg().show_type {
-            res.push_str(": ");
-            res.push_str(
-                &last
-                    .as_ref()
-                    .map(|last| last.t())
-                    .unwrap_or_default()
-                    .to_string(),
-            );
-            if let Some(Expr::Def(def)) = last {
-                res.push_str(&format!(" ({})", def.sig.ident()));
-            }
+    /// Writes the output of the code to `cfg.output`.
+    fn write_output(&mut self, out: &str) {
+        if !out.is_empty() {
+            writeln!(self.cfg_mut().output, "{out}").unwrap_or(());
+            self.cfg_mut().output.flush().unwrap_or(());
         }
-        Ok(res)
     }
-}
 
-impl DummyVM {
+    /// Evaluates `src` (read from `input`) in the rich display mode.
+    /// Returns the warnings and the printed output, and the representations (MIME type and data) of the value or the errors.
+    /// Used by the Jupyter kernel.
+    #[allow(clippy::type_complexity)]
+    pub fn eval_rich(
+        &mut self,
+        input: Input,
+        src: String,
+    ) -> (String, Result<Vec<(String, String)>, EvalErrors>) {
+        self.set_display_mode(DisplayMode::Rich);
+        let input = self.compiler.replace_input(input);
+        let (res, result) = self.execute(src);
+        self.compiler.replace_input(input);
+        (res, result.map(|(_, data)| parse_reprs(&data)))
+    }
+
+    /// Evaluates `src` and returns the value of the last expression and its (static) type.
+    /// The warnings and the printed output are written to `cfg.output`.
+    ///
+    /// ```no_run
+    /// # use erg::{DummyVM, Value};
+    /// let mut vm = DummyVM::default();
+    /// let (value, t) = vm.eval_value("{x = 1; y = [2]}".into()).unwrap();
+    /// assert_eq!(value.to_string(), "{x = 1; y = [2]}");
+    /// ```
+    pub fn eval_value(&mut self, src: String) -> Result<(Value, Type), EvalErrors> {
+        self.set_display_mode(DisplayMode::Value);
+        let (out, result) = self.execute(src);
+        self.write_output(&out);
+        let (last, data) = result?;
+        let value = Value::parse(&data).unwrap_or_else(|| Value::Object {
+            class: String::new(),
+            repr: data,
+        });
+        let t = last.map_or(Type::NoneType, |last| last.t());
+        Ok((value, t))
+    }
+
+    /// Defines `name` as `value` in the session (the same as evaluating `name = value`).
+    /// Values that cannot be written in Erg (see `Value::to_erg_expr`) should be passed by `declare`.
+    pub fn define(&mut self, name: &str, value: &Value) -> Result<(), EvalErrors> {
+        let Some(expr) = value.to_erg_expr() else {
+            return Err(EvalErrors::from(EvalError::feature_error(
+                Input::str(value.to_string()),
+                line!() as usize,
+                Location::Unknown,
+                &format!("defining `{value}`"),
+                "<module>".to_string(),
+            )));
+        };
+        self.eval_value(format!("{name} = {expr}")).map(|_| ())
+    }
+
+    /// Declares `name: t` in the session and binds it to the value of the Python expression `py_expr`.
+    /// The type is not checked, so `t` must be the type of the value.
+    pub fn declare(&mut self, name: &str, t: Type, py_expr: &str) -> Result<(), EvalErrors> {
+        self.set_display_mode(DisplayMode::Value);
+        let (out, result) = self.run_script(format!("globals()[{name:?}] = ({py_expr})"));
+        self.write_output(&out);
+        result?;
+        self.compiler.register_external(name, t);
+        Ok(())
+    }
+
     /// Execute the script specified in the configuration.
     pub fn exec(&mut self) -> Result<ExitStatus, EvalErrors> {
         Runnable::exec(self)
@@ -0,0 +1,650 @@
+//! A Jupyter kernel for Erg (`erg kernel <connection file>`).
+//!
+//! The messages are sent over the ZeroMQ-compatible sockets (see `zmq`) and evaluated by `DummyVM`.
+mod hmac;
+mod zmq;
+
+use std::env;
+use std::net::TcpStream;
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use erg_common::config::ErgConfig;
+use erg_common::consts::SEMVER;
+use erg_common::error::{ErrorDisplay, ErrorKind};
+use erg_common::interrupt::{interrupt, InterruptGuard};
+use erg_common::io::Input;
+use erg_common::random::random;
+use erg_common::stdin::word_before;
+use erg_common::style::remove_style;
+use erg_common::traits::{BlockKind, ExitStatus, Runnable};
+
+use crate::dummy::{DummyVM, EvalErrors};
+use crate::json::Json;
+
+use hmac::hmac_sha256_hex;
+use zmq::{recv_multipart, send_multipart, serve, SocketType};
+
+const PROTOCOL_VERSION: &str = "5.3";
+const DELIMITER: &[u8] = b"<IDS|MSG>";
+
+/// The current time in ISO 8601 format (UTC)
+fn now_iso8601() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let (days, secs) = (now.as_secs() / 86400, now.as_secs() % 86400);
+    // civil date from days (http://howardhinnant.github.io/date_algorithms.html)
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:06}Z",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        now.subsec_micros()
+    )
+}
+
+fn new_id() -> String {
+    format!("{:016x}{:016x}", random(), random())
+}
+
+#[derive(Debug, Clone)]
+struct Message {
+    /// routing identities (for the ROUTER sockets) or the topic (for the PUB socket)
+    ids: Vec<Vec<u8>>,
+    header: Json,
+    parent_header: Json,
+    metadata: Json,
+    content: Json,
+}
+
+impl Message {
+    fn msg_type(&self) -> &str {
+        self.header.str_of("msg_type")
+    }
+}
+
+/// Signs and verifies the messages.
+#[derive(Debug, Clone)]
+struct Session {
+    key: Vec<u8>,
+    id: String,
+}
+
+impl Session {
+    fn sign(&self, parts: &[&[u8]]) -> String {
+        if self.key.is_empty() {
+            String::new()
+        } else {
+            hmac_sha256_hex(&self.key, parts)
+        }
+    }
+
+    fn decode(&self, mut frames: Vec<Vec<u8>>) -> Result<Message, String> {
+        let delim = frames
+            .iter()
+            .position(|frame| frame == DELIMITER)
+            .ok_or("no delimiter")?;
+        let mut parts = frames.split_off(delim + 1);
+        frames.pop();
+        if parts.len() < 5 {
+            return Err("too few frames".into());
+        }
+        let parts = parts.drain(..5).collect::<Vec<_>>();
+        let signature = String::from_utf8_lossy(&parts[0]);
+        let expected = self.sign(&[&parts[1], &parts[2], &parts[3], &parts[4]]);
+        if signature != expected {
+            return Err("invalid signature".into());
+        }
+        let parse = |bytes: &[u8]| Json::parse(&String::from_utf8_lossy(bytes));
+        Ok(Message {
+            ids: frames,
+            header: parse(&parts[1])?,
+            parent_header: parse(&parts[2])?,
+            metadata: parse(&parts[3])?,
+            content: parse(&parts[4])?,
+        })
+    }
+
+    fn encode(&self, msg: &Message) -> Vec<Vec<u8>> {
+        let header = msg.header.to_string();
+        let parent_header = msg.parent_header.to_string();
+        let metadata = msg.metadata.to_string();
+        let content = msg.content.to_string();
+        let signature = self.sign(&[
+            header.as_bytes(),
+            parent_header.as_bytes(),
+            metadata.as_bytes(),
+            content.as_bytes(),
+        ]);
+        let mut frames = msg.ids.clone();
+        frames.push(DELIMITER.to_vec());
+        for part in [signature, header, parent_header, metadata, content] {
+            frames.push(part.into_bytes());
+        }
+        frames
+    }
+
+    fn new_message(
+        &self,
+        ids: Vec<Vec<u8>>,
+        parent: &Json,
+        msg_type: &str,
+        content: Json,
+    ) -> Message {
+        let header = Json::object([
+            ("msg_id", Json::from(new_id())),
+            ("session", Json::from(self.id.clone())),
+            ("username", Json::from("kernel")),
+            ("date", Json::from(now_iso8601())),
+            ("msg_type", Json::from(msg_type)),
+            ("version", Json::from(PROTOCOL_VERSION)),
+        ]);
+        Message {
+            ids,
+            header,
+            parent_header: parent.clone(),
+            metadata: Json::empty_object(),
+            content,
+        }
+    }
+
+    fn reply(&self, parent: &Message, msg_type: &str, content: Json) -> Message {
+        self.new_message(parent.ids.clone(), &parent.header, msg_type, content)
+    }
+}
+
+type Writer = Arc<Mutex<TcpStream>>;
+
+fn send(writer: &Writer, session: &Session, msg: &Message) {
+    if let Ok(mut stream) = writer.lock() {
+        // the client may have been disconnected
+        send_multipart(&mut *stream, &session.encode(msg)).unwrap_or(());
+    }
+}
+
+/// Reads messages from `stream` until it is closed, and calls `handle` with them and the writer of the connection.
+fn read_messages(stream: TcpStream, handle: impl Fn(Vec<Vec<u8>>, &Writer)) {
+    let Ok(writer) = stream.try_clone() else {
+        return;
+    };
+    let writer = Arc::new(Mutex::new(writer));
+    let mut reader = stream;
+    while let Ok(frames) = recv_multipart(&mut reader) {
+        handle(frames, &writer);
+    }
+}
+
+/// The IOPub channel (broadcasts the messages to all subscribers)
+#[derive(Debug, Clone, Default)]
+struct Publisher {
+    subscribers: Arc<Mutex<Vec<TcpStream>>>,
+}
+
+impl Publisher {
+    fn publish(&self, session: &Session, msg: &Message) {
+        let frames = session.encode(msg);
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.retain_mut(|stream| send_multipart(stream, &frames).is_ok());
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ConnectionInfo {
+    ip: String,
+    key: String,
+    shell_port: usize,
+    iopub_port: usize,
+    stdin_port: usize,
+    control_port: usize,
+    hb_port: usize,
+}
+
+impl ConnectionInfo {
+    fn parse(src: &str) -> Result<Self, String> {
+        let json = Json::parse(src)?;
+        if !matches!(json.str_of("transport"), "tcp" | "") {
+            return Err("only the tcp transport is supported".into());
+        }
+        if !matches!(json.str_of("signature_scheme"), "hmac-sha256" | "") {
+            return Err("only hmac-sha256 is supported as the signature scheme".into());
+        }
+        let port = |key: &str| {
+            json.get(key)
+                .and_then(Json::as_usize)
+                .ok_or_else(|| format!("`{key}` is not specified"))
+        };
+        Ok(Self {
+            ip: json.str_of("ip").to_string(),
+            key: json.str_of("key").to_string(),
+            shell_port: port("shell_port")?,
+            iopub_port: port("iopub_port")?,
+            stdin_port: port("stdin_port")?,
+            control_port: port("control_port")?,
+            hb_port: port("hb_port")?,
+        })
+    }
+
+    fn addr(&self, port: usize) -> String {
+        let ip = if self.ip.is_empty() {
+            "127.0.0.1"
+        } else {
+            &self.ip
+        };
+        format!("{ip}:{port}")
+    }
+}
+
+/// The kernelspec (`kernel.json`) to run this executable as a kernel
+fn kernel_spec() -> Json {
+    let exe = env::current_exe()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|_| "erg".to_string());
+    Json::object([
+        (
+            "argv",
+            Json::from(vec![exe, "kernel".into(), "{connection_file}".into()]),
+        ),
+        ("display_name", Json::from("Erg")),
+        ("language", Json::from("erg")),
+        ("interrupt_mode", Json::from("signal")),
+    ])
+}
+
+enum Request {
+    Shell(Vec<Vec<u8>>, Writer),
+    Control(Message, Writer),
+}
+
+/// `is_complete_reply` for `code` ("complete", "incomplete" or "invalid") and the indentation of the next line
+fn is_complete(vm: &DummyVM, code: &str) -> (&'static str, String) {
+    // a blank line closes the blocks
+    if code.trim().is_empty() || code.ends_with("\n\n") {
+        return ("complete", String::new());
+    }
+    if vm.expect_block(code) == BlockKind::MultiLineStr {
+        return ("incomplete", String::new());
+    }
+    let last = code.lines().last().unwrap_or_default();
+    let indent = last.len() - last.trim_start().len();
+    match vm.expect_block(last.trim()) {
+        BlockKind::Error => ("invalid", String::new()),
+        BlockKind::AtMark | BlockKind::ClassDef => ("incomplete", " ".repeat(indent)),
+        BlockKind::None if indent == 0 => ("complete", String::new()),
+        BlockKind::None => ("incomplete", " ".repeat(indent)),
+        _ => ("incomplete", " ".repeat(indent + 4)),
+    }
+}
+
+/// The name (or attribute access) around `cursor` (in chars), e.g. `foo.bar` in `print! foo.ba|r`
+fn word_at(code: &str, cursor: usize) -> String {
+    let before = code.chars().take(cursor).collect::<String>();
+    let rest = code
+        .chars()
+        .skip(cursor)
+        .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '!'));
+    word_before(&before).chars().chain(rest).collect()
+}
+
+/// `ename`, `evalue` and `traceback` of the error messages
+fn error_content(errs: &EvalErrors) -> Vec<(&'static str, Json)> {
+    let first = errs.iter().next();
+    let ename = first.map_or("Error".to_string(), |err| format!("{:?}", err.core.kind));
+    let evalue = first.map_or(String::new(), |err| remove_style(&err.core.main_message));
+    let traceback = errs
+        .iter()
+        .flat_map(|err| {
+            err.show()
+                .lines()
+                .map(|line| line.to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    vec![
+        ("ename", Json::from(ename)),
+        ("evalue", Json::from(evalue)),
+        ("traceback", Json::from(traceback)),
+    ]
+}
+
+/// A Jupyter kernel which evaluates the code with `DummyVM`
+#[derive(Debug)]
+pub struct JupyterKernel {
+    vm: DummyVM,
+    session: Session,
+    iopub: Publisher,
+    execution_count: usize,
+}
+
+impl JupyterKernel {
+    /// Runs the kernel with the connection file specified by `cfg.input`.
+    /// If it is not specified, prints the kernelspec.
+    pub fn run(cfg: ErgConfig) -> ExitStatus {
+        if cfg.input.is_repl() {
+            println!("{}", kernel_spec());
+            return ExitStatus::OK;
+        }
+        let mut input = cfg.input.clone();
+        let info = match ConnectionInfo::parse(&input.read()) {
+            Ok(info) => info,
+            Err(err) => {
+                eprintln!("invalid connection file: {err}");
+                return ExitStatus::ERR1;
+            }
+        };
+        match Self::start(cfg, info) {
+            Ok(stat) => stat,
+            Err(err) => {
+                eprintln!("failed to start the kernel: {err}");
+                ExitStatus::ERR1
+            }
+        }
+    }
+
+    fn start(cfg: ErgConfig, info: ConnectionInfo) -> std::io::Result<ExitStatus> {
+        let session = Session {
+            key: info.key.as_bytes().to_vec(),
+            id: new_id(),
+        };
+        let (tx, rx) = channel();
+        let iopub = Publisher::default();
+        let subscribers = iopub.subscribers.clone();
+        serve(
+            &info.addr(info.iopub_port),
+            SocketType::Pub,
+            move |stream| {
+                let Ok(mut reader) = stream.try_clone() else {
+                    return;
+                };
+                if let Ok(mut subscribers) = subscribers.lock() {
+                    subscribers.push(stream);
+                }
+                // the subscriptions are ignored (all messages are published)
+                while recv_multipart(&mut reader).is_ok() {}
+            },
+        )?;
+        serve(&info.addr(info.hb_port), SocketType::Rep, |stream| {
+            read_messages(stream, |frames, writer| {
+                if let Ok(mut stream) = writer.lock() {
+                    send_multipart(&mut *stream, &frames).unwrap_or(());
+                }
+            });
+        })?;
+        serve(&info.addr(info.stdin_port), SocketType::Router, |stream| {
+            // `input` is not supported
+            read_messages(stream, |_, _| {});
+        })?;
+        let shell_tx = tx.clone();
+        serve(
+            &info.addr(info.shell_port),
+            SocketType::Router,
+            move |stream| {
+                let tx = shell_tx.clone();
+                read_messages(stream, |frames, writer| {
+                    tx.send(Request::Shell(frames, writer.clone()))
+                        .unwrap_or(());
+                });
+            },
+        )?;
+        let control_session = session.clone();
+        serve(
+            &info.addr(info.control_port),
+            SocketType::Router,
+            move |stream| {
+                let session = control_session.clone();
+                let tx: Sender<Request> = tx.clone();
+                read_messages(stream, |frames, writer| {
+                    let Ok(msg) = session.decode(frames) else {
+                        return;
+                    };
+                    // handled here because the main thread may be running code
+                    if msg.msg_type() == "interrupt_request" {
+                        interrupt();
+                        let reply = session.reply(
+                            &msg,
+                            "interrupt_reply",
+                            Json::object([("status", Json::from("ok"))]),
+                        );
+                        send(writer, &session, &reply);
+                    } else {
+                        tx.send(Request::Control(msg, writer.clone())).unwrap_or(());
+                    }
+                });
+            },
+        )?;
+        // SIGINT is sent by Jupyter to interrupt the code, so it must not kill the idle kernel
+        let _guard = InterruptGuard::new();
+        let cfg = ErgConfig {
+            input: Input::repl(),
+            quiet_repl: true,
+            // long-running cells are interrupted by the user instead
+            py_server_timeout: u64::MAX,
+            ..cfg
+        };
+        let mut kernel = Self {
+            vm: DummyVM::new(cfg),
+            session,
+            iopub,
+            execution_count: 0,
+        };
+        for req in rx {
+            let (msg, writer) = match req {
+                Request::Shell(frames, writer) => match kernel.session.decode(frames) {
+                    Ok(msg) => (msg, writer),
+                    Err(err) => {
+                        eprintln!("invalid message: {err}");
+                        continue;
+                    }
+                },
+                Request::Control(msg, writer) => (msg, writer),
+            };
+            kernel.publish_status(&msg, "busy");
+            let shutdown = kernel.handle(&msg, &writer);
+            kernel.publish_status(&msg, "idle");
+            if shutdown {
+                break;
+            }
+        }
+        // the REPL server is closed when the VM is dropped
+        Ok(ExitStatus::OK)
+    }
+
+    fn publish(&self, parent: &Message, msg_type: &str, content: Json) {
+        let topic = format!("kernel.{}.{msg_type}", self.session.id).into_bytes();
+        let msg = self
+            .session
+            .new_message(vec![topic], &parent.header, msg_type, content);
+        self.iopub.publish(&self.session, &msg);
+    }
+
+    fn publish_status(&self, parent: &Message, state: &str) {
+        self.publish(
+            parent,
+            "status",
+            Json::object([("execution_state", Json::from(state))]),
+        );
+    }
+
+    fn reply(&self, writer: &Writer, parent: &Message, msg_type: &str, content: Json) {
+        let msg = self.session.reply(parent, msg_type, content);
+        send(writer, &self.session, &msg);
+    }
+
+    /// Handles a request and returns whether the kernel should be shut down.
+    fn handle(&mut self, msg: &Message, writer: &Writer) -> bool {
+        let content = &msg.content;
+        let ok = ("status", Json::from("ok"));
+        match msg.msg_type() {
+            "kernel_info_request" => {
+                let language_info = Json::object([
+                    ("name", Json::from("erg")),
+                    ("version", Json::from(SEMVER)),
+                    ("mimetype", Json::from("text/x-erg")),
+                    ("file_extension", Json::from(".er")),
+                ]);
+                let content = Json::object([
+                    ok,
+                    ("protocol_version", Json::from(PROTOCOL_VERSION)),
+                    ("implementation", Json::from("erg")),
+                    ("implementation_version", Json::from(SEMVER)),
+                    ("language_info", language_info),
+                    ("banner", Json::from(self.vm.start_message())),
+                    ("help_links", Json::Array(vec![])),
+                ]);
+                self.reply(writer, msg, "kernel_info_reply", content);
+            }
+            "execute_request" => return self.execute(msg, writer),
+            "complete_request" => {
+                let code = content.str_of("code");
+                let cursor = content
+                    .get("cursor_pos")
+                    .and_then(Json::as_usize)
+                    .unwrap_or(code.chars().count());
+                let before = code.chars().take(cursor).collect::<String>();
+                let word = word_before(&before);
+                let matches = self.vm.complete(word);
+                let content = Json::object([
+                    ok,
+                    ("matches", Json::from(matches)),
+                    ("cursor_start", Json::from(cursor - word.chars().count())),
+                    ("cursor_end", Json::from(cursor)),
+                    ("metadata", Json::empty_object()),
+                ]);
+                self.reply(writer, msg, "complete_reply", content);
+            }
+            "inspect_request" => {
+                let code = content.str_of("code");
+                let cursor = content
+                    .get("cursor_pos")
+                    .and_then(Json::as_usize)
+                    .unwrap_or(code.chars().count());
+                let doc = self.vm.doc_of(&word_at(code, cursor));
+                let data = doc.map_or(Json::empty_object(), |doc| {
+                    Json::object([("text/plain", Json::from(doc))])
+                });
+                let content = Json::object([
+                    ok,
+                    ("found", Json::from(data != Json::empty_object())),
+                    ("data", data),
+                    ("metadata", Json::empty_object()),
+                ]);
+                self.reply(writer, msg, "inspect_reply", content);
+            }
+            "is_complete_request" => {
+                let (status, indent) = is_complete(&self.vm, content.str_of("code"));
+                let mut entries = vec![("status", Json::from(status))];
+                if status == "incomplete" {
+                    entries.push(("indent", Json::from(indent)));
+                }
+                self.reply(writer, msg, "is_complete_reply", Json::object(entries));
+            }
+            "history_request" => {
+                let content = Json::object([ok, ("history", Json::Array(vec![]))]);
+                self.reply(writer, msg, "history_reply", content);
+            }
+            "comm_info_request" => {
+                let content = Json::object([ok, ("comms", Json::empty_object())]);
+                self.reply(writer, msg, "comm_info_reply", content);
+            }
+            "shutdown_request" => {
+                let restart = content.get("restart").cloned().unwrap_or(Json::Bool(false));
+                let content = Json::object([ok, ("restart", restart)]);
+                self.reply(writer, msg, "shutdown_reply", content);
+                return true;
+            }
+            other => {
+                eprintln!("unsupported message: {other}");
+            }
+        }
+        false
+    }
+
+    /// Handles `execute_request` and returns whether the kernel should be shut down (by `exit()`).
+    fn execute(&mut self, msg: &Message, writer: &Writer) -> bool {
+        let code = msg.content.str_of("code").to_string();
+        let silent = msg
+            .content
+            .get("silent")
+            .and_then(Json::as_bool)
+            .unwrap_or(false);
+        let store_history = msg
+            .content
+            .get("store_history")
+            .and_then(Json::as_bool)
+            .unwrap_or(!silent);
+        if store_history {
+            self.execution_count += 1;
+        }
+        let count = ("execution_count", Json::from(self.execution_count));
+        if !silent {
+            let content = Json::object([("code", Json::from(code.clone())), count.clone()]);
+            self.publish(msg, "execute_input", content);
+        }
+        let (out, result) = if code.trim().is_empty() {
+            (String::new(), Ok(vec![]))
+        } else {
+            self.vm.eval_rich(Input::str(code.clone()), code)
+        };
+        if !silent && !out.is_empty() {
+            let content = Json::object([
+                ("name", Json::from("stdout")),
+                ("text", Json::from(out + "\n")),
+            ]);
+            self.publish(msg, "stream", content);
+        }
+        match result {
+            Ok(reprs) => {
+                if !silent && !reprs.is_empty() {
+                    let data = Json::object(
+                        reprs
+                            .into_iter()
+                            .map(|(mime, data)| (mime, Json::from(data))),
+                    );
+                    let content = Json::object([
+                        count.clone(),
+                        ("data", data),
+                        ("metadata", Json::empty_object()),
+                    ]);
+                    self.publish(msg, "execute_result", content);
+                }
+                let content = Json::object([
+                    ("status", Json::from("ok")),
+                    count,
+                    ("user_expressions", Json::empty_object()),
+                    ("payload", Json::Array(vec![])),
+                ]);
+                self.reply(writer, msg, "execute_reply", content);
+                false
+            }
+            // `exit()` shuts down the kernel
+            Err(errs)
+                if errs
+                    .iter()
+                    .any(|err| err.core.kind == ErrorKind::SystemExit) =>
+            {
+                let content = Json::object([("status", Json::from("ok")), count]);
+                self.reply(writer, msg, "execute_reply", content);
+                true
+            }
+            Err(errs) => {
+                let error = error_content(&errs);
+                self.publish(msg, "error", Json::object(error.clone()));
+                let mut content = vec![("status", Json::from("error")), count];
+                content.extend(error);
+                self.reply(writer, msg, "execute_reply", Json::object(content));
+                false
+            }
+        }
+    }
+}
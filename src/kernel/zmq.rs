@@ -0,0 +1,160 @@
+//! A minimal implementation of ZMTP 3.0 (the wire protocol of ZeroMQ) for the Jupyter kernel.
+//! Only the binding side and the `NULL` security mechanism are supported.
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+const MORE: u8 = 0x01;
+const LONG: u8 = 0x02;
+const COMMAND: u8 = 0x04;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocketType {
+    Router,
+    Pub,
+    Rep,
+}
+
+impl SocketType {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Router => "ROUTER",
+            Self::Pub => "PUB",
+            Self::Rep => "REP",
+        }
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+fn write_frame(buf: &mut Vec<u8>, flags: u8, body: &[u8]) {
+    if body.len() > u8::MAX as usize {
+        buf.push(flags | LONG);
+        buf.extend((body.len() as u64).to_be_bytes());
+    } else {
+        buf.push(flags);
+        buf.push(body.len() as u8);
+    }
+    buf.extend_from_slice(body);
+}
+
+/// Returns the flags and the body of the frame
+fn read_frame(stream: &mut impl Read) -> io::Result<(u8, Vec<u8>)> {
+    let mut flags = [0; 1];
+    stream.read_exact(&mut flags)?;
+    let size = if flags[0] & LONG != 0 {
+        let mut size = [0; 8];
+        stream.read_exact(&mut size)?;
+        u64::from_be_bytes(size) as usize
+    } else {
+        let mut size = [0; 1];
+        stream.read_exact(&mut size)?;
+        size[0] as usize
+    };
+    let mut body = vec![0; size];
+    stream.read_exact(&mut body)?;
+    Ok((flags[0], body))
+}
+
+/// Exchanges the greeting and the `READY` command with the peer.
+fn handshake(stream: &mut TcpStream, ty: SocketType) -> io::Result<()> {
+    let mut greeting = [0; 64];
+    greeting[0] = 0xff;
+    greeting[9] = 0x7f;
+    greeting[10] = 3; // version 3.0
+    greeting[12..16].copy_from_slice(b"NULL");
+    stream.write_all(&greeting)?;
+    let mut peer = [0; 64];
+    stream.read_exact(&mut peer)?;
+    if peer[0] != 0xff || peer[9] & 1 == 0 || peer[10] < 3 {
+        return Err(invalid_data("unsupported ZMTP version"));
+    }
+    if &peer[12..16] != b"NULL" {
+        return Err(invalid_data("unsupported security mechanism"));
+    }
+    let mut ready = vec![5];
+    ready.extend_from_slice(b"READY");
+    let (key, value) = ("Socket-Type", ty.name());
+    ready.push(key.len() as u8);
+    ready.extend_from_slice(key.as_bytes());
+    ready.extend((value.len() as u32).to_be_bytes());
+    ready.extend_from_slice(value.as_bytes());
+    let mut buf = vec![];
+    write_frame(&mut buf, COMMAND, &ready);
+    stream.write_all(&buf)?;
+    loop {
+        let (flags, body) = read_frame(stream)?;
+        if flags & COMMAND == 0 {
+            return Err(invalid_data("expected a command"));
+        }
+        match body.get(1..1 + *body.first().unwrap_or(&0) as usize) {
+            Some(b"READY") => return Ok(()),
+            Some(b"ERROR") => return Err(invalid_data("handshake failed")),
+            _ => {}
+        }
+    }
+}
+
+/// Receives a multipart message (commands are skipped).
+pub fn recv_multipart(stream: &mut impl Read) -> io::Result<Vec<Vec<u8>>> {
+    let mut frames = vec![];
+    loop {
+        let (flags, body) = read_frame(stream)?;
+        if flags & COMMAND != 0 {
+            continue;
+        }
+        frames.push(body);
+        if flags & MORE == 0 {
+            return Ok(frames);
+        }
+    }
+}
+
+/// Sends a multipart message at once.
+pub fn send_multipart(stream: &mut impl Write, frames: &[Vec<u8>]) -> io::Result<()> {
+    let mut buf = vec![];
+    for (i, frame) in frames.iter().enumerate() {
+        let flags = if i + 1 < frames.len() { MORE } else { 0 };
+        write_frame(&mut buf, flags, frame);
+    }
+    stream.write_all(&buf)?;
+    stream.flush()
+}
+
+/// Binds `addr` and accepts the connections in a new thread.
+/// `handle` is called in another new thread for each connection after the handshake.
+pub fn serve<F>(addr: &str, ty: SocketType, handle: F) -> io::Result<()>
+where
+    F: Fn(TcpStream) + Clone + Send + 'static,
+{
+    let listener = TcpListener::bind(addr)?;
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let handle = handle.clone();
+            thread::spawn(move || {
+                if handshake(&mut stream, ty).is_ok() {
+                    handle(stream);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frames() {
+        let frames = vec![b"<IDS|MSG>".to_vec(), vec![b'a'; 300], vec![]];
+        let mut buf = vec![];
+        send_multipart(&mut buf, &frames).unwrap();
+        // short frame with MORE, long frame with MORE
+        assert_eq!(&buf[..2], &[MORE, 9]);
+        assert_eq!(buf[11], MORE | LONG);
+        assert_eq!(recv_multipart(&mut &buf[..]).unwrap(), frames);
+    }
+}
@@ -1,4 +1,9 @@
 extern crate erg_common;
 extern crate erg_compiler;
 mod dummy;
+mod json;
+mod kernel;
+mod value;
 pub use dummy::DummyVM;
+pub use kernel::JupyterKernel;
+pub use value::Value;
@@ -3,6 +3,7 @@ extern crate erg_compiler;
 extern crate erg_parser;
 
 use erg_common::config::{ErgConfig, ErgMode::*};
+use erg_common::explain;
 use erg_common::spawn::exec_new_thread;
 use erg_common::traits::{ExitStatus, Runnable};
 
@@ -14,12 +15,13 @@ use erg_compiler::build_hir::HIRBuilder;
 use erg_compiler::lower::ASTLowerer;
 use erg_compiler::transpile::Transpiler;
 use erg_compiler::ty::deserialize::Deserializer;
-use erg_compiler::Compiler;
+use erg_compiler::{Bundler, Compiler, PackageBuilder};
 
-use erg::DummyVM;
+use erg::{DummyVM, JupyterKernel};
 
 fn run() {
     let cfg = ErgConfig::parse();
+    let error_format = cfg.error_format.clone();
     let stat = match cfg.mode {
         Lex => LexerRunner::run(cfg),
         Parse => ParserRunner::run(cfg),
@@ -27,9 +29,13 @@ fn run() {
         TypeCheck => ASTLowerer::run(cfg),
         FullCheck => HIRBuilder::run(cfg),
         Compile => Compiler::run(cfg),
+        Build => PackageBuilder::run(cfg),
+        Bundle => Bundler::run(cfg),
+        Explain => explain::run(cfg),
         Transpile => Transpiler::run(cfg),
         Execute => DummyVM::run(cfg),
         Read => Deserializer::run(cfg),
+        Kernel => JupyterKernel::run(cfg),
         LanguageServer => {
             #[cfg(feature = "els")]
             {
@@ -45,6 +51,7 @@ fn run() {
             }
         }
     };
+    error_format.flush_sarif_log(&mut std::io::stderr());
     std::process::exit(stat.code);
 }
 
@@ -0,0 +1,180 @@
+# Tests `erg kernel` with a minimal Jupyter client (ZMTP 3.0 over TCP, without pyzmq)
+# usage: python kernel_test.py <path to erg>
+import hashlib
+import hmac
+import json
+import os
+import socket
+import subprocess
+import sys
+import tempfile
+import time
+import uuid
+
+KEY = b'secret'
+
+def free_port():
+    with socket.socket() as s:
+        s.bind(('127.0.0.1', 0))
+        return s.getsockname()[1]
+
+class Socket:
+    def __init__(self, port, socket_type):
+        for _ in range(100):
+            try:
+                self.sock = socket.create_connection(('127.0.0.1', port))
+                break
+            except OSError:
+                time.sleep(0.1)
+        greeting = bytearray(64)
+        greeting[0] = 0xff
+        greeting[9] = 0x7f
+        greeting[10] = 3
+        greeting[12:16] = b'NULL'
+        self.sock.sendall(greeting)
+        assert self.recv_exact(64)[10] == 3
+        name = socket_type.encode()
+        body = b'\x05READY' + b'\x0bSocket-Type' + len(name).to_bytes(4, 'big') + name
+        self.sock.sendall(bytes([0x04, len(body)]) + body)
+        flags, body = self.recv_frame()
+        assert flags & 0x04 and body[1:6] == b'READY'
+
+    def recv_exact(self, n):
+        buf = b''
+        while len(buf) < n:
+            chunk = self.sock.recv(n - len(buf))
+            if not chunk:
+                raise ConnectionResetError
+            buf += chunk
+        return buf
+
+    def recv_frame(self):
+        flags = self.recv_exact(1)[0]
+        if flags & 0x02:
+            size = int.from_bytes(self.recv_exact(8), 'big')
+        else:
+            size = self.recv_exact(1)[0]
+        return flags, self.recv_exact(size)
+
+    def recv_multipart(self):
+        frames = []
+        while True:
+            flags, body = self.recv_frame()
+            if flags & 0x04:
+                continue
+            frames.append(body)
+            if not flags & 0x01:
+                return frames
+
+    def send_multipart(self, frames):
+        buf = b''
+        for i, frame in enumerate(frames):
+            more = 0x01 if i + 1 < len(frames) else 0
+            if len(frame) > 255:
+                buf += bytes([more | 0x02]) + len(frame).to_bytes(8, 'big') + frame
+            else:
+                buf += bytes([more, len(frame)]) + frame
+        self.sock.sendall(buf)
+
+def sign(parts):
+    h = hmac.new(KEY, digestmod=hashlib.sha256)
+    for part in parts:
+        h.update(part)
+    return h.hexdigest().encode()
+
+SESSION = uuid.uuid4().hex
+
+def send(sock, msg_type, content):
+    header = {'msg_id': uuid.uuid4().hex, 'session': SESSION, 'username': 'test', 'msg_type': msg_type, 'version': '5.3'}
+    parts = [json.dumps(p).encode() for p in [header, {}, {}, content]]
+    sock.send_multipart([b'<IDS|MSG>', sign(parts)] + parts)
+    return header['msg_id']
+
+def recv(sock):
+    frames = sock.recv_multipart()
+    i = frames.index(b'<IDS|MSG>')
+    parts = frames[i + 2:i + 6]
+    assert frames[i + 1] == sign(parts), 'invalid signature'
+    header, parent, _metadata, content = [json.loads(p) for p in parts]
+    return header['msg_type'], parent.get('msg_id'), content
+
+def iopub_until_idle(iopub, msg_id):
+    msgs = []
+    while True:
+        msg_type, parent, content = recv(iopub)
+        if parent != msg_id:
+            continue
+        if msg_type == 'status' and content['execution_state'] == 'idle':
+            return msgs
+        msgs.append((msg_type, content))
+
+def request(shell, iopub, msg_type, content):
+    msg_id = send(shell, msg_type, content)
+    reply_type, parent, reply = recv(shell)
+    assert reply_type == msg_type.replace('_request', '_reply') and parent == msg_id, (reply_type, reply)
+    return reply, iopub_until_idle(iopub, msg_id)
+
+ports = {name: free_port() for name in ['shell_port', 'iopub_port', 'stdin_port', 'control_port', 'hb_port']}
+conn = dict(ports, ip='127.0.0.1', transport='tcp', key=KEY.decode(), signature_scheme='hmac-sha256')
+with tempfile.NamedTemporaryFile('w', suffix='.json', delete=False) as f:
+    json.dump(conn, f)
+kernel = subprocess.Popen([sys.argv[1], 'kernel', f.name])
+try:
+    iopub = Socket(ports['iopub_port'], 'SUB')
+    iopub.send_multipart([b'\x01'])
+    shell = Socket(ports['shell_port'], 'DEALER')
+    control = Socket(ports['control_port'], 'DEALER')
+    hb = Socket(ports['hb_port'], 'REQ')
+
+    hb.send_multipart([b'', b'ping'])
+    assert hb.recv_multipart() == [b'', b'ping']
+
+    # the subscription may not be ready yet
+    time.sleep(0.5)
+    reply, _ = request(shell, iopub, 'kernel_info_request', {})
+    assert reply['language_info']['name'] == 'erg'
+
+    reply, msgs = request(shell, iopub, 'execute_request', {'code': 'x = 1\nprint! x\nx + 1', 'silent': False})
+    assert reply['status'] == 'ok' and reply['execution_count'] == 1, reply
+    assert ('stream', {'name': 'stdout', 'text': '1\n'}) in msgs, msgs
+    results = [content for (msg_type, content) in msgs if msg_type == 'execute_result']
+    assert results[0]['data']['text/plain'] == '2', msgs
+
+    reply, msgs = request(shell, iopub, 'execute_request', {'code': 'print! 0\n1 / 0', 'silent': False})
+    assert reply['status'] == 'error' and reply['ename'] == 'ZeroDivisionError', reply
+    assert ('stream', {'name': 'stdout', 'text': '0\n'}) in msgs, msgs
+    assert any(msg_type == 'error' for (msg_type, _) in msgs)
+
+    reply, _ = request(shell, iopub, 'execute_request', {'code': 'y: Int = "a"', 'silent': False})
+    assert reply['status'] == 'error' and reply['ename'] == 'TypeError', reply
+
+    reply, _ = request(shell, iopub, 'complete_request', {'code': 'print! x', 'cursor_pos': 8})
+    assert 'x' in reply['matches'] and reply['cursor_start'] == 7, reply
+
+    reply, _ = request(shell, iopub, 'inspect_request', {'code': 'x + 1', 'cursor_pos': 0, 'detail_level': 0})
+    assert reply['found'] and reply['data']['text/plain'].startswith('x: '), reply
+
+    reply, _ = request(shell, iopub, 'is_complete_request', {'code': 'f x ='})
+    assert reply == {'status': 'incomplete', 'indent': '    '}, reply
+    reply, _ = request(shell, iopub, 'is_complete_request', {'code': 'f x = x'})
+    assert reply['status'] == 'complete', reply
+
+    # interrupt a long-running cell
+    msg_id = send(shell, 'execute_request', {'code': 'time = pyimport "time"\ntime.sleep! 30', 'silent': False})
+    time.sleep(2)
+    send(control, 'interrupt_request', {})
+    assert recv(control)[0] == 'interrupt_reply'
+    _, parent, reply = recv(shell)
+    assert parent == msg_id and reply['ename'] == 'KeyboardInterrupt', reply
+    iopub_until_idle(iopub, msg_id)
+
+    reply, _ = request(shell, iopub, 'execute_request', {'code': 'x', 'silent': False})
+    assert reply['status'] == 'ok', reply
+
+    send(control, 'shutdown_request', {'restart': False})
+    assert recv(control)[0] == 'shutdown_reply'
+    assert kernel.wait(timeout=10) == 0
+finally:
+    if kernel.poll() is None:
+        kernel.kill()
+    os.remove(f.name)
@@ -1,9 +1,15 @@
 # All strings must be quoted by single quotes to prevent shell interpretation
+import base64
 import socket
 import sys
 import importlib
 import io
+import json
+import queue
+import signal
+import threading
 import traceback
+import types
 
 class INST:
     # Informs that it is not a supported instruction.
@@ -20,23 +26,45 @@ class INST:
     EXIT = 0x05
     # Send from client to server. Let the REPL server to execute the code.
     EXECUTE = 0x06
+    # Send from server to client. Represents an uncaught exception (see `format_error`).
+    ERROR = 0x07
+    # Send from client to server. Raises `KeyboardInterrupt` in the running code.
+    INTERRUPT = 0x08
+    # Send from client to server. Sets the display mode ('0': the results are sent with `PRINT`, '1': `DISPLAY`, '2': `VALUE`).
+    # Send from server to client. Represents the output and the representations of the result (see `format_display`).
+    DISPLAY = 0x09
+    # Send from server to client. Represents the output and the result (see `format_value`).
+    VALUE = 0x0a
+
+# separators of the `ERROR` data
+GROUP_SEP = chr(0x1d)
+RECORD_SEP = chr(0x1e)
+UNIT_SEP = chr(0x1f)
 
 class MessageStream:
     def __init__(self, socket):
         self.socket = socket
         self._read_buf = bytearray()
 
+    def _recv_exact(self, size):
+        while len(self._read_buf) < size:
+            chunk = self.socket.recv(size - len(self._read_buf))
+            if not chunk:
+                raise ConnectionResetError
+            self._read_buf.extend(chunk)
+
     def recv_msg(self):
         self._read_buf.clear()
-        self._read_buf.extend(self.socket.recv(3))
+        self._recv_exact(3)
         inst = int.from_bytes(self._read_buf[:1], 'big')
         data_len = int.from_bytes(self._read_buf[1:3], 'big')
-        self._read_buf.extend(self.socket.recv(data_len))
+        self._recv_exact(3 + data_len)
 
         return (inst, self._read_buf[3:].decode('utf-8'))
 
     def send_msg(self, inst, data=''):
-        data_bytes = data.encode()
+        # the size field is 2 bytes
+        data_bytes = data.encode()[:0xffff]
         data_len = len(data_bytes)
         # one byte for inst, two bytes for size, and n bytes for data(Optional)
         raw_bytes = inst.to_bytes(1, 'big') + data_len.to_bytes(2, 'big') + data_bytes
@@ -46,6 +74,105 @@ class MessageStream:
     def close(self):
         self.socket.close()
 
+# `ERROR` data: output, exception type, message, and traceback frames (file, line, function) separated by `GROUP_SEP`
+def format_error(out, e):
+    frames = []
+    # `traceback.extract_tb` may fail on the position tables generated by Erg
+    tb = e.__traceback__
+    while tb is not None:
+        code = tb.tb_frame.f_code
+        # the frames of this server and the loader are omitted
+        if code.co_filename != '<string>':
+            frames.append(UNIT_SEP.join([code.co_filename, str(max(tb.tb_lineno or 0, 0)), code.co_name]))
+        tb = tb.tb_next
+    msg = str(e)
+    if isinstance(e, SyntaxError):
+        msg = str(e.msg)
+    return GROUP_SEP.join([out, type(e).__name__, msg, RECORD_SEP.join(frames)])
+
+# (MIME type, method) of the rich representations (the same as IPython)
+REPR_METHODS = [
+    ('text/html', '_repr_html_'),
+    ('text/markdown', '_repr_markdown_'),
+    ('text/latex', '_repr_latex_'),
+    ('image/svg+xml', '_repr_svg_'),
+    ('image/png', '_repr_png_'),
+    ('image/jpeg', '_repr_jpeg_'),
+]
+
+# `DISPLAY` data: output and the representations (MIME type and data separated by `UNIT_SEP`) separated by `GROUP_SEP`
+# The representations that do not fit in a message are omitted
+def format_display(out, values):
+    reprs = []
+    size = len(out.encode()) + 1
+    for value in values:
+        reprs.append('text/plain' + UNIT_SEP + repr(value))
+        size += len(reprs[-1].encode())
+        for (mime, method) in REPR_METHODS:
+            f = getattr(value, method, None)
+            if f is None or isinstance(value, type):
+                continue
+            try:
+                data = f()
+            except Exception:
+                continue
+            if isinstance(data, tuple):
+                data = data[0]
+            if data is None:
+                continue
+            if isinstance(data, bytes):
+                data = base64.b64encode(data).decode()
+            rep = mime + UNIT_SEP + str(data)
+            if size + len(rep.encode()) + 1 > 0xffff:
+                continue
+            reprs.append(rep)
+            size += len(rep.encode()) + 1
+    return GROUP_SEP.join([out, RECORD_SEP.join(reprs)])
+
+# converts the value into JSON: `[kind, ...]`
+def dump_value(value, depth=0):
+    cls = type(value).__name__
+    if depth > 100:
+        return ['object', cls, '...']
+    # mutable types of Erg (e.g. `IntMut`) wrap the value
+    if cls.endswith('Mut') and hasattr(value, 'value'):
+        return dump_value(value.value, depth)
+    if value is None:
+        return ['none']
+    if cls in ('bool', 'Bool'):
+        return ['bool', bool(value)]
+    if isinstance(value, int):
+        return ['int', str(int(value))]
+    if isinstance(value, float):
+        return ['float', repr(float(value))]
+    if isinstance(value, str):
+        return ['str', str(value)]
+    # records are named tuples
+    if isinstance(value, tuple) and hasattr(value, '_fields'):
+        return ['record', [[k, dump_value(v, depth + 1)] for (k, v) in zip(value._fields, value)]]
+    if isinstance(value, list):
+        return ['array', [dump_value(v, depth + 1) for v in value]]
+    if isinstance(value, tuple):
+        return ['tuple', [dump_value(v, depth + 1) for v in value]]
+    if isinstance(value, (set, frozenset)):
+        return ['set', [dump_value(v, depth + 1) for v in value]]
+    if isinstance(value, dict):
+        return ['dict', [[dump_value(k, depth + 1), dump_value(v, depth + 1)] for (k, v) in value.items()]]
+    if hasattr(value, '__dict__') and not callable(value) and not isinstance(value, types.ModuleType):
+        # private attributes are prefixed with '::'
+        fields = [[k[2:] if k.startswith('::') else k, dump_value(v, depth + 1)] for (k, v) in vars(value).items() if not k.startswith('_')]
+        return ['instance', cls, fields]
+    return ['object', cls, repr(value)]
+
+# `VALUE` data: output and the result (converted by `dump_value`) separated by `GROUP_SEP`
+# If the result does not fit in a message, only its class name is sent
+def format_value(out, values):
+    value = values[0] if values else None
+    data = json.dumps(dump_value(value))
+    if len(out.encode()) + len(data.encode()) + 1 > 0xffff:
+        data = json.dumps(['object', type(value).__name__, '...'])
+    return GROUP_SEP.join([out, data])
+
 server_socket = socket.socket()
 # DummyVM will replace this __PORT__ with free port
 server_socket.bind(('127.0.0.1', __PORT__))
@@ -55,70 +182,110 @@ server_socket.listen(1)
 already_loaded = False
 ctx = {'importlib': importlib}
 client_stream = MessageStream(client_socket)
+messages = queue.Queue()
+lock = threading.Lock()
+executing = False
+interrupt_requested = False
+display_mode = '0'
+# the values displayed by the last code (except in the mode '0')
+displayed = []
 
-while True:
+def displayhook(value):
+    if display_mode == '0':
+        sys.__displayhook__(value)
+    elif value is not None:
+        displayed.clear()
+        displayed.append(value)
+sys.displayhook = displayhook
+
+# Ctrl+C on the terminal is ignored; the client sends `INTERRUPT` instead
+def on_interrupt(signum, frame):
+    global interrupt_requested
+    if interrupt_requested:
+        interrupt_requested = False
+        raise KeyboardInterrupt
+signal.signal(signal.SIGINT, on_interrupt)
+
+def interrupt_main():
+    if hasattr(signal, 'pthread_kill'):
+        # also interrupts blocking calls such as `time.sleep`
+        signal.pthread_kill(threading.main_thread().ident, signal.SIGINT)
+    else:
+        import _thread
+        _thread.interrupt_main()
+
+# receives messages while the code is running
+def receive():
+    global interrupt_requested
+    while True:
+        try:
+            inst, data = client_stream.recv_msg()
+        except OSError: # when the client was crashed
+            messages.put((INST.EXIT, ''))
+            break
+        if inst == INST.INTERRUPT:
+            with lock:
+                if executing:
+                    interrupt_requested = True
+                    interrupt_main()
+            continue
+        messages.put((inst, data))
+        if inst == INST.EXIT:
+            break
+
+def run(code):
+    global executing, interrupt_requested
+    sys.stdout = io.StringIO()
+    displayed.clear()
+    res = ''
     try:
-        inst, data = client_stream.recv_msg()
-    except ConnectionResetError: # when the server was crashed
-        break
+        with lock:
+            executing = True
+        try:
+            res = str(exec(code, ctx))
+        finally:
+            with lock:
+                executing = False
+                interrupt_requested = False
+    except SystemExit:
+        client_stream.send_msg(INST.EXCEPTION, 'SystemExit')
+        return False
+    except BaseException as e:
+        out = sys.stdout.getvalue()[:-1]
+        client_stream.send_msg(INST.ERROR, format_error(out, e))
+        traceback.clear_frames(e.__traceback__)
+        return False
+    out = sys.stdout.getvalue()[:-1]
+    if display_mode == '1':
+        client_stream.send_msg(INST.DISPLAY, format_display(out, displayed))
+        displayed.clear()
+        return True
+    if display_mode == '2':
+        client_stream.send_msg(INST.VALUE, format_value(out, displayed))
+        displayed.clear()
+        return True
+    if res:
+        out += '\n'
+    client_stream.send_msg(INST.PRINT, out + res)
+    return True
+
+threading.Thread(target=receive, daemon=True).start()
+
+while True:
+    inst, data = messages.get()
     if inst == INST.EXIT: # when the server was closed successfully
         client_stream.send_msg(INST.EXIT)
         break
     elif inst == INST.LOAD:
-        sys.stdout = io.StringIO()
-        res = ''
-        exc = ''
-        resp_inst = INST.PRINT
-        buf = []
-        try:
-            if already_loaded:
-                # __MODULE__ will be replaced with module name
-                res = str(exec('importlib.reload(__MODULE__)', ctx))
-            else:
-                res = str(exec('import __MODULE__', ctx))
-            already_loaded = True
-        except SystemExit:
-            client_stream.send_msg(INST.EXCEPTION, 'SystemExit')
-            continue
-        except Exception as e:
-            try:
-                excs = traceback.format_exception(e)
-            except:
-                excs = traceback.format_exception_only(e.__class__, e)
-            exc = ''.join(excs).rstrip()
-            traceback.clear_frames(e.__traceback__)
-            resp_inst = INST.INITIALIZE
-        out = sys.stdout.getvalue()[:-1]
-        if out and exc or res:
-            out += '\n'
-        res = out + exc + res
-        buf.append(res)
-        client_stream.send_msg(resp_inst, ''.join(buf))
+        # __MODULE__ will be replaced with module name
+        if already_loaded:
+            run('importlib.reload(__MODULE__)')
+        else:
+            already_loaded = run('import __MODULE__')
     elif inst == INST.EXECUTE:
-        sys.stdout = io.StringIO()
-        res = ''
-        exc = ''
-        resp_inst = INST.PRINT
-        buf = []
-        try:
-            res = str(exec(data, ctx))
-        except SystemExit:
-            client_stream.send_msg(INST.EXCEPTION, 'SystemExit')
-            continue
-        except Exception as e:
-            try:
-                excs = traceback.format_exception(e)
-            except:
-                excs = traceback.format_exception_only(e.__class__, e)
-            exc = ''.join(excs).rstrip()
-            traceback.clear_frames(e.__traceback__)
-            resp_inst = INST.INITIALIZE
-        out = sys.stdout.getvalue()[:-1]
-        if out and exc or res:
-            out += '\n'
-        res = out + exc + res
-        buf.append(res)
-        client_stream.send_msg(resp_inst, ''.join(buf))
+        run(data)
+    elif inst == INST.DISPLAY:
+        display_mode = data
     else:
         client_stream.send_msg(INST.UNKNOWN)
 
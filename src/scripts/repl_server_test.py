@@ -38,3 +38,36 @@ s.send_msg(INST.EXIT, "")
 inst, recv_data = s.recv_msg()
 assert inst == INST.EXIT
 assert recv_data == ""
+
+try:
+    exec(compile("def f():\n    1 / 0\nf()", "test.er", "exec"), {})
+except ZeroDivisionError as e:
+    groups = format_error("out", e).split(GROUP_SEP)
+    assert groups[:3] == ["out", "ZeroDivisionError", "division by zero"]
+    # the first frame is this test file
+    frames = [frame.split(UNIT_SEP) for frame in groups[3].split(RECORD_SEP)]
+    assert frames[-2:] == [["test.er", "3", "<module>"], ["test.er", "2", "f"]]
+
+class Html:
+    def _repr_html_(self):
+        return "<b>x</b>"
+    def __repr__(self):
+        return "x"
+
+groups = format_display("out", [Html()]).split(GROUP_SEP)
+assert groups[0] == "out"
+reprs = [r.split(UNIT_SEP) for r in groups[1].split(RECORD_SEP)]
+assert ["text/plain", "x"] in reprs
+assert ["text/html", "<b>x</b>"] in reprs
+
+Record = __import__("collections").namedtuple("Record", ["a", "b"])
+value = Record(1, [True, {"k": (1.5, None)}])
+assert dump_value(value) == [
+    "record",
+    [
+        ["a", ["int", "1"]],
+        ["b", ["array", [["bool", True], ["dict", [[["str", "k"], ["tuple", [["float", "1.5"], ["none"]]]]]]]]],
+    ],
+]
+out, data = format_value("out", [print]).split(GROUP_SEP)
+assert out == "out" and json.loads(data)[:2] == ["object", "builtin_function_or_method"]
@@ -0,0 +1,294 @@
+//! Values exchanged with an application embedding Erg (see `DummyVM::eval_value`).
+use std::fmt;
+
+use erg_common::Str;
+
+use crate::json::Json;
+
+/// A Python object converted by the REPL server.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    None,
+    Bool(bool),
+    /// Integers that do not fit in `i64` are converted to `Object`
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Array(Vec<Value>),
+    Tuple(Vec<Value>),
+    Set(Vec<Value>),
+    Dict(Vec<(Value, Value)>),
+    Record(Vec<(String, Value)>),
+    /// An instance of a class and its attributes
+    DataClass {
+        name: String,
+        fields: Vec<(String, Value)>,
+    },
+    /// An object that cannot be converted, with the name of its class and its `repr`
+    Object {
+        class: String,
+        repr: String,
+    },
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Self::Bool(b)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(i: i64) -> Self {
+        Self::Int(i)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(f: f64) -> Self {
+        Self::Float(f)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Self::Str(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Self::Str(s)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(v: Vec<T>) -> Self {
+        Self::Array(v.into_iter().map(Into::into).collect())
+    }
+}
+
+fn fmt_fields(fields: &[(String, Value)], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if fields.is_empty() {
+        return write!(f, "{{=}}");
+    }
+    write!(f, "{{")?;
+    for (i, (name, value)) in fields.iter().enumerate() {
+        if i != 0 {
+            write!(f, "; ")?;
+        }
+        write!(f, "{name} = {value}")?;
+    }
+    write!(f, "}}")
+}
+
+fn fmt_elems(elems: &[Value], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for (i, elem) in elems.iter().enumerate() {
+        if i != 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{elem}")?;
+    }
+    Ok(())
+}
+
+/// Shown in the Erg syntax (`Object` is shown as its `repr`)
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::Bool(b) => write!(f, "{}", if *b { "True" } else { "False" }),
+            Self::Int(i) => write!(f, "{i}"),
+            Self::Float(fl) => write!(f, "{fl:?}"),
+            Self::Str(s) => write!(f, "\"{}\"", Str::rc(s).escape()),
+            Self::Array(arr) => {
+                write!(f, "[")?;
+                fmt_elems(arr, f)?;
+                write!(f, "]")
+            }
+            Self::Tuple(tup) => {
+                write!(f, "(")?;
+                fmt_elems(tup, f)?;
+                if tup.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
+            Self::Set(set) => {
+                write!(f, "{{")?;
+                fmt_elems(set, f)?;
+                write!(f, "}}")
+            }
+            Self::Dict(dict) => {
+                if dict.is_empty() {
+                    return write!(f, "{{:}}");
+                }
+                write!(f, "{{")?;
+                for (i, (k, v)) in dict.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{k}: {v}")?;
+                }
+                write!(f, "}}")
+            }
+            Self::Record(rec) => fmt_fields(rec, f),
+            Self::DataClass { name, fields } => {
+                write!(f, "{name} ")?;
+                fmt_fields(fields, f)
+            }
+            Self::Object { repr, .. } => write!(f, "{repr}"),
+        }
+    }
+}
+
+impl Value {
+    /// Parses the result of `dump_value` (in `repl_server.py`).
+    pub(crate) fn parse(data: &str) -> Option<Self> {
+        Self::from_json(&Json::parse(data).ok()?)
+    }
+
+    fn from_json(json: &Json) -> Option<Self> {
+        let Json::Array(elems) = json else {
+            return None;
+        };
+        let elems_of = |json: &Json| match json {
+            Json::Array(elems) => elems
+                .iter()
+                .map(Self::from_json)
+                .collect::<Option<Vec<_>>>(),
+            _ => None,
+        };
+        let fields_of = |json: &Json| match json {
+            Json::Array(fields) => fields
+                .iter()
+                .map(|field| match field {
+                    Json::Array(pair) if pair.len() == 2 => {
+                        Some((pair[0].as_str()?.to_string(), Self::from_json(&pair[1])?))
+                    }
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>(),
+            _ => None,
+        };
+        let str_at = |i: usize| elems.get(i).and_then(Json::as_str);
+        let value = match str_at(0)? {
+            "none" => Self::None,
+            "bool" => Self::Bool(elems.get(1)?.as_bool()?),
+            "int" => {
+                let int = str_at(1)?;
+                int.parse().map(Self::Int).unwrap_or_else(|_| Self::Object {
+                    class: "int".to_string(),
+                    repr: int.to_string(),
+                })
+            }
+            "float" => Self::Float(str_at(1)?.parse().ok()?),
+            "str" => Self::Str(str_at(1)?.to_string()),
+            "array" => Self::Array(elems_of(elems.get(1)?)?),
+            "tuple" => Self::Tuple(elems_of(elems.get(1)?)?),
+            "set" => Self::Set(elems_of(elems.get(1)?)?),
+            "dict" => {
+                let Json::Array(pairs) = elems.get(1)? else {
+                    return None;
+                };
+                let pairs = pairs
+                    .iter()
+                    .map(|pair| match elems_of(pair)?.as_slice() {
+                        [k, v] => Some((k.clone(), v.clone())),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Self::Dict(pairs)
+            }
+            "record" => Self::Record(fields_of(elems.get(1)?)?),
+            "instance" => Self::DataClass {
+                name: str_at(1)?.to_string(),
+                fields: fields_of(elems.get(2)?)?,
+            },
+            "object" => Self::Object {
+                class: str_at(1)?.to_string(),
+                repr: str_at(2)?.to_string(),
+            },
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// Returns the Erg expression that creates the value (the fields of records are public).
+    /// Instances of classes and unconvertible objects cannot be created.
+    pub fn to_erg_expr(&self) -> Option<String> {
+        let elems = |elems: &[Value]| {
+            elems
+                .iter()
+                .map(Value::to_erg_expr)
+                .collect::<Option<Vec<_>>>()
+                .map(|elems| elems.join(", "))
+        };
+        let expr = match self {
+            // integer literals are limited to 32 bits
+            Self::Int(i) if i32::try_from(*i).is_err() => format!("Int(\"{i}\")"),
+            Self::Float(fl) if !fl.is_finite() => format!("Float(\"{fl}\")"),
+            Self::Array(arr) => format!("[{}]", elems(arr)?),
+            Self::Tuple(tup) if tup.len() == 1 => format!("({},)", elems(tup)?),
+            Self::Tuple(tup) => format!("({})", elems(tup)?),
+            Self::Set(set) => format!("{{{}}}", elems(set)?),
+            Self::Dict(dict) if !dict.is_empty() => {
+                let pairs = dict
+                    .iter()
+                    .map(|(k, v)| Some(format!("{}: {}", k.to_erg_expr()?, v.to_erg_expr()?)))
+                    .collect::<Option<Vec<_>>>()?;
+                format!("{{{}}}", pairs.join(", "))
+            }
+            Self::Record(rec) if !rec.is_empty() => {
+                let fields = rec
+                    .iter()
+                    .map(|(name, value)| Some(format!(".{name} = {}", value.to_erg_expr()?)))
+                    .collect::<Option<Vec<_>>>()?;
+                format!("{{{}}}", fields.join("; "))
+            }
+            Self::DataClass { .. } | Self::Object { .. } => return None,
+            other => other.to_string(),
+        };
+        Some(expr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value() {
+        let data = r#"["record", [["a", ["int", "1"]], ["b", ["array", [["str", "x\n"], ["float", "inf"]]]], ["c", ["dict", [[["int", "99999999999999999999"], ["none"]]]]]]]"#;
+        let value = Value::parse(data).unwrap();
+        let big = Value::Object {
+            class: "int".to_string(),
+            repr: "99999999999999999999".to_string(),
+        };
+        assert_eq!(
+            value,
+            Value::Record(vec![
+                ("a".to_string(), Value::Int(1)),
+                (
+                    "b".to_string(),
+                    Value::Array(vec![Value::from("x\n"), Value::Float(f64::INFINITY)])
+                ),
+                ("c".to_string(), Value::Dict(vec![(big, Value::None)])),
+            ])
+        );
+        assert_eq!(
+            value.to_string(),
+            r#"{a = 1; b = ["x\n", inf]; c = {99999999999999999999: None}}"#
+        );
+        // `c` cannot be created
+        assert_eq!(value.to_erg_expr(), None);
+        let value = Value::Tuple(vec![Value::from(vec![1i64 << 40]), Value::Float(f64::NAN)]);
+        assert_eq!(
+            value.to_erg_expr().as_deref(),
+            Some(r#"([Int("1099511627776")], Float("NaN"))"#)
+        );
+        let value = Value::Record(vec![("a".to_string(), Value::Tuple(vec![Value::None]))]);
+        assert_eq!(value.to_erg_expr().as_deref(), Some("{.a = (None,)}"));
+        assert_eq!(Value::Dict(vec![]).to_erg_expr().as_deref(), Some("{:}"));
+        assert_eq!(Value::parse(r#"["int"]"#), None);
+    }
+}
@@ -1,9 +1,12 @@
-use erg::DummyVM;
+use erg::{DummyVM, Value};
 use erg_common::config::ErgConfig;
 use erg_common::error::MultiErrorDisplay;
 use erg_common::python_util::exec_py_code_with_output;
+use erg_common::traits::Runnable;
 use erg_compiler::artifact::Buildable;
 use erg_compiler::module::SharedCompilerResource;
+use erg_compiler::ty::constructors::func1;
+use erg_compiler::ty::Type;
 use erg_compiler::HIRBuilder;
 use erg_compiler::Transpiler;
 
@@ -70,6 +73,64 @@ while! do! c < 7, do!:
     Ok(())
 }
 
+#[test]
+fn test_transpiler_embedding_async() -> Result<(), ()> {
+    let mut trans = Transpiler::default();
+    let res = trans
+        .transpile(
+            "
+@Async
+add! x, y = x + y
+
+@Async
+.main!() =
+    a = await! add!(1, 2)
+    print!(a, end:=\"\")
+"
+            .into(),
+            "exec",
+        )
+        .map_err(|es| {
+            es.errors.write_all_stderr();
+        })?;
+    let code = res.object.code();
+    assert!(code.contains("async def main__erg_proc__("));
+    assert!(code.contains("(await ("));
+    let code = format!("{code}\nimport asyncio\nasyncio.run(main__erg_proc__())\n");
+    let res = exec_py_code_with_output(&code, &[]).map_err(|_| ())?;
+    assert!(res.status.success());
+    assert_eq!(res.stdout, b"3");
+    Ok(())
+}
+
+#[test]
+fn test_transpiler_embedding_generator() -> Result<(), ()> {
+    let mut trans = Transpiler::default();
+    let res = trans
+        .transpile(
+            "
+squares!(n: Nat) =
+    for! 0..<n, i =>
+        squares!.yield i * i
+    None
+
+for! squares!(4), s =>
+    print!(s, end:=\"\")
+"
+            .into(),
+            "exec",
+        )
+        .map_err(|es| {
+            es.errors.write_all_stderr();
+        })?;
+    let code = res.object.code();
+    assert!(code.contains("(yield ("));
+    let res = exec_py_code_with_output(&code, &[]).map_err(|_| ())?;
+    assert!(res.status.success());
+    assert_eq!(res.stdout, b"0149");
+    Ok(())
+}
+
 #[test]
 fn test_builder() -> Result<(), ()> {
     let mods = ["math", "time"];
@@ -82,3 +143,105 @@ fn test_builder() -> Result<(), ()> {
     let _res = checker.build(src, "exec");
     Ok(())
 }
+
+#[test]
+fn test_completion() -> Result<(), ()> {
+    let mut builder = <HIRBuilder as Runnable>::new(ErgConfig::default());
+    builder
+        .eval("abcde = 1\nabxyz = \"a\"".into())
+        .map_err(|es| {
+            es.write_all_stderr();
+        })?;
+    assert_eq!(builder.complete("abc"), vec!["abcde".to_string()]);
+    assert!(builder.complete("ab").contains(&"abxyz".to_string()));
+    assert!(builder
+        .complete("abxyz.upp")
+        .contains(&"abxyz.upper".to_string()));
+    assert!(builder.complete("zzz.").is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_pre_check() {
+    let mut builder = <HIRBuilder as Runnable>::new(ErgConfig::default());
+    assert!(builder.pre_check("prin 1".into()).is_err());
+    assert!(builder.pre_check("f(1".into()).is_err());
+    assert!(builder.pre_check("print! 1".into()).is_ok());
+    // definitions are not registered
+    assert!(builder.pre_check("some_var = 1".into()).is_ok());
+    assert!(builder.complete("some_v").is_empty());
+}
+
+#[test]
+fn test_vm_eval_value() -> Result<(), ()> {
+    let mut vm = DummyVM::default();
+    let (value, t) = vm
+        .eval_value("x = 1\n{a = x + 1; b = [\"c\"]; d = (1.5, None)}".into())
+        .map_err(|es| {
+            es.write_all_stderr();
+        })?;
+    assert_eq!(
+        value,
+        Value::Record(vec![
+            ("a".to_string(), Value::Int(2)),
+            ("b".to_string(), Value::from(vec!["c"])),
+            (
+                "d".to_string(),
+                Value::Tuple(vec![Value::Float(1.5), Value::None])
+            ),
+        ])
+    );
+    assert!(matches!(t, Type::Record(_)));
+    let (value, t) = vm.eval_value("{\"k\": 2 ** 70}".into()).map_err(|es| {
+        es.write_all_stderr();
+    })?;
+    let Value::Dict(dict) = value else {
+        panic!("not a dict: {value}");
+    };
+    assert_eq!(dict[0].1.to_string(), "1180591620717411303424");
+    assert!(t.to_string().starts_with("Dict"));
+    let (value, _) = vm
+        .eval_value("C = Class {a = Int}\nC.new {a = 1}".into())
+        .map_err(|es| {
+            es.write_all_stderr();
+        })?;
+    assert_eq!(value.to_string(), "C {a = 1}");
+    assert_eq!(
+        vm.eval_value("y = 1".into()).ok(),
+        Some((Value::None, Type::NoneType))
+    );
+    vm.eval_value("1 / 0".into()).expect_err("should err");
+    Ok(())
+}
+
+#[test]
+fn test_vm_define_and_declare() -> Result<(), ()> {
+    let mut vm = DummyVM::default();
+    let rec = Value::Record(vec![
+        ("name".to_string(), Value::from("erg")),
+        ("big".to_string(), Value::Int(1 << 40)),
+    ]);
+    vm.define("host", &rec).map_err(|es| {
+        es.write_all_stderr();
+    })?;
+    vm.declare("double", func1(Type::Int, Type::Int), "lambda x: x * 2")
+        .map_err(|es| {
+            es.write_all_stderr();
+        })?;
+    let (value, t) = vm
+        .eval_value("double(host.big) + host.name.count \"r\"".into())
+        .map_err(|es| {
+            es.write_all_stderr();
+        })?;
+    assert_eq!(value, Value::Int((1 << 41) + 1));
+    assert_eq!(t, Type::Int);
+    // the declared type is checked
+    vm.eval_value("double \"a\"".into())
+        .expect_err("should err");
+    let obj = Value::Object {
+        class: "function".to_string(),
+        repr: "<function f>".to_string(),
+    };
+    vm.define("f", &obj).expect_err("should err");
+    Ok(())
+}
@@ -195,6 +195,58 @@ fn exec_repl_invalid_def_after_the_at_sign() -> Result<(), ()> {
     )
 }
 
+#[test]
+#[ignore]
+fn exec_repl_commands() -> Result<(), ()> {
+    let session = std::env::temp_dir().join("erg_repl_session.er");
+    expect_repl_success(
+        "repl_commands",
+        vec![
+            "x = 1".to_string(),
+            ":type x + 1".to_string(),
+            ":doc x".to_string(),
+            ":dir".to_string(),
+            ":dir Int".to_string(),
+            ":load tests/should_ok/default_param.er".to_string(),
+            "assert f(1) == 4".to_string(),
+            ":reload".to_string(),
+            format!(":save {}", session.display()),
+            "exit()".to_string(),
+        ],
+    )
+}
+
+#[test]
+#[ignore]
+fn exec_repl_type_of_def() -> Result<(), ()> {
+    expect_repl_failure(
+        "repl_type_of_def",
+        [":type x = 1", ":type undefined_var", "exit()"]
+            .into_iter()
+            .map(|x| x.to_string())
+            .collect(),
+        2,
+    )
+}
+
+#[test]
+#[ignore]
+fn exec_repl_type_has_no_effects() -> Result<(), ()> {
+    expect_repl_success(
+        "repl_type_has_no_effects",
+        [
+            "x = ![1]",
+            ":type x.push! 1",
+            ":type x",
+            "_: Array!(Int, 1) = x",
+            "exit()",
+        ]
+        .into_iter()
+        .map(|x| x.to_string())
+        .collect(),
+    )
+}
+
 #[test]
 #[ignore]
 fn exec_repl_server_mock_test() -> Result<(), ()> {
@@ -206,3 +258,28 @@ fn exec_repl_server_mock_test() -> Result<(), ()> {
     );
     Ok(())
 }
+
+#[test]
+#[ignore]
+fn exec_jupyter_kernel_test() -> Result<(), ()> {
+    assert_eq!(
+        exec_py("src/scripts/kernel_test.py", &[env!("CARGO_BIN_EXE_erg")])
+            .ok()
+            .and_then(|s| s.code()),
+        Some(0)
+    );
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn exec_repl_runtime_error() -> Result<(), ()> {
+    expect_repl_failure(
+        "repl_runtime_error",
+        ["print! \"hi\"; 1 / 0", "x = 1", "print! x + 1", "exit()"]
+            .into_iter()
+            .map(|x| x.to_string())
+            .collect(),
+        1,
+    )
+}
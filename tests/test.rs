@@ -35,6 +35,11 @@ fn exec_associated_types() -> Result<(), ()> {
     expect_success("tests/should_ok/associated_types.er", 0)
 }
 
+#[test]
+fn exec_async() -> Result<(), ()> {
+    expect_success("tests/should_ok/async.er", 0)
+}
+
 #[test]
 fn exec_class() -> Result<(), ()> {
     expect_success("examples/class.er", 0)
@@ -60,6 +65,11 @@ fn exec_comment() -> Result<(), ()> {
     expect_success("tests/should_ok/comment.er", 0)
 }
 
+#[test]
+fn exec_const_fn() -> Result<(), ()> {
+    expect_success("tests/should_ok/const_fn.er", 0)
+}
+
 #[test]
 fn exec_control() -> Result<(), ()> {
     expect_success("examples/control.er", 2)
@@ -95,6 +105,11 @@ fn exec_dict_test() -> Result<(), ()> {
     expect_success("tests/should_ok/dict.er", 0)
 }
 
+#[test]
+fn exec_enum() -> Result<(), ()> {
+    expect_success("tests/should_ok/enum.er", 0)
+}
+
 #[test]
 fn exec_external() -> Result<(), ()> {
     let py_command = opt_which_python().unwrap();
@@ -110,6 +125,16 @@ fn exec_fib() -> Result<(), ()> {
     expect_success("examples/fib.er", 0)
 }
 
+#[test]
+fn exec_generator() -> Result<(), ()> {
+    expect_success("tests/should_ok/generator.er", 0)
+}
+
+#[test]
+fn exec_guard() -> Result<(), ()> {
+    expect_success("tests/should_ok/guard.er", 0)
+}
+
 #[test]
 fn exec_helloworld() -> Result<(), ()> {
     // HACK: When running the test with Windows, the exit code is 1 (the cause is unknown)
@@ -196,6 +221,11 @@ fn exec_map() -> Result<(), ()> {
     expect_success("tests/should_ok/map.er", 0)
 }
 
+#[test]
+fn exec_match() -> Result<(), ()> {
+    expect_success("tests/should_ok/match.er", 2)
+}
+
 #[test]
 fn exec_method() -> Result<(), ()> {
     expect_success("tests/should_ok/method.er", 0)
@@ -211,6 +241,11 @@ fn exec_mut_array() -> Result<(), ()> {
     expect_success("tests/should_ok/mut_array.er", 0)
 }
 
+#[test]
+fn exec_mut_dependent() -> Result<(), ()> {
+    expect_success("tests/should_ok/mut_dependent.er", 0)
+}
+
 #[test]
 fn exec_mut_dict() -> Result<(), ()> {
     expect_success("tests/should_ok/mut_dict.er", 0)
@@ -231,6 +266,11 @@ fn exec_operators() -> Result<(), ()> {
     expect_success("tests/should_ok/operators.er", 0)
 }
 
+#[test]
+fn exec_overload() -> Result<(), ()> {
+    expect_success("tests/should_ok/overload.er", 0)
+}
+
 #[test]
 fn exec_patch() -> Result<(), ()> {
     expect_success("examples/patch.er", 0)
@@ -241,6 +281,11 @@ fn exec_pattern() -> Result<(), ()> {
     expect_success("tests/should_ok/pattern.er", 0)
 }
 
+#[test]
+fn exec_poly_class() -> Result<(), ()> {
+    expect_success("tests/should_ok/poly_class.er", 0)
+}
+
 #[test]
 fn exec_pyimport_test() -> Result<(), ()> {
     expect_success("tests/should_ok/pyimport.er", 2)
@@ -301,6 +346,11 @@ fn exec_structural() -> Result<(), ()> {
     expect_success("tests/should_ok/structural.er", 0)
 }
 
+#[test]
+fn exec_structural_trait() -> Result<(), ()> {
+    expect_success("tests/should_ok/structural_trait.er", 0)
+}
+
 #[test]
 fn exec_subtyping() -> Result<(), ()> {
     expect_success("tests/should_ok/subtyping.er", 0)
@@ -321,6 +371,11 @@ fn exec_tuple() -> Result<(), ()> {
     expect_success("examples/tuple.er", 0)
 }
 
+#[test]
+fn exec_typed_dict() -> Result<(), ()> {
+    expect_success("tests/should_ok/typed_dict.er", 0)
+}
+
 #[test]
 fn exec_unit_test() -> Result<(), ()> {
     expect_success("examples/unit_test.er", 0)
@@ -381,6 +436,16 @@ fn exec_assert_cast() -> Result<(), ()> {
     expect_failure("examples/assert_cast.er", 0, 3)
 }
 
+#[test]
+fn exec_async_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/async.er", 0, 3)
+}
+
+#[test]
+fn exec_async_effect_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/async_effect.er", 0, 2)
+}
+
 #[test]
 fn exec_class_attr_err() -> Result<(), ()> {
     expect_failure("tests/should_err/class_attr.er", 1, 1)
@@ -391,6 +456,11 @@ fn exec_collection_err() -> Result<(), ()> {
     expect_failure("tests/should_err/collection.er", 0, 4)
 }
 
+#[test]
+fn exec_const_fn_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/const_fn.er", 0, 8)
+}
+
 #[test]
 fn exec_dependent_err() -> Result<(), ()> {
     expect_failure("tests/should_err/dependent.er", 0, 5)
@@ -412,6 +482,16 @@ fn exec_tests_impl() -> Result<(), ()> {
     expect_end_with("tests/should_ok/impl.er", 0, 1)
 }
 
+#[test]
+fn exec_generator_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/generator.er", 0, 4)
+}
+
+#[test]
+fn exec_guard_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/guard.er", 0, 4)
+}
+
 #[test]
 fn exec_impl_err() -> Result<(), ()> {
     expect_failure("tests/should_err/impl.er", 2, 2)
@@ -476,6 +556,11 @@ fn exec_structural_err() -> Result<(), ()> {
     expect_failure("tests/should_err/structural.er", 1, 9)
 }
 
+#[test]
+fn exec_structural_trait_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/structural_trait.er", 0, 5)
+}
+
 #[test]
 fn exec_subtyping_err() -> Result<(), ()> {
     expect_failure("tests/should_err/subtyping.er", 0, 17)
@@ -486,11 +571,21 @@ fn exec_tuple_err() -> Result<(), ()> {
     expect_failure("tests/should_err/tuple.er", 0, 1)
 }
 
+#[test]
+fn exec_typed_dict_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/typed_dict.er", 0, 5)
+}
+
 #[test]
 fn exec_callable() -> Result<(), ()> {
     expect_failure("tests/should_err/callable.er", 0, 6)
 }
 
+#[test]
+fn exec_match_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/match.er", 0, 3)
+}
+
 #[test]
 fn exec_method_err() -> Result<(), ()> {
     expect_failure("tests/should_err/method.er", 0, 2)
@@ -521,6 +616,16 @@ fn exec_mut_dict_err() -> Result<(), ()> {
     expect_failure("tests/should_err/mut_dict.er", 0, 3)
 }
 
+#[test]
+fn exec_overload_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/overload.er", 0, 2)
+}
+
+#[test]
+fn exec_poly_class_err() -> Result<(), ()> {
+    expect_failure("tests/should_err/poly_class.er", 0, 5)
+}
+
 #[test]
 fn exec_quantified_err() -> Result<(), ()> {
     expect_failure("tests/should_err/quantified.er", 0, 3)